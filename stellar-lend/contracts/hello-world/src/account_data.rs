@@ -0,0 +1,110 @@
+//! Account data module for StellarLend protocol
+//! One-call risk summary of a user's primary and cross-asset positions, valued at the
+//! registered (risk) prices with debt projected to the current borrow index.
+
+use crate::cross_asset::CrossAssets;
+use crate::normalization::Normalization;
+use crate::origination::OriginationFees;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{
+    CrossPosition, InterestRateManager, InterestRateStorage, ProtocolError, StateHelper,
+    TokenRegistry,
+};
+use soroban_sdk::{contracttype, Address, Env, Map};
+
+/// Price scale (1e8); an unpriced primary asset is valued 1:1
+const PRICE_SCALE: i128 = 100_000_000;
+
+/// Origination fees are basis points
+const BPS_SCALE: i128 = 10_000;
+
+/// Risk summary of an account across its positions
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AccountData {
+    /// Collateral value (1e18 base), LP tokens at their collateral factor
+    pub total_collateral_value: i128,
+    /// Debt value at the current borrow index (1e18 base)
+    pub total_debt_value: i128,
+    /// Collateral ratio as a percent of the liquidation threshold (100 = liquidatable),
+    /// `None` without debt
+    pub health_factor: Option<i128>,
+    /// Further debt value the account can draw before reaching the threshold, net of the
+    /// primary asset's origination fee added on top of each borrow
+    pub available_borrow_value: i128,
+    /// Origination fee charged on borrows of the primary asset (bps)
+    pub origination_fee_bps: i128,
+    /// Minimum collateral ratio in percent below which the account can be liquidated:
+    /// the strictest requirement across its collateral/debt pairs
+    pub liquidation_threshold: i128,
+}
+
+pub struct AccountView;
+
+impl AccountView {
+    pub fn get(env: &Env, user: &Address) -> Result<AccountData, ProtocolError> {
+        let mut collateral_value = 0i128;
+        let mut debt_value = 0i128;
+        let mut threshold: Option<i128> = None;
+
+        if let Some(position) = StateHelper::get_position(env, user) {
+            let asset = TokenRegistry::require_primary_asset(env)?;
+            let price = CrossAssets::valuation_price(env, &asset).unwrap_or(PRICE_SCALE);
+            let index = InterestRateStorage::current_borrow_index(env, &asset);
+            let debt = InterestRateManager::rebase(position.debt, position.borrow_index, index);
+            collateral_value += Normalization::value(env, &asset, position.collateral, price);
+            debt_value += Normalization::value(env, &asset, debt, price);
+            if debt > 0 {
+                threshold = Some(CollateralMatrix::effective_min_ratio(env));
+            }
+        }
+
+        if let Some(position) = StateHelper::get_cross_position(env, user) {
+            collateral_value += CrossAssets::collateral_value(env, &position.collateral)?;
+            debt_value += CrossAssets::value(env, &Self::current_debt(env, &position))?;
+            if !position.debt.is_empty() {
+                let ratio = CrossAssets::min_ratio(env, &position);
+                threshold = Some(threshold.map_or(ratio, |current| current.max(ratio)));
+            }
+        }
+
+        let liquidation_threshold =
+            threshold.unwrap_or_else(|| CollateralMatrix::effective_min_ratio(env));
+        let health_factor = if debt_value > 0 && liquidation_threshold > 0 {
+            Some((collateral_value * 100 / debt_value) * 100 / liquidation_threshold)
+        } else {
+            None
+        };
+        let capacity = if liquidation_threshold > 0 {
+            collateral_value * 100 / liquidation_threshold
+        } else {
+            0
+        };
+        let origination_fee_bps = TokenRegistry::require_primary_asset(env)
+            .map(|asset| OriginationFees::fee_bps(env, &asset))
+            .unwrap_or(0);
+        let headroom = (capacity - debt_value).max(0);
+        Ok(AccountData {
+            total_collateral_value: collateral_value,
+            total_debt_value: debt_value,
+            health_factor,
+            available_borrow_value: headroom * BPS_SCALE / (BPS_SCALE + origination_fee_bps),
+            origination_fee_bps,
+            liquidation_threshold,
+        })
+    }
+
+    /// Cross-asset debt balances rebased to each market's current borrow index
+    fn current_debt(env: &Env, position: &CrossPosition) -> Map<Address, i128> {
+        let mut debt = Map::new(env);
+        for (asset, principal) in position.debt.iter() {
+            let snapshot = position.borrow_index.get(asset.clone()).unwrap_or(0);
+            let index = InterestRateStorage::current_borrow_index(env, &asset);
+            debt.set(
+                asset,
+                InterestRateManager::rebase(principal, snapshot, index),
+            );
+        }
+        debt
+    }
+}
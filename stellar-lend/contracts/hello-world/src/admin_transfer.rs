@@ -0,0 +1,146 @@
+//! Admin transfer module for StellarLend protocol
+//! Two-step admin rotation: the current admin proposes a successor, who accepts once the
+//! configured delay has passed. The current admin can cancel until then.
+
+use crate::{ProtocolConfig, ProtocolError, UserManager};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Default wait between proposal and acceptance (2 days)
+const DEFAULT_DELAY: u64 = 2 * 24 * 60 * 60;
+
+/// Admin handover waiting for the successor's acceptance
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PendingAdminTransfer {
+    pub current_admin: Address,
+    pub new_admin: Address,
+    pub proposed_at: u64,
+    /// Earliest acceptance time
+    pub eta: u64,
+}
+
+/// Storage helper for the pending transfer and its delay
+pub struct AdminTransferStorage;
+
+impl AdminTransferStorage {
+    fn pending_key(env: &Env) -> Symbol {
+        Symbol::new(env, "admin_pending")
+    }
+    fn delay_key(env: &Env) -> Symbol {
+        Symbol::new(env, "admin_delay")
+    }
+
+    pub fn get_pending(env: &Env) -> Option<PendingAdminTransfer> {
+        env.storage().instance().get(&Self::pending_key(env))
+    }
+
+    pub fn save_pending(env: &Env, pending: &PendingAdminTransfer) {
+        env.storage()
+            .instance()
+            .set(&Self::pending_key(env), pending);
+    }
+
+    pub fn remove_pending(env: &Env) {
+        env.storage().instance().remove(&Self::pending_key(env));
+    }
+
+    pub fn get_delay(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Self::delay_key(env))
+            .unwrap_or(DEFAULT_DELAY)
+    }
+
+    pub fn save_delay(env: &Env, delay: u64) {
+        env.storage().instance().set(&Self::delay_key(env), &delay);
+    }
+}
+
+/// Admin rotation coordinator
+pub struct AdminTransfer;
+
+impl AdminTransfer {
+    /// Set the wait between proposing and accepting a transfer (admin only). Applies to
+    /// later proposals.
+    pub fn set_delay(env: &Env, caller: &Address, delay: u64) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        AdminTransferStorage::save_delay(env, delay);
+        Ok(())
+    }
+
+    pub fn get_delay(env: &Env) -> u64 {
+        AdminTransferStorage::get_delay(env)
+    }
+
+    /// Nominate `new_admin`, replacing any pending nomination (admin only)
+    pub fn propose(
+        env: &Env,
+        caller: &Address,
+        new_admin: &Address,
+    ) -> Result<PendingAdminTransfer, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if new_admin == caller {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let now = env.ledger().timestamp();
+        let pending = PendingAdminTransfer {
+            current_admin: caller.clone(),
+            new_admin: new_admin.clone(),
+            proposed_at: now,
+            eta: now + AdminTransferStorage::get_delay(env),
+        };
+        AdminTransferStorage::save_pending(env, &pending);
+        Self::publish(env, "admin_transfer_proposed", caller, new_admin);
+        Ok(pending)
+    }
+
+    /// Withdraw the pending nomination (admin only)
+    pub fn cancel(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let pending = AdminTransferStorage::get_pending(env).ok_or(ProtocolError::NotFound)?;
+        AdminTransferStorage::remove_pending(env);
+        Self::publish(env, "admin_transfer_cancelled", caller, &pending.new_admin);
+        Ok(())
+    }
+
+    /// Take over as admin once the delay has passed. The new admin gets a verified admin
+    /// profile and the previous admin's profile drops to a standard role.
+    pub fn accept(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        let pending = AdminTransferStorage::get_pending(env).ok_or(ProtocolError::NotFound)?;
+        if pending.new_admin != *caller {
+            return Err(ProtocolError::Unauthorized);
+        }
+        if env.ledger().timestamp() < pending.eta {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        AdminTransferStorage::remove_pending(env);
+        ProtocolConfig::set_admin(env, caller);
+        UserManager::bootstrap_admin(env, caller);
+        UserManager::retire_admin(env, &pending.current_admin);
+        Self::publish(
+            env,
+            "admin_transfer_accepted",
+            &pending.current_admin,
+            caller,
+        );
+        Ok(())
+    }
+
+    pub fn get_pending(env: &Env) -> Option<PendingAdminTransfer> {
+        AdminTransferStorage::get_pending(env)
+    }
+
+    fn publish(env: &Env, action: &str, from: &Address, to: &Address) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "admin")),
+            (
+                Symbol::new(env, "from"),
+                from.clone(),
+                Symbol::new(env, "to"),
+                to.clone(),
+            ),
+        );
+    }
+}
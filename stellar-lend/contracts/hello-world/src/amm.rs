@@ -2,14 +2,41 @@
 //!
 //! This module provides AMM integration for the StellarLend protocol, including:
 //! - Asset pair registration for supported AMMs
+//! - Swap quotes and execution through the `SwapAdapter` interface registered AMM
+//!   contracts implement
+//! - Fallback to the protocol's internal constant-product pools for unregistered pairs
 //! - Swap hooks for deleveraging and liquidation flows
 //! - Event emissions for AMM usage tracking
 //! - Integration with liquidation mechanisms
-#[cfg(not(test))]
+use crate::analytics::AnalyticsModule;
+use crate::fees::{FeeManager, AMM_FEE_BPS};
+use crate::internal_pool::InternalPools;
+use crate::persistent::PersistentStore;
 use crate::ProtocolEvent;
 #[allow(unused_imports)]
-use crate::{Position, ProtocolError, ReentrancyGuard, StateHelper};
-use soroban_sdk::{contracterror, contracttype, Address, Env, Map, Symbol, Vec};
+use crate::{Position, ProtocolError, ReentrancyGuard, StateHelper, TransferEnforcer};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contractclient, contracterror, contracttype, Address, Env, Map, Symbol, Vec};
+
+/// Interface every registered AMM address implements; the protocol itself only calls it
+/// through `SwapAdapterClient`
+#[allow(dead_code)]
+#[contractclient(name = "SwapAdapterClient")]
+pub trait SwapAdapter {
+    /// Output for selling `amount_in` of `asset_in` for `asset_out`
+    fn quote(env: Env, asset_in: Address, asset_out: Address, amount_in: i128) -> i128;
+
+    /// Swap `amount_in` of `asset_in`, already transferred to the adapter, and transfer
+    /// at least `min_out` of `asset_out` to `to`. Returns the amount transferred.
+    fn swap(
+        env: Env,
+        asset_in: Address,
+        asset_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128;
+}
 
 /// AMM-specific error types
 #[contracterror]
@@ -37,14 +64,14 @@ pub enum AMMError {
 impl From<AMMError> for ProtocolError {
     fn from(err: AMMError) -> Self {
         match err {
-            AMMError::PairNotRegistered => ProtocolError::NotFound,
+            AMMError::PairNotRegistered => ProtocolError::AmmPairNotRegistered,
             AMMError::PairAlreadyExists => ProtocolError::AlreadyExists,
             AMMError::InvalidAMMAddress => ProtocolError::InvalidAddress,
-            AMMError::InsufficientLiquidity => ProtocolError::InvalidAmount,
-            AMMError::SlippageExceeded => ProtocolError::InvalidAmount,
-            AMMError::InvalidSwapParams => ProtocolError::InvalidParameters,
+            AMMError::InsufficientLiquidity => ProtocolError::InsufficientLiquidity,
+            AMMError::SlippageExceeded => ProtocolError::SlippageProtectionTriggered,
+            AMMError::InvalidSwapParams => ProtocolError::AmmInvalidSwapParams,
             AMMError::Unauthorized => ProtocolError::Unauthorized,
-            AMMError::SwapFailed => ProtocolError::InvalidAmount,
+            AMMError::SwapFailed => ProtocolError::AmmSwapFailed,
         }
     }
 }
@@ -151,6 +178,15 @@ impl SwapParams {
     }
 }
 
+/// Whether a swap went through
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum SwapStatus {
+    Executed,
+    /// The pair's quote was below `min_amount_out`; nothing was swapped
+    SlippageRejected,
+}
+
 /// Swap result information
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -167,6 +203,7 @@ pub struct SwapResult {
     pub fee_paid: i128,
     /// Timestamp of the swap
     pub timestamp: u64,
+    pub status: SwapStatus,
 }
 
 impl SwapResult {
@@ -185,6 +222,15 @@ impl SwapResult {
             slippage_bps: 0, // Calculated separately
             fee_paid,
             timestamp,
+            status: SwapStatus::Executed,
+        }
+    }
+
+    /// A swap rejected for slippage before any tokens moved
+    pub fn rejected(amount_in: i128, timestamp: u64) -> Self {
+        Self {
+            status: SwapStatus::SlippageRejected,
+            ..Self::new(amount_in, 0, 0, timestamp)
         }
     }
 
@@ -192,8 +238,42 @@ impl SwapResult {
         self.slippage_bps = slippage_bps;
         self
     }
+
+    /// Fail flows that cannot continue without the swap's output. The error reverts
+    /// the whole call, including the rejection's count against the pair.
+    pub fn require_executed(self) -> Result<Self, ProtocolError> {
+        match self.status {
+            SwapStatus::Executed => Ok(self),
+            SwapStatus::SlippageRejected => Err(AMMError::SlippageExceeded.into()),
+        }
+    }
 }
 
+/// A swap in a pair's history
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SwapRecord {
+    /// Sequence number within the pair, starting at 1 (doubles as the pagination cursor)
+    pub seq: u64,
+    pub user: Address,
+    pub asset_in: Address,
+    pub asset_out: Address,
+    pub result: SwapResult,
+}
+
+/// Page of a pair's swap history
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SwapHistoryPage {
+    /// Records ordered oldest first
+    pub records: Vec<SwapRecord>,
+    /// Cursor to pass to the next query, if more records exist
+    pub next_cursor: Option<u64>,
+}
+
+/// Largest page `get_swap_history_page` returns
+pub const MAX_SWAP_HISTORY_PAGE: u32 = 50;
+
 /// Pair key type for storage
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -216,6 +296,43 @@ impl PairKey {
     }
 }
 
+/// Thresholds that automatically deactivate a failing pair
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PairHealthConfig {
+    /// Consecutive swap failures that deactivate the pair (0 disables)
+    pub max_consecutive_failures: u32,
+    /// Failure rate within the window that deactivates the pair (bps, 0 disables)
+    pub max_failure_rate_bps: i128,
+    /// Failure-rate window length in seconds
+    pub window: u64,
+    /// Swaps required in the window before the failure rate is judged
+    pub min_samples: u32,
+}
+
+impl PairHealthConfig {
+    pub fn default_config() -> Self {
+        Self {
+            max_consecutive_failures: 5,
+            max_failure_rate_bps: 5000,
+            window: 3600,
+            min_samples: 10,
+        }
+    }
+}
+
+/// Swap outcome tracking for a pair
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct PairHealth {
+    pub consecutive_failures: u32,
+    pub window_start: u64,
+    pub window_swaps: u32,
+    pub window_failures: u32,
+    /// Set when the pair was deactivated by the failure thresholds
+    pub auto_deactivated: bool,
+}
+
 /// AMM registry storage management
 pub struct AMMStorage;
 
@@ -233,6 +350,50 @@ impl AMMStorage {
         Symbol::new(env, "amm_swap_history")
     }
 
+    fn health_config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "amm_health_cfg")
+    }
+
+    fn pair_health_key(env: &Env, asset_a: &Address, asset_b: &Address) -> (Symbol, PairKey) {
+        (
+            Symbol::new(env, "amm_pair_health"),
+            PairKey::new(asset_a.clone(), asset_b.clone()),
+        )
+    }
+
+    fn pair_swap_count_key(env: &Env, key: &PairKey) -> (Symbol, PairKey) {
+        (Symbol::new(env, "amm_pair_swaps"), key.clone())
+    }
+
+    fn pair_swap_key(env: &Env, key: &PairKey, seq: u64) -> (Symbol, PairKey, u64) {
+        (Symbol::new(env, "amm_pair_swap"), key.clone(), seq)
+    }
+
+    /// Get the auto-deactivation thresholds
+    pub fn get_health_config(env: &Env) -> PairHealthConfig {
+        env.storage()
+            .instance()
+            .get(&Self::health_config_key(env))
+            .unwrap_or_else(PairHealthConfig::default_config)
+    }
+
+    /// Save the auto-deactivation thresholds
+    pub fn save_health_config(env: &Env, config: &PairHealthConfig) {
+        env.storage()
+            .instance()
+            .set(&Self::health_config_key(env), config);
+    }
+
+    /// Get a pair's swap outcome tracking
+    pub fn get_pair_health(env: &Env, asset_a: &Address, asset_b: &Address) -> PairHealth {
+        PersistentStore::get(env, &Self::pair_health_key(env, asset_a, asset_b)).unwrap_or_default()
+    }
+
+    /// Save a pair's swap outcome tracking
+    pub fn save_pair_health(env: &Env, asset_a: &Address, asset_b: &Address, health: &PairHealth) {
+        PersistentStore::set(env, &Self::pair_health_key(env, asset_a, asset_b), health);
+    }
+
     /// Get all registered pairs
     pub fn get_all_pairs(env: &Env) -> Map<PairKey, AssetPair> {
         env.storage()
@@ -279,10 +440,7 @@ impl AMMStorage {
 
     /// Get swap history
     pub fn get_swap_history(env: &Env) -> Vec<SwapResult> {
-        env.storage()
-            .instance()
-            .get(&Self::swap_history_key(env))
-            .unwrap_or_else(|| Vec::new(env))
+        PersistentStore::get(env, &Self::swap_history_key(env)).unwrap_or_else(|| Vec::new(env))
     }
 
     /// Add swap to history
@@ -295,9 +453,30 @@ impl AMMStorage {
             history = history.slice(history.len() - 100..);
         }
 
-        env.storage()
-            .instance()
-            .set(&Self::swap_history_key(env), &history);
+        PersistentStore::set(env, &Self::swap_history_key(env), &history);
+    }
+
+    /// Swaps recorded for a pair so far
+    pub fn get_pair_swap_count(env: &Env, key: &PairKey) -> u64 {
+        PersistentStore::get(env, &Self::pair_swap_count_key(env, key)).unwrap_or(0)
+    }
+
+    pub fn get_pair_swap(env: &Env, key: &PairKey, seq: u64) -> Option<SwapRecord> {
+        PersistentStore::get(env, &Self::pair_swap_key(env, key, seq))
+    }
+
+    /// Append a record to its pair's history, assigning it the next sequence number
+    pub fn append_pair_swap(env: &Env, record: &mut SwapRecord) {
+        let key = PairKey::new(record.asset_in.clone(), record.asset_out.clone());
+        let seq = Self::get_pair_swap_count(env, &key) + 1;
+        record.seq = seq;
+        PersistentStore::set(env, &Self::pair_swap_key(env, &key, seq), record);
+        PersistentStore::set(env, &Self::pair_swap_count_key(env, &key), &seq);
+    }
+
+    /// Extend the TTL of the swap history entry, returning how many exist
+    pub fn bump(env: &Env) -> u32 {
+        PersistentStore::bump(env, &Self::swap_history_key(env)) as u32
     }
 }
 
@@ -407,15 +586,199 @@ impl AMMRegistry {
         pair.last_updated = env.ledger().timestamp();
 
         AMMStorage::save_pair(env, &pair);
+        AMMStorage::save_pair_health(env, asset_a, asset_b, &PairHealth::default());
         Ok(())
     }
 
+    /// Set the failure thresholds that deactivate pairs
+    pub fn set_health_config(env: &Env, config: PairHealthConfig) -> Result<(), ProtocolError> {
+        if !(0..=10000).contains(&config.max_failure_rate_bps)
+            || (config.max_failure_rate_bps > 0 && (config.window == 0 || config.min_samples == 0))
+        {
+            return Err(AMMError::InvalidSwapParams.into());
+        }
+        AMMStorage::save_health_config(env, &config);
+        Ok(())
+    }
+
+    /// Get a pair's swap outcome tracking
+    pub fn get_pair_health(env: &Env, asset_a: &Address, asset_b: &Address) -> PairHealth {
+        AMMStorage::get_pair_health(env, asset_a, asset_b)
+    }
+
+    /// Record a swap outcome, deactivating the pair once its failure streak or failure
+    /// rate crosses the configured thresholds. Only an admin can reactivate it.
+    fn record_swap_outcome(env: &Env, pair: &AssetPair, failed: bool) {
+        let config = AMMStorage::get_health_config(env);
+        let mut health = AMMStorage::get_pair_health(env, &pair.asset_a, &pair.asset_b);
+        let now = env.ledger().timestamp();
+
+        if config.window > 0 && now.saturating_sub(health.window_start) >= config.window {
+            health.window_start = now;
+            health.window_swaps = 0;
+            health.window_failures = 0;
+        }
+        health.window_swaps += 1;
+        if failed {
+            health.consecutive_failures += 1;
+            health.window_failures += 1;
+        } else {
+            health.consecutive_failures = 0;
+        }
+
+        let streak_tripped = config.max_consecutive_failures > 0
+            && health.consecutive_failures >= config.max_consecutive_failures;
+        let rate_tripped = config.max_failure_rate_bps > 0
+            && health.window_swaps >= config.min_samples
+            && (health.window_failures as i128) * 10000
+                >= (health.window_swaps as i128) * config.max_failure_rate_bps;
+
+        if failed && (streak_tripped || rate_tripped) {
+            let mut deactivated = pair.clone();
+            deactivated.is_active = false;
+            deactivated.last_updated = now;
+            AMMStorage::save_pair(env, &deactivated);
+            health.auto_deactivated = true;
+
+            ProtocolEvent::SecurityIncident(soroban_sdk::String::from_str(
+                env,
+                "amm_pair_auto_deactivated",
+            ))
+            .emit(env);
+            env.events().publish(
+                (
+                    Symbol::new(env, "amm_pair_deactivated"),
+                    Symbol::new(env, "pair"),
+                ),
+                (
+                    pair.asset_a.clone(),
+                    pair.asset_b.clone(),
+                    Symbol::new(env, "consecutive_failures"),
+                    health.consecutive_failures,
+                    Symbol::new(env, "window_failures"),
+                    health.window_failures,
+                ),
+            );
+        }
+        AMMStorage::save_pair_health(env, &pair.asset_a, &pair.asset_b, &health);
+    }
+
     /// Get total number of registered pairs
     pub fn get_total_pairs(env: &Env) -> i128 {
         AMMStorage::get_pair_count(env)
     }
 
-    /// Execute a swap through registered AMM
+    /// Output the pair's adapter quotes for `amount_in`
+    fn quote_pair(
+        env: &Env,
+        pair: &AssetPair,
+        asset_in: &Address,
+        asset_out: &Address,
+        amount_in: i128,
+    ) -> Result<i128, ProtocolError> {
+        let quoted =
+            SwapAdapterClient::new(env, &pair.amm_address).quote(asset_in, asset_out, &amount_in);
+        if quoted <= 0 {
+            return Err(AMMError::SwapFailed.into());
+        }
+        Ok(quoted)
+    }
+
+    /// Quote a swap at the registered AMM, or the internal pool without one: the output
+    /// for `amount_in` after the standard protocol swap fee (loyalty discounts not applied)
+    pub fn quote_swap(
+        env: &Env,
+        asset_in: &Address,
+        asset_out: &Address,
+        amount_in: i128,
+    ) -> Result<i128, ProtocolError> {
+        if amount_in <= 0 {
+            return Err(AMMError::InvalidSwapParams.into());
+        }
+        let fee = (amount_in * AMM_FEE_BPS) / 10000;
+        match AMMStorage::get_pair(env, asset_in, asset_out).filter(|pair| pair.is_active) {
+            Some(pair) => Self::quote_pair(env, &pair, asset_in, asset_out, amount_in - fee),
+            None => InternalPools::quote(env, asset_in, asset_out, amount_in - fee)
+                .ok_or(AMMError::PairNotRegistered.into()),
+        }
+    }
+
+    /// Send `amount_in` of the contract's `asset_in` to the pair's adapter and verify that
+    /// the reported output, and at least `min_out`, of `asset_out` came back. Returns the
+    /// amount received.
+    fn swap_through_adapter(
+        env: &Env,
+        pair: &AssetPair,
+        asset_in: &Address,
+        asset_out: &Address,
+        amount_in: i128,
+        min_out: i128,
+    ) -> Result<i128, ProtocolError> {
+        let flow = Symbol::new(env, "amm_swap");
+        let contract = env.current_contract_address();
+        TransferEnforcer::transfer_out_asset(
+            env,
+            asset_in,
+            &pair.amm_address,
+            amount_in,
+            flow.clone(),
+        )?;
+        let before = TokenClient::new(env, asset_out).balance(&contract);
+        let reported = SwapAdapterClient::new(env, &pair.amm_address)
+            .swap(asset_in, asset_out, &amount_in, &min_out, &contract);
+        let received = TransferEnforcer::verify_received(
+            env,
+            asset_out,
+            &pair.amm_address,
+            before,
+            reported,
+            flow,
+        )?;
+        // Fee-on-transfer outputs may arrive short of the report but never below `min_out`
+        if received < min_out {
+            return Err(AMMError::SlippageExceeded.into());
+        }
+        Ok(received)
+    }
+
+    /// Swap the user's own tokens: `amount_in` is pulled from `params.user` and the output
+    /// is paid out to them
+    pub fn swap_for_user(env: &Env, params: SwapParams) -> Result<SwapResult, ProtocolError> {
+        params.user.require_auth();
+        if params.amount_in <= 0 {
+            return Err(AMMError::InvalidSwapParams.into());
+        }
+        let flow = Symbol::new(env, "amm_swap");
+        let received = TransferEnforcer::transfer_in_asset(
+            env,
+            &params.asset_in,
+            &params.user,
+            params.amount_in,
+            flow.clone(),
+        )?;
+        let user = params.user.clone();
+        let asset_out = params.asset_out.clone();
+        let asset_in = params.asset_in.clone();
+        let result = Self::execute_swap(
+            env,
+            SwapParams {
+                amount_in: received,
+                ..params
+            },
+        )?;
+        // A rejected swap hands the input back
+        let (payout, amount) = match result.status {
+            SwapStatus::Executed => (asset_out, result.amount_out),
+            SwapStatus::SlippageRejected => (asset_in, received),
+        };
+        TransferEnforcer::transfer_out_asset(env, &payout, &user, amount, flow)?;
+        Ok(result)
+    }
+
+    /// Execute a swap of tokens the contract holds through the pair's adapter, or the
+    /// internal pool when no active pair is registered; the output stays with the contract.
+    /// A pair quoting below `min_amount_out` returns `Ok` with a `SlippageRejected`
+    /// status, so the failure it records against the pair is kept.
     pub fn execute_swap(env: &Env, params: SwapParams) -> Result<SwapResult, ProtocolError> {
         ReentrancyGuard::enter(env)?;
         let result = (|| -> Result<SwapResult, ProtocolError> {
@@ -433,33 +796,78 @@ impl AMMRegistry {
                 return Err(AMMError::SwapFailed.into());
             }
 
-            // Get the pair
+            // Get the pair; without an active one the internal pool is the fallback
             let pair = AMMStorage::get_pair(env, &params.asset_in, &params.asset_out)
-                .ok_or(AMMError::PairNotRegistered)?;
-
-            if !pair.is_active {
-                return Err(AMMError::PairNotRegistered.into());
-            }
+                .filter(|pair| pair.is_active);
 
-            // In a real implementation, this would call the actual AMM contract
-            // For now, we simulate the swap result
-            let fee_bps = 30; // 0.3% fee
+            let fee_bps = FeeManager::amm_fee_bps(env, &params.user);
             let fee = (params.amount_in * fee_bps) / 10000;
             let amount_after_fee = params.amount_in - fee;
 
-            // Simulated exchange rate (1:1 for simplicity - in production would call AMM)
-            let amount_out = amount_after_fee;
-
-            // Check slippage
-            if amount_out < params.min_amount_out {
-                return Err(AMMError::SlippageExceeded.into());
-            }
+            let amount_out = match pair {
+                Some(pair) => {
+                    // Check slippage against the AMM's quote before moving tokens; a
+                    // venue returning too little counts against the pair
+                    let quoted = Self::quote_pair(
+                        env,
+                        &pair,
+                        &params.asset_in,
+                        &params.asset_out,
+                        amount_after_fee,
+                    )?;
+                    if quoted < params.min_amount_out {
+                        Self::record_swap_outcome(env, &pair, true);
+                        AnalyticsModule::record_error(
+                            env,
+                            "execute_swap",
+                            AMMError::SlippageExceeded.into(),
+                        );
+                        return Ok(SwapResult::rejected(
+                            params.amount_in,
+                            env.ledger().timestamp(),
+                        ));
+                    }
+                    let amount_out = Self::swap_through_adapter(
+                        env,
+                        &pair,
+                        &params.asset_in,
+                        &params.asset_out,
+                        amount_after_fee,
+                        params.min_amount_out,
+                    )?;
+                    Self::record_swap_outcome(env, &pair, false);
+                    amount_out
+                }
+                None => {
+                    let quoted = InternalPools::quote(
+                        env,
+                        &params.asset_in,
+                        &params.asset_out,
+                        amount_after_fee,
+                    )
+                    .ok_or(AMMError::PairNotRegistered)?;
+                    if quoted < params.min_amount_out {
+                        return Err(AMMError::SlippageExceeded.into());
+                    }
+                    InternalPools::swap(env, &params.asset_in, &params.asset_out, amount_after_fee)?
+                }
+            };
 
             let timestamp = env.ledger().timestamp();
             let swap_result = SwapResult::new(params.amount_in, amount_out, fee, timestamp);
 
-            // Store swap in history
+            // Store swap in the global and the pair's history
             AMMStorage::add_swap_to_history(env, &swap_result);
+            AMMStorage::append_pair_swap(
+                env,
+                &mut SwapRecord {
+                    seq: 0,
+                    user: params.user.clone(),
+                    asset_in: params.asset_in.clone(),
+                    asset_out: params.asset_out.clone(),
+                    result: swap_result.clone(),
+                },
+            );
 
             // Emit swap event (only in non-test environment)
             #[cfg(not(test))]
@@ -500,8 +908,11 @@ impl AMMRegistry {
         )
         .with_slippage(200); // 2% slippage tolerance for liquidations
 
-        // Execute the swap
+        // Execute the swap; a rejected one leaves the position as it is
         let swap_result = Self::execute_swap(env, params)?;
+        if swap_result.status != SwapStatus::Executed {
+            return Ok(swap_result);
+        }
 
         // Update user position with swap results
         if let Some(mut position) = StateHelper::get_position(env, liquidator) {
@@ -533,8 +944,11 @@ impl AMMRegistry {
         )
         .with_slippage(150); // 1.5% slippage tolerance for deleveraging
 
-        // Execute the swap
+        // Execute the swap; a rejected one leaves the position as it is
         let swap_result = Self::execute_swap(env, params)?;
+        if swap_result.status != SwapStatus::Executed {
+            return Ok(swap_result);
+        }
 
         // Update user position
         if let Some(mut position) = StateHelper::get_position(env, user) {
@@ -551,6 +965,39 @@ impl AMMRegistry {
         AMMStorage::get_swap_history(env)
     }
 
+    /// Page through a pair's swaps in either direction, oldest first, starting after
+    /// `cursor` (0 for the first page). At most `MAX_SWAP_HISTORY_PAGE` records per page.
+    pub fn get_swap_history_page(
+        env: &Env,
+        asset_a: &Address,
+        asset_b: &Address,
+        cursor: u64,
+        limit: u32,
+    ) -> Result<SwapHistoryPage, ProtocolError> {
+        if limit == 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let limit = limit.min(MAX_SWAP_HISTORY_PAGE);
+        let key = PairKey::new(asset_a.clone(), asset_b.clone());
+        let count = AMMStorage::get_pair_swap_count(env, &key);
+
+        let mut records = Vec::new(env);
+        let mut seq = cursor;
+        while seq < count && records.len() < limit {
+            seq += 1;
+            // Records whose TTL lapsed are skipped rather than ending the page
+            if let Some(record) = AMMStorage::get_pair_swap(env, &key, seq) {
+                records.push_back(record);
+            }
+        }
+
+        let next_cursor = if seq < count { Some(seq) } else { None };
+        Ok(SwapHistoryPage {
+            records,
+            next_cursor,
+        })
+    }
+
     /// Get all registered pairs
     pub fn get_all_pairs(env: &Env) -> Vec<AssetPair> {
         let pairs_map = AMMStorage::get_all_pairs(env);
@@ -567,6 +1014,7 @@ impl AMMRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test::{MockAmm, MockToken, MockTokenClient};
     use crate::Contract;
     use soroban_sdk::{testutils::Address as _, Address, Env};
 
@@ -577,13 +1025,20 @@ mod tests {
         (env, contract_id)
     }
 
+    /// A token with a balance for `holder`, so swaps move real funds
+    fn funded_asset(env: &Env, holder: &Address) -> Address {
+        let asset = env.register(MockToken, ());
+        MockTokenClient::new(env, &asset).mint(holder, &100_000_000);
+        asset
+    }
+
     #[test]
     fn test_register_amm_pair() {
         let (env, contract_id) = create_test_env();
 
         let asset_a = Address::generate(&env);
         let asset_b = Address::generate(&env);
-        let amm_address = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
 
         env.as_contract(&contract_id, || {
             // Register pair
@@ -610,7 +1065,7 @@ mod tests {
 
         let asset_a = Address::generate(&env);
         let asset_b = Address::generate(&env);
-        let amm_address = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
 
         env.as_contract(&contract_id, || {
             // Register pair first time
@@ -641,7 +1096,7 @@ mod tests {
 
         let asset_a = Address::generate(&env);
         let asset_b = Address::generate(&env);
-        let amm_address = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
 
         env.as_contract(&contract_id, || {
             // Register in one order
@@ -668,9 +1123,9 @@ mod tests {
         let (env, contract_id) = create_test_env();
 
         let user = Address::generate(&env);
-        let asset_in = Address::generate(&env);
-        let asset_out = Address::generate(&env);
-        let amm_address = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
+        let asset_in = funded_asset(&env, &contract_id);
+        let asset_out = funded_asset(&env, &amm_address);
 
         env.as_contract(&contract_id, || {
             // Register pair
@@ -708,9 +1163,9 @@ mod tests {
         let (env, contract_id) = create_test_env();
 
         let liquidator = Address::generate(&env);
-        let collateral_asset = Address::generate(&env);
-        let debt_asset = Address::generate(&env);
-        let amm_address = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
+        let collateral_asset = funded_asset(&env, &contract_id);
+        let debt_asset = funded_asset(&env, &amm_address);
 
         env.as_contract(&contract_id, || {
             // Register pair
@@ -754,9 +1209,9 @@ mod tests {
         let (env, contract_id) = create_test_env();
 
         let user = Address::generate(&env);
-        let asset_in = Address::generate(&env);
-        let asset_out = Address::generate(&env);
-        let amm_address = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
+        let asset_in = funded_asset(&env, &contract_id);
+        let asset_out = funded_asset(&env, &amm_address);
 
         env.as_contract(&contract_id, || {
             // Register pair
@@ -788,4 +1243,133 @@ mod tests {
             assert_eq!(history.len(), 3);
         });
     }
+
+    #[test]
+    fn test_swap_history_page_per_pair() {
+        let (env, contract_id) = create_test_env();
+
+        let user = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
+        let asset_in = funded_asset(&env, &contract_id);
+        let asset_out = funded_asset(&env, &amm_address);
+        let other_out = funded_asset(&env, &amm_address);
+
+        env.as_contract(&contract_id, || {
+            for out in [&asset_out, &other_out] {
+                AMMRegistry::register_pair(
+                    &env,
+                    asset_in.clone(),
+                    out.clone(),
+                    amm_address.clone(),
+                    None,
+                )
+                .unwrap();
+            }
+            for amount in 1..=3 {
+                let params = SwapParams::new(
+                    user.clone(),
+                    asset_in.clone(),
+                    asset_out.clone(),
+                    amount * 10_000,
+                    0,
+                );
+                AMMRegistry::execute_swap(&env, params).unwrap();
+            }
+            let params =
+                SwapParams::new(user.clone(), asset_in.clone(), other_out.clone(), 10_000, 0);
+            AMMRegistry::execute_swap(&env, params).unwrap();
+
+            // Pages are per pair and either asset order finds the same history
+            let page =
+                AMMRegistry::get_swap_history_page(&env, &asset_out, &asset_in, 0, 2).unwrap();
+            assert_eq!(page.records.len(), 2);
+            assert_eq!(page.records.get(0).unwrap().seq, 1);
+            assert_eq!(page.next_cursor, Some(2));
+            let page =
+                AMMRegistry::get_swap_history_page(&env, &asset_in, &asset_out, 2, 2).unwrap();
+            assert_eq!(page.records.len(), 1);
+            let last = page.records.get(0).unwrap();
+            assert_eq!((last.seq, last.result.amount_in), (3, 30_000));
+            assert_eq!(page.next_cursor, None);
+
+            let page =
+                AMMRegistry::get_swap_history_page(&env, &asset_in, &other_out, 0, 100).unwrap();
+            assert_eq!(page.records.len(), 1);
+            assert_eq!(
+                AMMRegistry::get_swap_history_page(&env, &asset_in, &other_out, 0, 0),
+                Err(ProtocolError::InvalidParameters)
+            );
+        });
+    }
+
+    #[test]
+    fn test_repeated_swap_failures_deactivate_pair() {
+        let (env, contract_id) = create_test_env();
+
+        let user = Address::generate(&env);
+        let amm_address = env.register(MockAmm, ());
+        let asset_in = funded_asset(&env, &contract_id);
+        let asset_out = funded_asset(&env, &amm_address);
+
+        env.as_contract(&contract_id, || {
+            AMMRegistry::register_pair(
+                &env,
+                asset_in.clone(),
+                asset_out.clone(),
+                amm_address,
+                None,
+            )
+            .unwrap();
+            AMMRegistry::set_health_config(
+                &env,
+                PairHealthConfig {
+                    max_consecutive_failures: 3,
+                    max_failure_rate_bps: 0,
+                    window: 3600,
+                    min_samples: 1,
+                },
+            )
+            .unwrap();
+
+            let swap = |min_out: i128| {
+                AMMRegistry::execute_swap(
+                    &env,
+                    SwapParams::new(
+                        user.clone(),
+                        asset_in.clone(),
+                        asset_out.clone(),
+                        1_000_000,
+                        min_out,
+                    ),
+                )
+            };
+
+            // A success in between resets the streak
+            let rejected = |min_out| swap(min_out).unwrap().status == SwapStatus::SlippageRejected;
+            assert!(rejected(1_000_000));
+            assert!(rejected(1_000_000));
+            assert!(!rejected(900_000));
+            for _ in 0..3 {
+                assert!(rejected(1_000_000));
+            }
+
+            assert!(!AMMRegistry::is_pair_registered(
+                &env, &asset_in, &asset_out
+            ));
+            let health = AMMRegistry::get_pair_health(&env, &asset_in, &asset_out);
+            assert!(health.auto_deactivated);
+            assert_eq!(health.consecutive_failures, 3);
+            assert_eq!(
+                swap(900_000).unwrap_err(),
+                ProtocolError::AmmPairNotRegistered
+            );
+
+            AMMRegistry::activate_pair(&env, &asset_in, &asset_out).unwrap();
+            assert_eq!(
+                AMMRegistry::get_pair_health(&env, &asset_in, &asset_out),
+                PairHealth::default()
+            );
+            assert!(swap(900_000).is_ok());
+        });
+    }
 }
@@ -0,0 +1,135 @@
+//! Amortization module for StellarLend protocol
+//! Read-only term loan schedule previews computed from current rates
+
+use crate::{InterestRateStorage, ProtocolError};
+use soroban_sdk::{contracttype, Env, Vec};
+
+/// Rate scale (1.0 = 1e8)
+const RATE_SCALE: i128 = 100000000;
+
+/// Seconds per year used for rate normalization
+const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
+
+/// Upper bound on installments to keep previews cheap
+const MAX_INSTALLMENTS: u32 = 360;
+
+/// Which current rate the preview is priced with
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RateMode {
+    /// Instantaneous borrow rate
+    Variable,
+    /// Smoothed borrow rate
+    Smoothed,
+}
+
+/// Single projected installment
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Installment {
+    pub index: u32,
+    pub due_date: u64,
+    pub principal: i128,
+    pub interest: i128,
+    /// Principal still outstanding after this installment
+    pub remaining_principal: i128,
+}
+
+/// Full projected schedule for a term loan
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AmortizationSchedule {
+    pub principal: i128,
+    pub term: u64,
+    /// Annual rate used for the projection (scaled by 1e8)
+    pub annual_rate: i128,
+    pub installments: Vec<Installment>,
+    pub total_interest: i128,
+    pub total_cost: i128,
+}
+
+/// Term loan schedule calculator
+pub struct Amortization;
+
+impl Amortization {
+    /// Project an equal-installment schedule for `principal` over `term` seconds.
+    /// Nothing is written to storage.
+    pub fn preview_term_loan(
+        env: &Env,
+        principal: i128,
+        term: u64,
+        installments: u32,
+        rate_mode: RateMode,
+    ) -> Result<AmortizationSchedule, ProtocolError> {
+        if principal <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if installments == 0 || installments > MAX_INSTALLMENTS || term < installments as u64 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let state = InterestRateStorage::get_state(env);
+        let annual_rate = match rate_mode {
+            RateMode::Variable => state.current_borrow_rate,
+            RateMode::Smoothed => state.smoothed_borrow_rate,
+        };
+
+        let period = term / installments as u64;
+        let period_rate = annual_rate
+            .saturating_mul(period as i128)
+            .saturating_div(SECONDS_PER_YEAR);
+        let payment = Self::level_payment(principal, period_rate, installments);
+
+        let start = env.ledger().timestamp();
+        let mut schedule = Vec::new(env);
+        let mut remaining = principal;
+        let mut total_interest = 0i128;
+        for index in 0..installments {
+            let interest = remaining.saturating_mul(period_rate) / RATE_SCALE;
+            // The final installment clears any rounding remainder
+            let principal_part = if index + 1 == installments {
+                remaining
+            } else {
+                (payment - interest).clamp(0, remaining)
+            };
+            remaining -= principal_part;
+            total_interest += interest;
+            schedule.push_back(Installment {
+                index,
+                due_date: start + period * (index as u64 + 1),
+                principal: principal_part,
+                interest,
+                remaining_principal: remaining,
+            });
+        }
+
+        Ok(AmortizationSchedule {
+            principal,
+            term,
+            annual_rate,
+            installments: schedule,
+            total_interest,
+            total_cost: principal + total_interest,
+        })
+    }
+
+    /// Annuity payment: P * r * (1+r)^n / ((1+r)^n - 1), or P / n at a zero rate
+    fn level_payment(principal: i128, period_rate: i128, installments: u32) -> i128 {
+        let n = installments as i128;
+        if period_rate <= 0 {
+            return (principal + n - 1) / n;
+        }
+        let mut growth = RATE_SCALE;
+        for _ in 0..installments {
+            growth = growth.saturating_mul(RATE_SCALE + period_rate) / RATE_SCALE;
+        }
+        let denominator = growth - RATE_SCALE;
+        if denominator <= 0 {
+            return (principal + n - 1) / n;
+        }
+        principal
+            .saturating_mul(period_rate)
+            .saturating_mul(growth)
+            .saturating_div(RATE_SCALE.saturating_mul(denominator))
+    }
+}
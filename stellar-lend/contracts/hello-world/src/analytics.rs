@@ -11,7 +11,14 @@
 use core::cmp::min;
 use soroban_sdk::{contracterror, contracttype, vec, Address, Env, Map, String, Symbol, Vec};
 
-use crate::{ProtocolError, ProtocolEvent};
+use crate::persistent::PersistentStore;
+use crate::solvency::Solvency;
+use crate::tvl_cap::TvlCapStorage;
+use crate::watchdog::Watchdog;
+use crate::{
+    EmergencyFund, EmergencyStorage, InterestRateState, InterestRateStorage, ProtocolConfig,
+    ProtocolError, ProtocolEvent, TokenRegistry,
+};
 
 /// Analytics-specific error types
 #[contracterror]
@@ -19,22 +26,22 @@ use crate::{ProtocolError, ProtocolEvent};
 #[repr(u32)]
 pub enum AnalyticsError {
     /// Invalid time range for analytics query
-    InvalidTimeRange = 1,
+    InvalidTimeRange = 6001,
     /// Analytics data not found
-    DataNotFound = 2,
+    DataNotFound = 6002,
     /// Invalid analytics parameters
-    InvalidParameters = 3,
+    InvalidParameters = 6003,
     /// Analytics storage limit exceeded
-    StorageLimitExceeded = 4,
+    StorageLimitExceeded = 6004,
     /// Unauthorized access to analytics data
-    UnauthorizedAccess = 5,
+    UnauthorizedAccess = 6005,
 }
 
 impl From<AnalyticsError> for ProtocolError {
     fn from(err: AnalyticsError) -> Self {
         match err {
-            AnalyticsError::InvalidTimeRange => ProtocolError::InvalidParameters,
-            AnalyticsError::DataNotFound => ProtocolError::NotFound,
+            AnalyticsError::InvalidTimeRange => ProtocolError::AnalyticsInvalidTimeRange,
+            AnalyticsError::DataNotFound => ProtocolError::AnalyticsDataNotFound,
             AnalyticsError::InvalidParameters => ProtocolError::InvalidParameters,
             AnalyticsError::StorageLimitExceeded => ProtocolError::StorageLimitExceeded,
             AnalyticsError::UnauthorizedAccess => ProtocolError::Unauthorized,
@@ -201,15 +208,23 @@ impl AssetAnalytics {
     }
 }
 
-/// Historical data point
+/// Daily protocol snapshot taken by keepers
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
-pub struct HistoricalDataPoint {
-    /// Timestamp of the data point
+pub struct ProtocolSnapshot {
+    /// Day index (timestamp / 86400)
+    pub day: u64,
+    /// Timestamp the snapshot was taken
     pub timestamp: u64,
+    /// Primary pool state (utilization, rates, totals)
+    pub pool_state: InterestRateState,
+    /// Emergency fund reserves
+    pub reserves: EmergencyFund,
     /// Protocol metrics at this time
     pub metrics: ProtocolMetrics,
-    /// Asset-specific data
+    /// Risk analytics at this time
+    pub risk: RiskAnalytics,
+    /// Supply, borrows, utilization and rate of each registered asset and market
     pub asset_data: Map<Address, AssetAnalytics>,
 }
 
@@ -283,6 +298,67 @@ impl PerformanceMetrics {
     }
 }
 
+/// Compact record of a single liquidation, kept for risk calibration replays
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationRecord {
+    /// Sequential record id (doubles as the pagination cursor)
+    pub id: u64,
+    /// Liquidation timestamp
+    pub timestamp: u64,
+    /// Liquidator address
+    pub liquidator: Address,
+    /// Liquidated user address
+    pub user: Address,
+    /// Debt repaid by the liquidator
+    pub debt_repaid: i128,
+    /// Collateral seized including the bonus
+    pub collateral_seized: i128,
+    /// Latest oracle price of the collateral asset (scaled by 1e8, 0 if none recorded)
+    pub collateral_price: i128,
+    /// Latest oracle price of the debt asset (scaled by 1e8, 0 if none recorded)
+    pub debt_price: i128,
+    /// Minimum collateral ratio in force at liquidation time (percent)
+    pub collateral_factor: i128,
+    /// Liquidation incentive in force at liquidation time (scaled by 1e8)
+    pub liquidation_bonus: i128,
+    /// Debt left uncovered by remaining collateral after the liquidation
+    pub shortfall: i128,
+}
+
+/// Page of liquidation records returned by history queries
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationHistoryPage {
+    /// Matching records ordered oldest first
+    pub records: Vec<LiquidationRecord>,
+    /// Cursor to pass to the next query, if more records may match
+    pub next_cursor: Option<u64>,
+}
+
+/// Failure count for one entrypoint and error code
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ErrorStat {
+    /// Entrypoint that failed (`overflow` once a bucket's distinct keys are exhausted)
+    pub entrypoint: Symbol,
+    /// `ProtocolError` code
+    pub code: u32,
+    /// Failures within the queried window
+    pub count: u64,
+}
+
+/// Error stats are bucketed hourly
+const ERROR_BUCKET_SECONDS: u64 = 3600;
+/// Buckets kept (7 days); also the widest queryable window
+const ERROR_BUCKET_RETENTION: u64 = 168;
+/// Distinct (entrypoint, code) pairs tracked per bucket
+const MAX_ERROR_KEYS: u32 = 32;
+/// Liquidation records kept; appending past this drops the oldest
+const MAX_LIQUIDATION_RECORDS: u64 = 1000;
+/// Most recent liquidation records refreshed by `bump_storage`
+const MAX_BUMPED_LIQUIDATION_RECORDS: u64 = 100;
+
 /// Analytics storage management
 pub struct AnalyticsStorage;
 
@@ -291,14 +367,23 @@ impl AnalyticsStorage {
     fn protocol_metrics_key(env: &Env) -> Symbol {
         Symbol::new(env, "protocol_metrics")
     }
-    fn user_analytics_key(env: &Env) -> Symbol {
-        Symbol::new(env, "user_analytics")
+    fn user_analytics_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "user_analytics"), user.clone())
+    }
+    fn analytics_users_key(env: &Env) -> Symbol {
+        Symbol::new(env, "analytics_users")
     }
     fn asset_analytics_key(env: &Env) -> Symbol {
         Symbol::new(env, "asset_analytics")
     }
-    fn historical_data_key(env: &Env) -> Symbol {
-        Symbol::new(env, "historical_data")
+    fn snapshot_key(env: &Env, day: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "snapshot"), day)
+    }
+    fn snapshot_days_key(env: &Env) -> Symbol {
+        Symbol::new(env, "snapshot_days")
+    }
+    fn snapshot_retention_key(env: &Env) -> Symbol {
+        Symbol::new(env, "snapshot_retention")
     }
     fn risk_analytics_key(env: &Env) -> Symbol {
         Symbol::new(env, "risk_analytics")
@@ -309,6 +394,18 @@ impl AnalyticsStorage {
     fn activity_log_key(env: &Env) -> Symbol {
         Symbol::new(env, "activity_log")
     }
+    fn liquidation_record_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "liq_record"), id)
+    }
+    fn liquidation_count_key(env: &Env) -> Symbol {
+        Symbol::new(env, "liq_record_count")
+    }
+    fn error_bucket_key(env: &Env, bucket: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "err_bucket"), bucket)
+    }
+    fn error_buckets_key(env: &Env) -> Symbol {
+        Symbol::new(env, "err_buckets")
+    }
 
     // Protocol metrics
     pub fn get_protocol_metrics(env: &Env) -> ProtocolMetrics {
@@ -324,43 +421,72 @@ impl AnalyticsStorage {
             .set(&Self::protocol_metrics_key(env), metrics);
     }
 
-    // User analytics
+    // User analytics (persistent, one entry per user plus an index of tracked users)
+    pub fn get_analytics_users(env: &Env) -> Vec<Address> {
+        PersistentStore::get(env, &Self::analytics_users_key(env)).unwrap_or_else(|| vec![env])
+    }
+
+    /// All tracked users' analytics, assembled from the per-user entries
     pub fn get_user_analytics(env: &Env) -> Map<Address, UserAnalytics> {
-        env.storage()
-            .instance()
-            .get(&Self::user_analytics_key(env))
-            .unwrap_or_else(|| Map::new(env))
+        let mut analytics_map = Map::new(env);
+        for user in Self::get_analytics_users(env).iter() {
+            if let Some(analytics) = Self::find_user_analytics(env, &user) {
+                analytics_map.set(user, analytics);
+            }
+        }
+        analytics_map
     }
 
-    pub fn put_user_analytics(env: &Env, analytics: &Map<Address, UserAnalytics>) {
-        env.storage()
-            .instance()
-            .set(&Self::user_analytics_key(env), analytics);
+    pub fn find_user_analytics(env: &Env, user: &Address) -> Option<UserAnalytics> {
+        PersistentStore::get(env, &Self::user_analytics_key(env, user))
     }
 
     pub fn get_user_analytics_for_user(env: &Env, user: &Address) -> UserAnalytics {
-        let analytics_map = Self::get_user_analytics(env);
-        analytics_map.get(user.clone()).unwrap_or_default()
+        Self::find_user_analytics(env, user).unwrap_or_default()
     }
 
     pub fn update_user_analytics(env: &Env, user: &Address, analytics: &UserAnalytics) {
-        let mut analytics_map = Self::get_user_analytics(env);
-        analytics_map.set(user.clone(), analytics.clone());
-        Self::put_user_analytics(env, &analytics_map);
+        let key = Self::user_analytics_key(env, user);
+        if !env.storage().persistent().has(&key) {
+            let mut users = Self::get_analytics_users(env);
+            users.push_back(user.clone());
+            PersistentStore::set(env, &Self::analytics_users_key(env), &users);
+        }
+        PersistentStore::set(env, &key, analytics);
+    }
+
+    /// Extend the TTL of a user's analytics entry, returning how many exist
+    pub fn bump_user(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::user_analytics_key(env, user)) as u32
+    }
+
+    /// Extend the TTL of the shared analytics entries, retained snapshots and error buckets,
+    /// and the most recent liquidation records, returning how many exist
+    pub fn bump_shared(env: &Env) -> u32 {
+        let mut bumped = PersistentStore::bump(env, &Self::analytics_users_key(env)) as u32
+            + PersistentStore::bump(env, &Self::activity_log_key(env)) as u32
+            + PersistentStore::bump(env, &Self::asset_analytics_key(env)) as u32
+            + PersistentStore::bump(env, &Self::liquidation_count_key(env)) as u32;
+        for day in Self::get_snapshot_days(env).iter() {
+            bumped += PersistentStore::bump(env, &Self::snapshot_key(env, day)) as u32;
+        }
+        for bucket in Self::get_error_buckets(env).iter() {
+            bumped += PersistentStore::bump(env, &Self::error_bucket_key(env, bucket)) as u32;
+        }
+        let count = Self::get_liquidation_count(env);
+        for id in count.saturating_sub(MAX_BUMPED_LIQUIDATION_RECORDS) + 1..=count {
+            bumped += PersistentStore::bump(env, &Self::liquidation_record_key(env, id)) as u32;
+        }
+        bumped
     }
 
     // Asset analytics
     pub fn get_asset_analytics(env: &Env) -> Map<Address, AssetAnalytics> {
-        env.storage()
-            .instance()
-            .get(&Self::asset_analytics_key(env))
-            .unwrap_or_else(|| Map::new(env))
+        PersistentStore::get(env, &Self::asset_analytics_key(env)).unwrap_or_else(|| Map::new(env))
     }
 
     pub fn _put_asset_analytics(env: &Env, analytics: &Map<Address, AssetAnalytics>) {
-        env.storage()
-            .instance()
-            .set(&Self::asset_analytics_key(env), analytics);
+        PersistentStore::set(env, &Self::asset_analytics_key(env), analytics);
     }
 
     pub fn get_asset_analytics_for_asset(env: &Env, asset: &Address) -> AssetAnalytics {
@@ -376,18 +502,43 @@ impl AnalyticsStorage {
         Self::_put_asset_analytics(env, &analytics_map);
     }
 
-    // Historical data
-    pub fn get_historical_data(env: &Env) -> Map<u64, HistoricalDataPoint> {
+    // Daily snapshots (persistent storage, index of retained days kept oldest first)
+    pub fn get_snapshot(env: &Env, day: u64) -> Option<ProtocolSnapshot> {
+        PersistentStore::get(env, &Self::snapshot_key(env, day))
+    }
+
+    pub fn put_snapshot(env: &Env, snapshot: &ProtocolSnapshot) {
+        PersistentStore::set(env, &Self::snapshot_key(env, snapshot.day), snapshot);
+    }
+
+    pub fn remove_snapshot(env: &Env, day: u64) {
+        PersistentStore::remove(env, &Self::snapshot_key(env, day));
+    }
+
+    pub fn get_snapshot_days(env: &Env) -> Vec<u64> {
         env.storage()
             .instance()
-            .get(&Self::historical_data_key(env))
-            .unwrap_or_else(|| Map::new(env))
+            .get(&Self::snapshot_days_key(env))
+            .unwrap_or_else(|| Vec::new(env))
     }
 
-    pub fn put_historical_data(env: &Env, data: &Map<u64, HistoricalDataPoint>) {
+    pub fn put_snapshot_days(env: &Env, days: &Vec<u64>) {
         env.storage()
             .instance()
-            .set(&Self::historical_data_key(env), data);
+            .set(&Self::snapshot_days_key(env), days);
+    }
+
+    pub fn get_snapshot_retention(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::snapshot_retention_key(env))
+            .unwrap_or(30)
+    }
+
+    pub fn put_snapshot_retention(env: &Env, retention: u32) {
+        env.storage()
+            .instance()
+            .set(&Self::snapshot_retention_key(env), &retention);
     }
 
     // Risk analytics
@@ -420,16 +571,68 @@ impl AnalyticsStorage {
 
     // Activity log
     pub fn get_activity_log(env: &Env) -> Vec<ActivityLogEntry> {
+        PersistentStore::get(env, &Self::activity_log_key(env)).unwrap_or_else(|| vec![env])
+    }
+
+    pub fn put_activity_log(env: &Env, log: &Vec<ActivityLogEntry>) {
+        PersistentStore::set(env, &Self::activity_log_key(env), log);
+    }
+
+    // Liquidation history
+    pub fn get_liquidation_count(env: &Env) -> u64 {
+        PersistentStore::get(env, &Self::liquidation_count_key(env)).unwrap_or(0)
+    }
+
+    pub fn get_liquidation_record(env: &Env, id: u64) -> Option<LiquidationRecord> {
+        PersistentStore::get(env, &Self::liquidation_record_key(env, id))
+    }
+
+    /// Append a record, assigning it the next sequential id and dropping the oldest
+    /// record beyond the retention limit
+    pub fn append_liquidation_record(env: &Env, record: &mut LiquidationRecord) {
+        let id = Self::get_liquidation_count(env) + 1;
+        record.id = id;
+        PersistentStore::set(env, &Self::liquidation_record_key(env, id), record);
+        PersistentStore::set(env, &Self::liquidation_count_key(env), &id);
+        if id > MAX_LIQUIDATION_RECORDS {
+            PersistentStore::remove(
+                env,
+                &Self::liquidation_record_key(env, id - MAX_LIQUIDATION_RECORDS),
+            );
+        }
+    }
+
+    // Error stats
+    pub fn get_error_bucket(env: &Env, bucket: u64) -> Vec<ErrorStat> {
+        PersistentStore::get(env, &Self::error_bucket_key(env, bucket))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn put_error_bucket(env: &Env, bucket: u64, stats: &Vec<ErrorStat>) {
+        PersistentStore::set(env, &Self::error_bucket_key(env, bucket), stats);
+    }
+
+    pub fn get_error_buckets(env: &Env) -> Vec<u64> {
         env.storage()
             .instance()
-            .get(&Self::activity_log_key(env))
-            .unwrap_or_else(|| vec![env])
+            .get(&Self::error_buckets_key(env))
+            .unwrap_or_else(|| Vec::new(env))
     }
 
-    pub fn put_activity_log(env: &Env, log: &Vec<ActivityLogEntry>) {
+    /// Register a new bucket and drop those past retention
+    pub fn open_error_bucket(env: &Env, bucket: u64) {
+        let mut kept = Vec::new(env);
+        for existing in Self::get_error_buckets(env).iter() {
+            if existing + ERROR_BUCKET_RETENTION <= bucket {
+                PersistentStore::remove(env, &Self::error_bucket_key(env, existing));
+            } else {
+                kept.push_back(existing);
+            }
+        }
+        kept.push_back(bucket);
         env.storage()
             .instance()
-            .set(&Self::activity_log_key(env), log);
+            .set(&Self::error_buckets_key(env), &kept);
     }
 }
 
@@ -609,34 +812,120 @@ impl AnalyticsModule {
 
         AnalyticsStorage::put_protocol_metrics(env, &metrics);
 
-        // Store historical snapshot (daily)
-        Self::store_historical_snapshot(env, &metrics)?;
-
         Ok(())
     }
 
-    /// Store historical snapshot
-    fn store_historical_snapshot(
-        env: &Env,
-        metrics: &ProtocolMetrics,
-    ) -> Result<(), ProtocolError> {
+    /// Capture today's protocol snapshot and prune snapshots beyond the retention count.
+    /// Intended to be called once per day by a keeper; returns the day index.
+    pub fn snapshot_protocol(env: &Env) -> Result<u64, ProtocolError> {
         let timestamp = env.ledger().timestamp();
-        let day_bucket = timestamp / 86400; // Daily buckets
+        let day = timestamp / 86400; // Daily buckets
 
-        let mut historical_data = AnalyticsStorage::get_historical_data(env);
+        if AnalyticsStorage::get_snapshot(env, day).is_some() {
+            return Err(ProtocolError::AlreadyExists);
+        }
 
-        let data_point = HistoricalDataPoint {
+        let snapshot = ProtocolSnapshot {
+            day,
             timestamp,
-            metrics: metrics.clone(),
-            asset_data: Map::new(env), // Will be populated separately
+            pool_state: InterestRateStorage::get_state(env),
+            reserves: EmergencyStorage::get(env).fund,
+            metrics: AnalyticsStorage::get_protocol_metrics(env),
+            risk: AnalyticsStorage::get_risk_analytics(env),
+            asset_data: Self::asset_snapshot(env),
         };
+        AnalyticsStorage::put_snapshot(env, &snapshot);
+        Watchdog::heartbeat(env, "keeper");
+
+        let mut days = AnalyticsStorage::get_snapshot_days(env);
+        days.push_back(day);
+        Self::prune_snapshots(env, &mut days);
+        AnalyticsStorage::put_snapshot_days(env, &days);
+
+        env.events().publish(
+            (
+                Symbol::new(env, "protocol_snapshot"),
+                Symbol::new(env, "day"),
+            ),
+            (
+                Symbol::new(env, "day"),
+                day,
+                Symbol::new(env, "timestamp"),
+                timestamp,
+            ),
+        );
+
+        Ok(day)
+    }
 
-        historical_data.set(day_bucket, data_point);
-        AnalyticsStorage::put_historical_data(env, &historical_data);
+    /// Current market totals of every registered asset and every asset with its own market.
+    /// The primary asset adds its single-asset positions: supply is its tracked TVL and
+    /// borrows include their outstanding debt.
+    fn asset_snapshot(env: &Env) -> Map<Address, AssetAnalytics> {
+        let primary = TokenRegistry::require_primary_asset(env).ok();
+        let mut assets = TokenRegistry::list_assets(env).values();
+        for market in InterestRateStorage::get_markets(env).iter() {
+            if !assets.contains(&market) {
+                assets.push_back(market);
+            }
+        }
+        let mut data = Map::new(env);
+        for asset in assets.iter() {
+            let state = InterestRateStorage::get_market_state(env, &asset);
+            let mut analytics = AnalyticsStorage::get_asset_analytics_for_asset(env, &asset);
+            if primary.as_ref() == Some(&asset) {
+                analytics.total_supply = TvlCapStorage::get_asset_tvl(env, &asset);
+                analytics.total_borrows =
+                    state.total_borrowed + Solvency::primary_debt(env, &asset);
+                analytics.utilization_rate = if analytics.total_supply > 0 {
+                    analytics.total_borrows.saturating_mul(100000000) / analytics.total_supply
+                } else {
+                    0
+                };
+            } else {
+                analytics.total_supply = state.total_supplied;
+                analytics.total_borrows = state.total_borrowed;
+                analytics.utilization_rate = state.utilization_rate;
+            }
+            analytics.interest_rate = state.current_borrow_rate;
+            analytics.last_update = env.ledger().timestamp();
+            data.set(asset, analytics);
+        }
+        data
+    }
+
+    /// Set how many daily snapshots are retained (admin only)
+    pub fn set_snapshot_retention(
+        env: &Env,
+        caller: &Address,
+        retention: u32,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if retention == 0 {
+            return Err(AnalyticsError::InvalidParameters.into());
+        }
+        AnalyticsStorage::put_snapshot_retention(env, retention);
 
+        let mut days = AnalyticsStorage::get_snapshot_days(env);
+        Self::prune_snapshots(env, &mut days);
+        AnalyticsStorage::put_snapshot_days(env, &days);
         Ok(())
     }
 
+    fn prune_snapshots(env: &Env, days: &mut Vec<u64>) {
+        let retention = AnalyticsStorage::get_snapshot_retention(env);
+        while days.len() > retention {
+            if let Some(oldest) = days.pop_front() {
+                AnalyticsStorage::remove_snapshot(env, oldest);
+            }
+        }
+    }
+
+    /// Get the snapshot for a day index
+    pub fn get_snapshot(env: &Env, day: u64) -> Result<ProtocolSnapshot, ProtocolError> {
+        AnalyticsStorage::get_snapshot(env, day).ok_or_else(|| AnalyticsError::DataNotFound.into())
+    }
+
     /// Get comprehensive protocol report
     pub fn get_protocol_report(env: &Env) -> Result<ProtocolReport, ProtocolError> {
         let protocol_metrics = AnalyticsStorage::get_protocol_metrics(env);
@@ -688,13 +977,13 @@ impl AnalyticsModule {
     /// Get asset-specific report
     pub fn get_asset_report(env: &Env, asset: &Address) -> Result<AssetReport, ProtocolError> {
         let asset_analytics = AnalyticsStorage::get_asset_analytics_for_asset(env, asset);
-        let historical_data = AnalyticsStorage::get_historical_data(env);
-
-        // Get historical data for this asset
+        // Get historical data for this asset from retained daily snapshots
         let mut asset_history = Map::new(env);
-        for (timestamp, data_point) in historical_data.iter() {
-            if let Some(asset_data) = data_point.asset_data.get(asset.clone()) {
-                asset_history.set(timestamp, asset_data);
+        for day in AnalyticsStorage::get_snapshot_days(env).iter() {
+            if let Some(snapshot) = AnalyticsStorage::get_snapshot(env, day) {
+                if let Some(asset_data) = snapshot.asset_data.get(asset.clone()) {
+                    asset_history.set(day, asset_data);
+                }
             }
         }
 
@@ -775,6 +1064,170 @@ impl AnalyticsModule {
         Ok(())
     }
 
+    /// Persist a liquidation record for later risk calibration
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_liquidation(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        debt_repaid: i128,
+        collateral_seized: i128,
+        collateral_price: i128,
+        debt_price: i128,
+        collateral_factor: i128,
+        liquidation_bonus: i128,
+        shortfall: i128,
+    ) -> u64 {
+        let mut record = LiquidationRecord {
+            id: 0,
+            timestamp: env.ledger().timestamp(),
+            liquidator: liquidator.clone(),
+            user: user.clone(),
+            debt_repaid,
+            collateral_seized,
+            collateral_price,
+            debt_price,
+            collateral_factor,
+            liquidation_bonus,
+            shortfall,
+        };
+        AnalyticsStorage::append_liquidation_record(env, &mut record);
+        record.id
+    }
+
+    /// Page through liquidation records within `[from_ts, to_ts]`, starting after `cursor`.
+    /// Pass `cursor = 0` for the first page; each page scans at most 200 records. Only the
+    /// latest 1000 records are kept.
+    pub fn get_liquidation_history(
+        env: &Env,
+        from_ts: u64,
+        to_ts: u64,
+        cursor: u64,
+    ) -> Result<LiquidationHistoryPage, ProtocolError> {
+        if to_ts < from_ts {
+            return Err(AnalyticsError::InvalidTimeRange.into());
+        }
+
+        const PAGE_SIZE: u32 = 50;
+        const SCAN_LIMIT: u64 = 200;
+
+        let count = AnalyticsStorage::get_liquidation_count(env);
+        let mut records = Vec::new(env);
+        let mut id = cursor.max(count.saturating_sub(MAX_LIQUIDATION_RECORDS));
+        let mut scanned = 0;
+
+        while id < count && records.len() < PAGE_SIZE && scanned < SCAN_LIMIT {
+            id += 1;
+            scanned += 1;
+            if let Some(record) = AnalyticsStorage::get_liquidation_record(env, id) {
+                // Records are appended in time order, so nothing later can match
+                if record.timestamp > to_ts {
+                    id = count;
+                    break;
+                }
+                if record.timestamp >= from_ts {
+                    records.push_back(record);
+                }
+            }
+        }
+
+        let next_cursor = if id < count { Some(id) } else { None };
+        Ok(LiquidationHistoryPage {
+            records,
+            next_cursor,
+        })
+    }
+
+    /// Count a failure by error code in the current hourly bucket. The host reverts the
+    /// storage writes of a failed invocation, so this is only for failures handled on a
+    /// path that still returns `Ok`; failures returned to the caller go through `track`.
+    pub fn record_error(env: &Env, entrypoint: &str, error: ProtocolError) {
+        let bucket = env.ledger().timestamp() / ERROR_BUCKET_SECONDS;
+        let mut stats = AnalyticsStorage::get_error_bucket(env, bucket);
+        if stats.is_empty() {
+            AnalyticsStorage::open_error_bucket(env, bucket);
+        }
+
+        let find = |stats: &Vec<ErrorStat>, entrypoint: &Symbol, code: u32| {
+            stats
+                .iter()
+                .position(|stat| stat.entrypoint == *entrypoint && stat.code == code)
+        };
+        let mut entrypoint = Symbol::new(env, entrypoint);
+        let mut code = error as u32;
+        let mut index = find(&stats, &entrypoint, code);
+        if index.is_none() && stats.len() >= MAX_ERROR_KEYS {
+            // Bounded cardinality: further distinct pairs share one overflow counter
+            entrypoint = Symbol::new(env, "overflow");
+            code = 0;
+            index = find(&stats, &entrypoint, code);
+        }
+        match index {
+            Some(i) => {
+                let mut stat = stats.get(i as u32).unwrap();
+                stat.count += 1;
+                stats.set(i as u32, stat);
+            }
+            None => stats.push_back(ErrorStat {
+                entrypoint,
+                code,
+                count: 1,
+            }),
+        }
+        AnalyticsStorage::put_error_bucket(env, bucket, &stats);
+    }
+
+    /// Pass `result` through, reporting it in an `entrypoint_failed` event if it is a
+    /// failure. Failed invocations keep no storage, so the event (surfaced by simulation
+    /// and diagnostic output) is the only record of them.
+    pub fn track<T>(
+        env: &Env,
+        entrypoint: &str,
+        result: Result<T, ProtocolError>,
+    ) -> Result<T, ProtocolError> {
+        if let Err(error) = &result {
+            env.events().publish(
+                (
+                    Symbol::new(env, "entrypoint_failed"),
+                    Symbol::new(env, entrypoint),
+                ),
+                *error as u32,
+            );
+        }
+        result
+    }
+
+    /// Failure counts per entrypoint and error code over the last `window` seconds,
+    /// at hourly granularity. `window` must be between 1 second and 7 days.
+    pub fn get_error_stats(env: &Env, window: u64) -> Result<Vec<ErrorStat>, ProtocolError> {
+        if window == 0 || window > ERROR_BUCKET_RETENTION * ERROR_BUCKET_SECONDS {
+            return Err(AnalyticsError::InvalidTimeRange.into());
+        }
+        let now = env.ledger().timestamp();
+        let first_bucket = now.saturating_sub(window) / ERROR_BUCKET_SECONDS;
+
+        let mut totals: Vec<ErrorStat> = Vec::new(env);
+        for bucket in AnalyticsStorage::get_error_buckets(env).iter() {
+            if bucket < first_bucket {
+                continue;
+            }
+            for stat in AnalyticsStorage::get_error_bucket(env, bucket).iter() {
+                match totals
+                    .iter()
+                    .position(|t| t.entrypoint == stat.entrypoint && t.code == stat.code)
+                {
+                    Some(i) => {
+                        let mut total = totals.get(i as u32).unwrap();
+                        total.count += stat.count;
+                        totals.set(i as u32, total);
+                    }
+                    None => totals.push_back(stat),
+                }
+            }
+        }
+        Ok(totals)
+    }
+
     /// Retrieve recent activity entries in newest-first order with an upper bound
     pub fn get_recent_activity(env: &Env, limit: u32) -> ActivityFeed {
         let log = AnalyticsStorage::get_activity_log(env);
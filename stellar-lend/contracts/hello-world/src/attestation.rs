@@ -0,0 +1,215 @@
+//! Attestation module for StellarLend protocol
+//! Lets approved third-party attestors (accounts or contracts, e.g. KYC providers) set
+//! users' verification status programmatically instead of an analyst doing it by hand.
+//! The admin registers each attestor with a cap on the users it may verify and can revoke
+//! it at any time. Every attestation is recorded against the user so audits can see who
+//! verified them. Attestors only act on standard users; privileged roles stay with the
+//! admin and managers.
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, UserManager, UserRole, VerificationStatus};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// A registered attestor and its usage
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Attestor {
+    pub attestor: Address,
+    /// Most users this attestor may mark verified
+    pub max_verifications: u32,
+    /// Users marked verified so far
+    pub verifications: u32,
+    /// Cleared on revocation; revoked attestors can no longer attest
+    pub active: bool,
+}
+
+/// Latest attestation made for a user
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AttestationRecord {
+    pub attestor: Address,
+    pub status: VerificationStatus,
+    pub timestamp: u64,
+}
+
+/// Storage helper for attestors and attestations
+pub struct AttestationStorage;
+
+impl AttestationStorage {
+    fn list_key(env: &Env) -> Symbol {
+        Symbol::new(env, "attestors")
+    }
+
+    fn attestor_key(env: &Env, attestor: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "attestor"), attestor.clone())
+    }
+
+    fn record_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "attestation"), user.clone())
+    }
+
+    pub fn get_list(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&Self::list_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_list(env: &Env, list: &Vec<Address>) {
+        env.storage().instance().set(&Self::list_key(env), list);
+    }
+
+    pub fn get_attestor(env: &Env, attestor: &Address) -> Option<Attestor> {
+        PersistentStore::get(env, &Self::attestor_key(env, attestor))
+    }
+
+    pub fn save_attestor(env: &Env, attestor: &Attestor) {
+        PersistentStore::set(env, &Self::attestor_key(env, &attestor.attestor), attestor);
+    }
+
+    pub fn get_record(env: &Env, user: &Address) -> Option<AttestationRecord> {
+        PersistentStore::get(env, &Self::record_key(env, user))
+    }
+
+    pub fn save_record(env: &Env, user: &Address, record: &AttestationRecord) {
+        PersistentStore::set(env, &Self::record_key(env, user), record);
+    }
+}
+
+/// Attestor registry and programmatic verification
+pub struct Attestations;
+
+impl Attestations {
+    /// Register an attestor or update its cap, reactivating a revoked one (admin only)
+    pub fn set_attestor(
+        env: &Env,
+        caller: &Address,
+        attestor: &Address,
+        max_verifications: u32,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if max_verifications == 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let entry = match AttestationStorage::get_attestor(env, attestor) {
+            Some(existing) => Attestor {
+                max_verifications,
+                active: true,
+                ..existing
+            },
+            None => {
+                let mut list = AttestationStorage::get_list(env);
+                list.push_back(attestor.clone());
+                AttestationStorage::save_list(env, &list);
+                Attestor {
+                    attestor: attestor.clone(),
+                    max_verifications,
+                    verifications: 0,
+                    active: true,
+                }
+            }
+        };
+        AttestationStorage::save_attestor(env, &entry);
+        Self::publish_attestor(env, &entry);
+        Ok(())
+    }
+
+    /// Stop an attestor from attesting (admin only). Users it already verified keep their
+    /// status until an analyst or another attestor changes it.
+    pub fn revoke_attestor(
+        env: &Env,
+        caller: &Address,
+        attestor: &Address,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut entry =
+            AttestationStorage::get_attestor(env, attestor).ok_or(ProtocolError::NotFound)?;
+        entry.active = false;
+        AttestationStorage::save_attestor(env, &entry);
+        Self::publish_attestor(env, &entry);
+        Ok(())
+    }
+
+    /// Set a standard user's verification status as an active attestor. Marking a user
+    /// verified counts against the attestor's cap.
+    pub fn attest(
+        env: &Env,
+        attestor: &Address,
+        user: &Address,
+        status: VerificationStatus,
+    ) -> Result<(), ProtocolError> {
+        attestor.require_auth();
+        let mut entry =
+            AttestationStorage::get_attestor(env, attestor).ok_or(ProtocolError::Unauthorized)?;
+        if !entry.active {
+            return Err(ProtocolError::Unauthorized);
+        }
+        if status == VerificationStatus::Pending {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let role = UserManager::find_profile(env, user)
+            .map(|profile| profile.role)
+            .unwrap_or(UserRole::Standard);
+        if role != UserRole::Standard {
+            return Err(ProtocolError::UserRoleViolation);
+        }
+        if status == VerificationStatus::Verified {
+            if entry.verifications >= entry.max_verifications {
+                return Err(ProtocolError::UserLimitExceeded);
+            }
+            entry.verifications += 1;
+            AttestationStorage::save_attestor(env, &entry);
+        }
+
+        UserManager::apply_verification_status(env, user, status.clone());
+        AttestationStorage::save_record(
+            env,
+            user,
+            &AttestationRecord {
+                attestor: attestor.clone(),
+                status,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+        env.events().publish(
+            (Symbol::new(env, "user_attested"), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "attestor"),
+                attestor.clone(),
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_attestor(env: &Env, attestor: &Address) -> Option<Attestor> {
+        AttestationStorage::get_attestor(env, attestor)
+    }
+
+    /// Every attestor ever registered, including revoked ones
+    pub fn get_attestors(env: &Env) -> Vec<Address> {
+        AttestationStorage::get_list(env)
+    }
+
+    pub fn get_record(env: &Env, user: &Address) -> Option<AttestationRecord> {
+        AttestationStorage::get_record(env, user)
+    }
+
+    fn publish_attestor(env: &Env, entry: &Attestor) {
+        env.events().publish(
+            (
+                Symbol::new(env, "attestor_updated"),
+                Symbol::new(env, "attestor"),
+            ),
+            (
+                Symbol::new(env, "attestor"),
+                entry.attestor.clone(),
+                Symbol::new(env, "max_verifications"),
+                entry.max_verifications,
+                Symbol::new(env, "active"),
+                entry.active,
+            ),
+        );
+    }
+}
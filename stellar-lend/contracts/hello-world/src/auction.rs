@@ -0,0 +1,367 @@
+//! Liquidation auction module for StellarLend protocol
+//! Dutch auctions as an alternative to instant liquidation: an auction offers a portion of
+//! an unhealthy position's debt at a collateral price that decays over the auction, so
+//! bidders compete on timing rather than the fixed liquidation incentive. Bids repay debt
+//! in the primary asset and receive collateral at the current price; auctions may be
+//! filled partially across several bids until settled or expired.
+
+use crate::liquidation_grace::LiquidationGrace;
+use crate::persistent::PersistentStore;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{
+    InterestRateManager, InterestRateStorage, Position, ProtocolConfig, ProtocolError,
+    ProtocolEvent, RiskConfigStorage, StateHelper, TransferEnforcer,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Basis point scale (10000 = collateral valued 1:1 with debt)
+const BPS_SCALE: i128 = 10000;
+
+/// Auction pricing settings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AuctionConfig {
+    /// Auction length in seconds
+    pub duration: u64,
+    /// Debt paid per unit of collateral at the start (bps of par)
+    pub start_price_bps: i128,
+    /// Debt paid per unit of collateral at expiry (bps of par)
+    pub end_price_bps: i128,
+}
+
+impl AuctionConfig {
+    pub fn default_config() -> Self {
+        Self {
+            duration: 3600,
+            start_price_bps: 10500,
+            end_price_bps: 9000,
+        }
+    }
+}
+
+/// Lifecycle of an auction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum AuctionStatus {
+    Active,
+    Settled,
+    Expired,
+    /// Cancelled through break-glass, releasing its escrowed collateral
+    Cancelled,
+}
+
+/// A dutch auction over part of a user's debt
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationAuction {
+    pub id: u64,
+    pub user: Address,
+    pub debt_portion: i128,
+    pub debt_remaining: i128,
+    pub collateral_sold: i128,
+    pub started_at: u64,
+    pub expires_at: u64,
+    pub start_price_bps: i128,
+    pub end_price_bps: i128,
+    pub status: AuctionStatus,
+}
+
+/// Outcome of a single bid
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AuctionFill {
+    pub debt_repaid: i128,
+    pub collateral_received: i128,
+    pub price_bps: i128,
+}
+
+/// Storage helper for auctions
+pub struct AuctionStorage;
+
+impl AuctionStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "auction_cfg")
+    }
+    fn count_key(env: &Env) -> Symbol {
+        Symbol::new(env, "auction_count")
+    }
+    fn auction_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "auction"), id)
+    }
+    fn user_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "auction_user"), user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> AuctionConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key(env))
+            .unwrap_or_else(AuctionConfig::default_config)
+    }
+
+    pub fn save_config(env: &Env, config: &AuctionConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get_auction(env: &Env, id: u64) -> Option<LiquidationAuction> {
+        PersistentStore::get(env, &Self::auction_key(env, id))
+    }
+
+    pub fn save_auction(env: &Env, auction: &LiquidationAuction) {
+        PersistentStore::set(env, &Self::auction_key(env, auction.id), auction);
+    }
+
+    pub fn get_user_auction(env: &Env, user: &Address) -> Option<u64> {
+        PersistentStore::get(env, &Self::user_key(env, user))
+    }
+
+    pub fn set_user_auction(env: &Env, user: &Address, id: u64) {
+        PersistentStore::set(env, &Self::user_key(env, user), &id);
+    }
+
+    /// Extend the TTL of a user's latest auction id, returning how many entries exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::user_key(env, user)) as u32
+    }
+
+    pub fn next_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get::<Symbol, u64>(&Self::count_key(env))
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::count_key(env), &id);
+        id
+    }
+}
+
+/// Dutch auction engine for liquidations
+pub struct LiquidationAuctions;
+
+impl LiquidationAuctions {
+    /// Set auction length and price range (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: AuctionConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.duration == 0
+            || config.end_price_bps <= 0
+            || config.end_price_bps >= config.start_price_bps
+            || config.start_price_bps > 2 * BPS_SCALE
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        AuctionStorage::save_config(env, &config);
+        Ok(())
+    }
+
+    /// Load and accrue a position, requiring it to be below its minimum ratio and past any
+    /// grace window
+    fn liquidatable_position(env: &Env, user: &Address) -> Result<Position, ProtocolError> {
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+        if position.debt <= 0
+            || (position.collateral * 100) / position.debt
+                >= CollateralMatrix::effective_min_ratio(env)
+        {
+            return Err(ProtocolError::NotEligibleForLiquidation);
+        }
+        LiquidationGrace::ensure_liquidatable(env, &position)?;
+        Ok(position)
+    }
+
+    /// Start an auction over `debt_portion` of an unhealthy position's debt, at most the
+    /// close factor. One auction per user may be active at a time.
+    pub fn start(
+        env: &Env,
+        keeper: &Address,
+        user: &Address,
+        debt_portion: i128,
+    ) -> Result<u64, ProtocolError> {
+        keeper.require_auth();
+        if debt_portion <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if Self::active_auction(env, user).is_some() {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        let position = Self::liquidatable_position(env, user)?;
+        let max_portion = (position.debt * RiskConfigStorage::get(env).close_factor) / 100000000;
+        if debt_portion > max_portion {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        // Keep the accrual so the interest (and its reserve cut) is not counted again
+        StateHelper::save_position(env, &position);
+
+        let config = AuctionStorage::get_config(env);
+        let now = env.ledger().timestamp();
+        let auction = LiquidationAuction {
+            id: AuctionStorage::next_id(env),
+            user: user.clone(),
+            debt_portion,
+            debt_remaining: debt_portion,
+            collateral_sold: 0,
+            started_at: now,
+            expires_at: now + config.duration,
+            start_price_bps: config.start_price_bps,
+            end_price_bps: config.end_price_bps,
+            status: AuctionStatus::Active,
+        };
+        AuctionStorage::save_auction(env, &auction);
+        AuctionStorage::set_user_auction(env, user, auction.id);
+
+        let asset = crate::TokenRegistry::require_primary_asset(env)?;
+        ProtocolEvent::AuctionStarted(user.clone(), asset, debt_portion).emit(env);
+        Ok(auction.id)
+    }
+
+    /// Collateral price of an auction at the current time, decaying linearly from the
+    /// start to the end price
+    pub fn current_price_bps(env: &Env, auction: &LiquidationAuction) -> i128 {
+        let duration = auction.expires_at.saturating_sub(auction.started_at).max(1) as i128;
+        let elapsed =
+            (env.ledger().timestamp().saturating_sub(auction.started_at) as i128).min(duration);
+        auction.start_price_bps
+            - (auction.start_price_bps - auction.end_price_bps) * elapsed / duration
+    }
+
+    /// Repay up to `repay_amount` of the auctioned debt and receive collateral at the
+    /// current price. The auction settles once its debt portion is fully repaid.
+    pub fn bid(
+        env: &Env,
+        bidder: &Address,
+        user: &Address,
+        repay_amount: i128,
+    ) -> Result<AuctionFill, ProtocolError> {
+        bidder.require_auth();
+        if repay_amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if bidder == user {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let mut auction = Self::active_auction(env, user).ok_or(ProtocolError::NotFound)?;
+        let mut position = Self::liquidatable_position(env, user)?;
+
+        let price_bps = Self::current_price_bps(env, &auction);
+        let mut debt_repaid = repay_amount.min(auction.debt_remaining).min(position.debt);
+        let mut collateral_received = debt_repaid * BPS_SCALE / price_bps;
+        if collateral_received > position.collateral {
+            collateral_received = position.collateral;
+            debt_repaid = collateral_received * price_bps / BPS_SCALE;
+        }
+        if debt_repaid <= 0 || collateral_received <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let asset = crate::TokenRegistry::require_primary_asset(env)?;
+        let received = TransferEnforcer::transfer_in(
+            env,
+            &asset,
+            bidder,
+            debt_repaid,
+            Symbol::new(env, "auction"),
+        )?;
+        if received < debt_repaid {
+            return Err(ProtocolError::BalanceInvariantViolation);
+        }
+        position.debt -= debt_repaid;
+        position.collateral -= collateral_received;
+        StateHelper::save_position(env, &position);
+        TransferEnforcer::transfer_out(
+            env,
+            &asset,
+            bidder,
+            collateral_received,
+            Symbol::new(env, "auction"),
+        )?;
+
+        auction.debt_remaining -= debt_repaid;
+        auction.collateral_sold += collateral_received;
+        ProtocolEvent::AuctionBidPlaced(bidder.clone(), user.clone(), debt_repaid).emit(env);
+        if auction.debt_remaining == 0 || position.debt == 0 || position.collateral == 0 {
+            auction.status = AuctionStatus::Settled;
+            ProtocolEvent::AuctionSettled(
+                bidder.clone(),
+                user.clone(),
+                auction.collateral_sold,
+                auction.debt_portion - auction.debt_remaining,
+            )
+            .emit(env);
+        }
+        AuctionStorage::save_auction(env, &auction);
+
+        Ok(AuctionFill {
+            debt_repaid,
+            collateral_received,
+            price_bps,
+        })
+    }
+
+    /// Collateral escrowed for the user's active auction: the most its remaining debt
+    /// can still draw at the end price. Withdrawals cannot dip into it.
+    pub fn escrowed_collateral(env: &Env, user: &Address) -> i128 {
+        match Self::active_auction(env, user) {
+            Some(auction) => auction.debt_remaining * BPS_SCALE / auction.end_price_bps.max(1),
+            None => 0,
+        }
+    }
+
+    /// Cancel an active auction, releasing its escrowed collateral back to the position.
+    /// Returns the amount released.
+    pub fn cancel(env: &Env, id: u64) -> Result<i128, ProtocolError> {
+        let mut auction = Self::get_auction(env, id).ok_or(ProtocolError::NotFound)?;
+        if auction.status != AuctionStatus::Active {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let released = Self::escrowed_collateral(env, &auction.user);
+        auction.status = AuctionStatus::Cancelled;
+        AuctionStorage::save_auction(env, &auction);
+        env.events().publish(
+            (
+                Symbol::new(env, "auction_cancelled"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "auction_id"),
+                id,
+                Symbol::new(env, "user"),
+                auction.user,
+                Symbol::new(env, "released"),
+                released,
+            ),
+        );
+        Ok(released)
+    }
+
+    /// Auction by id; active auctions past their expiry report `Expired`
+    pub fn get_auction(env: &Env, id: u64) -> Option<LiquidationAuction> {
+        AuctionStorage::get_auction(env, id).map(|mut auction| {
+            if auction.status == AuctionStatus::Active
+                && env.ledger().timestamp() > auction.expires_at
+            {
+                auction.status = AuctionStatus::Expired;
+            }
+            auction
+        })
+    }
+
+    /// Latest auction of a user, in any status
+    pub fn user_auction(env: &Env, user: &Address) -> Option<LiquidationAuction> {
+        AuctionStorage::get_user_auction(env, user).and_then(|id| Self::get_auction(env, id))
+    }
+
+    /// The user's auction if it is still active
+    pub fn active_auction(env: &Env, user: &Address) -> Option<LiquidationAuction> {
+        Self::user_auction(env, user).filter(|auction| auction.status == AuctionStatus::Active)
+    }
+
+    pub fn get_config(env: &Env) -> AuctionConfig {
+        AuctionStorage::get_config(env)
+    }
+}
@@ -0,0 +1,115 @@
+//! Auth adapter module for StellarLend protocol
+//! Lets custodial users register a policy contract that must approve their operations
+
+use crate::persistent::PersistentStore;
+use crate::{OperationKind, ProtocolError, UserManager};
+use soroban_sdk::{vec, Address, Env, IntoVal, Symbol};
+
+/// Storage helper for per-user auth adapters
+pub struct AuthAdapterStorage;
+
+impl AuthAdapterStorage {
+    fn adapter_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "auth_adapter"), user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Option<Address> {
+        PersistentStore::get(env, &Self::adapter_key(env, user))
+    }
+
+    pub fn set(env: &Env, user: &Address, adapter: &Address) {
+        PersistentStore::set(env, &Self::adapter_key(env, user), adapter);
+    }
+
+    pub fn remove(env: &Env, user: &Address) {
+        PersistentStore::remove(env, &Self::adapter_key(env, user));
+    }
+
+    /// Extend the TTL of a user's adapter entry, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::adapter_key(env, user)) as u32
+    }
+}
+
+/// Cross-contract approval checks against user-registered policy engines
+pub struct AuthAdapter;
+
+impl AuthAdapter {
+    /// Register (or replace) the policy contract that must approve the user's operations
+    pub fn set_adapter(env: &Env, user: &Address, adapter: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if *adapter == *user || *adapter == env.current_contract_address() {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        AuthAdapterStorage::set(env, user, adapter);
+        env.events().publish(
+            (
+                Symbol::new(env, "auth_adapter_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "adapter"),
+                Some(adapter.clone()),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Remove the user's policy contract so operations no longer need external approval
+    pub fn clear_adapter(env: &Env, user: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if AuthAdapterStorage::get(env, user).is_none() {
+            return Err(ProtocolError::NotFound);
+        }
+        AuthAdapterStorage::remove(env, user);
+        env.events().publish(
+            (
+                Symbol::new(env, "auth_adapter_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "adapter"),
+                None::<Address>,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_adapter(env: &Env, user: &Address) -> Option<Address> {
+        AuthAdapterStorage::get(env, user)
+    }
+
+    /// Ask the registered adapter (if any) to approve the operation.
+    /// The adapter must expose `approve_operation(user: Address, op: Symbol, amount: i128) -> bool`.
+    /// A refusal fails the operation with `OperationNotApproved` and, like any failed call,
+    /// leaves no record behind.
+    pub fn ensure_approved(
+        env: &Env,
+        user: &Address,
+        operation: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let adapter = match AuthAdapterStorage::get(env, user) {
+            Some(adapter) => adapter,
+            None => return Ok(()),
+        };
+
+        let op = UserManager::operation_symbol(env, operation);
+        let args = vec![
+            env,
+            user.clone().into_val(env),
+            op.into_val(env),
+            amount.into_val(env),
+        ];
+        let approved: bool =
+            env.invoke_contract(&adapter, &Symbol::new(env, "approve_operation"), args);
+        if !approved {
+            return Err(ProtocolError::OperationNotApproved);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Auto-repay module for StellarLend protocol
+//! Lets borrowers route a share of incoming deposits to outstanding debt
+
+use crate::persistent::PersistentStore;
+use crate::{CrossPosition, Position, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Basis point scale
+const BPS_SCALE: i128 = 10000;
+
+/// Per-user auto-repay preference and lifetime volume
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct AutoRepayPreference {
+    pub enabled: bool,
+    /// Share of each deposit applied to debt (bps)
+    pub share_bps: i128,
+    /// Lifetime debt repaid from deposits
+    pub total_repaid: i128,
+}
+
+/// Protocol-wide auto-repay analytics
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct AutoRepayStats {
+    pub total_repaid: i128,
+    pub repay_count: u64,
+}
+
+/// Storage helper for auto-repay preferences
+pub struct AutoRepayStorage;
+
+impl AutoRepayStorage {
+    fn preference_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "auto_repay"), user.clone())
+    }
+    fn stats_key(env: &Env) -> Symbol {
+        Symbol::new(env, "auto_repay_stats")
+    }
+
+    pub fn get_preference(env: &Env, user: &Address) -> AutoRepayPreference {
+        PersistentStore::get(env, &Self::preference_key(env, user)).unwrap_or_default()
+    }
+
+    pub fn save_preference(env: &Env, user: &Address, preference: &AutoRepayPreference) {
+        PersistentStore::set(env, &Self::preference_key(env, user), preference);
+    }
+
+    /// Extend the TTL of a user's preference entry, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::preference_key(env, user)) as u32
+    }
+
+    pub fn get_stats(env: &Env) -> AutoRepayStats {
+        env.storage()
+            .instance()
+            .get(&Self::stats_key(env))
+            .unwrap_or_default()
+    }
+
+    pub fn save_stats(env: &Env, stats: &AutoRepayStats) {
+        env.storage().instance().set(&Self::stats_key(env), stats);
+    }
+}
+
+/// Auto-repay from incoming deposits
+pub struct AutoRepay;
+
+impl AutoRepay {
+    /// Enable or disable auto-repay and set the share of deposits applied to debt
+    pub fn set_preference(
+        env: &Env,
+        user: &Address,
+        enabled: bool,
+        share_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if enabled && !(1..=BPS_SCALE).contains(&share_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut preference = AutoRepayStorage::get_preference(env, user);
+        preference.enabled = enabled;
+        preference.share_bps = if enabled { share_bps } else { 0 };
+        AutoRepayStorage::save_preference(env, user, &preference);
+        env.events().publish(
+            (
+                Symbol::new(env, "auto_repay_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "enabled"),
+                enabled,
+                Symbol::new(env, "share_bps"),
+                preference.share_bps,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Split a deposit between debt repayment and collateral on an accrued position.
+    /// Returns the amount applied to debt.
+    pub fn apply_deposit(env: &Env, user: &Address, position: &mut Position, amount: i128) -> i128 {
+        let repaid = Self::route(env, user, position.debt, amount);
+        position.debt -= repaid;
+        position.collateral += amount - repaid;
+        repaid
+    }
+
+    /// `apply_deposit` for a deposit of `asset` into an accrued multi-asset position;
+    /// only debt in the same asset is repaid. Returns the amount applied to debt.
+    pub fn apply_cross_deposit(
+        env: &Env,
+        user: &Address,
+        position: &mut CrossPosition,
+        asset: &Address,
+        amount: i128,
+    ) -> i128 {
+        let debt = position.debt.get(asset.clone()).unwrap_or(0);
+        let repaid = Self::route(env, user, debt, amount);
+        if repaid == debt {
+            position.debt.remove(asset.clone());
+        } else {
+            position.debt.set(asset.clone(), debt - repaid);
+        }
+        let collateral = position.collateral.get(asset.clone()).unwrap_or(0);
+        position
+            .collateral
+            .set(asset.clone(), collateral + amount - repaid);
+        repaid
+    }
+
+    /// Share of a deposit owed to `debt` under the user's preference, recorded in the
+    /// stats when non-zero
+    fn route(env: &Env, user: &Address, debt: i128, amount: i128) -> i128 {
+        let mut preference = AutoRepayStorage::get_preference(env, user);
+        let repaid = if preference.enabled && debt > 0 {
+            (amount * preference.share_bps / BPS_SCALE).min(debt)
+        } else {
+            0
+        };
+        if repaid == 0 {
+            return 0;
+        }
+
+        preference.total_repaid += repaid;
+        AutoRepayStorage::save_preference(env, user, &preference);
+        let mut stats = AutoRepayStorage::get_stats(env);
+        stats.total_repaid += repaid;
+        stats.repay_count += 1;
+        AutoRepayStorage::save_stats(env, &stats);
+
+        env.events().publish(
+            (Symbol::new(env, "auto_repaid"), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "deposit"),
+                amount,
+                Symbol::new(env, "repaid"),
+                repaid,
+            ),
+        );
+        repaid
+    }
+
+    pub fn get_preference(env: &Env, user: &Address) -> AutoRepayPreference {
+        AutoRepayStorage::get_preference(env, user)
+    }
+
+    pub fn get_stats(env: &Env) -> AutoRepayStats {
+        AutoRepayStorage::get_stats(env)
+    }
+}
@@ -0,0 +1,130 @@
+//! Batch module for StellarLend protocol
+//! Runs a list of deposit/borrow/repay/withdraw actions for one user in a single call.
+//! The batch holds the reentrancy guard for its whole duration and is atomic: the first
+//! failing action fails the call and reverts every action before it.
+
+use crate::borrow::BorrowModule;
+use crate::deposit::DepositModule;
+use crate::repay::RepayModule;
+use crate::withdraw::WithdrawModule;
+use crate::{ProtocolError, ReentrancyGuard, StateHelper};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Maximum actions per batch
+pub const MAX_BATCH_ACTIONS: u32 = 10;
+
+/// One action of a batch. The asset is `None` for the primary position and the asset of
+/// the multi-asset position otherwise.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ProtocolAction {
+    Deposit(Option<Address>, i128),
+    Borrow(Option<Address>, i128),
+    Repay(Option<Address>, i128),
+    Withdraw(Option<Address>, i128),
+}
+
+/// Outcome of one batch action with the position balances right after it
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ActionResult {
+    pub action: ProtocolAction,
+    /// Collateral in the action's asset after the action
+    pub collateral: i128,
+    /// Debt in the action's asset after the action
+    pub debt: i128,
+}
+
+/// Atomic multi-action execution
+pub struct Batch;
+
+impl Batch {
+    /// Execute `actions` in order for `user`, returning one result per action
+    pub fn execute(
+        env: &Env,
+        user: &Address,
+        actions: Vec<ProtocolAction>,
+    ) -> Result<Vec<ActionResult>, ProtocolError> {
+        user.require_auth();
+        if actions.is_empty() || actions.len() > MAX_BATCH_ACTIONS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        ReentrancyGuard::enter_batch(env)?;
+        let mut results = Vec::new(env);
+        let mut outcome = Ok(());
+        for action in actions.iter() {
+            outcome = Self::run(env, user, &action);
+            if outcome.is_err() {
+                break;
+            }
+            let asset = match &action {
+                ProtocolAction::Deposit(asset, _)
+                | ProtocolAction::Borrow(asset, _)
+                | ProtocolAction::Repay(asset, _)
+                | ProtocolAction::Withdraw(asset, _) => asset.clone(),
+            };
+            let (collateral, debt) = Self::balances(env, user, &asset);
+            results.push_back(ActionResult {
+                action,
+                collateral,
+                debt,
+            });
+        }
+        ReentrancyGuard::exit_batch(env);
+        outcome?;
+
+        env.events().publish(
+            (Symbol::new(env, "batch_executed"), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "actions"),
+                results.len(),
+            ),
+        );
+        Ok(results)
+    }
+
+    fn run(env: &Env, user: &Address, action: &ProtocolAction) -> Result<(), ProtocolError> {
+        match action {
+            ProtocolAction::Deposit(None, amount) => {
+                crate::deposit_collateral(env.clone(), user.clone(), *amount)
+            }
+            ProtocolAction::Deposit(Some(asset), amount) => {
+                DepositModule::deposit_collateral_asset(env, user, user, asset, *amount)
+            }
+            ProtocolAction::Borrow(None, amount) => {
+                crate::borrow(env.clone(), user.clone(), *amount)
+            }
+            ProtocolAction::Borrow(Some(asset), amount) => {
+                BorrowModule::borrow_asset(env, user, asset, *amount)
+            }
+            ProtocolAction::Repay(None, amount) => crate::repay(env.clone(), user.clone(), *amount),
+            ProtocolAction::Repay(Some(asset), amount) => {
+                RepayModule::repay_asset(env, user, user, asset, *amount).map(|_| ())
+            }
+            ProtocolAction::Withdraw(None, amount) => {
+                crate::withdraw(env.clone(), user.clone(), None, *amount)
+            }
+            ProtocolAction::Withdraw(Some(asset), amount) => {
+                WithdrawModule::withdraw_asset(env, user, asset, None, *amount)
+            }
+        }
+    }
+
+    fn balances(env: &Env, user: &Address, asset: &Option<Address>) -> (i128, i128) {
+        match asset {
+            None => StateHelper::get_position(env, user)
+                .map(|position| (position.collateral, position.debt))
+                .unwrap_or((0, 0)),
+            Some(asset) => StateHelper::get_cross_position(env, user)
+                .map(|position| {
+                    (
+                        position.collateral.get(asset.clone()).unwrap_or(0),
+                        position.debt.get(asset.clone()).unwrap_or(0),
+                    )
+                })
+                .unwrap_or((0, 0)),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Withdrawal beneficiary module for StellarLend protocol
+//! Opt-in per-user allowlist of addresses that withdrawals may be sent to. New entries only
+//! become usable after a delay so a compromised key cannot immediately drain to an attacker.
+
+use crate::persistent::PersistentStore;
+use crate::withdraw::WithdrawError;
+use crate::ProtocolError;
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Delay before a newly added beneficiary can receive withdrawals (1 day)
+pub const BENEFICIARY_DELAY: u64 = 86400;
+
+/// Maximum beneficiaries per user
+const MAX_BENEFICIARIES: u32 = 8;
+
+/// An allowlisted withdrawal recipient
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Beneficiary {
+    pub address: Address,
+    /// Withdrawals to this address are accepted from this timestamp on
+    pub active_at: u64,
+}
+
+/// Storage helper for beneficiary allowlists
+pub struct BeneficiaryStorage;
+
+impl BeneficiaryStorage {
+    fn key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "beneficiaries"), user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Vec<Beneficiary> {
+        PersistentStore::get(env, &Self::key(env, user)).unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save(env: &Env, user: &Address, beneficiaries: &Vec<Beneficiary>) {
+        if beneficiaries.is_empty() {
+            PersistentStore::remove(env, &Self::key(env, user));
+        } else {
+            PersistentStore::set(env, &Self::key(env, user), beneficiaries);
+        }
+    }
+
+    /// Extend the TTL of a user's allowlist, returning how many entries exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::key(env, user)) as u32
+    }
+}
+
+/// Withdrawal beneficiary allowlists
+pub struct Beneficiaries;
+
+impl Beneficiaries {
+    /// Allowlist a recipient; it becomes usable after `BENEFICIARY_DELAY`
+    pub fn add(env: &Env, user: &Address, beneficiary: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if beneficiary == user {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut beneficiaries = BeneficiaryStorage::get(env, user);
+        if beneficiaries
+            .iter()
+            .any(|entry| entry.address == *beneficiary)
+        {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        if beneficiaries.len() >= MAX_BENEFICIARIES {
+            return Err(ProtocolError::StorageLimitExceeded);
+        }
+
+        let active_at = env.ledger().timestamp() + BENEFICIARY_DELAY;
+        beneficiaries.push_back(Beneficiary {
+            address: beneficiary.clone(),
+            active_at,
+        });
+        BeneficiaryStorage::save(env, user, &beneficiaries);
+        env.events().publish(
+            (
+                Symbol::new(env, "beneficiary_added"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "beneficiary"),
+                beneficiary.clone(),
+                Symbol::new(env, "active_at"),
+                active_at,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Remove a recipient from the allowlist; takes effect immediately
+    pub fn remove(env: &Env, user: &Address, beneficiary: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let beneficiaries = BeneficiaryStorage::get(env, user);
+        let mut kept = Vec::new(env);
+        for entry in beneficiaries.iter() {
+            if entry.address != *beneficiary {
+                kept.push_back(entry);
+            }
+        }
+        if kept.len() == beneficiaries.len() {
+            return Err(ProtocolError::NotFound);
+        }
+        BeneficiaryStorage::save(env, user, &kept);
+        env.events().publish(
+            (
+                Symbol::new(env, "beneficiary_removed"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "beneficiary"),
+                beneficiary.clone(),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Resolve the recipient of a withdrawal: the user themselves, or an active
+    /// allowlisted beneficiary. Callers must already hold the user's signature.
+    pub fn resolve_recipient(
+        env: &Env,
+        user: &Address,
+        to: Option<Address>,
+    ) -> Result<Address, ProtocolError> {
+        let recipient = match to {
+            Some(recipient) if recipient != *user => recipient,
+            _ => return Ok(user.clone()),
+        };
+        let now = env.ledger().timestamp();
+        let allowed = BeneficiaryStorage::get(env, user)
+            .iter()
+            .any(|entry| entry.address == recipient && entry.active_at <= now);
+        if !allowed {
+            return Err(WithdrawError::BeneficiaryNotAllowed.into());
+        }
+        Ok(recipient)
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Vec<Beneficiary> {
+        BeneficiaryStorage::get(env, user)
+    }
+}
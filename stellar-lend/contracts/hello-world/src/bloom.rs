@@ -0,0 +1,85 @@
+//! Activity bloom index for StellarLend protocol
+//! Per-epoch bloom filters of addresses involved in recorded events, for indexer pruning
+
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{Address, Bytes, Env, Symbol};
+
+/// Epoch length covered by one filter (1 day)
+pub const EPOCH_SECONDS: u64 = 86400;
+
+/// Filter size in bytes (2048 bits)
+const FILTER_BYTES: u32 = 256;
+
+/// Bit positions set per address
+const HASH_COUNT: usize = 3;
+
+/// Storage helper for epoch filters (persistent, one entry per epoch)
+pub struct BloomStorage;
+
+impl BloomStorage {
+    fn key(env: &Env, epoch: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "bloom"), epoch)
+    }
+
+    pub fn get(env: &Env, epoch: u64) -> Option<Bytes> {
+        env.storage().persistent().get(&Self::key(env, epoch))
+    }
+
+    pub fn save(env: &Env, epoch: u64, filter: &Bytes) {
+        env.storage()
+            .persistent()
+            .set(&Self::key(env, epoch), filter);
+    }
+}
+
+/// Bloom filter maintenance and queries
+pub struct ActivityBloom;
+
+impl ActivityBloom {
+    pub fn epoch_of(timestamp: u64) -> u64 {
+        timestamp / EPOCH_SECONDS
+    }
+
+    /// Bit indexes for an address, taken from its SHA-256 digest
+    fn bit_indexes(env: &Env, addr: &Address) -> [u32; HASH_COUNT] {
+        let digest = env.crypto().sha256(&addr.clone().to_xdr(env)).to_array();
+        let mut indexes = [0u32; HASH_COUNT];
+        for (i, index) in indexes.iter_mut().enumerate() {
+            let word = u16::from_be_bytes([digest[2 * i], digest[2 * i + 1]]) as u32;
+            *index = word % (FILTER_BYTES * 8);
+        }
+        indexes
+    }
+
+    /// Record that `addr` was involved in an event at `timestamp`
+    pub fn insert(env: &Env, addr: &Address, timestamp: u64) {
+        let epoch = Self::epoch_of(timestamp);
+        let mut filter = BloomStorage::get(env, epoch)
+            .unwrap_or_else(|| Bytes::from_slice(env, &[0u8; FILTER_BYTES as usize]));
+        let mut changed = false;
+        for bit in Self::bit_indexes(env, addr) {
+            let byte = filter.get(bit / 8).unwrap_or(0);
+            let mask = 1u8 << (bit % 8);
+            if byte & mask == 0 {
+                filter.set(bit / 8, byte | mask);
+                changed = true;
+            }
+        }
+        if changed {
+            BloomStorage::save(env, epoch, &filter);
+        }
+    }
+
+    /// `false` means `addr` definitely had no recorded activity in `epoch`;
+    /// `true` means it may have (false positives are possible)
+    pub fn might_have_activity(env: &Env, addr: &Address, epoch: u64) -> bool {
+        let filter = match BloomStorage::get(env, epoch) {
+            Some(filter) => filter,
+            None => return false,
+        };
+        Self::bit_indexes(env, addr).iter().all(|bit| {
+            let byte = filter.get(bit / 8).unwrap_or(0);
+            byte & (1u8 << (bit % 8)) != 0
+        })
+    }
+}
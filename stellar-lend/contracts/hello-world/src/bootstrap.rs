@@ -0,0 +1,254 @@
+//! Bootstrap campaign module for StellarLend protocol
+//! Boosted rewards and reduced withdrawal fees for early depositors of newly listed assets
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Basis point scale
+const BPS_SCALE: i128 = 10000;
+
+/// Highest reward multiplier a campaign may grant (5x)
+const MAX_MULTIPLIER_BPS: i128 = 50000;
+
+/// Campaign parameters fixed at listing
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BootstrapCampaign {
+    pub asset: Address,
+    pub start: u64,
+    /// Campaign expires automatically at this timestamp
+    pub end: u64,
+    /// Reward multiplier for deposits during the window (10000 = 1x)
+    pub reward_multiplier_bps: i128,
+    /// Share of the withdrawal fee waived for participants (bps)
+    pub withdraw_fee_discount_bps: i128,
+}
+
+/// Campaign analytics for evaluating incentive effectiveness
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct BootstrapStats {
+    pub participants: u32,
+    pub total_deposited: i128,
+    pub total_reward_points: i128,
+    /// Withdrawals by participants while the campaign was active
+    pub withdrawn_during_campaign: i128,
+    pub fees_waived: i128,
+}
+
+/// A depositor's participation in a campaign
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BootstrapParticipant {
+    pub deposited: i128,
+    pub reward_points: i128,
+    pub joined_at: u64,
+}
+
+/// Storage helper for bootstrap campaigns
+pub struct BootstrapStorage;
+
+impl BootstrapStorage {
+    fn campaign_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "boot_campaign"), asset.clone())
+    }
+    fn stats_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "boot_stats"), asset.clone())
+    }
+    fn participant_key(env: &Env, asset: &Address, user: &Address) -> (Symbol, Address, Address) {
+        (Symbol::new(env, "boot_user"), asset.clone(), user.clone())
+    }
+
+    pub fn get_campaign(env: &Env, asset: &Address) -> Option<BootstrapCampaign> {
+        PersistentStore::get(env, &Self::campaign_key(env, asset))
+    }
+
+    pub fn save_campaign(env: &Env, campaign: &BootstrapCampaign) {
+        PersistentStore::set(env, &Self::campaign_key(env, &campaign.asset), campaign);
+    }
+
+    pub fn get_stats(env: &Env, asset: &Address) -> BootstrapStats {
+        PersistentStore::get(env, &Self::stats_key(env, asset)).unwrap_or_default()
+    }
+
+    pub fn save_stats(env: &Env, asset: &Address, stats: &BootstrapStats) {
+        PersistentStore::set(env, &Self::stats_key(env, asset), stats);
+    }
+
+    pub fn get_participant(
+        env: &Env,
+        asset: &Address,
+        user: &Address,
+    ) -> Option<BootstrapParticipant> {
+        PersistentStore::get(env, &Self::participant_key(env, asset, user))
+    }
+
+    pub fn save_participant(
+        env: &Env,
+        asset: &Address,
+        user: &Address,
+        participant: &BootstrapParticipant,
+    ) {
+        PersistentStore::set(env, &Self::participant_key(env, asset, user), participant);
+    }
+
+    /// Extend the TTL of an asset's campaign and stats, returning how many entries exist
+    pub fn bump_asset(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::campaign_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::stats_key(env, asset)) as u32
+    }
+
+    /// Extend the TTL of a user's participation in an asset's campaign, returning how many entries exist
+    pub fn bump_participant(env: &Env, asset: &Address, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::participant_key(env, asset, user)) as u32
+    }
+}
+
+/// Bootstrap campaign management
+pub struct Bootstrap;
+
+impl Bootstrap {
+    /// Register an asset and open its bootstrap campaign in one step (admin only)
+    pub fn list_asset_with_campaign(
+        env: &Env,
+        caller: &Address,
+        key: Symbol,
+        token: Address,
+        duration: u64,
+        reward_multiplier_bps: i128,
+        withdraw_fee_discount_bps: i128,
+    ) -> Result<BootstrapCampaign, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if duration == 0
+            || !(BPS_SCALE..=MAX_MULTIPLIER_BPS).contains(&reward_multiplier_bps)
+            || !(0..=BPS_SCALE).contains(&withdraw_fee_discount_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if BootstrapStorage::get_campaign(env, &token).is_some() {
+            return Err(ProtocolError::AlreadyExists);
+        }
+
+        TokenRegistry::register(env, key, token.clone())?;
+
+        let start = env.ledger().timestamp();
+        let campaign = BootstrapCampaign {
+            asset: token.clone(),
+            start,
+            end: start + duration,
+            reward_multiplier_bps,
+            withdraw_fee_discount_bps,
+        };
+        BootstrapStorage::save_campaign(env, &campaign);
+        BootstrapStorage::save_stats(env, &token, &BootstrapStats::default());
+
+        env.events().publish(
+            (
+                Symbol::new(env, "bootstrap_started"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                token,
+                Symbol::new(env, "end"),
+                campaign.end,
+                Symbol::new(env, "multiplier_bps"),
+                reward_multiplier_bps,
+            ),
+        );
+        Ok(campaign)
+    }
+
+    /// Campaign for `asset` if it has not yet expired
+    pub fn active_campaign(env: &Env, asset: &Address) -> Option<BootstrapCampaign> {
+        BootstrapStorage::get_campaign(env, asset)
+            .filter(|campaign| env.ledger().timestamp() < campaign.end)
+    }
+
+    /// Credit boosted reward points for a deposit of `asset` made during its active campaign
+    pub fn on_deposit(env: &Env, user: &Address, asset: &Address, amount: i128) {
+        let campaign = match Self::active_campaign(env, asset) {
+            Some(campaign) => campaign,
+            None => return,
+        };
+
+        let points = amount * campaign.reward_multiplier_bps / BPS_SCALE;
+        let mut stats = BootstrapStorage::get_stats(env, asset);
+        let mut participant = match BootstrapStorage::get_participant(env, asset, user) {
+            Some(participant) => participant,
+            None => {
+                stats.participants += 1;
+                BootstrapParticipant {
+                    deposited: 0,
+                    reward_points: 0,
+                    joined_at: env.ledger().timestamp(),
+                }
+            }
+        };
+        participant.deposited += amount;
+        participant.reward_points += points;
+        stats.total_deposited += amount;
+        stats.total_reward_points += points;
+        BootstrapStorage::save_participant(env, asset, user, &participant);
+        BootstrapStorage::save_stats(env, asset, &stats);
+    }
+
+    /// Withdrawal fee owed on `amount` of `asset`, discounted for participants of the
+    /// asset's active campaign. Waived fees are counted in the campaign stats.
+    pub fn withdraw_fee(env: &Env, user: &Address, asset: &Address, amount: i128) -> i128 {
+        let (fee, waived) = Self::quote_withdraw_fee(env, user, asset, amount);
+        if Self::participating_campaign(env, user, asset).is_some() {
+            let mut stats = BootstrapStorage::get_stats(env, asset);
+            stats.withdrawn_during_campaign += amount;
+            stats.fees_waived += waived;
+            BootstrapStorage::save_stats(env, asset, &stats);
+        }
+        fee
+    }
+
+    /// Withdrawal fee owed on `amount` of `asset` and the part of it waived, without
+    /// recording anything. Returns (fee, waived).
+    pub fn quote_withdraw_fee(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> (i128, i128) {
+        let fee = amount * ProtocolConfig::get_withdraw_fee_bps(env) / BPS_SCALE;
+        match Self::participating_campaign(env, user, asset) {
+            Some(campaign) => {
+                let waived = fee * campaign.withdraw_fee_discount_bps / BPS_SCALE;
+                (fee - waived, waived)
+            }
+            None => (fee, 0),
+        }
+    }
+
+    /// Active campaign of `asset` when `user` takes part in it
+    fn participating_campaign(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+    ) -> Option<BootstrapCampaign> {
+        let campaign = Self::active_campaign(env, asset)?;
+        BootstrapStorage::get_participant(env, asset, user)?;
+        Some(campaign)
+    }
+
+    pub fn get_campaign(env: &Env, asset: &Address) -> Option<BootstrapCampaign> {
+        BootstrapStorage::get_campaign(env, asset)
+    }
+
+    pub fn get_stats(env: &Env, asset: &Address) -> BootstrapStats {
+        BootstrapStorage::get_stats(env, asset)
+    }
+
+    pub fn get_participant(
+        env: &Env,
+        asset: &Address,
+        user: &Address,
+    ) -> Option<BootstrapParticipant> {
+        BootstrapStorage::get_participant(env, asset, user)
+    }
+}
@@ -1,13 +1,19 @@
 //! Borrow module for StellarLend protocol
-//! Handles borrowing functionality and related operations
+//! Handles borrowing functionality and related operations. Borrows add the origination
+//! fee (see `origination`) to the debt drawn.
 
 use crate::analytics::AnalyticsModule;
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::oracle::Oracle;
+use crate::origination::OriginationFees;
+use crate::outflow_limit::OutflowLimits;
+use crate::risk_matrix::CollateralMatrix;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
-    TransferEnforcer, UserManager,
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ProtocolEvent,
+    ReentrancyGuard, StateHelper, TokenRegistry, TransferEnforcer, UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Borrow-specific errors
 #[contracterror]
@@ -77,15 +83,9 @@ impl BorrowModule {
                 return Err(BorrowError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Borrow)?;
-
-            // Check if borrow is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_borrow {
-                return Err(BorrowError::ProtocolPaused.into());
-            }
-
-            UserManager::ensure_operation_allowed(env, borrower, OperationKind::Borrow, amount)?;
+            OperationGate::check(env, borrower, OperationKind::Borrow, None, amount)?;
+            OutflowLimits::record_primary(env, amount)?;
+            Oracle::ensure_primary_fresh(env)?;
 
             // Load user position
             let mut position = match StateHelper::get_position(env, borrower) {
@@ -95,16 +95,13 @@ impl BorrowModule {
 
             // Accrue interest
             let state = InterestRateStorage::update_state(env);
-            InterestRateManager::accrue_interest_for_position(
-                env,
-                &mut position,
-                state.current_borrow_rate,
-                state.current_supply_rate,
-            );
-
-            // Check collateral ratio
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let new_debt = position.debt + amount;
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+
+            // Check collateral ratio, counting the origination fee added to the debt
+            let asset = TokenRegistry::require_primary_asset(env)?;
+            let fee = OriginationFees::fee_for(env, &asset, amount);
+            let min_ratio = CollateralMatrix::effective_min_ratio(env);
+            let new_debt = position.debt + amount + fee;
             let collateral_ratio = if new_debt > 0 {
                 (position.collateral * 100) / new_debt
             } else {
@@ -116,9 +113,16 @@ impl BorrowModule {
             }
 
             // Update position
-            TransferEnforcer::transfer_out(env, borrower, amount, Symbol::new(env, "borrow"))?;
+            TransferEnforcer::transfer_out(
+                env,
+                &asset,
+                borrower,
+                amount,
+                Symbol::new(env, "borrow"),
+            )?;
             position.debt = new_debt;
             StateHelper::save_position(env, &position);
+            OriginationFees::collect(env, borrower, &asset, fee);
 
             // Emit event
             ProtocolEvent::PositionUpdated(
@@ -140,52 +144,76 @@ impl BorrowModule {
         result
     }
 
-    /// Borrow a specific asset against total cross-asset collateral
-    pub fn _borrow_asset(
+    /// Borrow a specific asset against the total value of the user's multi-asset collateral
+    pub fn borrow_asset(
         env: &Env,
-        user: &String,
+        user: &Address,
         asset: &Address,
         amount: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::borrow_asset_to(env, user, asset, amount, user)
+    }
+
+    /// Borrow against `user`'s multi-asset position, sending the funds to `recipient`
+    pub fn borrow_asset_to(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+        recipient: &Address,
     ) -> Result<(), ProtocolError> {
         ReentrancyGuard::enter(env)?;
         let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(BorrowError::InvalidAddress.into());
-            }
             if amount <= 0 {
                 return Err(BorrowError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Borrow)?;
+            OperationGate::check(env, user, OperationKind::Borrow, Some(asset), amount)?;
+            if CrossAssets::get_asset_price(env, asset).is_none() {
+                return Err(BorrowError::AssetNotSupported.into());
+            }
+            OutflowLimits::record(env, asset, amount)?;
 
-            let user_addr = crate::AddressHelper::require_valid_address(env, user)?;
+            // Bring the market current before this flow moves its totals
+            InterestRateManager::accrue_market(env, asset);
 
-            // For cross-asset borrowing, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
+            let mut position = match StateHelper::get_cross_position(env, user) {
                 Some(pos) => pos,
                 None => return Err(BorrowError::PositionNotFound.into()),
             };
 
-            // Check collateral ratio
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let new_debt = position.debt + amount;
-            let collateral_ratio = if new_debt > 0 {
-                (position.collateral * 100) / new_debt
-            } else {
-                0
-            };
+            InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
 
-            if collateral_ratio < min_ratio {
+            // Check collateral ratio across all assets after the new debt
+            let debt = position.debt.get(asset.clone()).unwrap_or(0);
+            if debt == 0 {
+                position.borrow_index.set(
+                    asset.clone(),
+                    InterestRateStorage::current_borrow_index(env, asset),
+                );
+            }
+            let fee = OriginationFees::fee_for(env, asset, amount);
+            position.debt.set(asset.clone(), debt + amount + fee);
+            Oracle::ensure_position_fresh(env, &position)?;
+            if !CrossAssets::is_healthy(env, &position)? {
                 return Err(BorrowError::InsufficientCollateralRatio.into());
             }
 
-            // Update position
-            position.debt = new_debt;
-            StateHelper::save_position(env, &position);
-
-            // Emit cross-asset borrow event
-            ProtocolEvent::CrossBorrow(user_addr, asset.clone(), amount).emit(env);
+            TransferEnforcer::transfer_out(
+                env,
+                asset,
+                recipient,
+                amount,
+                Symbol::new(env, "borrow"),
+            )?;
+            StateHelper::save_cross_position(env, &position);
+            InterestRateStorage::apply_market_flow(env, asset, 0, amount + fee);
+            OriginationFees::collect(env, user, asset, fee);
+
+            ProtocolEvent::CrossBorrow(user.clone(), asset.clone(), amount).emit(env);
+
+            AnalyticsModule::record_activity(env, user, "borrow", amount, Some(asset.clone()))?;
+            UserManager::record_activity(env, user, OperationKind::Borrow, amount)?;
 
             Ok(())
         })();
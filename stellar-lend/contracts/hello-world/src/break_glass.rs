@@ -0,0 +1,264 @@
+//! Break-glass module for StellarLend protocol
+//! Last-resort admin operations gated on Paused status, manager quorum and a postmortem record
+
+use crate::auction::LiquidationAuctions;
+use crate::{
+    EmergencyManager, EmergencyStatus, EmergencyStorage, Position, ProtocolConfig, ProtocolError,
+    ProtocolEvent, StateHelper,
+};
+use alloc::format;
+use soroban_sdk::{contracttype, Address, Env, String, Symbol, Vec};
+
+/// Last-resort operations available through the break-glass namespace
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum BreakGlassAction {
+    /// Overwrite a user's position: user, collateral, debt
+    ForceSetPosition(Address, i128, i128),
+    /// Cancel an active liquidation auction by id, releasing its escrowed collateral
+    ForceCancelAuction(u64),
+}
+
+impl BreakGlassAction {
+    fn as_symbol(&self, env: &Env) -> Symbol {
+        match self {
+            BreakGlassAction::ForceSetPosition(_, _, _) => Symbol::new(env, "force_set_position"),
+            BreakGlassAction::ForceCancelAuction(_) => Symbol::new(env, "force_cancel_auction"),
+        }
+    }
+}
+
+/// Pending break-glass call collecting manager approvals
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BreakGlassProposal {
+    pub id: u64,
+    pub action: BreakGlassAction,
+    pub reason: String,
+    pub proposer: Address,
+    pub approvals: Vec<Address>,
+    pub created_at: u64,
+    pub executed: bool,
+}
+
+/// Immutable postmortem record written when a break-glass call executes
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct IncidentRecord {
+    pub id: u64,
+    pub action: BreakGlassAction,
+    pub reason: String,
+    pub proposer: Address,
+    pub approvals: Vec<Address>,
+    pub pause_reason: Option<String>,
+    pub executed_at: u64,
+}
+
+/// Storage helper for break-glass proposals and incident records
+pub struct BreakGlassStorage;
+
+impl BreakGlassStorage {
+    fn counter_key(env: &Env) -> Symbol {
+        Symbol::new(env, "bg_counter")
+    }
+    fn quorum_key(env: &Env) -> Symbol {
+        Symbol::new(env, "bg_quorum")
+    }
+    fn proposal_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "bg_proposal"), id)
+    }
+    fn incident_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "bg_incident"), id)
+    }
+
+    pub fn next_id(env: &Env) -> u64 {
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&Self::counter_key(env))
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&Self::counter_key(env), &(id + 1));
+        id + 1
+    }
+
+    pub fn get_quorum(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::quorum_key(env))
+            .unwrap_or(2)
+    }
+
+    pub fn set_quorum(env: &Env, quorum: u32) {
+        env.storage()
+            .instance()
+            .set(&Self::quorum_key(env), &quorum);
+    }
+
+    pub fn get_proposal(env: &Env, id: u64) -> Option<BreakGlassProposal> {
+        env.storage().instance().get(&Self::proposal_key(env, id))
+    }
+
+    pub fn save_proposal(env: &Env, proposal: &BreakGlassProposal) {
+        env.storage()
+            .instance()
+            .set(&Self::proposal_key(env, proposal.id), proposal);
+    }
+
+    pub fn get_incident(env: &Env, id: u64) -> Option<IncidentRecord> {
+        env.storage().instance().get(&Self::incident_key(env, id))
+    }
+
+    /// Incident records are write-once
+    pub fn save_incident(env: &Env, record: &IncidentRecord) -> Result<(), ProtocolError> {
+        let key = Self::incident_key(env, record.id);
+        if env.storage().instance().has(&key) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        env.storage().instance().set(&key, record);
+        Ok(())
+    }
+}
+
+/// Break-glass coordinator
+pub struct BreakGlass;
+
+impl BreakGlass {
+    fn ensure_paused(env: &Env) -> Result<(), ProtocolError> {
+        if EmergencyStorage::get(env).status != EmergencyStatus::Paused {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        Ok(())
+    }
+
+    fn ensure_manager(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        if !EmergencyManager::is_authorized(env, caller) {
+            return Err(ProtocolError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Set the number of distinct manager approvals required (admin only, at least 2)
+    pub fn set_quorum(env: &Env, caller: &Address, quorum: u32) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if quorum < 2 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        BreakGlassStorage::set_quorum(env, quorum);
+        Ok(())
+    }
+
+    /// Open a break-glass proposal; the proposer's approval is counted immediately
+    pub fn propose(
+        env: &Env,
+        caller: &Address,
+        action: BreakGlassAction,
+        reason: String,
+    ) -> Result<u64, ProtocolError> {
+        Self::ensure_manager(env, caller)?;
+        Self::ensure_paused(env)?;
+        if reason.is_empty() {
+            return Err(ProtocolError::InvalidInput);
+        }
+
+        let mut approvals = Vec::new(env);
+        approvals.push_back(caller.clone());
+        let proposal = BreakGlassProposal {
+            id: BreakGlassStorage::next_id(env),
+            action,
+            reason,
+            proposer: caller.clone(),
+            approvals,
+            created_at: env.ledger().timestamp(),
+            executed: false,
+        };
+        BreakGlassStorage::save_proposal(env, &proposal);
+        ProtocolEvent::AuditTrail(
+            Symbol::new(env, "break_glass_proposed"),
+            Symbol::new(env, &format!("bg_{}", proposal.id)),
+        )
+        .emit(env);
+        Ok(proposal.id)
+    }
+
+    /// Approve a proposal; executes it once quorum is reached. Returns whether it executed.
+    pub fn approve(env: &Env, caller: &Address, id: u64) -> Result<bool, ProtocolError> {
+        Self::ensure_manager(env, caller)?;
+        Self::ensure_paused(env)?;
+
+        let mut proposal =
+            BreakGlassStorage::get_proposal(env, id).ok_or(ProtocolError::NotFound)?;
+        if proposal.executed {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        if proposal.approvals.contains(caller) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        proposal.approvals.push_back(caller.clone());
+
+        let executed = proposal.approvals.len() >= BreakGlassStorage::get_quorum(env);
+        if executed {
+            Self::execute(env, &proposal)?;
+            proposal.executed = true;
+        }
+        BreakGlassStorage::save_proposal(env, &proposal);
+        Ok(executed)
+    }
+
+    fn execute(env: &Env, proposal: &BreakGlassProposal) -> Result<(), ProtocolError> {
+        match &proposal.action {
+            BreakGlassAction::ForceSetPosition(user, collateral, debt) => {
+                if *collateral < 0 || *debt < 0 {
+                    return Err(ProtocolError::InvalidAmount);
+                }
+                let mut position = StateHelper::get_position(env, user)
+                    .unwrap_or_else(|| Position::new(user.clone(), 0, 0));
+                position.collateral = *collateral;
+                position.debt = *debt;
+                StateHelper::save_position(env, &position);
+                let collateral_ratio = if position.debt > 0 {
+                    (position.collateral * 100) / position.debt
+                } else {
+                    0
+                };
+                ProtocolEvent::PositionUpdated(
+                    user.clone(),
+                    position.collateral,
+                    position.debt,
+                    collateral_ratio,
+                )
+                .emit(env);
+            }
+            BreakGlassAction::ForceCancelAuction(id) => {
+                LiquidationAuctions::cancel(env, *id)?;
+            }
+        }
+
+        let record = IncidentRecord {
+            id: proposal.id,
+            action: proposal.action.clone(),
+            reason: proposal.reason.clone(),
+            proposer: proposal.proposer.clone(),
+            approvals: proposal.approvals.clone(),
+            pause_reason: EmergencyStorage::get(env).reason,
+            executed_at: env.ledger().timestamp(),
+        };
+        BreakGlassStorage::save_incident(env, &record)?;
+        ProtocolEvent::AuditTrail(
+            proposal.action.as_symbol(env),
+            Symbol::new(env, &format!("incident_{}", proposal.id)),
+        )
+        .emit(env);
+        Ok(())
+    }
+
+    pub fn get_proposal(env: &Env, id: u64) -> Option<BreakGlassProposal> {
+        BreakGlassStorage::get_proposal(env, id)
+    }
+
+    pub fn get_incident(env: &Env, id: u64) -> Option<IncidentRecord> {
+        BreakGlassStorage::get_incident(env, id)
+    }
+}
@@ -0,0 +1,532 @@
+//! Bridge attestation module for StellarLend protocol
+//! One versioned, chain-agnostic attestation format for bridged collateral. Every registered
+//! bridge adapter submits the same payload, signed by the network's attester set, so credits
+//! are auditable and replay-protected by nonce regardless of which adapter delivered them.
+//! Collateral leaves the same way: the user's collateral is debited and the tokens, less
+//! the network fee, are handed to the adapter to lock or burn before it releases them on
+//! the destination network. Each network may cap single transfers and the volume moved
+//! in each direction per day.
+
+use crate::analytics::AnalyticsModule;
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::liquidity_guard::LiquidityGuard;
+use crate::oracle::Oracle;
+use crate::persistent::PersistentStore;
+use crate::release_schedule::ReleaseSchedule;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{
+    CrossPosition, InterestRateManager, InterestRateStorage, OperationKind, Position,
+    ProtocolConfig, ProtocolError, ProtocolEvent, ReentrancyGuard, StateHelper, TokenRegistry,
+    TransferEnforcer, UserManager,
+};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{contracterror, contracttype, Address, Bytes, BytesN, Env, String, Symbol, Vec};
+
+/// Attestation format version accepted by this contract
+pub const ATTESTATION_VERSION: u32 = 1;
+
+/// Maximum attesters per network
+const MAX_SIGNERS: u32 = 16;
+
+/// Basis point scale
+const BPS_SCALE: i128 = 10000;
+
+/// Highest bridge fee (10%)
+const MAX_FEE_BPS: i128 = 1000;
+
+/// Length of the daily volume window (seconds)
+const FLOW_WINDOW: u64 = 86400;
+
+/// Bridge-specific errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum BridgeError {
+    UnsupportedVersion = 8001,
+    NetworkNotRegistered = 8002,
+    AdapterMismatch = 8003,
+    NonceAlreadyUsed = 8004,
+    InsufficientSignatures = 8005,
+    UnknownSigner = 8006,
+    LimitExceeded = 8007,
+}
+
+impl From<BridgeError> for ProtocolError {
+    fn from(err: BridgeError) -> Self {
+        match err {
+            BridgeError::UnsupportedVersion => ProtocolError::BridgeUnsupportedVersion,
+            BridgeError::NetworkNotRegistered => ProtocolError::NotFound,
+            BridgeError::AdapterMismatch => ProtocolError::Unauthorized,
+            BridgeError::NonceAlreadyUsed => ProtocolError::BridgeNonceAlreadyUsed,
+            BridgeError::InsufficientSignatures => ProtocolError::BridgeInsufficientSignatures,
+            BridgeError::UnknownSigner => ProtocolError::BridgeInsufficientSignatures,
+            BridgeError::LimitExceeded => ProtocolError::InvalidAmount,
+        }
+    }
+}
+
+/// The attested facts; this is what attesters sign
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AttestationPayload {
+    pub version: u32,
+    /// Network the collateral was locked on
+    pub source_network: String,
+    /// Recipient of the credit on this chain
+    pub user: Address,
+    /// Asset credited on this chain
+    pub asset: Address,
+    pub amount: i128,
+    /// Unique per source network; each nonce is credited at most once
+    pub nonce: u64,
+}
+
+/// An ed25519 signature by one attester over the attestation message
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AttestationSignature {
+    pub signer: BytesN<32>,
+    pub signature: BytesN<64>,
+}
+
+/// A payload with its signature set
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BridgeAttestation {
+    pub payload: AttestationPayload,
+    pub signatures: Vec<AttestationSignature>,
+}
+
+/// A registered source network
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BridgeNetwork {
+    pub network_id: String,
+    /// Adapter contract allowed to submit attestations for this network
+    pub adapter: Address,
+    /// Attester public keys (ed25519)
+    pub signers: Vec<BytesN<32>>,
+    /// Distinct valid signatures required
+    pub threshold: u32,
+    /// Fee withheld from credited amounts (bps)
+    pub fee_bps: i128,
+}
+
+/// Transfer limits of a network; 0 leaves a limit off
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct BridgeLimits {
+    /// Largest single transfer in either direction
+    pub max_per_transfer: i128,
+    /// Most that may be bridged in, and separately out, per day
+    pub daily_limit: i128,
+}
+
+/// Volume bridged through a network in the current daily window
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct BridgeFlow {
+    pub window_start: u64,
+    pub inbound: i128,
+    pub outbound: i128,
+}
+
+/// Storage helper for bridge networks, limits and consumed nonces
+pub struct BridgeStorage;
+
+impl BridgeStorage {
+    fn network_key(env: &Env, network_id: &String) -> (Symbol, String) {
+        (Symbol::new(env, "bridge_network"), network_id.clone())
+    }
+    fn nonce_key(env: &Env, network_id: &String, nonce: u64) -> (Symbol, String, u64) {
+        (Symbol::new(env, "bridge_nonce"), network_id.clone(), nonce)
+    }
+    fn limits_key(env: &Env, network_id: &String) -> (Symbol, String) {
+        (Symbol::new(env, "bridge_limits"), network_id.clone())
+    }
+    fn flow_key(env: &Env, network_id: &String) -> (Symbol, String) {
+        (Symbol::new(env, "bridge_flow"), network_id.clone())
+    }
+
+    pub fn get_network(env: &Env, network_id: &String) -> Option<BridgeNetwork> {
+        env.storage()
+            .instance()
+            .get(&Self::network_key(env, network_id))
+    }
+
+    pub fn save_network(env: &Env, network: &BridgeNetwork) {
+        env.storage()
+            .instance()
+            .set(&Self::network_key(env, &network.network_id), network);
+    }
+
+    pub fn is_nonce_used(env: &Env, network_id: &String, nonce: u64) -> bool {
+        PersistentStore::get::<_, bool>(env, &Self::nonce_key(env, network_id, nonce))
+            .unwrap_or(false)
+    }
+
+    pub fn mark_nonce_used(env: &Env, network_id: &String, nonce: u64) {
+        PersistentStore::set(env, &Self::nonce_key(env, network_id, nonce), &true);
+    }
+
+    pub fn get_limits(env: &Env, network_id: &String) -> BridgeLimits {
+        env.storage()
+            .instance()
+            .get(&Self::limits_key(env, network_id))
+            .unwrap_or_default()
+    }
+
+    pub fn save_limits(env: &Env, network_id: &String, limits: &BridgeLimits) {
+        env.storage()
+            .instance()
+            .set(&Self::limits_key(env, network_id), limits);
+    }
+
+    pub fn get_flow(env: &Env, network_id: &String) -> BridgeFlow {
+        env.storage()
+            .instance()
+            .get(&Self::flow_key(env, network_id))
+            .unwrap_or_default()
+    }
+
+    pub fn save_flow(env: &Env, network_id: &String, flow: &BridgeFlow) {
+        env.storage()
+            .instance()
+            .set(&Self::flow_key(env, network_id), flow);
+    }
+}
+
+/// Attestation verification shared by every bridge adapter
+pub struct AttestationVerifier;
+
+impl AttestationVerifier {
+    /// Bytes attesters sign: the payload bound to this contract's address
+    pub fn message(env: &Env, payload: &AttestationPayload) -> Bytes {
+        (env.current_contract_address(), payload.clone()).to_xdr(env)
+    }
+
+    /// Check version, adapter, nonce and signature threshold, then consume the nonce.
+    /// Invalid signatures from registered signers abort the call.
+    pub fn verify(
+        env: &Env,
+        adapter: &Address,
+        attestation: &BridgeAttestation,
+    ) -> Result<BridgeNetwork, ProtocolError> {
+        let payload = &attestation.payload;
+        if payload.version != ATTESTATION_VERSION {
+            return Err(BridgeError::UnsupportedVersion.into());
+        }
+        if payload.amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let network = BridgeStorage::get_network(env, &payload.source_network)
+            .ok_or(BridgeError::NetworkNotRegistered)?;
+        if network.adapter != *adapter {
+            return Err(BridgeError::AdapterMismatch.into());
+        }
+        if BridgeStorage::is_nonce_used(env, &network.network_id, payload.nonce) {
+            return Err(BridgeError::NonceAlreadyUsed.into());
+        }
+
+        let message = Self::message(env, payload);
+        let mut seen: Vec<BytesN<32>> = Vec::new(env);
+        for entry in attestation.signatures.iter() {
+            if !network.signers.contains(&entry.signer) {
+                return Err(BridgeError::UnknownSigner.into());
+            }
+            if seen.contains(&entry.signer) {
+                continue;
+            }
+            env.crypto()
+                .ed25519_verify(&entry.signer, &message, &entry.signature);
+            seen.push_back(entry.signer);
+        }
+        if seen.len() < network.threshold {
+            return Err(BridgeError::InsufficientSignatures.into());
+        }
+
+        BridgeStorage::mark_nonce_used(env, &network.network_id, payload.nonce);
+        Ok(network)
+    }
+}
+
+/// Bridge network registry and collateral credits
+pub struct Bridge;
+
+impl Bridge {
+    /// Register or replace a source network with its adapter and attester set (admin only)
+    pub fn register_network(
+        env: &Env,
+        caller: &Address,
+        network: BridgeNetwork,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if network.network_id.is_empty()
+            || network.signers.is_empty()
+            || network.signers.len() > MAX_SIGNERS
+            || network.threshold == 0
+            || network.threshold > network.signers.len()
+            || !(0..=MAX_FEE_BPS).contains(&network.fee_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        BridgeStorage::save_network(env, &network);
+        ProtocolEvent::BridgeRegistered(
+            network.network_id.clone(),
+            network.adapter.clone(),
+            network.fee_bps,
+        )
+        .emit(env);
+        Ok(())
+    }
+
+    /// Credit bridged collateral from a verified attestation. The adapter delivers the
+    /// tokens; the user is credited the amount received less the network fee.
+    pub fn bridge_in(
+        env: &Env,
+        adapter: &Address,
+        attestation: BridgeAttestation,
+    ) -> Result<i128, ProtocolError> {
+        adapter.require_auth();
+        let network = AttestationVerifier::verify(env, adapter, &attestation)?;
+        let payload = attestation.payload;
+        OperationGate::check(
+            env,
+            &payload.user,
+            OperationKind::Deposit,
+            Some(&payload.asset),
+            payload.amount,
+        )?;
+        Self::record_flow(env, &network.network_id, payload.amount, true)?;
+
+        let received = TransferEnforcer::transfer_in_asset(
+            env,
+            &payload.asset,
+            adapter,
+            payload.amount,
+            Symbol::new(env, "bridge_in"),
+        )?;
+        let fee = received * network.fee_bps / BPS_SCALE;
+        let credited = received - fee;
+
+        if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(&payload.asset) {
+            let mut position = StateHelper::get_position(env, &payload.user)
+                .unwrap_or_else(|| Position::new(payload.user.clone(), 0, 0));
+            position.collateral += credited;
+            StateHelper::save_position(env, &position);
+        } else {
+            CrossAssets::require_price(env, &payload.asset)?;
+            let mut position = StateHelper::get_cross_position(env, &payload.user)
+                .unwrap_or_else(|| CrossPosition::new(env, payload.user.clone()));
+            let balance = position.collateral.get(payload.asset.clone()).unwrap_or(0);
+            position
+                .collateral
+                .set(payload.asset.clone(), balance + credited);
+            StateHelper::save_cross_position(env, &position);
+            InterestRateStorage::apply_market_flow(env, &payload.asset, credited, 0);
+        }
+
+        ProtocolEvent::AssetBridgedIn(
+            payload.user,
+            network.network_id,
+            payload.asset,
+            credited,
+            fee,
+        )
+        .emit(env);
+        Ok(credited)
+    }
+
+    /// Bridge `amount` of the user's `asset` collateral out to `network_id`. The collateral
+    /// is debited under the same checks as a withdrawal and the tokens, less the network
+    /// fee kept by the pool, are sent to the network's adapter. Returns the amount sent.
+    pub fn bridge_out(
+        env: &Env,
+        user: &Address,
+        network_id: &String,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        ReentrancyGuard::enter(env)?;
+        let result = (|| -> Result<i128, ProtocolError> {
+            if amount <= 0 {
+                return Err(ProtocolError::InvalidAmount);
+            }
+            let network = BridgeStorage::get_network(env, network_id)
+                .ok_or(BridgeError::NetworkNotRegistered)?;
+            OperationGate::check(env, user, OperationKind::Withdraw, Some(asset), amount)?;
+            Self::record_flow(env, network_id, amount, false)?;
+
+            let fee = amount * network.fee_bps / BPS_SCALE;
+            let sent = amount - fee;
+            if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset) {
+                Self::debit_primary(env, user, amount)?;
+            } else {
+                Self::debit_cross(env, user, asset, amount)?;
+            }
+            LiquidityGuard::ensure_withdrawable(env, asset, sent)?;
+            if sent > 0 {
+                TransferEnforcer::transfer_out_asset(
+                    env,
+                    asset,
+                    &network.adapter,
+                    sent,
+                    Symbol::new(env, "bridge_out"),
+                )?;
+            }
+
+            ProtocolEvent::AssetBridgedOut(
+                user.clone(),
+                network.network_id,
+                asset.clone(),
+                sent,
+                fee,
+            )
+            .emit(env);
+            AnalyticsModule::record_activity(env, user, "bridge_out", amount, Some(asset.clone()))?;
+            UserManager::record_activity(env, user, OperationKind::Withdraw, amount)?;
+            Ok(sent)
+        })();
+        ReentrancyGuard::exit(env);
+        result
+    }
+
+    /// Set a network's transfer limits (admin only)
+    pub fn set_limits(
+        env: &Env,
+        caller: &Address,
+        network_id: &String,
+        limits: BridgeLimits,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if BridgeStorage::get_network(env, network_id).is_none() {
+            return Err(BridgeError::NetworkNotRegistered.into());
+        }
+        if limits.max_per_transfer < 0 || limits.daily_limit < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        BridgeStorage::save_limits(env, network_id, &limits);
+        env.events().publish(
+            (
+                Symbol::new(env, "bridge_limits_updated"),
+                Symbol::new(env, "network"),
+            ),
+            (
+                Symbol::new(env, "network"),
+                network_id.clone(),
+                Symbol::new(env, "max_per_transfer"),
+                limits.max_per_transfer,
+                Symbol::new(env, "daily_limit"),
+                limits.daily_limit,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_limits(env: &Env, network_id: &String) -> BridgeLimits {
+        BridgeStorage::get_limits(env, network_id)
+    }
+
+    /// Volume bridged through a network in the current window; a lapsed window reads as empty
+    pub fn get_flow(env: &Env, network_id: &String) -> BridgeFlow {
+        let flow = BridgeStorage::get_flow(env, network_id);
+        let now = env.ledger().timestamp();
+        if now >= flow.window_start.saturating_add(FLOW_WINDOW) {
+            return BridgeFlow {
+                window_start: now,
+                inbound: 0,
+                outbound: 0,
+            };
+        }
+        flow
+    }
+
+    pub fn get_network(env: &Env, network_id: &String) -> Option<BridgeNetwork> {
+        BridgeStorage::get_network(env, network_id)
+    }
+
+    pub fn is_nonce_used(env: &Env, network_id: &String, nonce: u64) -> bool {
+        BridgeStorage::is_nonce_used(env, network_id, nonce)
+    }
+
+    /// Check `amount` against the network's limits and add it to the window's volume
+    fn record_flow(
+        env: &Env,
+        network_id: &String,
+        amount: i128,
+        inbound: bool,
+    ) -> Result<(), ProtocolError> {
+        let limits = BridgeStorage::get_limits(env, network_id);
+        if limits.max_per_transfer > 0 && amount > limits.max_per_transfer {
+            return Err(BridgeError::LimitExceeded.into());
+        }
+        let mut flow = Self::get_flow(env, network_id);
+        let used = if inbound {
+            &mut flow.inbound
+        } else {
+            &mut flow.outbound
+        };
+        *used += amount;
+        if limits.daily_limit > 0 && *used > limits.daily_limit {
+            return Err(BridgeError::LimitExceeded.into());
+        }
+        BridgeStorage::save_flow(env, network_id, &flow);
+        Ok(())
+    }
+
+    fn debit_primary(env: &Env, user: &Address, amount: i128) -> Result<(), ProtocolError> {
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+        if position.collateral < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        position.collateral -= amount;
+        if position.collateral < ReleaseSchedule::locked_amount(env, user) {
+            return Err(ProtocolError::WithdrawCollateralLocked);
+        }
+        if position.debt > 0 {
+            Oracle::ensure_primary_fresh(env)?;
+            if (position.collateral * 100) / position.debt
+                < CollateralMatrix::effective_min_ratio(env)
+            {
+                return Err(ProtocolError::InsufficientCollateralRatio);
+            }
+        }
+        StateHelper::save_position(env, &position);
+        Ok(())
+    }
+
+    fn debit_cross(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        InterestRateManager::accrue_market(env, asset);
+        let mut position =
+            StateHelper::get_cross_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
+        let balance = position.collateral.get(asset.clone()).unwrap_or(0);
+        if balance < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        if balance == amount {
+            position.collateral.remove(asset.clone());
+        } else {
+            position.collateral.set(asset.clone(), balance - amount);
+        }
+        if !position.debt.is_empty() {
+            Oracle::ensure_position_fresh(env, &position)?;
+        }
+        if !CrossAssets::is_healthy(env, &position)? {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+        StateHelper::save_cross_position(env, &position);
+        InterestRateStorage::apply_market_flow(env, asset, -amount, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! Circuit breaker module for StellarLend protocol
+//! Per-asset breakers that pause borrows and/or withdrawals of an asset while its oracle
+//! volatility index or market utilization is above admin-set thresholds. A tripped breaker
+//! stays tripped for its cooldown and resumes on its own once the metrics are back below
+//! the thresholds.
+
+use crate::oracle::Oracle;
+use crate::persistent::PersistentStore;
+use crate::{InterestRateStorage, OperationKind, ProtocolConfig, ProtocolError, ProtocolEvent};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Thresholds and scope of an asset's breaker
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BreakerConfig {
+    /// Volatility index (bps, see `Oracle::volatility_bps`) that trips the breaker, 0 = off
+    pub max_volatility_bps: i128,
+    /// Market utilization (scaled by 1e8) that trips the breaker, 0 = off
+    pub max_utilization: i128,
+    /// Seconds a trip lasts before the breaker may resume
+    pub cooldown: u64,
+    pub pause_borrow: bool,
+    pub pause_withdraw: bool,
+}
+
+/// Breaker status of an asset with the metrics it was last evaluated on
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BreakerState {
+    pub asset: Address,
+    pub tripped: bool,
+    /// Metric behind the current or last trip (`volatility` or `utilization`)
+    pub reason: Option<Symbol>,
+    pub tripped_at: u64,
+    /// Earliest time a trip can resume
+    pub resumes_at: u64,
+    pub volatility_bps: i128,
+    pub utilization: i128,
+}
+
+/// Storage helper for breaker configs and persisted states
+pub struct BreakerStorage;
+
+impl BreakerStorage {
+    fn config_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "breaker_config"), asset.clone())
+    }
+    fn state_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "breaker_state"), asset.clone())
+    }
+
+    pub fn get_config(env: &Env, asset: &Address) -> Option<BreakerConfig> {
+        PersistentStore::get(env, &Self::config_key(env, asset))
+    }
+
+    pub fn save_config(env: &Env, asset: &Address, config: &BreakerConfig) {
+        PersistentStore::set(env, &Self::config_key(env, asset), config);
+    }
+
+    pub fn get_state(env: &Env, asset: &Address) -> Option<BreakerState> {
+        PersistentStore::get(env, &Self::state_key(env, asset))
+    }
+
+    pub fn save_state(env: &Env, state: &BreakerState) {
+        PersistentStore::set(env, &Self::state_key(env, &state.asset), state);
+    }
+
+    pub fn remove(env: &Env, asset: &Address) {
+        PersistentStore::remove(env, &Self::config_key(env, asset));
+        PersistentStore::remove(env, &Self::state_key(env, asset));
+    }
+
+    pub fn bump(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::config_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::state_key(env, asset)) as u32
+    }
+}
+
+/// Automatic per-asset circuit breakers
+pub struct CircuitBreakers;
+
+impl CircuitBreakers {
+    /// Configure an asset's breaker (admin only). Replaces any previous config and keeps a
+    /// running trip until its cooldown ends.
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        config: BreakerConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.max_volatility_bps < 0
+            || config.max_utilization < 0
+            || (config.max_volatility_bps == 0 && config.max_utilization == 0)
+            || config.cooldown == 0
+            || !(config.pause_borrow || config.pause_withdraw)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        BreakerStorage::save_config(env, asset, &config);
+        Self::refresh(env, asset);
+        Ok(())
+    }
+
+    /// Remove an asset's breaker, resuming any paused operations (admin only)
+    pub fn clear_config(env: &Env, caller: &Address, asset: &Address) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let tripped = BreakerStorage::get_state(env, asset)
+            .map(|state| state.tripped)
+            .unwrap_or(false);
+        BreakerStorage::remove(env, asset);
+        if tripped {
+            Self::publish(env, asset, false, None);
+        }
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env, asset: &Address) -> Option<BreakerConfig> {
+        BreakerStorage::get_config(env, asset)
+    }
+
+    /// Current breaker status, evaluated at the latest metrics without writing. `None`
+    /// when the asset has no breaker.
+    pub fn get_state(env: &Env, asset: &Address) -> Option<BreakerState> {
+        let config = BreakerStorage::get_config(env, asset)?;
+        Some(Self::evaluate(
+            env,
+            asset,
+            &config,
+            BreakerStorage::get_state(env, asset),
+        ))
+    }
+
+    fn evaluate(
+        env: &Env,
+        asset: &Address,
+        config: &BreakerConfig,
+        stored: Option<BreakerState>,
+    ) -> BreakerState {
+        let now = env.ledger().timestamp();
+        let volatility_bps = Oracle::volatility_bps(env, asset);
+        let utilization = InterestRateStorage::get_market_state(env, asset).utilization_rate;
+        let mut state = stored.unwrap_or(BreakerState {
+            asset: asset.clone(),
+            tripped: false,
+            reason: None,
+            tripped_at: 0,
+            resumes_at: 0,
+            volatility_bps,
+            utilization,
+        });
+        state.volatility_bps = volatility_bps;
+        state.utilization = utilization;
+        if state.tripped && now < state.resumes_at {
+            return state;
+        }
+
+        let reason = if config.max_volatility_bps > 0 && volatility_bps >= config.max_volatility_bps
+        {
+            Some(Symbol::new(env, "volatility"))
+        } else if config.max_utilization > 0 && utilization >= config.max_utilization {
+            Some(Symbol::new(env, "utilization"))
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => {
+                state.tripped = true;
+                state.reason = Some(reason);
+                state.tripped_at = now;
+                state.resumes_at = now + config.cooldown;
+            }
+            None => state.tripped = false,
+        }
+        state
+    }
+
+    /// Re-evaluate an asset's breaker and persist it when it trips or resumes
+    pub fn refresh(env: &Env, asset: &Address) -> Option<BreakerState> {
+        let config = BreakerStorage::get_config(env, asset)?;
+        let stored = BreakerStorage::get_state(env, asset);
+        let state = Self::evaluate(env, asset, &config, stored.clone());
+        let changed = match &stored {
+            Some(previous) => {
+                previous.tripped != state.tripped || previous.tripped_at != state.tripped_at
+            }
+            None => state.tripped,
+        };
+        if changed {
+            BreakerStorage::save_state(env, &state);
+            Self::publish(env, asset, state.tripped, state.reason.clone());
+        }
+        Some(state)
+    }
+
+    /// Reject `operation` on `asset` while its breaker is tripped and covers it. Like the
+    /// watchdog, a rejected call does not persist the trip; price updates and
+    /// `poke_circuit_breaker` do.
+    pub fn ensure_allowed(
+        env: &Env,
+        asset: &Address,
+        operation: OperationKind,
+    ) -> Result<(), ProtocolError> {
+        if !Self::covers(env, asset, operation) {
+            return Ok(());
+        }
+        match Self::refresh(env, asset) {
+            Some(state) if state.tripped => Err(ProtocolError::CircuitBreakerTripped),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `ensure_allowed` would reject `operation` on `asset` right now, without
+    /// persisting anything
+    pub fn is_blocking(env: &Env, asset: &Address, operation: OperationKind) -> bool {
+        Self::covers(env, asset, operation)
+            && Self::get_state(env, asset)
+                .map(|state| state.tripped)
+                .unwrap_or(false)
+    }
+
+    fn covers(env: &Env, asset: &Address, operation: OperationKind) -> bool {
+        match BreakerStorage::get_config(env, asset) {
+            Some(config) => match operation {
+                OperationKind::Borrow => config.pause_borrow,
+                OperationKind::Withdraw => config.pause_withdraw,
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    fn publish(env: &Env, asset: &Address, tripped: bool, reason: Option<Symbol>) {
+        ProtocolEvent::CircuitBreaker(tripped).emit(env);
+        env.events().publish(
+            (
+                Symbol::new(env, "circuit_breaker"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "tripped"),
+                tripped,
+                Symbol::new(env, "reason"),
+                reason,
+            ),
+        );
+    }
+}
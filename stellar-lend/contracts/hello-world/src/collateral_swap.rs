@@ -0,0 +1,138 @@
+//! Collateral swap module for StellarLend protocol
+//! Rotates collateral of a multi-asset position from one asset to another through the AMM
+//! registry in a single call. The sold collateral never leaves the position: it is taken
+//! out, swapped and credited back as the bought asset, and the position must be healthy
+//! once the swap settles.
+
+use crate::amm::{AMMRegistry, SwapParams, SwapResult};
+use crate::analytics::AnalyticsModule;
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::oracle::Oracle;
+use crate::{
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ReentrancyGuard,
+    StateHelper,
+};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Collateral rotation through the AMM registry
+pub struct CollateralSwap;
+
+impl CollateralSwap {
+    /// Swap `amount` of `from_asset` collateral for at least `min_out` of `to_asset`
+    /// collateral. Fails with `InsufficientCollateralRatio` when a healthy position would
+    /// end up under its minimum ratio or an unhealthy one would get worse.
+    pub fn swap(
+        env: &Env,
+        user: &Address,
+        from_asset: &Address,
+        to_asset: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<SwapResult, ProtocolError> {
+        user.require_auth();
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if from_asset == to_asset {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        OperationGate::check(env, user, OperationKind::Withdraw, Some(from_asset), amount)?;
+        OperationGate::check(env, user, OperationKind::Deposit, Some(to_asset), amount)?;
+        CrossAssets::require_price(env, to_asset)?;
+
+        // The swap takes the guard itself, so hold it as a batch for the whole rotation
+        ReentrancyGuard::enter_batch(env)?;
+        let result = Self::rotate(env, user, from_asset, to_asset, amount, min_out);
+        ReentrancyGuard::exit_batch(env);
+        result
+    }
+
+    fn rotate(
+        env: &Env,
+        user: &Address,
+        from_asset: &Address,
+        to_asset: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<SwapResult, ProtocolError> {
+        InterestRateManager::accrue_market(env, from_asset);
+        InterestRateManager::accrue_market(env, to_asset);
+        let mut position =
+            StateHelper::get_cross_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
+        if !position.debt.is_empty() {
+            Oracle::ensure_position_fresh(env, &position)?;
+        }
+        let before = CrossAssets::health(env, &position)?;
+
+        let balance = position.collateral.get(from_asset.clone()).unwrap_or(0);
+        if balance < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+
+        let swap = AMMRegistry::execute_swap(
+            env,
+            SwapParams::new(
+                user.clone(),
+                from_asset.clone(),
+                to_asset.clone(),
+                amount,
+                min_out,
+            ),
+        )?
+        .require_executed()?;
+
+        if balance == amount {
+            position.collateral.remove(from_asset.clone());
+        } else {
+            position
+                .collateral
+                .set(from_asset.clone(), balance - amount);
+        }
+        let bought = position.collateral.get(to_asset.clone()).unwrap_or(0);
+        position
+            .collateral
+            .set(to_asset.clone(), bought + swap.amount_out);
+        // A healthy position must stay healthy; an unhealthy one may only improve
+        let after = CrossAssets::health(env, &position)?;
+        let healthy_before = before.debt_value == 0 || before.collateral_ratio >= before.min_ratio;
+        let healthy_after = after.debt_value == 0 || after.collateral_ratio >= after.min_ratio;
+        if !healthy_after && (healthy_before || after.collateral_ratio < before.collateral_ratio) {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+
+        StateHelper::save_cross_position(env, &position);
+        InterestRateStorage::apply_market_flow(env, from_asset, -amount, 0);
+        InterestRateStorage::apply_market_flow(env, to_asset, swap.amount_out, 0);
+
+        env.events().publish(
+            (
+                Symbol::new(env, "collateral_swapped"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "from_asset"),
+                from_asset.clone(),
+                Symbol::new(env, "to_asset"),
+                to_asset.clone(),
+                Symbol::new(env, "amount_in"),
+                amount,
+                Symbol::new(env, "amount_out"),
+                swap.amount_out,
+                Symbol::new(env, "collateral_ratio"),
+                after.collateral_ratio,
+            ),
+        );
+        AnalyticsModule::record_activity(
+            env,
+            user,
+            "swap_collateral",
+            amount,
+            Some(from_asset.clone()),
+        )?;
+        Ok(swap)
+    }
+}
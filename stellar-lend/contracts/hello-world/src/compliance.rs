@@ -0,0 +1,288 @@
+//! Compliance module for StellarLend protocol
+//! Screening evaluated by the operation gate on every user flow. Deny-listed users are
+//! blocked outright; other users carry an optional jurisdiction tag, and managers add
+//! rules that cap an operation's per-call amount for one jurisdiction or for everyone
+//! (a cap of 0 blocks the operation). A blocked call fails with `OperationNotApproved` and
+//! emits a `ComplianceAlert` naming the deny list or the rule id.
+
+use crate::persistent::PersistentStore;
+use crate::{OperationKind, ProtocolError, ProtocolEvent, UserManager};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol, Vec};
+
+/// Compliance errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ComplianceError {
+    Blocked = 13001,
+}
+
+impl From<ComplianceError> for ProtocolError {
+    fn from(err: ComplianceError) -> Self {
+        match err {
+            ComplianceError::Blocked => ProtocolError::OperationNotApproved,
+        }
+    }
+}
+
+/// Most rules kept at once
+const MAX_RULES: u32 = 20;
+
+/// A per-call cap on one operation
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ComplianceRule {
+    pub id: u32,
+    /// Jurisdiction tag the rule applies to; every user when `None`
+    pub jurisdiction: Option<Symbol>,
+    /// Restricted operation: `deposit`, `borrow`, `repay`, `withdraw`, `liquidate` or
+    /// `flash_loan`
+    pub operation: Symbol,
+    /// Largest amount allowed per call, 0 to block the operation
+    pub max_amount: i128,
+}
+
+/// Storage helper for the deny list, jurisdiction tags and rules
+pub struct ComplianceStorage;
+
+impl ComplianceStorage {
+    fn rules_key(env: &Env) -> Symbol {
+        Symbol::new(env, "compliance_rules")
+    }
+
+    fn next_id_key(env: &Env) -> Symbol {
+        Symbol::new(env, "compliance_rule_id")
+    }
+
+    fn denied_count_key(env: &Env) -> Symbol {
+        Symbol::new(env, "deny_list_count")
+    }
+
+    fn denied_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "deny_listed"), user.clone())
+    }
+
+    fn jurisdiction_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "jurisdiction"), user.clone())
+    }
+
+    pub fn get_rules(env: &Env) -> Vec<ComplianceRule> {
+        env.storage()
+            .instance()
+            .get(&Self::rules_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_rules(env: &Env, rules: &Vec<ComplianceRule>) {
+        env.storage().instance().set(&Self::rules_key(env), rules);
+    }
+
+    pub fn next_id(env: &Env) -> u32 {
+        let id: u32 = env
+            .storage()
+            .instance()
+            .get(&Self::next_id_key(env))
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::next_id_key(env), &id);
+        id
+    }
+
+    /// Number of deny-listed users, so screening skips the lookup while the list is empty
+    pub fn denied_count(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::denied_count_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn is_denied(env: &Env, user: &Address) -> bool {
+        PersistentStore::get(env, &Self::denied_key(env, user)).unwrap_or(false)
+    }
+
+    pub fn set_denied(env: &Env, user: &Address, denied: bool) {
+        if Self::is_denied(env, user) == denied {
+            return;
+        }
+        let count = Self::denied_count(env);
+        let key = Self::denied_key(env, user);
+        let count = if denied {
+            PersistentStore::set(env, &key, &true);
+            count + 1
+        } else {
+            PersistentStore::remove(env, &key);
+            count.saturating_sub(1)
+        };
+        env.storage()
+            .instance()
+            .set(&Self::denied_count_key(env), &count);
+    }
+
+    pub fn get_jurisdiction(env: &Env, user: &Address) -> Option<Symbol> {
+        PersistentStore::get(env, &Self::jurisdiction_key(env, user))
+    }
+
+    pub fn save_jurisdiction(env: &Env, user: &Address, jurisdiction: Option<Symbol>) {
+        let key = Self::jurisdiction_key(env, user);
+        match jurisdiction {
+            Some(tag) => PersistentStore::set(env, &key, &tag),
+            None => PersistentStore::remove(env, &key),
+        }
+    }
+}
+
+/// Compliance screening
+pub struct Compliance;
+
+impl Compliance {
+    /// Add or remove a user from the deny list (manager role or above)
+    pub fn set_deny_listed(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        denied: bool,
+    ) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::require_manager(env, caller)?;
+        ComplianceStorage::set_denied(env, user, denied);
+        env.events().publish(
+            (
+                Symbol::new(env, "deny_list_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "denied"),
+                denied,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Tag a user with a jurisdiction, or clear the tag with `None` (manager role or above)
+    pub fn set_jurisdiction(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        jurisdiction: Option<Symbol>,
+    ) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::require_manager(env, caller)?;
+        ComplianceStorage::save_jurisdiction(env, user, jurisdiction.clone());
+        env.events().publish(
+            (
+                Symbol::new(env, "jurisdiction_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "jurisdiction"),
+                jurisdiction,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Add a rule (manager role or above). Returns its id.
+    pub fn add_rule(
+        env: &Env,
+        caller: &Address,
+        jurisdiction: Option<Symbol>,
+        operation: Symbol,
+        max_amount: i128,
+    ) -> Result<u32, ProtocolError> {
+        caller.require_auth();
+        UserManager::require_manager(env, caller)?;
+        crate::gate::OperationGate::parse_operation(env, &operation)?;
+        if max_amount < 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut rules = ComplianceStorage::get_rules(env);
+        if rules.len() >= MAX_RULES {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let id = ComplianceStorage::next_id(env);
+        rules.push_back(ComplianceRule {
+            id,
+            jurisdiction,
+            operation,
+            max_amount,
+        });
+        ComplianceStorage::save_rules(env, &rules);
+        Self::publish_rule(env, "compliance_rule_added", id);
+        Ok(id)
+    }
+
+    /// Remove a rule (manager role or above)
+    pub fn remove_rule(env: &Env, caller: &Address, id: u32) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::require_manager(env, caller)?;
+        let mut rules = ComplianceStorage::get_rules(env);
+        let index = rules
+            .iter()
+            .position(|rule| rule.id == id)
+            .ok_or(ProtocolError::NotFound)?;
+        rules.remove(index as u32);
+        ComplianceStorage::save_rules(env, &rules);
+        Self::publish_rule(env, "compliance_rule_removed", id);
+        Ok(())
+    }
+
+    /// Reject `operation` for `amount` when the user is deny-listed or a rule caps it
+    pub fn evaluate(
+        env: &Env,
+        user: &Address,
+        operation: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        if ComplianceStorage::denied_count(env) > 0 && ComplianceStorage::is_denied(env, user) {
+            return Self::block(env, user, "deny_list", 0);
+        }
+        let rules = ComplianceStorage::get_rules(env);
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let operation = UserManager::operation_symbol(env, operation);
+        let mut jurisdiction: Option<Option<Symbol>> = None;
+        for rule in rules.iter() {
+            if rule.operation != operation || amount <= rule.max_amount {
+                continue;
+            }
+            if let Some(tag) = &rule.jurisdiction {
+                let user_tag = jurisdiction
+                    .get_or_insert_with(|| ComplianceStorage::get_jurisdiction(env, user));
+                if user_tag.as_ref() != Some(tag) {
+                    continue;
+                }
+            }
+            return Self::block(env, user, "rule", rule.id);
+        }
+        Ok(())
+    }
+
+    pub fn get_rules(env: &Env) -> Vec<ComplianceRule> {
+        ComplianceStorage::get_rules(env)
+    }
+
+    pub fn is_deny_listed(env: &Env, user: &Address) -> bool {
+        ComplianceStorage::is_denied(env, user)
+    }
+
+    pub fn get_jurisdiction(env: &Env, user: &Address) -> Option<Symbol> {
+        ComplianceStorage::get_jurisdiction(env, user)
+    }
+
+    fn block(env: &Env, user: &Address, reason: &str, rule_id: u32) -> Result<(), ProtocolError> {
+        ProtocolEvent::ComplianceAlert(user.clone(), Symbol::new(env, reason), rule_id).emit(env);
+        Err(ComplianceError::Blocked.into())
+    }
+
+    fn publish_rule(env: &Env, action: &str, id: u32) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "rule")),
+            (Symbol::new(env, "id"), id),
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Credit delegation module for StellarLend protocol
+//! A delegator lets a borrower draw debt of one asset against the delegator's multi-asset
+//! collateral, up to a limit. The debt sits on the delegator's position, so every draw must
+//! keep that position healthy; each line tracks what its borrower has drawn and not yet
+//! repaid. Revoking a line blocks further draws but leaves outstanding debt in place.
+
+use crate::borrow::BorrowModule;
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::persistent::PersistentStore;
+use crate::repay::RepayModule;
+use crate::{OperationKind, ProtocolError};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
+
+/// Credit delegation errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum CreditError {
+    LineExceeded = 12001,
+}
+
+impl From<CreditError> for ProtocolError {
+    fn from(err: CreditError) -> Self {
+        match err {
+            CreditError::LineExceeded => ProtocolError::UserLimitExceeded,
+        }
+    }
+}
+
+/// Borrowing allowance granted by a delegator
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CreditLine {
+    pub delegator: Address,
+    pub borrower: Address,
+    pub asset: Address,
+    /// Most the borrower may have drawn at once
+    pub limit: i128,
+    /// Drawn and not yet repaid through the line
+    pub drawn: i128,
+    /// Revoked lines accept repayments only
+    pub revoked: bool,
+}
+
+/// Storage helper for credit lines
+pub struct CreditLineStorage;
+
+impl CreditLineStorage {
+    fn key(
+        env: &Env,
+        delegator: &Address,
+        borrower: &Address,
+        asset: &Address,
+    ) -> (Symbol, Address, Address, Address) {
+        (
+            Symbol::new(env, "credit_line"),
+            delegator.clone(),
+            borrower.clone(),
+            asset.clone(),
+        )
+    }
+
+    pub fn get(
+        env: &Env,
+        delegator: &Address,
+        borrower: &Address,
+        asset: &Address,
+    ) -> Option<CreditLine> {
+        PersistentStore::get(env, &Self::key(env, delegator, borrower, asset))
+    }
+
+    pub fn save(env: &Env, line: &CreditLine) {
+        PersistentStore::set(
+            env,
+            &Self::key(env, &line.delegator, &line.borrower, &line.asset),
+            line,
+        );
+    }
+}
+
+/// Delegated borrowing against another user's collateral
+pub struct CreditDelegation;
+
+impl CreditDelegation {
+    /// Let `borrower` draw up to `limit` of `asset` against the delegator's position.
+    /// Re-approving updates the limit and reinstates a revoked line; debt already drawn
+    /// stays on the line.
+    pub fn approve(
+        env: &Env,
+        delegator: &Address,
+        borrower: &Address,
+        asset: &Address,
+        limit: i128,
+    ) -> Result<CreditLine, ProtocolError> {
+        delegator.require_auth();
+        if delegator == borrower {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if limit <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if CrossAssets::get_asset_price(env, asset).is_none() {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        let drawn = CreditLineStorage::get(env, delegator, borrower, asset)
+            .map(|line| line.drawn)
+            .unwrap_or(0);
+        let line = CreditLine {
+            delegator: delegator.clone(),
+            borrower: borrower.clone(),
+            asset: asset.clone(),
+            limit,
+            drawn,
+            revoked: false,
+        };
+        CreditLineStorage::save(env, &line);
+        Self::publish(env, "credit_line_approved", &line, limit);
+        Ok(line)
+    }
+
+    /// Stop new draws on a line; outstanding debt is unaffected
+    pub fn revoke(
+        env: &Env,
+        delegator: &Address,
+        borrower: &Address,
+        asset: &Address,
+    ) -> Result<(), ProtocolError> {
+        delegator.require_auth();
+        let mut line = CreditLineStorage::get(env, delegator, borrower, asset)
+            .ok_or(ProtocolError::NotFound)?;
+        line.revoked = true;
+        CreditLineStorage::save(env, &line);
+        Self::publish(env, "credit_line_revoked", &line, line.drawn);
+        Ok(())
+    }
+
+    /// Draw `amount` on a line: the debt is added to the delegator's position, which must
+    /// stay healthy, and the funds go to the borrower
+    pub fn draw(
+        env: &Env,
+        borrower: &Address,
+        delegator: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<CreditLine, ProtocolError> {
+        borrower.require_auth();
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut line = CreditLineStorage::get(env, delegator, borrower, asset)
+            .ok_or(ProtocolError::NotFound)?;
+        if line.revoked {
+            return Err(ProtocolError::Unauthorized);
+        }
+        if line.drawn + amount > line.limit {
+            return Err(CreditError::LineExceeded.into());
+        }
+        OperationGate::check(env, borrower, OperationKind::Borrow, Some(asset), amount)?;
+
+        BorrowModule::borrow_asset_to(env, delegator, asset, amount, borrower)?;
+        line.drawn += amount;
+        CreditLineStorage::save(env, &line);
+        Self::publish(env, "credit_line_drawn", &line, amount);
+        Ok(line)
+    }
+
+    /// Repay debt drawn on a line from the borrower's balance; also allowed once revoked.
+    /// Returns the amount repaid.
+    pub fn repay(
+        env: &Env,
+        borrower: &Address,
+        delegator: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        borrower.require_auth();
+        let mut line = CreditLineStorage::get(env, delegator, borrower, asset)
+            .ok_or(ProtocolError::NotFound)?;
+        if line.drawn == 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        let repaid = RepayModule::repay_asset(env, borrower, delegator, asset, amount)?;
+        line.drawn = (line.drawn - repaid).max(0);
+        CreditLineStorage::save(env, &line);
+        Self::publish(env, "credit_line_repaid", &line, repaid);
+        Ok(repaid)
+    }
+
+    pub fn get(
+        env: &Env,
+        delegator: &Address,
+        borrower: &Address,
+        asset: &Address,
+    ) -> Option<CreditLine> {
+        CreditLineStorage::get(env, delegator, borrower, asset)
+    }
+
+    fn publish(env: &Env, action: &str, line: &CreditLine, amount: i128) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "delegator")),
+            (
+                Symbol::new(env, "delegator"),
+                line.delegator.clone(),
+                Symbol::new(env, "borrower"),
+                line.borrower.clone(),
+                Symbol::new(env, "asset"),
+                line.asset.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+            ),
+        );
+    }
+}
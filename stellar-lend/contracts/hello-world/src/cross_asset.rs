@@ -0,0 +1,169 @@
+//! Cross-asset module for StellarLend protocol
+//! Registered asset prices and valuation of multi-asset positions
+
+use crate::analytics::AnalyticsModule;
+use crate::lp_pricing::LpPricing;
+use crate::normalization::Normalization;
+use crate::oracle::Oracle;
+use crate::persistent::PersistentStore;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{CrossPosition, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol};
+
+/// Valuation of a multi-asset position at registered prices
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CrossPositionHealth {
+    /// Total collateral value (1e18 base)
+    pub collateral_value: i128,
+    /// Total debt value (1e18 base)
+    pub debt_value: i128,
+    /// Collateral ratio in percent, 0 when there is no debt
+    pub collateral_ratio: i128,
+    /// Strictest minimum ratio across the held collateral/debt pairs
+    pub min_ratio: i128,
+}
+
+/// Storage helper for registered asset prices
+pub struct AssetPriceStorage;
+
+impl AssetPriceStorage {
+    fn key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "asset_price"), asset.clone())
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> Option<i128> {
+        PersistentStore::get(env, &Self::key(env, asset))
+    }
+
+    pub fn set(env: &Env, asset: &Address, price: i128) {
+        PersistentStore::set(env, &Self::key(env, asset), &price);
+    }
+
+    /// Extend the TTL of an asset's price entry, returning how many exist
+    pub fn bump(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::key(env, asset)) as u32
+    }
+}
+
+/// Pricing and health math for multi-asset positions
+pub struct CrossAssets;
+
+impl CrossAssets {
+    /// Register an asset's price (1e8 scale), subject to its oracle bounds (admin only).
+    /// Only assets with a registered price can be supplied or borrowed cross-asset.
+    /// Returns `false`, keeping the rejection on record, for a price outside the bounds.
+    pub fn set_asset_price(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        price: i128,
+    ) -> Result<bool, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if price <= 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if !Oracle::check_price(env, asset, None, price) {
+            AnalyticsModule::record_error(env, "set_asset_price", ProtocolError::OracleFailure);
+            return Ok(false);
+        }
+        Normalization::cache_decimals(env, asset);
+        AssetPriceStorage::set(env, asset, price);
+        Oracle::record_price_update(env, asset, price);
+        env.events().publish(
+            (
+                Symbol::new(env, "asset_price_set"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "price"),
+                price,
+            ),
+        );
+        Ok(true)
+    }
+
+    pub fn get_asset_price(env: &Env, asset: &Address) -> Option<i128> {
+        AssetPriceStorage::get(env, asset)
+    }
+
+    /// Registered price used for valuation: its TWAP when the oracle enables TWAP for
+    /// risk checks, otherwise the spot price
+    pub fn valuation_price(env: &Env, asset: &Address) -> Option<i128> {
+        AssetPriceStorage::get(env, asset).map(|spot| Oracle::risk_price(env, asset, spot))
+    }
+
+    /// Valuation price of an asset, or the fair price of a registered LP token,
+    /// rejecting unpriced assets
+    pub fn require_price(env: &Env, asset: &Address) -> Result<i128, ProtocolError> {
+        match Self::valuation_price(env, asset) {
+            Some(price) => Ok(price),
+            None => LpPricing::fair_price(env, asset),
+        }
+    }
+
+    /// Total value of per-asset balances at registered prices, normalized for each
+    /// asset's decimals (1e18 base)
+    pub fn value(env: &Env, balances: &Map<Address, i128>) -> Result<i128, ProtocolError> {
+        let mut total = 0i128;
+        for (asset, amount) in balances.iter() {
+            total += Normalization::value(env, &asset, amount, Self::require_price(env, &asset)?);
+        }
+        Ok(total)
+    }
+
+    /// `value` for collateral balances, with LP tokens counted at their collateral factor
+    pub fn collateral_value(
+        env: &Env,
+        balances: &Map<Address, i128>,
+    ) -> Result<i128, ProtocolError> {
+        let mut total = 0i128;
+        for (asset, amount) in balances.iter() {
+            total += match Self::valuation_price(env, &asset) {
+                Some(price) => Normalization::value(env, &asset, amount, price),
+                None => LpPricing::collateral_value(env, &asset, amount)?,
+            };
+        }
+        Ok(total)
+    }
+
+    /// Strictest pairwise requirement across held collateral and debt assets,
+    /// or the global minimum ratio when the position has no pairs
+    pub fn min_ratio(env: &Env, position: &CrossPosition) -> i128 {
+        let mut strictest: Option<i128> = None;
+        for collateral in position.collateral.keys().iter() {
+            for debt in position.debt.keys().iter() {
+                let ratio = CollateralMatrix::min_ratio_for_pair(env, &collateral, &debt);
+                strictest = Some(strictest.map_or(ratio, |current| current.max(ratio)));
+            }
+        }
+        strictest.unwrap_or_else(|| ProtocolConfig::get_min_collateral_ratio(env))
+    }
+
+    pub fn health(
+        env: &Env,
+        position: &CrossPosition,
+    ) -> Result<CrossPositionHealth, ProtocolError> {
+        let collateral_value = Self::collateral_value(env, &position.collateral)?;
+        let debt_value = Self::value(env, &position.debt)?;
+        let collateral_ratio = if debt_value > 0 {
+            (collateral_value * 100) / debt_value
+        } else {
+            0
+        };
+        Ok(CrossPositionHealth {
+            collateral_value,
+            debt_value,
+            collateral_ratio,
+            min_ratio: Self::min_ratio(env, position),
+        })
+    }
+
+    /// Whether the position meets its minimum ratio (always true without debt)
+    pub fn is_healthy(env: &Env, position: &CrossPosition) -> Result<bool, ProtocolError> {
+        let health = Self::health(env, position)?;
+        Ok(health.debt_value == 0 || health.collateral_ratio >= health.min_ratio)
+    }
+}
@@ -0,0 +1,118 @@
+//! Deleverage module for StellarLend protocol
+//! Self-liquidation for the primary position: works out how much collateral to sell
+//! through the registered AMM pair so the swap proceeds repay enough debt to reach a target
+//! health factor, then runs the sale through `deleverage_swap_hook`.
+
+use crate::amm::{AMMRegistry, SwapResult};
+use crate::fees::FeeManager;
+use crate::gate::OperationGate;
+use crate::risk_matrix::CollateralMatrix;
+use crate::safety_rewards::SafetyRewards;
+use crate::{
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ReentrancyGuard,
+    StateHelper, TokenRegistry,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Fees are basis points of the amount sold
+const BPS_SCALE: i128 = 10_000;
+
+/// Outcome of a deleverage; nothing is sold when the position already meets the target
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DeleverageResult {
+    pub collateral_sold: i128,
+    pub debt_repaid: i128,
+    /// Health factor after the call in percent, `None` once the debt is gone
+    pub health_factor: Option<i128>,
+}
+
+/// Collateral sales that bring a position back to health
+pub struct Deleverage;
+
+impl Deleverage {
+    /// Sell just enough primary collateral through the AMM to lift the position's health
+    /// factor (percent, 100 = at the liquidation threshold) to `target_health_factor`,
+    /// repaying all debt when the target is out of reach
+    pub fn to_health(
+        env: &Env,
+        user: &Address,
+        target_health_factor: i128,
+    ) -> Result<DeleverageResult, ProtocolError> {
+        user.require_auth();
+        if target_health_factor <= 100 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let asset = TokenRegistry::require_primary_asset(env)?;
+
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+        if position.debt == 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        StateHelper::save_position(env, &position);
+        if SafetyRewards::health_factor(env, &position).unwrap_or(i128::MAX) >= target_health_factor
+        {
+            return Ok(DeleverageResult {
+                collateral_sold: 0,
+                debt_repaid: 0,
+                health_factor: SafetyRewards::health_factor(env, &position),
+            });
+        }
+
+        // Solve (C - x) / (D - x * (1 - fee)) >= target * min_ratio for the sale x
+        let fee_bps = FeeManager::amm_fee_bps(env, user);
+        let required = target_health_factor * CollateralMatrix::effective_min_ratio(env);
+        let numerator =
+            required * position.debt * BPS_SCALE - position.collateral * BPS_SCALE * BPS_SCALE;
+        let denominator = required * (BPS_SCALE - fee_bps) - BPS_SCALE * BPS_SCALE;
+        if denominator <= 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let full_repay =
+            (position.debt * BPS_SCALE + BPS_SCALE - fee_bps - 1) / (BPS_SCALE - fee_bps);
+        let sell = ((numerator + denominator - 1) / denominator).min(full_repay);
+        if sell > position.collateral {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        let min_repaid = sell * (BPS_SCALE - fee_bps) / BPS_SCALE;
+        OperationGate::check(env, user, OperationKind::Repay, Some(&asset), min_repaid)?;
+        let debt = position.debt;
+
+        // The swap takes the guard itself, so hold it as a batch for the whole sale
+        ReentrancyGuard::enter_batch(env)?;
+        let result = AMMRegistry::deleverage_swap_hook(env, user, &asset, &asset, sell, min_repaid)
+            .and_then(SwapResult::require_executed)
+            .and_then(|swap| {
+                let mut position =
+                    StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+                position.collateral -= sell;
+                position.debt = position.debt.max(0);
+                StateHelper::save_position(env, &position);
+                Ok(DeleverageResult {
+                    collateral_sold: sell,
+                    debt_repaid: swap.amount_out.min(debt),
+                    health_factor: SafetyRewards::health_factor(env, &position),
+                })
+            });
+        ReentrancyGuard::exit_batch(env);
+        let result = result?;
+
+        env.events().publish(
+            (Symbol::new(env, "deleveraged"), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "collateral_sold"),
+                result.collateral_sold,
+                Symbol::new(env, "debt_repaid"),
+                result.debt_repaid,
+                Symbol::new(env, "health_factor"),
+                result.health_factor,
+            ),
+        );
+        Ok(result)
+    }
+}
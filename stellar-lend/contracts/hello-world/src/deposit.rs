@@ -2,12 +2,17 @@
 //! Handles collateral deposits and related functionality
 
 use crate::analytics::AnalyticsModule;
+use crate::auto_repay::AutoRepay;
+use crate::bootstrap::Bootstrap;
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::tvl_cap::TvlCaps;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, Position,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
-    TransferEnforcer, UserManager,
+    CrossPosition, InterestRateManager, InterestRateStorage, OperationKind, Position,
+    ProtocolError, ProtocolEvent, ReentrancyGuard, StateHelper, TokenRegistry, TransferEnforcer,
+    UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Deposit-specific errors
 #[contracterror]
@@ -18,6 +23,7 @@ pub enum DepositError {
     InvalidAddress = 1002,
     ProtocolPaused = 1003,
     InsufficientCollateral = 1004,
+    TvlCapExceeded = 1005,
 }
 
 impl From<DepositError> for ProtocolError {
@@ -27,6 +33,7 @@ impl From<DepositError> for ProtocolError {
             DepositError::InvalidAddress => ProtocolError::InvalidAddress,
             DepositError::ProtocolPaused => ProtocolError::ProtocolPaused,
             DepositError::InsufficientCollateral => ProtocolError::InsufficientCollateral,
+            DepositError::TvlCapExceeded => ProtocolError::DepositTvlCapExceeded,
         }
     }
 }
@@ -62,9 +69,11 @@ impl DepositParams {
 pub struct DepositModule;
 
 impl DepositModule {
-    /// Deposit collateral into the protocol
+    /// Deposit collateral into the protocol, funded by `payer` (the depositor unless an
+    /// operator deposits on their behalf)
     pub fn deposit_collateral(
         env: &Env,
+        payer: &Address,
         depositor: &Address,
         amount: i128,
     ) -> Result<(), ProtocolError> {
@@ -74,17 +83,18 @@ impl DepositModule {
                 return Err(DepositError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Deposit)?;
+            OperationGate::check(env, depositor, OperationKind::Deposit, None, amount)?;
+            let asset = TokenRegistry::require_primary_asset(env)?;
+            TvlCaps::check_deposit(env, &asset, amount)?;
 
-            // Check if deposit is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_deposit {
-                return Err(DepositError::ProtocolPaused.into());
-            }
-
-            UserManager::ensure_operation_allowed(env, depositor, OperationKind::Deposit, amount)?;
-
-            TransferEnforcer::transfer_in(env, depositor, amount, Symbol::new(env, "deposit"))?;
+            // Credit what actually arrived (fee-on-transfer tokens deliver less)
+            let received = TransferEnforcer::transfer_in(
+                env,
+                &asset,
+                payer,
+                amount,
+                Symbol::new(env, "deposit"),
+            )?;
 
             // Load user position with error handling
             let mut position = match StateHelper::get_position(env, depositor) {
@@ -94,18 +104,14 @@ impl DepositModule {
 
             // Accrue interest before updating position
             let state = InterestRateStorage::update_state(env);
-            InterestRateManager::accrue_interest_for_position(
-                env,
-                &mut position,
-                state.current_borrow_rate,
-                state.current_supply_rate,
-            );
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
 
-            // Update position
-            position.collateral += amount;
+            // Update position, routing the user's auto-repay share to debt first
+            AutoRepay::apply_deposit(env, depositor, &mut position, received);
 
             // Save position
             StateHelper::save_position(env, &position);
+            Bootstrap::on_deposit(env, depositor, &asset, received);
 
             // Emit event
             let collateral_ratio = if position.debt > 0 {
@@ -133,39 +139,50 @@ impl DepositModule {
         result
     }
 
-    /// Deposit collateral for a specific asset (cross-asset)
-    pub fn _deposit_collateral_asset(
+    /// Deposit collateral of a specific asset into the user's multi-asset position, funded
+    /// by `payer`
+    pub fn deposit_collateral_asset(
         env: &Env,
-        user: &String,
+        payer: &Address,
+        user: &Address,
         asset: &Address,
         amount: i128,
     ) -> Result<(), ProtocolError> {
         ReentrancyGuard::enter(env)?;
         let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(DepositError::InvalidAddress.into());
-            }
             if amount <= 0 {
                 return Err(DepositError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Deposit)?;
+            OperationGate::check(env, user, OperationKind::Deposit, Some(asset), amount)?;
+            CrossAssets::require_price(env, asset)?;
+            TvlCaps::check_deposit(env, asset, amount)?;
 
-            let user_addr = crate::AddressHelper::require_valid_address(env, user)?;
+            let received = TransferEnforcer::transfer_in(
+                env,
+                asset,
+                payer,
+                amount,
+                Symbol::new(env, "deposit"),
+            )?;
 
-            // For cross-asset deposits, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => Position::new(user_addr.clone(), 0, 0),
-            };
+            // Bring the market current before this flow moves its totals
+            InterestRateManager::accrue_market(env, asset);
 
-            // Update position
-            position.collateral += amount;
-            StateHelper::save_position(env, &position);
+            let mut position = StateHelper::get_cross_position(env, user)
+                .unwrap_or_else(|| CrossPosition::new(env, user.clone()));
+            InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
+
+            // Route the user's auto-repay share to debt in this asset first
+            let repaid = AutoRepay::apply_cross_deposit(env, user, &mut position, asset, received);
+            StateHelper::save_cross_position(env, &position);
+            Bootstrap::on_deposit(env, user, asset, received);
+            InterestRateStorage::apply_market_flow(env, asset, received - repaid, -repaid);
+
+            ProtocolEvent::CrossDeposit(user.clone(), asset.clone(), received).emit(env);
 
-            // Emit cross-asset deposit event
-            ProtocolEvent::CrossDeposit(user_addr, asset.clone(), amount).emit(env);
+            AnalyticsModule::record_activity(env, user, "deposit", received, Some(asset.clone()))?;
+            UserManager::record_activity(env, user, OperationKind::Deposit, amount)?;
 
             Ok(())
         })();
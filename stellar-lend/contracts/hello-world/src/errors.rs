@@ -0,0 +1,137 @@
+//! Error namespace module for StellarLend protocol
+//! Maps every error code the contract can return to its owning module and short name
+//!
+//! Codes are namespaced by module prefix range:
+//! * `1..=999` core (`ProtocolError`)
+//! * `1000..=1999` deposit, `2000..=2999` borrow, `3000..=3999` repay
+//! * `4000..=4999` withdraw, `5000..=5999` liquidation
+//! * `6000..=6999` analytics, `7000..=7999` AMM, `8000..=8999` bridge
+//! * `9000..=9999` oracle
+//! * `10000..=10999` outflow limits, `11000..=11999` token registration
+//! * `12000..=12999` credit delegation, `13000..=13999` compliance, `14000..=14999` keeper
+//! * `15000..=15999` emergency management
+//!
+//! Entrypoints return `ProtocolError`, so only its codes reach callers. Module errors
+//! without a namespaced `ProtocolError` variant surface as their core equivalent (a
+//! `BorrowError::InsufficientCollateralRatio` is returned as core code 3), and their
+//! namespaced codes are not described here.
+
+use crate::ProtocolError;
+use soroban_sdk::{contracttype, Env, Error, Symbol};
+
+/// Module and short name for an error code
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ErrorDescription {
+    pub code: u32,
+    pub module: Symbol,
+    pub name: Symbol,
+}
+
+/// Translation table from error codes to modules and names
+pub struct ErrorCatalog;
+
+impl ErrorCatalog {
+    /// Module owning a code, derived from its prefix range
+    pub fn module_for(code: u32) -> Option<&'static str> {
+        match code {
+            1..=999 => Some("core"),
+            1000..=1999 => Some("deposit"),
+            2000..=2999 => Some("borrow"),
+            3000..=3999 => Some("repay"),
+            4000..=4999 => Some("withdraw"),
+            5000..=5999 => Some("liquidation"),
+            6000..=6999 => Some("analytics"),
+            7000..=7999 => Some("amm"),
+            8000..=8999 => Some("bridge"),
+            9000..=9999 => Some("oracle"),
+            10000..=10999 => Some("outflow"),
+            11000..=11999 => Some("token"),
+            12000..=12999 => Some("credit"),
+            13000..=13999 => Some("compliance"),
+            14000..=14999 => Some("keeper"),
+            15000..=15999 => Some("emergency"),
+            _ => None,
+        }
+    }
+
+    /// The `ProtocolError` behind a code, if the contract can return it
+    pub fn returnable(code: u32) -> Option<ProtocolError> {
+        ProtocolError::try_from(Error::from_contract_error(code)).ok()
+    }
+
+    /// Short name for a code the contract can return
+    pub fn name_for(code: u32) -> Option<&'static str> {
+        let name = match Self::returnable(code)? {
+            // Core
+            ProtocolError::Unauthorized => "Unauthorized",
+            ProtocolError::InsufficientCollateral => "InsufficientCollateral",
+            ProtocolError::InsufficientCollateralRatio => "InsufficientCollateralRatio",
+            ProtocolError::InvalidAmount => "InvalidAmount",
+            ProtocolError::InvalidAddress => "InvalidAddress",
+            ProtocolError::PositionNotFound => "PositionNotFound",
+            ProtocolError::AlreadyInitialized => "AlreadyInitialized",
+            ProtocolError::NotInitialized => "NotInitialized",
+            ProtocolError::InvalidInput => "InvalidInput",
+            ProtocolError::NotEligibleForLiquidation => "NotEligibleForLiquidation",
+            ProtocolError::ProtocolPaused => "ProtocolPaused",
+            ProtocolError::AssetNotSupported => "AssetNotSupported",
+            ProtocolError::OracleFailure => "OracleFailure",
+            ProtocolError::ReentrancyDetected => "ReentrancyDetected",
+            ProtocolError::StorageError => "StorageError",
+            ProtocolError::ConfigurationError => "ConfigurationError",
+            ProtocolError::NotFound => "NotFound",
+            ProtocolError::AlreadyExists => "AlreadyExists",
+            ProtocolError::InvalidOperation => "InvalidOperation",
+            ProtocolError::RecoveryFailed => "RecoveryFailed",
+            ProtocolError::InvalidParameters => "InvalidParameters",
+            ProtocolError::StorageLimitExceeded => "StorageLimitExceeded",
+            ProtocolError::RecoveryModeRestricted => "RecoveryModeRestricted",
+            ProtocolError::EmergencyFundInsufficient => "EmergencyFundInsufficient",
+            ProtocolError::UserNotVerified => "UserNotVerified",
+            ProtocolError::UserSuspended => "UserSuspended",
+            ProtocolError::UserLimitExceeded => "UserLimitExceeded",
+            ProtocolError::UserRoleViolation => "UserRoleViolation",
+            ProtocolError::BalanceInvariantViolation => "BalanceInvariantViolation",
+            ProtocolError::InsufficientLiquidity => "InsufficientLiquidity",
+            ProtocolError::SlippageProtectionTriggered => "SlippageProtectionTriggered",
+            ProtocolError::OperationNotApproved => "OperationNotApproved",
+            ProtocolError::LegacyApiDisabled => "LegacyApiDisabled",
+            ProtocolError::UserQuarantined => "UserQuarantined",
+            ProtocolError::CircuitBreakerTripped => "CircuitBreakerTripped",
+            // Deposit
+            ProtocolError::DepositTvlCapExceeded => "TvlCapExceeded",
+            // Withdraw
+            ProtocolError::WithdrawCollateralLocked => "CollateralLocked",
+            ProtocolError::WithdrawBeneficiaryNotAllowed => "BeneficiaryNotAllowed",
+            // Analytics
+            ProtocolError::AnalyticsInvalidTimeRange => "InvalidTimeRange",
+            ProtocolError::AnalyticsDataNotFound => "DataNotFound",
+            // AMM
+            ProtocolError::AmmPairNotRegistered => "PairNotRegistered",
+            ProtocolError::AmmInvalidSwapParams => "InvalidSwapParams",
+            ProtocolError::AmmSwapFailed => "SwapFailed",
+            // Bridge
+            ProtocolError::BridgeUnsupportedVersion => "UnsupportedVersion",
+            ProtocolError::BridgeNonceAlreadyUsed => "NonceAlreadyUsed",
+            ProtocolError::BridgeInsufficientSignatures => "InsufficientSignatures",
+            // Oracle
+            ProtocolError::OracleNoFeeds => "NoFeeds",
+            ProtocolError::OracleNoValidPrice => "NoValidPrice",
+            ProtocolError::OracleDeviationExceeded => "DeviationExceeded",
+            ProtocolError::OracleStalePrice => "StalePrice",
+        };
+        Some(name)
+    }
+
+    /// Describe a code the contract can return, or `None` if it never returns it
+    pub fn describe(env: &Env, code: u32) -> Option<ErrorDescription> {
+        let module = Self::module_for(code)?;
+        let name = Self::name_for(code)?;
+        Some(ErrorDescription {
+            code,
+            module: Symbol::new(env, module),
+            name: Symbol::new(env, name),
+        })
+    }
+}
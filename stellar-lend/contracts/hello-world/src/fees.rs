@@ -0,0 +1,178 @@
+//! Fee tier module for StellarLend protocol
+//! Maps the analytics loyalty tier of a user to discounts on the flash loan fee, the
+//! protocol's interest spread (the reserve factor's share withheld from suppliers) and the
+//! AMM swap fee. Discounts are set per tier by the admin and applied in each flow.
+
+use crate::analytics::AnalyticsStorage;
+use crate::{InterestRateState, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Discounts are basis points of the fee (10000 = fee waived)
+const BPS_SCALE: i128 = 10_000;
+
+/// Highest loyalty tier analytics assigns
+const MAX_TIER: i128 = 5;
+
+/// Rate scale of interest rates and the reserve factor (1e8)
+const RATE_SCALE: i128 = 100_000_000;
+
+/// Swap fee charged by the AMM hook before discounts
+pub const AMM_FEE_BPS: i128 = 30;
+
+/// Fee discounts granted to one loyalty tier
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TierDiscount {
+    pub tier: i128,
+    pub flash_loan_bps: i128,
+    pub interest_spread_bps: i128,
+    pub amm_bps: i128,
+}
+
+/// Fees a user pays after their tier discount
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UserFeeSchedule {
+    pub loyalty_tier: i128,
+    pub discount: TierDiscount,
+    pub flash_loan_fee_bps: i128,
+    /// Reserve factor applied to the user's supply interest (scaled by 1e8)
+    pub reserve_factor: i128,
+    pub amm_fee_bps: i128,
+}
+
+/// Storage helper for the tier schedule
+pub struct FeeStorage;
+
+impl FeeStorage {
+    fn schedule_key(env: &Env) -> Symbol {
+        Symbol::new(env, "fee_tiers")
+    }
+
+    pub fn get_schedule(env: &Env) -> Vec<TierDiscount> {
+        env.storage()
+            .instance()
+            .get(&Self::schedule_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_schedule(env: &Env, schedule: &Vec<TierDiscount>) {
+        env.storage()
+            .instance()
+            .set(&Self::schedule_key(env), schedule);
+    }
+}
+
+/// Loyalty fee discounts
+pub struct FeeManager;
+
+impl FeeManager {
+    /// Replace the tier schedule (admin only). Tiers are 1..=5 and listed once; tiers
+    /// missing from the schedule get no discount.
+    pub fn set_schedule(
+        env: &Env,
+        caller: &Address,
+        schedule: Vec<TierDiscount>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        for (idx, entry) in schedule.iter().enumerate() {
+            let valid_bps = [
+                entry.flash_loan_bps,
+                entry.interest_spread_bps,
+                entry.amm_bps,
+            ]
+            .iter()
+            .all(|bps| (0..=BPS_SCALE).contains(bps));
+            if !(1..=MAX_TIER).contains(&entry.tier) || !valid_bps {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            if schedule
+                .iter()
+                .skip(idx + 1)
+                .any(|other| other.tier == entry.tier)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        FeeStorage::save_schedule(env, &schedule);
+        env.events().publish(
+            (
+                Symbol::new(env, "fee_tiers_updated"),
+                Symbol::new(env, "schedule"),
+            ),
+            (Symbol::new(env, "tiers"), schedule.len()),
+        );
+        Ok(())
+    }
+
+    pub fn get_schedule(env: &Env) -> Vec<TierDiscount> {
+        FeeStorage::get_schedule(env)
+    }
+
+    pub fn loyalty_tier(env: &Env, user: &Address) -> i128 {
+        AnalyticsStorage::get_user_analytics_for_user(env, user).loyalty_tier
+    }
+
+    /// Discounts for the user's current tier, all zero when the tier has none
+    pub fn discount(env: &Env, user: &Address) -> TierDiscount {
+        let tier = Self::loyalty_tier(env, user);
+        FeeStorage::get_schedule(env)
+            .iter()
+            .find(|entry| entry.tier == tier)
+            .unwrap_or(TierDiscount {
+                tier,
+                flash_loan_bps: 0,
+                interest_spread_bps: 0,
+                amm_bps: 0,
+            })
+    }
+
+    fn discounted(fee: i128, discount_bps: i128) -> i128 {
+        fee - fee * discount_bps / BPS_SCALE
+    }
+
+    pub fn flash_loan_fee_bps(env: &Env, user: &Address, fee_bps: i128) -> i128 {
+        Self::discounted(fee_bps, Self::discount(env, user).flash_loan_bps)
+    }
+
+    pub fn amm_fee_bps(env: &Env, user: &Address) -> i128 {
+        Self::discounted(AMM_FEE_BPS, Self::discount(env, user).amm_bps)
+    }
+
+    pub fn reserve_factor(env: &Env, user: &Address, reserve_factor: i128) -> i128 {
+        Self::discounted(
+            reserve_factor,
+            Self::discount(env, user).interest_spread_bps,
+        )
+    }
+
+    /// Supply rate for the user: the market's supply rate plus the discounted part of the
+    /// spread the reserve factor withholds
+    pub fn supply_rate(
+        env: &Env,
+        user: &Address,
+        state: &InterestRateState,
+        reserve_factor: i128,
+    ) -> i128 {
+        let waived = reserve_factor - Self::reserve_factor(env, user, reserve_factor);
+        if waived <= 0 {
+            return state.current_supply_rate;
+        }
+        state.current_supply_rate + state.smoothed_borrow_rate.saturating_mul(waived) / RATE_SCALE
+    }
+
+    pub fn user_schedule(env: &Env, user: &Address) -> UserFeeSchedule {
+        let reserve_factor = crate::InterestRateStorage::get_config(env).reserve_factor;
+        UserFeeSchedule {
+            loyalty_tier: Self::loyalty_tier(env, user),
+            discount: Self::discount(env, user),
+            flash_loan_fee_bps: Self::flash_loan_fee_bps(
+                env,
+                user,
+                ProtocolConfig::get_flash_loan_fee_bps(env),
+            ),
+            reserve_factor: Self::reserve_factor(env, user, reserve_factor),
+            amm_fee_bps: Self::amm_fee_bps(env, user),
+        }
+    }
+}
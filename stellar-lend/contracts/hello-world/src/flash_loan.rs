@@ -1,5 +1,51 @@
-use crate::{ProtocolError, ProtocolEvent, ReentrancyGuard};
-use soroban_sdk::{vec, Address, Env, IntoVal, Symbol};
+use crate::fees::FeeManager;
+use crate::gate::OperationGate;
+use crate::referral::Referrals;
+use crate::{
+    OperationKind, ProtocolConfig, ProtocolError, ProtocolEvent, ReentrancyGuard, TokenRegistry,
+    TransferEnforcer,
+};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracttype, vec, Address, Env, IntoVal, Symbol, Vec};
+
+/// Maximum number of assets borrowed by one batch
+const MAX_BATCH_LEGS: u32 = 8;
+
+/// Time-boxed zero-fee flash loan allowance for a receiver contract
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FlashLoanPromo {
+    pub receiver: Address,
+    pub start: u64,
+    pub end: u64,
+    /// Total principal that may be borrowed fee-free
+    pub volume_cap: i128,
+    pub volume_used: i128,
+    pub loans: u32,
+}
+
+/// Storage helper for flash loan promos
+pub struct FlashLoanPromoStorage;
+
+impl FlashLoanPromoStorage {
+    fn key(env: &Env, receiver: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "flash_promo"), receiver.clone())
+    }
+
+    pub fn get(env: &Env, receiver: &Address) -> Option<FlashLoanPromo> {
+        env.storage().instance().get(&Self::key(env, receiver))
+    }
+
+    pub fn save(env: &Env, promo: &FlashLoanPromo) {
+        env.storage()
+            .instance()
+            .set(&Self::key(env, &promo.receiver), promo);
+    }
+
+    pub fn remove(env: &Env, receiver: &Address) {
+        env.storage().instance().remove(&Self::key(env, receiver));
+    }
+}
 
 #[allow(dead_code)]
 pub struct FlashLoan;
@@ -18,6 +64,8 @@ impl FlashLoan {
         }
         ReentrancyGuard::enter(env)?;
         let result = {
+            let fee_bps = FeeManager::flash_loan_fee_bps(env, initiator, fee_bps);
+            let fee_bps = Self::consume_promo(env, receiver_contract, amount, fee_bps);
             let fee = (amount * fee_bps) / 10000;
             ProtocolEvent::FlashLoanInitiated(initiator.clone(), asset.clone(), amount, fee)
                 .emit(env);
@@ -30,6 +78,7 @@ impl FlashLoan {
             ];
             let _: () =
                 env.invoke_contract(receiver_contract, &Symbol::new(env, "on_flash_loan"), args);
+            Referrals::on_fee(env, initiator, asset, fee);
             ProtocolEvent::FlashLoanCompleted(initiator.clone(), asset.clone(), amount, fee)
                 .emit(env);
             Ok(())
@@ -37,4 +86,204 @@ impl FlashLoan {
         ReentrancyGuard::exit(env);
         result
     }
+
+    /// Flash-borrow several registered assets at once. Every leg is sent to `receiver`,
+    /// which gets a single `on_flash_loan_batch(loans, fees, initiator)` callback and must
+    /// return each amount plus its fee before the call ends. Promo waivers apply per leg.
+    ///
+    /// Returns the fee charged for each leg, in request order.
+    pub fn execute_batch(
+        env: &Env,
+        initiator: &Address,
+        receiver: &Address,
+        loans: Vec<(Address, i128)>,
+    ) -> Result<Vec<i128>, ProtocolError> {
+        initiator.require_auth();
+        if loans.is_empty() || loans.len() > MAX_BATCH_LEGS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let registered = TokenRegistry::list_assets(env).values();
+        let mut total = 0i128;
+        for (idx, (asset, amount)) in loans.iter().enumerate() {
+            if amount <= 0 {
+                return Err(ProtocolError::InvalidAmount);
+            }
+            if !registered.contains(&asset) {
+                return Err(ProtocolError::AssetNotSupported);
+            }
+            if loans.iter().skip(idx + 1).any(|(other, _)| other == asset) {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            total += amount;
+        }
+        OperationGate::check(env, initiator, OperationKind::FlashLoan, None, total)?;
+
+        ReentrancyGuard::enter(env)?;
+        let result = Self::run_batch(env, initiator, receiver, &loans);
+        ReentrancyGuard::exit(env);
+        result
+    }
+
+    fn run_batch(
+        env: &Env,
+        initiator: &Address,
+        receiver: &Address,
+        loans: &Vec<(Address, i128)>,
+    ) -> Result<Vec<i128>, ProtocolError> {
+        let fee_bps = FeeManager::flash_loan_fee_bps(
+            env,
+            initiator,
+            ProtocolConfig::get_flash_loan_fee_bps(env),
+        );
+        let contract = env.current_contract_address();
+        let mut fees = Vec::new(env);
+        let mut balances = Vec::new(env);
+        for (asset, amount) in loans.iter() {
+            let fee = amount * Self::consume_promo(env, receiver, amount, fee_bps) / 10000;
+            balances.push_back(TokenClient::new(env, &asset).balance(&contract));
+            fees.push_back(fee);
+            ProtocolEvent::FlashLoanInitiated(initiator.clone(), asset.clone(), amount, fee)
+                .emit(env);
+            TransferEnforcer::transfer_out(
+                env,
+                &asset,
+                receiver,
+                amount,
+                Symbol::new(env, "flash_loan"),
+            )?;
+        }
+
+        let args = vec![
+            env,
+            loans.clone().into_val(env),
+            fees.into_val(env),
+            initiator.clone().into_val(env),
+        ];
+        let _: () = env.invoke_contract(receiver, &Symbol::new(env, "on_flash_loan_batch"), args);
+
+        for (idx, (asset, amount)) in loans.iter().enumerate() {
+            let idx = idx as u32;
+            let fee = fees.get(idx).unwrap_or(0);
+            let before = balances.get(idx).unwrap_or(0);
+            if TokenClient::new(env, &asset).balance(&contract) < before + fee {
+                return Err(ProtocolError::BalanceInvariantViolation);
+            }
+            Referrals::on_fee(env, initiator, &asset, fee);
+            ProtocolEvent::FlashLoanCompleted(initiator.clone(), asset.clone(), amount, fee)
+                .emit(env);
+        }
+        Ok(fees)
+    }
+
+    /// Grant `receiver` fee-free flash loans between `start` and `end` up to
+    /// `volume_cap` total principal (admin only). Replaces any existing promo.
+    pub fn grant_promo(
+        env: &Env,
+        caller: &Address,
+        receiver: &Address,
+        start: u64,
+        end: u64,
+        volume_cap: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if end <= start || end <= env.ledger().timestamp() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if volume_cap <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        FlashLoanPromoStorage::save(
+            env,
+            &FlashLoanPromo {
+                receiver: receiver.clone(),
+                start,
+                end,
+                volume_cap,
+                volume_used: 0,
+                loans: 0,
+            },
+        );
+        env.events().publish(
+            (
+                Symbol::new(env, "flash_promo_granted"),
+                Symbol::new(env, "receiver"),
+            ),
+            (
+                Symbol::new(env, "receiver"),
+                receiver.clone(),
+                Symbol::new(env, "start"),
+                start,
+                Symbol::new(env, "end"),
+                end,
+                Symbol::new(env, "volume_cap"),
+                volume_cap,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Withdraw a receiver's promo before it expires (admin only)
+    pub fn revoke_promo(
+        env: &Env,
+        caller: &Address,
+        receiver: &Address,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if FlashLoanPromoStorage::get(env, receiver).is_none() {
+            return Err(ProtocolError::NotFound);
+        }
+        Self::end_promo(env, receiver, "flash_promo_revoked");
+        Ok(())
+    }
+
+    /// The receiver's promo, unless it has expired
+    pub fn get_promo(env: &Env, receiver: &Address) -> Option<FlashLoanPromo> {
+        FlashLoanPromoStorage::get(env, receiver)
+            .filter(|promo| env.ledger().timestamp() <= promo.end)
+    }
+
+    /// Fee to charge for a loan to `receiver`: zero when an active promo has room for
+    /// `amount`, in which case the usage is recorded. Expired promos are removed.
+    #[allow(dead_code)]
+    fn consume_promo(env: &Env, receiver: &Address, amount: i128, fee_bps: i128) -> i128 {
+        let mut promo = match FlashLoanPromoStorage::get(env, receiver) {
+            Some(promo) => promo,
+            None => return fee_bps,
+        };
+        let now = env.ledger().timestamp();
+        if now > promo.end {
+            Self::end_promo(env, receiver, "flash_promo_expired");
+            return fee_bps;
+        }
+        if now < promo.start || promo.volume_used + amount > promo.volume_cap {
+            return fee_bps;
+        }
+
+        promo.volume_used += amount;
+        promo.loans += 1;
+        FlashLoanPromoStorage::save(env, &promo);
+        env.events().publish(
+            (
+                Symbol::new(env, "flash_promo_used"),
+                Symbol::new(env, "receiver"),
+            ),
+            (
+                Symbol::new(env, "receiver"),
+                receiver.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+                Symbol::new(env, "volume_used"),
+                promo.volume_used,
+            ),
+        );
+        0
+    }
+
+    fn end_promo(env: &Env, receiver: &Address, event: &str) {
+        FlashLoanPromoStorage::remove(env, receiver);
+        env.events().publish(
+            (Symbol::new(env, event), Symbol::new(env, "receiver")),
+            (Symbol::new(env, "receiver"), receiver.clone()),
+        );
+    }
 }
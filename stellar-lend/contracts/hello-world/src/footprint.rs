@@ -0,0 +1,155 @@
+//! Storage footprint module for StellarLend protocol
+//! Estimates ledger entries and value sizes per user and protocol-wide for rent budgeting
+
+use crate::analytics::AnalyticsStorage;
+use crate::auth_adapter::AuthAdapterStorage;
+use crate::insurance::InsuranceStorage;
+use crate::{
+    EmergencyStorage, EventStorage, InterestRateStorage, RiskConfigStorage, StateHelper,
+    UserManager,
+};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Symbol, Val, Vec};
+
+/// Entries of one kind and their encoded value size
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FootprintEntry {
+    pub kind: Symbol,
+    pub entries: u32,
+    /// Encoded (XDR) value size in bytes, excluding keys
+    pub bytes: u32,
+}
+
+/// Storage footprint broken down by entry kind
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StorageFootprint {
+    pub entries: Vec<FootprintEntry>,
+    pub total_entries: u32,
+    pub total_bytes: u32,
+}
+
+impl StorageFootprint {
+    fn new(env: &Env) -> Self {
+        Self {
+            entries: Vec::new(env),
+            total_entries: 0,
+            total_bytes: 0,
+        }
+    }
+
+    fn add(&mut self, env: &Env, kind: &str, entries: u32, bytes: u32) {
+        if entries == 0 {
+            return;
+        }
+        self.entries.push_back(FootprintEntry {
+            kind: Symbol::new(env, kind),
+            entries,
+            bytes,
+        });
+        self.total_entries += entries;
+        self.total_bytes += bytes;
+    }
+
+    fn add_value<T: IntoVal<Env, Val> + Clone>(&mut self, env: &Env, kind: &str, value: &T) {
+        let bytes = value.clone().to_xdr(env).len();
+        self.add(env, kind, 1, bytes);
+    }
+}
+
+/// Footprint estimation
+pub struct StorageFootprintReporter;
+
+impl StorageFootprintReporter {
+    /// Entries attributable to a single user
+    pub fn user_footprint(env: &Env, user: &Address) -> StorageFootprint {
+        let mut footprint = StorageFootprint::new(env);
+
+        if let Some(position) = StateHelper::get_position(env, user) {
+            footprint.add_value(env, "position", &position);
+        }
+        if let Some(profile) = UserManager::find_profile(env, user) {
+            footprint.add_value(env, "profile", &profile);
+        }
+        if let Some(analytics) = AnalyticsStorage::find_user_analytics(env, user) {
+            footprint.add_value(env, "analytics", &analytics);
+        }
+        if let Some(adapter) = AuthAdapterStorage::get(env, user) {
+            footprint.add_value(env, "auth_adapter", &adapter);
+        }
+        if let Some(election) = InsuranceStorage::find_election(env, user) {
+            footprint.add_value(env, "coverage_election", &election);
+        }
+
+        footprint
+    }
+
+    /// Protocol-wide shared entries. Liquidation records are estimated from the latest one.
+    pub fn protocol_footprint(env: &Env) -> StorageFootprint {
+        let mut footprint = StorageFootprint::new(env);
+
+        footprint.add_value(env, "emergency_state", &EmergencyStorage::get(env));
+        footprint.add_value(env, "risk_config", &RiskConfigStorage::get(env));
+        footprint.add_value(
+            env,
+            "interest_config",
+            &InterestRateStorage::get_config(env),
+        );
+        footprint.add_value(env, "interest_state", &InterestRateStorage::get_state(env));
+        footprint.add_value(
+            env,
+            "protocol_metrics",
+            &AnalyticsStorage::get_protocol_metrics(env),
+        );
+        footprint.add_value(
+            env,
+            "analytics_users",
+            &AnalyticsStorage::get_analytics_users(env),
+        );
+        footprint.add_value(
+            env,
+            "activity_log",
+            &AnalyticsStorage::get_activity_log(env),
+        );
+        let mut log_pages = 0u32;
+        let mut log_bytes = 0u32;
+        for page in EventStorage::retained_pages(env).iter() {
+            log_pages += 1;
+            log_bytes = log_bytes.saturating_add(page.events.to_xdr(env).len());
+        }
+        footprint.add(env, "event_pages", log_pages, log_bytes);
+        footprint.add_value(env, "event_aggregates", &EventStorage::get_aggregates(env));
+
+        let liquidations = AnalyticsStorage::get_liquidation_count(env);
+        if let Some(latest) = AnalyticsStorage::get_liquidation_record(env, liquidations) {
+            let bytes = latest.to_xdr(env).len();
+            footprint.add(
+                env,
+                "liquidation_records",
+                liquidations as u32,
+                bytes.saturating_mul(liquidations as u32),
+            );
+        }
+
+        let mut snapshot_bytes = 0u32;
+        let days = AnalyticsStorage::get_snapshot_days(env);
+        for day in days.iter() {
+            if let Some(snapshot) = AnalyticsStorage::get_snapshot(env, day) {
+                snapshot_bytes += snapshot.to_xdr(env).len();
+            }
+        }
+        footprint.add(env, "snapshots", days.len(), snapshot_bytes);
+
+        let mut error_bytes = 0u32;
+        let buckets = AnalyticsStorage::get_error_buckets(env);
+        for bucket in buckets.iter() {
+            error_bytes += AnalyticsStorage::get_error_bucket(env, bucket)
+                .to_xdr(env)
+                .len();
+        }
+        footprint.add(env, "error_stats", buckets.len(), error_bytes);
+
+        footprint
+    }
+}
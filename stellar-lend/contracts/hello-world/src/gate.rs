@@ -0,0 +1,217 @@
+//! Operation gate for StellarLend protocol
+//! The single check every user-facing flow runs before moving funds. In order it applies
+//! the emergency status (including the keeper watchdog and Recovery unwind stages), the
+//! risk config pause switches, the asset's own pause flags, the user's profile, limits
+//! and quarantine, compliance screening, the Recovery utilization cap, and the asset's
+//! circuit breaker. Flows on the primary market pass no asset and are checked against
+//! the registered primary asset.
+
+use crate::circuit_breaker::CircuitBreakers;
+use crate::compliance::Compliance;
+use crate::persistent::PersistentStore;
+use crate::unwind::RecoveryUnwind;
+use crate::watchdog::Watchdog;
+use crate::{
+    EmergencyManager, OperationKind, ProtocolError, RiskConfigStorage, TokenRegistry, UserManager,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Pause switches of a single asset, on top of the protocol-wide ones
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetPauseFlags {
+    pub pause_deposit: bool,
+    pub pause_borrow: bool,
+    pub pause_withdraw: bool,
+    pub pause_liquidate: bool,
+}
+
+impl AssetPauseFlags {
+    fn pauses(&self, operation: OperationKind) -> bool {
+        match operation {
+            OperationKind::Deposit => self.pause_deposit,
+            OperationKind::Borrow => self.pause_borrow,
+            OperationKind::Withdraw => self.pause_withdraw,
+            OperationKind::Liquidate => self.pause_liquidate,
+            _ => false,
+        }
+    }
+}
+
+/// Whether an operation is open protocol-wide, for frontends
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OperationGateStatus {
+    pub operation: Symbol,
+    /// Asset checked; the primary asset when none was given
+    pub asset: Option<Address>,
+    pub allowed: bool,
+    /// Layer that blocks the operation: `emergency`, `risk_pause`, `asset_pause`,
+    /// `recovery_cap` or `circuit_breaker`
+    pub blocked_by: Option<Symbol>,
+    /// Error code the operation would fail with, 0 when allowed
+    pub error_code: u32,
+}
+
+/// Storage helper for per-asset pause flags
+pub struct AssetPauseStorage;
+
+impl AssetPauseStorage {
+    fn key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "asset_pause"), asset.clone())
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> AssetPauseFlags {
+        PersistentStore::get(env, &Self::key(env, asset)).unwrap_or_default()
+    }
+
+    pub fn save(env: &Env, asset: &Address, flags: &AssetPauseFlags) {
+        let key = Self::key(env, asset);
+        if *flags == AssetPauseFlags::default() {
+            PersistentStore::remove(env, &key);
+        } else {
+            PersistentStore::set(env, &key, flags);
+        }
+    }
+}
+
+/// Combined pause, limit and breaker checks
+pub struct OperationGate;
+
+impl OperationGate {
+    /// Reject `operation` by `user` for `amount` of `asset` (the primary asset when `None`)
+    /// unless every layer allows it
+    pub fn check(
+        env: &Env,
+        user: &Address,
+        operation: OperationKind,
+        asset: Option<&Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        EmergencyManager::ensure_operation_allowed(env, operation)?;
+        RiskConfigStorage::get(env).ensure_not_paused(operation)?;
+        let asset = Self::resolve(env, asset);
+        if let Some(asset) = &asset {
+            if AssetPauseStorage::get(env, asset).pauses(operation) {
+                return Err(ProtocolError::ProtocolPaused);
+            }
+        }
+        UserManager::ensure_operation_allowed(env, user, operation, amount)?;
+        Compliance::evaluate(env, user, operation, amount)?;
+        if let Some(asset) = &asset {
+            RecoveryUnwind::ensure_within_cap(env, asset, operation, amount)?;
+            CircuitBreakers::ensure_allowed(env, asset, operation)?;
+        }
+        Ok(())
+    }
+
+    /// The user-independent layers of `check`, evaluated without writing anything. The
+    /// Recovery utilization cap blocks once it leaves no room for any amount.
+    pub fn status(
+        env: &Env,
+        operation: Symbol,
+        asset: Option<Address>,
+    ) -> Result<OperationGateStatus, ProtocolError> {
+        let kind = Self::parse_operation(env, &operation)?;
+        let asset = Self::resolve(env, asset.as_ref());
+        let blocked = Self::first_block(env, kind, asset.as_ref());
+        Ok(OperationGateStatus {
+            operation,
+            asset,
+            allowed: blocked.is_none(),
+            error_code: blocked
+                .as_ref()
+                .map(|(_, error)| *error as u32)
+                .unwrap_or(0),
+            blocked_by: blocked.map(|(layer, _)| Symbol::new(env, layer)),
+        })
+    }
+
+    /// Set an asset's pause flags (admin or emergency manager)
+    pub fn set_asset_pause_flags(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        flags: AssetPauseFlags,
+    ) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        if !EmergencyManager::is_authorized(env, caller) {
+            return Err(ProtocolError::Unauthorized);
+        }
+        AssetPauseStorage::save(env, asset, &flags);
+        env.events().publish(
+            (
+                Symbol::new(env, "asset_pause_updated"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "deposit"),
+                flags.pause_deposit,
+                Symbol::new(env, "borrow"),
+                flags.pause_borrow,
+                Symbol::new(env, "withdraw"),
+                flags.pause_withdraw,
+                Symbol::new(env, "liquidate"),
+                flags.pause_liquidate,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_asset_pause_flags(env: &Env, asset: &Address) -> AssetPauseFlags {
+        AssetPauseStorage::get(env, asset)
+    }
+
+    fn first_block(
+        env: &Env,
+        operation: OperationKind,
+        asset: Option<&Address>,
+    ) -> Option<(&'static str, ProtocolError)> {
+        let watched = !matches!(operation, OperationKind::Admin | OperationKind::Governance);
+        if watched && Watchdog::is_expired(env) {
+            return Some(("emergency", ProtocolError::ProtocolPaused));
+        }
+        if let Err(error) = EmergencyManager::check_status(env, operation) {
+            return Some(("emergency", error));
+        }
+        if let Err(error) = RiskConfigStorage::get(env).ensure_not_paused(operation) {
+            return Some(("risk_pause", error));
+        }
+        let asset = asset?;
+        if AssetPauseStorage::get(env, asset).pauses(operation) {
+            return Some(("asset_pause", ProtocolError::ProtocolPaused));
+        }
+        if let Err(error) = RecoveryUnwind::ensure_within_cap(env, asset, operation, 1) {
+            return Some(("recovery_cap", error));
+        }
+        if CircuitBreakers::is_blocking(env, asset, operation) {
+            return Some(("circuit_breaker", ProtocolError::CircuitBreakerTripped));
+        }
+        None
+    }
+
+    fn resolve(env: &Env, asset: Option<&Address>) -> Option<Address> {
+        match asset {
+            Some(asset) => Some(asset.clone()),
+            None => TokenRegistry::require_primary_asset(env).ok(),
+        }
+    }
+
+    pub fn parse_operation(env: &Env, operation: &Symbol) -> Result<OperationKind, ProtocolError> {
+        let kinds = [
+            ("deposit", OperationKind::Deposit),
+            ("borrow", OperationKind::Borrow),
+            ("repay", OperationKind::Repay),
+            ("withdraw", OperationKind::Withdraw),
+            ("liquidate", OperationKind::Liquidate),
+            ("flash_loan", OperationKind::FlashLoan),
+        ];
+        kinds
+            .iter()
+            .find(|(name, _)| Symbol::new(env, name) == *operation)
+            .map(|(_, kind)| *kind)
+            .ok_or(ProtocolError::InvalidInput)
+    }
+}
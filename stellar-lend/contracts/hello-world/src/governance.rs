@@ -1,18 +1,59 @@
+//! Governance module for StellarLend protocol
+//! Proposals carry parameter updates as (key, value) pairs. A proposal that reaches quorum
+//! with more votes for than against is queued behind a timelock, after which anyone can
+//! execute it; execution applies each pair through the emergency parameter update logic.
+//!
+//! Vote weight comes from the admin-selected `VotingPowerSource`: one vote per verified
+//! user, the voter's share of primary-asset collateral, or governance tokens staked with
+//! the contract before the proposal was created. Users may delegate their weight; a
+//! delegate's vote carries the weight of every delegator who has not voted yet.
+
 #![allow(dead_code)]
-use soroban_sdk::{contracttype, Address, Env, Map, Symbol};
+use crate::persistent::PersistentStore;
+use crate::{
+    EmergencyManager, EmergencyParamUpdate, ProtocolConfig, ProtocolError, StateHelper,
+    TokenRegistry, TransferEnforcer, UserManager,
+};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol, Vec};
+
+/// Maximum number of parameter updates carried by one proposal
+const MAX_ACTIONS: u32 = 10;
+/// Stake checkpoints retained per user; older ones are dropped
+const MAX_CHECKPOINTS: u32 = 16;
+/// Delegators one address may represent
+const MAX_DELEGATORS: u32 = 20;
+/// Collateral-share weights are expressed in basis points of total supply
+const SHARE_SCALE: i128 = 10_000;
+
+/// Where vote weight comes from
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum VotingPowerSource {
+    /// One vote per verified user
+    PerVerifiedUser,
+    /// Share of total primary-asset supply held as collateral, in basis points
+    Collateral,
+    /// Governance token staked with the contract before the proposal was created
+    Token(Address),
+}
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
 pub struct Proposal {
     pub id: u64,
     pub proposer: Address,
-    pub title: soroban_sdk::String,
+    pub title: String,
+    /// Parameter updates applied on execution, in order
+    pub actions: Vec<(Symbol, i128)>,
     pub created: u64,
     pub voting_ends: u64,
+    /// Earliest execution time once queued, zero before
     pub queued_until: u64,
     pub for_votes: i128,
     pub against_votes: i128,
     pub executed: bool,
+    /// Total primary-asset supply at creation, the base for collateral-share weights
+    pub total_supplied: i128,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -23,26 +64,57 @@ pub struct VoteReceipt {
     pub weight: i128,
 }
 
+/// Voting period, quorum and timelock applied to new proposals
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct GovConfig {
+    /// Seconds a proposal accepts votes
+    pub voting_period: u64,
+    /// Minimum total vote weight for a proposal to pass
+    pub quorum: i128,
+    /// Seconds between queueing and execution
+    pub timelock: u64,
+}
+
+impl GovConfig {
+    pub fn default_config() -> Self {
+        Self {
+            voting_period: 3 * 24 * 60 * 60,
+            quorum: 1,
+            timelock: 2 * 24 * 60 * 60,
+        }
+    }
+}
+
 pub struct GovStorage;
 
 impl GovStorage {
-    fn proposals_key(env: &Env) -> Symbol {
-        Symbol::new(env, "gov_proposals")
+    fn proposal_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "gov_proposal"), id)
     }
-    fn receipts_key(env: &Env) -> Symbol {
-        Symbol::new(env, "gov_receipts")
+    fn receipt_key(env: &Env, id: u64, voter: &Address) -> (Symbol, u64, Address) {
+        (Symbol::new(env, "gov_receipt"), id, voter.clone())
     }
     fn counter_key(env: &Env) -> Symbol {
         Symbol::new(env, "gov_counter")
     }
-    fn quorum_bps_key(env: &Env) -> Symbol {
-        Symbol::new(env, "gov_quorum_bps")
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "gov_config")
+    }
+    fn power_source_key(env: &Env) -> Symbol {
+        Symbol::new(env, "gov_power")
     }
-    fn timelock_key(env: &Env) -> Symbol {
-        Symbol::new(env, "gov_timelock")
+    fn total_staked_key(env: &Env) -> Symbol {
+        Symbol::new(env, "gov_total_staked")
     }
-    fn delegation_key(env: &Env) -> Symbol {
-        Symbol::new(env, "gov_delegation")
+    fn checkpoints_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "gov_stake"), user.clone())
+    }
+    fn delegation_key(env: &Env, from: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "gov_delegation"), from.clone())
+    }
+    fn delegators_key(env: &Env, to: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "gov_delegators"), to.clone())
     }
 
     pub fn next_id(env: &Env) -> u64 {
@@ -58,92 +130,393 @@ impl GovStorage {
     }
 
     pub fn save_proposal(env: &Env, p: &Proposal) {
-        let mut map: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&Self::proposals_key(env))
-            .unwrap_or_else(|| Map::new(env));
-        map.set(p.id, p.clone());
-        env.storage()
-            .instance()
-            .set(&Self::proposals_key(env), &map);
+        PersistentStore::set(env, &Self::proposal_key(env, p.id), p);
     }
 
     pub fn get_proposal(env: &Env, id: u64) -> Option<Proposal> {
-        let map: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&Self::proposals_key(env))
-            .unwrap_or_else(|| Map::new(env));
-        map.get(id)
+        PersistentStore::get(env, &Self::proposal_key(env, id))
     }
 
     pub fn save_receipt(env: &Env, id: u64, r: &VoteReceipt) {
-        let key = (Self::receipts_key(env), id);
-        let mut map: Map<Address, VoteReceipt> = env
-            .storage()
+        PersistentStore::set(env, &Self::receipt_key(env, id, &r.voter), r);
+    }
+
+    pub fn get_receipt(env: &Env, id: u64, voter: &Address) -> Option<VoteReceipt> {
+        PersistentStore::get(env, &Self::receipt_key(env, id, voter))
+    }
+
+    pub fn get_config(env: &Env) -> GovConfig {
+        env.storage()
             .instance()
-            .get(&key)
-            .unwrap_or_else(|| Map::new(env));
-        map.set(r.voter.clone(), r.clone());
-        env.storage().instance().set(&key, &map);
+            .get(&Self::config_key(env))
+            .unwrap_or_else(GovConfig::default_config)
     }
 
-    pub fn get_quorum_bps(env: &Env) -> i128 {
+    pub fn save_config(env: &Env, config: &GovConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get_power_source(env: &Env) -> VotingPowerSource {
         env.storage()
             .instance()
-            .get(&Self::quorum_bps_key(env))
-            .unwrap_or(1000)
+            .get(&Self::power_source_key(env))
+            .unwrap_or(VotingPowerSource::PerVerifiedUser)
     }
-    pub fn set_quorum_bps(env: &Env, bps: i128) {
+
+    pub fn save_power_source(env: &Env, source: &VotingPowerSource) {
         env.storage()
             .instance()
-            .set(&Self::quorum_bps_key(env), &bps);
+            .set(&Self::power_source_key(env), source);
     }
-    pub fn get_timelock(env: &Env) -> u64 {
+
+    pub fn get_total_staked(env: &Env) -> i128 {
         env.storage()
             .instance()
-            .get(&Self::timelock_key(env))
-            .unwrap_or(60)
+            .get(&Self::total_staked_key(env))
+            .unwrap_or(0)
     }
-    pub fn set_timelock(env: &Env, secs: u64) {
+
+    fn save_total_staked(env: &Env, total: i128) {
         env.storage()
             .instance()
-            .set(&Self::timelock_key(env), &secs);
+            .set(&Self::total_staked_key(env), &total);
+    }
+
+    /// Staked balance history as (timestamp, balance) pairs, oldest first
+    pub fn get_checkpoints(env: &Env, user: &Address) -> Vec<(u64, i128)> {
+        PersistentStore::get(env, &Self::checkpoints_key(env, user)).unwrap_or(Vec::new(env))
+    }
+
+    /// Record `balance` as of now, replacing a checkpoint taken at the same timestamp
+    fn push_checkpoint(env: &Env, user: &Address, balance: i128) {
+        let now = env.ledger().timestamp();
+        let mut points = Self::get_checkpoints(env, user);
+        if let Some((ts, _)) = points.last() {
+            if ts == now {
+                points.pop_back();
+            }
+        }
+        points.push_back((now, balance));
+        while points.len() > MAX_CHECKPOINTS {
+            points.pop_front();
+        }
+        PersistentStore::set(env, &Self::checkpoints_key(env, user), &points);
+    }
+
+    pub fn get_delegate(env: &Env, from: &Address) -> Option<Address> {
+        PersistentStore::get(env, &Self::delegation_key(env, from))
+    }
+
+    pub fn get_delegators(env: &Env, to: &Address) -> Vec<Address> {
+        PersistentStore::get(env, &Self::delegators_key(env, to)).unwrap_or(Vec::new(env))
+    }
+
+    fn save_delegators(env: &Env, to: &Address, delegators: &Vec<Address>) {
+        let key = Self::delegators_key(env, to);
+        if delegators.is_empty() {
+            PersistentStore::remove(env, &key);
+        } else {
+            PersistentStore::set(env, &key, delegators);
+        }
+    }
+
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::checkpoints_key(env, user)) as u32
+            + PersistentStore::bump(env, &Self::delegation_key(env, user)) as u32
+            + PersistentStore::bump(env, &Self::delegators_key(env, user)) as u32
     }
 }
 
 pub struct Governance;
 
 impl Governance {
+    /// Set the voting period, quorum and timelock for new proposals (admin only)
+    pub fn set_config(env: &Env, caller: &Address, config: GovConfig) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.voting_period == 0 || config.quorum <= 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        GovStorage::save_config(env, &config);
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env) -> GovConfig {
+        GovStorage::get_config(env)
+    }
+
+    /// Choose where vote weight comes from for proposals voted on from now (admin only).
+    /// The staked token can only change while nothing is staked.
+    pub fn set_power_source(
+        env: &Env,
+        caller: &Address,
+        source: VotingPowerSource,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let current = GovStorage::get_power_source(env);
+        let is_token = matches!(current, VotingPowerSource::Token(_));
+        if is_token && current != source && GovStorage::get_total_staked(env) > 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        GovStorage::save_power_source(env, &source);
+        Ok(())
+    }
+
+    pub fn get_power_source(env: &Env) -> VotingPowerSource {
+        GovStorage::get_power_source(env)
+    }
+
+    fn is_verified(env: &Env, user: &Address) -> bool {
+        UserManager::get_profile(env, user)
+            .verification
+            .is_verified()
+    }
+
+    /// Governance token staked by `user` at the end of the last checkpoint before `at`;
+    /// zero when the retained history starts later
+    pub fn staked_at(env: &Env, user: &Address, at: u64) -> i128 {
+        let mut balance = 0;
+        for (ts, amount) in GovStorage::get_checkpoints(env, user).iter() {
+            if ts >= at {
+                break;
+            }
+            balance = amount;
+        }
+        balance
+    }
+
+    pub fn staked(env: &Env, user: &Address) -> i128 {
+        GovStorage::get_checkpoints(env, user)
+            .last()
+            .map(|(_, amount)| amount)
+            .unwrap_or(0)
+    }
+
+    fn stake_token(env: &Env) -> Result<Address, ProtocolError> {
+        match GovStorage::get_power_source(env) {
+            VotingPowerSource::Token(token) => Ok(token),
+            _ => Err(ProtocolError::InvalidOperation),
+        }
+    }
+
+    /// Lock governance tokens with the contract; they count toward proposals created
+    /// after this call
+    pub fn stake(env: &Env, user: &Address, amount: i128) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        let token = Self::stake_token(env)?;
+        let received = TransferEnforcer::transfer_in_asset(
+            env,
+            &token,
+            user,
+            amount,
+            Symbol::new(env, "gov_stake"),
+        )?;
+        let balance = Self::staked(env, user) + received;
+        GovStorage::push_checkpoint(env, user, balance);
+        GovStorage::save_total_staked(env, GovStorage::get_total_staked(env) + received);
+        Self::publish_stake(env, "gov_staked", user, received, balance);
+        Ok(balance)
+    }
+
+    /// Return staked governance tokens. Open proposals keep the weight snapshotted at
+    /// their creation.
+    pub fn unstake(env: &Env, user: &Address, amount: i128) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        let token = Self::stake_token(env)?;
+        let staked = Self::staked(env, user);
+        if amount <= 0 || amount > staked {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let balance = staked - amount;
+        GovStorage::push_checkpoint(env, user, balance);
+        GovStorage::save_total_staked(env, GovStorage::get_total_staked(env) - amount);
+        TransferEnforcer::transfer_out_asset(
+            env,
+            &token,
+            user,
+            amount,
+            Symbol::new(env, "gov_unstake"),
+        )?;
+        Self::publish_stake(env, "gov_unstaked", user, amount, balance);
+        Ok(balance)
+    }
+
+    fn publish_stake(env: &Env, action: &str, user: &Address, amount: i128, balance: i128) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+                Symbol::new(env, "balance"),
+                balance,
+            ),
+        );
+    }
+
+    /// Total primary-asset supply, the denominator of collateral-share weights
+    fn total_supplied(env: &Env) -> i128 {
+        TokenRegistry::require_primary_asset(env)
+            .map(|asset| crate::tvl_cap::TvlCapStorage::get_asset_tvl(env, &asset))
+            .unwrap_or(0)
+    }
+
+    /// Own vote weight of `voter` on a proposal, excluding delegated weight. Verified
+    /// users only.
+    pub fn voting_power(env: &Env, voter: &Address, proposal: &Proposal) -> i128 {
+        if !Self::is_verified(env, voter) {
+            return 0;
+        }
+        match GovStorage::get_power_source(env) {
+            VotingPowerSource::PerVerifiedUser => 1,
+            VotingPowerSource::Collateral => {
+                if proposal.total_supplied <= 0 {
+                    return 0;
+                }
+                let collateral = StateHelper::get_position(env, voter)
+                    .map(|p| p.collateral)
+                    .unwrap_or(0);
+                (collateral * SHARE_SCALE / proposal.total_supplied).min(SHARE_SCALE)
+            }
+            VotingPowerSource::Token(_) => Self::staked_at(env, voter, proposal.created),
+        }
+    }
+
+    /// Hand `from`'s vote weight to `to`, or take it back when `to` is `from`. A user
+    /// with a delegate cannot vote directly.
+    pub fn delegate(env: &Env, from: &Address, to: &Address) -> Result<(), ProtocolError> {
+        from.require_auth();
+        if let Some(current) = GovStorage::get_delegate(env, from) {
+            let mut delegators = GovStorage::get_delegators(env, &current);
+            if let Some(idx) = delegators.first_index_of(from) {
+                delegators.remove(idx);
+            }
+            GovStorage::save_delegators(env, &current, &delegators);
+            PersistentStore::remove(env, &GovStorage::delegation_key(env, from));
+        }
+        if from != to {
+            // Delegation is one level deep: delegates cannot pass weight on
+            let chained = GovStorage::get_delegate(env, to).is_some()
+                || !GovStorage::get_delegators(env, from).is_empty();
+            if chained {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            let mut delegators = GovStorage::get_delegators(env, to);
+            if delegators.len() >= MAX_DELEGATORS {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            delegators.push_back(from.clone());
+            GovStorage::save_delegators(env, to, &delegators);
+            PersistentStore::set(env, &GovStorage::delegation_key(env, from), to);
+        }
+        env.events().publish(
+            (
+                Symbol::new(env, "votes_delegated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                from.clone(),
+                Symbol::new(env, "delegate"),
+                to.clone(),
+            ),
+        );
+        Ok(())
+    }
+
+    fn publish(env: &Env, action: &str, id: u64, actor: &Address) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "proposal")),
+            (
+                Symbol::new(env, "proposal"),
+                id,
+                Symbol::new(env, "actor"),
+                actor.clone(),
+            ),
+        );
+    }
+
+    /// Open a proposal for the configured voting period. Only verified users may propose,
+    /// and every action must name a known parameter key.
     pub fn propose(
         env: &Env,
         proposer: &Address,
-        title: soroban_sdk::String,
-        voting_period_secs: u64,
-    ) -> Proposal {
+        title: String,
+        actions: Vec<(Symbol, i128)>,
+    ) -> Result<Proposal, ProtocolError> {
+        proposer.require_auth();
+        if !Self::is_verified(env, proposer) {
+            return Err(ProtocolError::UserNotVerified);
+        }
+        if title.is_empty() || actions.is_empty() || actions.len() > MAX_ACTIONS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        for (key, _) in actions.iter() {
+            if !EmergencyManager::is_param_key(env, &key) {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+
         let now = env.ledger().timestamp();
-        let id = GovStorage::next_id(env);
         let p = Proposal {
-            id,
+            id: GovStorage::next_id(env),
             proposer: proposer.clone(),
             title,
+            actions,
             created: now,
-            voting_ends: now + voting_period_secs,
+            voting_ends: now + GovStorage::get_config(env).voting_period,
             queued_until: 0,
             for_votes: 0,
             against_votes: 0,
             executed: false,
+            total_supplied: Self::total_supplied(env),
         };
         GovStorage::save_proposal(env, &p);
-        p
+        Self::publish(env, "proposal_created", p.id, proposer);
+        Ok(p)
     }
 
-    pub fn vote(env: &Env, id: u64, voter: &Address, support: bool, weight: i128) -> Proposal {
-        let mut p = GovStorage::get_proposal(env, id).unwrap();
+    /// Cast `voter`'s weight, plus that of delegators who have not voted, for or against a
+    /// proposal, once, before voting ends. Delegators get receipts so they cannot be
+    /// counted twice.
+    pub fn vote(
+        env: &Env,
+        id: u64,
+        voter: &Address,
+        support: bool,
+    ) -> Result<Proposal, ProtocolError> {
+        voter.require_auth();
+        let mut p = GovStorage::get_proposal(env, id).ok_or(ProtocolError::NotFound)?;
         if env.ledger().timestamp() > p.voting_ends {
-            return p;
+            return Err(ProtocolError::InvalidOperation);
+        }
+        if GovStorage::get_receipt(env, id, voter).is_some() {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        if GovStorage::get_delegate(env, voter).is_some() {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let mut weight = Self::voting_power(env, voter, &p);
+        for delegator in GovStorage::get_delegators(env, voter).iter() {
+            if GovStorage::get_receipt(env, id, &delegator).is_some() {
+                continue;
+            }
+            let delegated = Self::voting_power(env, &delegator, &p);
+            weight += delegated;
+            GovStorage::save_receipt(
+                env,
+                id,
+                &VoteReceipt {
+                    voter: delegator.clone(),
+                    support,
+                    weight: delegated,
+                },
+            );
         }
+        if weight <= 0 {
+            return Err(ProtocolError::UserNotVerified);
+        }
+
         if support {
             p.for_votes += weight;
         } else {
@@ -159,43 +532,57 @@ impl Governance {
             },
         );
         GovStorage::save_proposal(env, &p);
-        p
+        Self::publish(env, "proposal_voted", id, voter);
+        Ok(p)
     }
 
-    pub fn queue(env: &Env, id: u64) -> Proposal {
-        let mut p = GovStorage::get_proposal(env, id).unwrap();
+    /// Start the timelock of a proposal that passed: voting has ended, total weight meets
+    /// the quorum and more weight was cast for than against
+    pub fn queue(env: &Env, caller: &Address, id: u64) -> Result<Proposal, ProtocolError> {
+        caller.require_auth();
+        let mut p = GovStorage::get_proposal(env, id).ok_or(ProtocolError::NotFound)?;
         let now = env.ledger().timestamp();
-        let quorum = GovStorage::get_quorum_bps(env);
-        let total = p.for_votes + p.against_votes;
-        let have_quorum = if total == 0 {
-            false
-        } else {
-            (p.for_votes * 10000 / total) >= quorum
-        };
-        if have_quorum && now >= p.voting_ends {
-            p.queued_until = now + GovStorage::get_timelock(env);
+        let config = GovStorage::get_config(env);
+        let passed =
+            p.for_votes + p.against_votes >= config.quorum && p.for_votes > p.against_votes;
+        if now <= p.voting_ends || p.queued_until != 0 || !passed {
+            return Err(ProtocolError::InvalidOperation);
         }
+        p.queued_until = now + config.timelock;
         GovStorage::save_proposal(env, &p);
-        p
+        Self::publish(env, "proposal_queued", id, caller);
+        Ok(p)
     }
 
-    pub fn execute(env: &Env, id: u64) -> Proposal {
-        let mut p = GovStorage::get_proposal(env, id).unwrap();
-        let now = env.ledger().timestamp();
-        if now >= p.queued_until && p.queued_until != 0 {
-            p.executed = true;
+    /// Apply a queued proposal's parameter updates once its timelock has elapsed
+    pub fn execute(env: &Env, caller: &Address, id: u64) -> Result<Proposal, ProtocolError> {
+        caller.require_auth();
+        let mut p = GovStorage::get_proposal(env, id).ok_or(ProtocolError::NotFound)?;
+        if p.executed {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        if p.queued_until == 0 || env.ledger().timestamp() < p.queued_until {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        for (key, value) in p.actions.iter() {
+            let update = EmergencyParamUpdate::new(env, key, value, p.proposer.clone());
+            EmergencyManager::apply_single_update(env, &update)?;
         }
+        p.executed = true;
         GovStorage::save_proposal(env, &p);
-        p
+        Self::publish(env, "proposal_executed", id, caller);
+        Ok(p)
+    }
+
+    pub fn get_proposal(env: &Env, id: u64) -> Option<Proposal> {
+        GovStorage::get_proposal(env, id)
     }
 
-    pub fn delegate(env: &Env, from: &Address, to: &Address) {
-        let key = (GovStorage::delegation_key(env), from.clone());
-        env.storage().instance().set(&key, to);
+    pub fn get_receipt(env: &Env, id: u64, voter: &Address) -> Option<VoteReceipt> {
+        GovStorage::get_receipt(env, id, voter)
     }
 
     pub fn get_delegate(env: &Env, from: &Address) -> Option<Address> {
-        let key = (GovStorage::delegation_key(env), from.clone());
-        env.storage().instance().get(&key)
+        GovStorage::get_delegate(env, from)
     }
 }
@@ -0,0 +1,223 @@
+//! Guardian module for StellarLend protocol
+//! M-of-N guardian confirmations for pausing, entering recovery and applying queued
+//! emergency parameter updates. While a guardian set is configured these actions no longer
+//! run on a single manager's call.
+
+use crate::{EmergencyManager, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol, Vec};
+
+/// Largest guardian set the admin may configure
+const MAX_GUARDIANS: u32 = 10;
+
+/// Guardian set, confirmation threshold and the window confirmations must land in
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct GuardianConfig {
+    pub guardians: Vec<Address>,
+    pub threshold: u32,
+    /// Seconds from the first confirmation within which the threshold must be reached
+    pub window: u64,
+}
+
+/// Emergency action executed once enough guardians confirm it
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum GuardianAction {
+    Pause(Option<String>),
+    EnterRecovery(Option<String>),
+    ApplyParamUpdates,
+}
+
+impl GuardianAction {
+    fn as_symbol(&self, env: &Env) -> Symbol {
+        match self {
+            GuardianAction::Pause(_) => Symbol::new(env, "pause"),
+            GuardianAction::EnterRecovery(_) => Symbol::new(env, "recovery"),
+            GuardianAction::ApplyParamUpdates => Symbol::new(env, "apply_params"),
+        }
+    }
+}
+
+/// Confirmations collected for an action, one round per action kind
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct GuardianRequest {
+    pub action: GuardianAction,
+    pub confirmations: Vec<Address>,
+    pub opened_at: u64,
+}
+
+/// Storage helper for guardian configuration and pending requests
+pub struct GuardianStorage;
+
+impl GuardianStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "guardian_config")
+    }
+    fn request_key(env: &Env, kind: Symbol) -> (Symbol, Symbol) {
+        (Symbol::new(env, "guardian_req"), kind)
+    }
+
+    pub fn get_config(env: &Env) -> Option<GuardianConfig> {
+        env.storage().instance().get(&Self::config_key(env))
+    }
+
+    pub fn save_config(env: &Env, config: &GuardianConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn remove_config(env: &Env) {
+        env.storage().instance().remove(&Self::config_key(env));
+    }
+
+    pub fn get_request(env: &Env, kind: Symbol) -> Option<GuardianRequest> {
+        env.storage().instance().get(&Self::request_key(env, kind))
+    }
+
+    pub fn save_request(env: &Env, request: &GuardianRequest) {
+        let key = Self::request_key(env, request.action.as_symbol(env));
+        env.storage().instance().set(&key, request);
+    }
+
+    pub fn remove_request(env: &Env, kind: Symbol) {
+        env.storage()
+            .instance()
+            .remove(&Self::request_key(env, kind));
+    }
+}
+
+/// Guardian multi-sig coordinator
+pub struct Guardians;
+
+impl Guardians {
+    /// Whether emergency actions currently need guardian confirmations
+    pub fn is_enabled(env: &Env) -> bool {
+        GuardianStorage::get_config(env).is_some()
+    }
+
+    /// Replace the guardian set (admin only). An empty set turns the scheme off and drops
+    /// pending requests; otherwise the threshold must be between 1 and the set size.
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: GuardianConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        for action in [
+            GuardianAction::Pause(None),
+            GuardianAction::EnterRecovery(None),
+            GuardianAction::ApplyParamUpdates,
+        ] {
+            GuardianStorage::remove_request(env, action.as_symbol(env));
+        }
+        if config.guardians.is_empty() {
+            GuardianStorage::remove_config(env);
+            Self::publish_config(env, 0, 0);
+            return Ok(());
+        }
+
+        let count = config.guardians.len();
+        if count > MAX_GUARDIANS
+            || config.threshold == 0
+            || config.threshold > count
+            || config.window == 0
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        for (idx, guardian) in config.guardians.iter().enumerate() {
+            if config.guardians.iter().skip(idx + 1).any(|g| g == guardian) {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        GuardianStorage::save_config(env, &config);
+        Self::publish_config(env, count, config.threshold);
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env) -> Option<GuardianConfig> {
+        GuardianStorage::get_config(env)
+    }
+
+    pub fn get_request(env: &Env, action: &GuardianAction) -> Option<GuardianRequest> {
+        GuardianStorage::get_request(env, action.as_symbol(env))
+    }
+
+    /// Confirm an emergency action. Confirmations for the same action accumulate until the
+    /// threshold is met or the window since the first one closes; a confirmation for a
+    /// different payload of the same kind, or after the window, starts a new round.
+    ///
+    /// Returns whether this confirmation executed the action.
+    pub fn confirm(
+        env: &Env,
+        guardian: &Address,
+        action: GuardianAction,
+    ) -> Result<bool, ProtocolError> {
+        guardian.require_auth();
+        let config = GuardianStorage::get_config(env).ok_or(ProtocolError::InvalidOperation)?;
+        if !config.guardians.contains(guardian) {
+            return Err(ProtocolError::Unauthorized);
+        }
+
+        let now = env.ledger().timestamp();
+        let kind = action.as_symbol(env);
+        let mut request = match GuardianStorage::get_request(env, kind.clone()) {
+            Some(request)
+                if request.action == action && now <= request.opened_at + config.window =>
+            {
+                request
+            }
+            _ => GuardianRequest {
+                action: action.clone(),
+                confirmations: Vec::new(env),
+                opened_at: now,
+            },
+        };
+        if request.confirmations.contains(guardian) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        request.confirmations.push_back(guardian.clone());
+        let confirmations = request.confirmations.len();
+        env.events().publish(
+            (Symbol::new(env, "guardian_confirmed"), kind.clone()),
+            (
+                Symbol::new(env, "guardian"),
+                guardian.clone(),
+                Symbol::new(env, "confirmations"),
+                confirmations,
+                Symbol::new(env, "threshold"),
+                config.threshold,
+            ),
+        );
+
+        if confirmations < config.threshold {
+            GuardianStorage::save_request(env, &request);
+            return Ok(false);
+        }
+        GuardianStorage::remove_request(env, kind.clone());
+        match action {
+            GuardianAction::Pause(reason) => EmergencyManager::set_paused(env, guardian, reason),
+            GuardianAction::EnterRecovery(plan) => EmergencyManager::set_recovery(env, plan),
+            GuardianAction::ApplyParamUpdates => EmergencyManager::apply_pending_updates(env)?,
+        }
+        env.events().publish(
+            (Symbol::new(env, "guardian_executed"), kind),
+            (Symbol::new(env, "confirmations"), request.confirmations),
+        );
+        Ok(true)
+    }
+
+    fn publish_config(env: &Env, guardians: u32, threshold: u32) {
+        env.events().publish(
+            (
+                Symbol::new(env, "guardians_updated"),
+                Symbol::new(env, "config"),
+            ),
+            (
+                Symbol::new(env, "guardians"),
+                guardians,
+                Symbol::new(env, "threshold"),
+                threshold,
+            ),
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Health index module for StellarLend protocol
+//! Buckets borrowing positions by health factor on every position write, so keepers can
+//! find underwater positions without scanning every user. Callers who surface positions
+//! that went underwater since their last write earn a small bounty from the emergency fund.
+//! Each bucket entry is its own storage slot, and multi-asset positions are indexed
+//! separately from primary ones.
+
+use crate::cross_asset::CrossAssets;
+use crate::liquidate::LiquidationModule;
+use crate::persistent::PersistentStore;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{
+    CrossPosition, EmergencyManager, Position, ProtocolConfig, ProtocolError, StateHelper,
+    TokenRegistry, TransferEnforcer,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Health factor points per bucket (100 = exactly at the minimum ratio)
+const BUCKET_WIDTH: i128 = 10;
+
+/// Number of buckets; positions at or above `BUCKET_WIDTH * BUCKET_COUNT` are not indexed
+const BUCKET_COUNT: u32 = 20;
+
+/// Maximum positions returned by one `liquidatable` query
+const MAX_RESULTS: u32 = 50;
+
+/// Maximum users checked by one report
+const MAX_REPORT: u32 = 20;
+
+/// Maximum index entries read by one query
+const MAX_SCANNED: u32 = 200;
+
+/// Where a user sits in the index
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct HealthSlot {
+    pub bucket: u32,
+    pub slot: u32,
+}
+
+/// An underwater position with the size of the largest liquidation it accepts
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidatablePosition {
+    pub user: Address,
+    pub max_repay: i128,
+    pub collateral_seized: i128,
+}
+
+/// Storage helper for health buckets and report flags
+pub struct HealthIndexStorage;
+
+impl HealthIndexStorage {
+    fn len_key(env: &Env, cross: bool, bucket: u32) -> (Symbol, bool, u32) {
+        (Symbol::new(env, "hf_len"), cross, bucket)
+    }
+    fn slot_key(env: &Env, cross: bool, slot: &HealthSlot) -> (Symbol, bool, u32, u32) {
+        (Symbol::new(env, "hf_slot"), cross, slot.bucket, slot.slot)
+    }
+    fn user_key(env: &Env, cross: bool, user: &Address) -> (Symbol, bool, Address) {
+        (Symbol::new(env, "hf_user"), cross, user.clone())
+    }
+    fn flagged_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "hf_flagged"), user.clone())
+    }
+    fn bounty_key(env: &Env) -> Symbol {
+        Symbol::new(env, "hf_bounty")
+    }
+
+    pub fn bucket_len(env: &Env, cross: bool, bucket: u32) -> u32 {
+        PersistentStore::get(env, &Self::len_key(env, cross, bucket)).unwrap_or(0)
+    }
+
+    fn set_bucket_len(env: &Env, cross: bool, bucket: u32, len: u32) {
+        if len == 0 {
+            PersistentStore::remove(env, &Self::len_key(env, cross, bucket));
+        } else {
+            PersistentStore::set(env, &Self::len_key(env, cross, bucket), &len);
+        }
+    }
+
+    pub fn get_slot_user(env: &Env, cross: bool, slot: &HealthSlot) -> Option<Address> {
+        PersistentStore::get(env, &Self::slot_key(env, cross, slot))
+    }
+
+    pub fn get_user_slot(env: &Env, cross: bool, user: &Address) -> Option<HealthSlot> {
+        PersistentStore::get(env, &Self::user_key(env, cross, user))
+    }
+
+    fn put(env: &Env, cross: bool, user: &Address, slot: &HealthSlot) {
+        PersistentStore::set(env, &Self::slot_key(env, cross, slot), user);
+        PersistentStore::set(env, &Self::user_key(env, cross, user), slot);
+    }
+
+    /// Append `user` to the end of `bucket`
+    pub fn insert(env: &Env, cross: bool, user: &Address, bucket: u32) {
+        let len = Self::bucket_len(env, cross, bucket);
+        Self::put(env, cross, user, &HealthSlot { bucket, slot: len });
+        Self::set_bucket_len(env, cross, bucket, len + 1);
+    }
+
+    /// Take `user` out of its bucket, moving the bucket's last entry into the freed slot
+    pub fn remove(env: &Env, cross: bool, user: &Address) {
+        let Some(slot) = Self::get_user_slot(env, cross, user) else {
+            return;
+        };
+        let last = HealthSlot {
+            bucket: slot.bucket,
+            slot: Self::bucket_len(env, cross, slot.bucket) - 1,
+        };
+        if last.slot != slot.slot {
+            if let Some(moved) = Self::get_slot_user(env, cross, &last) {
+                Self::put(env, cross, &moved, &slot);
+            }
+        }
+        PersistentStore::remove(env, &Self::slot_key(env, cross, &last));
+        PersistentStore::remove(env, &Self::user_key(env, cross, user));
+        Self::set_bucket_len(env, cross, slot.bucket, last.slot);
+    }
+
+    pub fn is_flagged(env: &Env, user: &Address) -> bool {
+        PersistentStore::get(env, &Self::flagged_key(env, user)).unwrap_or(false)
+    }
+
+    pub fn set_flagged(env: &Env, user: &Address, flagged: bool) {
+        if flagged {
+            PersistentStore::set(env, &Self::flagged_key(env, user), &true);
+        } else {
+            PersistentStore::remove(env, &Self::flagged_key(env, user));
+        }
+    }
+
+    pub fn get_bounty(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::bounty_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn set_bounty(env: &Env, bounty: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::bounty_key(env), &bounty);
+    }
+
+    /// Extend the TTL of a user's index entries, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        let mut bumped = PersistentStore::bump(env, &Self::flagged_key(env, user)) as u32;
+        for cross in [false, true] {
+            if let Some(slot) = Self::get_user_slot(env, cross, user) {
+                bumped += PersistentStore::bump(env, &Self::user_key(env, cross, user)) as u32
+                    + PersistentStore::bump(env, &Self::slot_key(env, cross, &slot)) as u32
+                    + PersistentStore::bump(env, &Self::len_key(env, cross, slot.bucket)) as u32;
+            }
+        }
+        bumped
+    }
+}
+
+/// Health-factor index over primary and multi-asset positions
+pub struct HealthIndex;
+
+impl HealthIndex {
+    /// Health factor in percent of the minimum ratio, `None` without debt
+    fn health_factor(env: &Env, collateral: i128, debt: i128) -> Option<i128> {
+        if debt <= 0 {
+            return None;
+        }
+        let min_ratio = CollateralMatrix::effective_min_ratio(env);
+        if min_ratio <= 0 {
+            return None;
+        }
+        Some((collateral * 100 / debt) * 100 / min_ratio)
+    }
+
+    /// Health factor of a multi-asset position at registered prices, `None` without debt
+    /// or when an asset has no price
+    fn cross_health_factor(env: &Env, position: &CrossPosition) -> Option<i128> {
+        let health = CrossAssets::health(env, position).ok()?;
+        if health.debt_value <= 0 || health.min_ratio <= 0 {
+            return None;
+        }
+        Some(health.collateral_ratio * 100 / health.min_ratio)
+    }
+
+    /// Bucket for a health factor, `None` when healthy enough to leave unindexed
+    fn bucket_for(health_factor: Option<i128>) -> Option<u32> {
+        let bucket = health_factor? / BUCKET_WIDTH;
+        if bucket >= BUCKET_COUNT as i128 {
+            None
+        } else {
+            Some(bucket.max(0) as u32)
+        }
+    }
+
+    /// Move a user's primary (or, with `cross`, multi-asset) position to the bucket for
+    /// `health_factor`, clearing the primary report flag once it is back above water
+    fn place(env: &Env, cross: bool, user: &Address, health_factor: Option<i128>) {
+        let underwater = matches!(health_factor, Some(hf) if hf < 100);
+        if !cross && !underwater && HealthIndexStorage::is_flagged(env, user) {
+            HealthIndexStorage::set_flagged(env, user, false);
+        }
+        let bucket = Self::bucket_for(health_factor);
+        let previous = HealthIndexStorage::get_user_slot(env, cross, user).map(|s| s.bucket);
+        if bucket == previous {
+            return;
+        }
+        HealthIndexStorage::remove(env, cross, user);
+        if let Some(bucket) = bucket {
+            HealthIndexStorage::insert(env, cross, user, bucket);
+        }
+    }
+
+    /// Re-bucket a position after it is written
+    pub fn on_position_change(env: &Env, position: &Position) {
+        Self::place(
+            env,
+            false,
+            &position.user,
+            Self::health_factor(env, position.collateral, position.debt),
+        );
+    }
+
+    /// Re-bucket a multi-asset position after it is written
+    pub fn on_cross_position_change(env: &Env, position: &CrossPosition) {
+        Self::place(
+            env,
+            true,
+            &position.user,
+            Self::cross_health_factor(env, position),
+        );
+    }
+
+    /// Set the bounty paid per newly surfaced underwater position (admin only)
+    pub fn set_bounty(env: &Env, caller: &Address, bounty: i128) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if bounty < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        HealthIndexStorage::set_bounty(env, bounty);
+        Ok(())
+    }
+
+    pub fn get_bounty(env: &Env) -> i128 {
+        HealthIndexStorage::get_bounty(env)
+    }
+
+    /// Current bucket of a user's primary (or, with `cross`, multi-asset) position,
+    /// `None` when not indexed
+    pub fn user_bucket(env: &Env, cross: bool, user: &Address) -> Option<u32> {
+        HealthIndexStorage::get_user_slot(env, cross, user).map(|slot| slot.bucket)
+    }
+
+    /// Users in the index, riskiest buckets first, up to `MAX_SCANNED` entries
+    fn scan(env: &Env, cross: bool, buckets: u32) -> Vec<Address> {
+        let mut users = Vec::new(env);
+        for bucket in 0..buckets {
+            for slot in 0..HealthIndexStorage::bucket_len(env, cross, bucket) {
+                if users.len() >= MAX_SCANNED {
+                    return users;
+                }
+                if let Some(user) =
+                    HealthIndexStorage::get_slot_user(env, cross, &HealthSlot { bucket, slot })
+                {
+                    users.push_back(user);
+                }
+            }
+        }
+        users
+    }
+
+    /// Up to `limit` indexed positions that are liquidatable now, riskiest buckets first.
+    /// Each candidate is re-checked at its current debt, so stale buckets never produce
+    /// false positives.
+    pub fn liquidatable(env: &Env, limit: u32) -> Vec<LiquidatablePosition> {
+        let limit = limit.min(MAX_RESULTS);
+        let mut positions = Vec::new(env);
+        for user in Self::scan(env, false, BUCKET_COUNT).iter() {
+            if positions.len() >= limit {
+                break;
+            }
+            if let Ok(max) = LiquidationModule::max_liquidatable(env, &user) {
+                if max.debt_repaid > 0 {
+                    positions.push_back(LiquidatablePosition {
+                        user,
+                        max_repay: max.debt_repaid,
+                        collateral_seized: max.collateral_seized,
+                    });
+                }
+            }
+        }
+        positions
+    }
+
+    /// Up to `limit` users whose multi-asset position is below its minimum ratio now,
+    /// riskiest buckets first, re-checked at current prices
+    pub fn underwater_cross(env: &Env, limit: u32) -> Vec<Address> {
+        let limit = limit.min(MAX_RESULTS);
+        let mut users = Vec::new(env);
+        // Buckets below a health factor of 100
+        for user in Self::scan(env, true, 100 / BUCKET_WIDTH as u32).iter() {
+            if users.len() >= limit {
+                break;
+            }
+            let underwater = StateHelper::get_cross_position(env, &user)
+                .and_then(|position| Self::cross_health_factor(env, &position))
+                .is_some_and(|hf| hf < 100);
+            if underwater {
+                users.push_back(user);
+            }
+        }
+        users
+    }
+
+    /// Re-index `users`' primary and multi-asset positions and pay the caller the bounty
+    /// for each primary position that is liquidatable and was not already reported since it went underwater. The
+    /// bounty is drawn from the unreserved emergency fund and capped at what is available.
+    ///
+    /// Returns the bounty paid.
+    pub fn report(env: &Env, caller: &Address, users: Vec<Address>) -> Result<i128, ProtocolError> {
+        caller.require_auth();
+        if users.is_empty() || users.len() > MAX_REPORT {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let mut surfaced = 0i128;
+        for user in users.iter() {
+            if let Some(position) = StateHelper::get_cross_position(env, &user) {
+                Self::on_cross_position_change(env, &position);
+            }
+            let position = match StateHelper::get_position(env, &user) {
+                Some(position) => position,
+                None => continue,
+            };
+            let debt = LiquidationModule::accrued_debt(env, &position);
+            Self::place(
+                env,
+                false,
+                &user,
+                Self::health_factor(env, position.collateral, debt),
+            );
+            let liquidatable = LiquidationModule::max_liquidatable(env, &user)
+                .is_ok_and(|max| max.debt_repaid > 0);
+            if liquidatable && !HealthIndexStorage::is_flagged(env, &user) {
+                HealthIndexStorage::set_flagged(env, &user, true);
+                surfaced += 1;
+            }
+        }
+
+        let bounty = (surfaced * HealthIndexStorage::get_bounty(env))
+            .min(EmergencyManager::available_fund(env))
+            .max(0);
+        if bounty > 0 {
+            let token = match EmergencyManager::draw_fund(env, bounty)? {
+                Some(token) => token,
+                None => TokenRegistry::require_primary_asset(env)?,
+            };
+            TransferEnforcer::transfer_out_asset(
+                env,
+                &token,
+                caller,
+                bounty,
+                Symbol::new(env, "hf_bounty"),
+            )?;
+        }
+
+        env.events().publish(
+            (
+                Symbol::new(env, "underwater_reported"),
+                Symbol::new(env, "caller"),
+            ),
+            (
+                Symbol::new(env, "caller"),
+                caller.clone(),
+                Symbol::new(env, "surfaced"),
+                surfaced,
+                Symbol::new(env, "bounty"),
+                bounty,
+            ),
+        );
+        Ok(bounty)
+    }
+}
@@ -0,0 +1,305 @@
+//! Insurance cohort module for StellarLend protocol
+//! Tracks covered vs uncovered supply per asset so shortfall losses hit the right cohort
+
+use crate::persistent::PersistentStore;
+use crate::safety_module::SafetyModule;
+use crate::{ProtocolConfig, ProtocolError, StateHelper, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Default wait between coverage toggles (7 days)
+const DEFAULT_TOGGLE_COOLDOWN: u64 = 7 * 86400;
+
+/// Loss index scale
+const INDEX_SCALE: i128 = 100000000;
+
+/// Supply and loss accounting for the covered and uncovered cohorts of one asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CoverageCohorts {
+    pub asset: Address,
+    /// Supply from users keeping insurance coverage
+    pub covered_supply: i128,
+    /// Supply from users who opted out
+    pub uncovered_supply: i128,
+    /// Insurance reserve absorbing covered-cohort losses first
+    pub reserve: i128,
+    /// Cumulative loss per unit of covered supply (scaled by 1e8)
+    pub covered_loss_index: i128,
+    /// Cumulative loss per unit of uncovered supply (scaled by 1e8)
+    pub uncovered_loss_index: i128,
+    /// Lifetime losses written down across both cohorts
+    pub total_written_down: i128,
+}
+
+impl CoverageCohorts {
+    pub fn new(asset: Address) -> Self {
+        Self {
+            asset,
+            covered_supply: 0,
+            uncovered_supply: 0,
+            reserve: 0,
+            covered_loss_index: 0,
+            uncovered_loss_index: 0,
+            total_written_down: 0,
+        }
+    }
+}
+
+/// Per-user coverage election
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CoverageElection {
+    pub opted_out: bool,
+    pub last_toggle: u64,
+}
+
+/// Outcome of applying a shortfall to a cohort
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ShortfallOutcome {
+    /// Slashed from the asset's safety module stakers
+    pub absorbed_by_stakers: i128,
+    pub absorbed_by_reserve: i128,
+    pub written_down: i128,
+}
+
+/// Storage helper for insurance cohorts
+pub struct InsuranceStorage;
+
+impl InsuranceStorage {
+    fn cohorts_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "ins_cohorts"), asset.clone())
+    }
+    fn election_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "ins_election"), user.clone())
+    }
+    fn cooldown_key(env: &Env) -> Symbol {
+        Symbol::new(env, "ins_cooldown")
+    }
+
+    pub fn get_cohorts(env: &Env, asset: &Address) -> CoverageCohorts {
+        PersistentStore::get(env, &Self::cohorts_key(env, asset))
+            .unwrap_or_else(|| CoverageCohorts::new(asset.clone()))
+    }
+
+    pub fn save_cohorts(env: &Env, cohorts: &CoverageCohorts) {
+        PersistentStore::set(env, &Self::cohorts_key(env, &cohorts.asset), cohorts);
+    }
+
+    pub fn get_election(env: &Env, user: &Address) -> CoverageElection {
+        Self::find_election(env, user).unwrap_or(CoverageElection {
+            opted_out: false,
+            last_toggle: 0,
+        })
+    }
+
+    pub fn find_election(env: &Env, user: &Address) -> Option<CoverageElection> {
+        PersistentStore::get(env, &Self::election_key(env, user))
+    }
+
+    pub fn save_election(env: &Env, user: &Address, election: &CoverageElection) {
+        PersistentStore::set(env, &Self::election_key(env, user), election);
+    }
+
+    /// Extend the TTL of a user's coverage election, returning how many entries exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::election_key(env, user)) as u32
+    }
+
+    /// Extend the TTL of an asset's coverage cohorts, returning how many entries exist
+    pub fn bump_asset(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::cohorts_key(env, asset)) as u32
+    }
+
+    pub fn get_cooldown(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Self::cooldown_key(env))
+            .unwrap_or(DEFAULT_TOGGLE_COOLDOWN)
+    }
+
+    pub fn set_cooldown(env: &Env, secs: u64) {
+        env.storage()
+            .instance()
+            .set(&Self::cooldown_key(env), &secs);
+    }
+}
+
+/// Coverage cohort accounting
+pub struct InsuranceCohorts;
+
+impl InsuranceCohorts {
+    /// Move a user's supply change into the cohort they belong to.
+    /// Called whenever a position's collateral changes.
+    pub fn on_supply_change(env: &Env, user: &Address, delta: i128) {
+        if delta == 0 {
+            return;
+        }
+        let asset = match TokenRegistry::require_primary_asset(env) {
+            Ok(asset) => asset,
+            Err(_) => return,
+        };
+        let mut cohorts = InsuranceStorage::get_cohorts(env, &asset);
+        if InsuranceStorage::get_election(env, user).opted_out {
+            cohorts.uncovered_supply = (cohorts.uncovered_supply + delta).max(0);
+        } else {
+            cohorts.covered_supply = (cohorts.covered_supply + delta).max(0);
+        }
+        InsuranceStorage::save_cohorts(env, &cohorts);
+    }
+
+    /// Opt in or out of coverage, moving current supply between cohorts
+    pub fn set_coverage(env: &Env, user: &Address, covered: bool) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let mut election = InsuranceStorage::get_election(env, user);
+        if election.opted_out != covered {
+            return Ok(());
+        }
+
+        let now = env.ledger().timestamp();
+        if election.last_toggle > 0
+            && now < election.last_toggle + InsuranceStorage::get_cooldown(env)
+        {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        let supply = StateHelper::get_position(env, user)
+            .map(|position| position.collateral)
+            .unwrap_or(0);
+        if let Ok(asset) = TokenRegistry::require_primary_asset(env) {
+            let mut cohorts = InsuranceStorage::get_cohorts(env, &asset);
+            if covered {
+                cohorts.uncovered_supply = (cohorts.uncovered_supply - supply).max(0);
+                cohorts.covered_supply += supply;
+            } else {
+                cohorts.covered_supply = (cohorts.covered_supply - supply).max(0);
+                cohorts.uncovered_supply += supply;
+            }
+            InsuranceStorage::save_cohorts(env, &cohorts);
+        }
+
+        election.opted_out = !covered;
+        election.last_toggle = now;
+        InsuranceStorage::save_election(env, user, &election);
+        env.events().publish(
+            (
+                Symbol::new(env, "coverage_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "covered"),
+                covered,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Set the wait between coverage toggles (admin only)
+    pub fn set_toggle_cooldown(
+        env: &Env,
+        caller: &Address,
+        secs: u64,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        InsuranceStorage::set_cooldown(env, secs);
+        Ok(())
+    }
+
+    /// Add funds to an asset's insurance reserve (admin only)
+    pub fn fund_reserve(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut cohorts = InsuranceStorage::get_cohorts(env, asset);
+        cohorts.reserve += amount;
+        InsuranceStorage::save_cohorts(env, &cohorts);
+        Ok(())
+    }
+
+    /// Apply a shortfall to one cohort (admin only).
+    /// The asset's safety module stakers are slashed first, within their slash limit.
+    /// Covered-cohort losses are then absorbed by the reserve; whatever remains (and every
+    /// other uncovered-cohort loss) is written down against that cohort's supply only.
+    pub fn apply_shortfall(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        covered_cohort: bool,
+        loss: i128,
+    ) -> Result<ShortfallOutcome, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if loss <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut cohorts = InsuranceStorage::get_cohorts(env, asset);
+
+        let absorbed_by_stakers = SafetyModule::cover_shortfall(env, asset, loss);
+        let loss = loss - absorbed_by_stakers;
+        let absorbed_by_reserve = if covered_cohort {
+            loss.min(cohorts.reserve)
+        } else {
+            0
+        };
+        cohorts.reserve -= absorbed_by_reserve;
+        let remaining = loss - absorbed_by_reserve;
+
+        let supply = if covered_cohort {
+            cohorts.covered_supply
+        } else {
+            cohorts.uncovered_supply
+        };
+        let written_down = remaining.min(supply);
+        if written_down > 0 {
+            let index_delta = (written_down * INDEX_SCALE) / supply;
+            if covered_cohort {
+                cohorts.covered_supply -= written_down;
+                cohorts.covered_loss_index += index_delta;
+            } else {
+                cohorts.uncovered_supply -= written_down;
+                cohorts.uncovered_loss_index += index_delta;
+            }
+            cohorts.total_written_down += written_down;
+        }
+        InsuranceStorage::save_cohorts(env, &cohorts);
+
+        env.events().publish(
+            (
+                Symbol::new(env, "shortfall_applied"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "covered"),
+                covered_cohort,
+                Symbol::new(env, "stakers"),
+                absorbed_by_stakers,
+                Symbol::new(env, "reserve"),
+                absorbed_by_reserve,
+                Symbol::new(env, "written_down"),
+                written_down,
+            ),
+        );
+        Ok(ShortfallOutcome {
+            absorbed_by_stakers,
+            absorbed_by_reserve,
+            written_down,
+        })
+    }
+
+    pub fn get_cohorts(env: &Env, asset: &Address) -> CoverageCohorts {
+        InsuranceStorage::get_cohorts(env, asset)
+    }
+
+    pub fn is_covered(env: &Env, user: &Address) -> bool {
+        !InsuranceStorage::get_election(env, user).opted_out
+    }
+}
@@ -0,0 +1,254 @@
+//! Internal pool module for StellarLend protocol
+//! Minimal constant-product (x * y = k) pools hosted by the protocol itself. They back
+//! swaps for pairs without an active external AMM, so liquidations and deleverages always
+//! have an exit. Reserves are held in the contract's own token balances and tracked here;
+//! providers receive pool shares for their deposits.
+
+use crate::amm::PairKey;
+use crate::gate::OperationGate;
+use crate::lp_pricing::isqrt;
+use crate::persistent::PersistentStore;
+use crate::{OperationKind, ProtocolError, ProtocolEvent, ReentrancyScope, TransferEnforcer};
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol};
+
+/// Reserves and outstanding shares of an internal pool; `reserve_a` belongs to the lower
+/// address of the pair
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct InternalPool {
+    pub reserve_a: i128,
+    pub reserve_b: i128,
+    pub total_shares: i128,
+}
+
+/// Storage helper for internal pools
+pub struct InternalPoolStorage;
+
+impl InternalPoolStorage {
+    fn pools_key(env: &Env) -> Symbol {
+        Symbol::new(env, "int_pools")
+    }
+
+    fn shares_key(env: &Env, key: &PairKey, provider: &Address) -> (Symbol, PairKey, Address) {
+        (
+            Symbol::new(env, "int_pool_lp"),
+            key.clone(),
+            provider.clone(),
+        )
+    }
+
+    pub fn get(env: &Env, key: &PairKey) -> Option<InternalPool> {
+        let pools: Map<PairKey, InternalPool> = env
+            .storage()
+            .instance()
+            .get(&Self::pools_key(env))
+            .unwrap_or_else(|| Map::new(env));
+        pools.get(key.clone())
+    }
+
+    pub fn save(env: &Env, key: &PairKey, pool: &InternalPool) {
+        let mut pools: Map<PairKey, InternalPool> = env
+            .storage()
+            .instance()
+            .get(&Self::pools_key(env))
+            .unwrap_or_else(|| Map::new(env));
+        pools.set(key.clone(), pool.clone());
+        env.storage().instance().set(&Self::pools_key(env), &pools);
+    }
+
+    pub fn get_shares(env: &Env, key: &PairKey, provider: &Address) -> i128 {
+        PersistentStore::get(env, &Self::shares_key(env, key, provider)).unwrap_or(0)
+    }
+
+    pub fn save_shares(env: &Env, key: &PairKey, provider: &Address, shares: i128) {
+        let storage_key = Self::shares_key(env, key, provider);
+        if shares == 0 {
+            PersistentStore::remove(env, &storage_key);
+        } else {
+            PersistentStore::set(env, &storage_key, &shares);
+        }
+    }
+}
+
+/// Protocol-hosted constant-product pools
+pub struct InternalPools;
+
+impl InternalPools {
+    /// The pool for a pair, if it holds liquidity
+    pub fn get(env: &Env, asset_a: &Address, asset_b: &Address) -> Option<InternalPool> {
+        InternalPoolStorage::get(env, &PairKey::new(asset_a.clone(), asset_b.clone()))
+            .filter(|pool| pool.total_shares > 0)
+    }
+
+    pub fn shares(env: &Env, asset_a: &Address, asset_b: &Address, provider: &Address) -> i128 {
+        InternalPoolStorage::get_shares(
+            env,
+            &PairKey::new(asset_a.clone(), asset_b.clone()),
+            provider,
+        )
+    }
+
+    /// Deposit up to `amount_a`/`amount_b` at the pool's current ratio (any ratio for the
+    /// first deposit). Only the amounts matching the ratio are pulled. Returns the shares
+    /// minted.
+    pub fn add_liquidity(
+        env: &Env,
+        provider: &Address,
+        asset_a: &Address,
+        asset_b: &Address,
+        amount_a: i128,
+        amount_b: i128,
+    ) -> Result<i128, ProtocolError> {
+        provider.require_auth();
+        if asset_a == asset_b {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if amount_a <= 0 || amount_b <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        for (asset, amount) in [(asset_a, amount_a), (asset_b, amount_b)] {
+            OperationGate::check(env, provider, OperationKind::Deposit, Some(asset), amount)?;
+        }
+        let _guard = ReentrancyScope::enter(env)?;
+        let key = PairKey::new(asset_a.clone(), asset_b.clone());
+        // Work in the pool's normalized order
+        let (amount_a, amount_b) = if key.asset_a == *asset_a {
+            (amount_a, amount_b)
+        } else {
+            (amount_b, amount_a)
+        };
+        let mut pool = InternalPoolStorage::get(env, &key).unwrap_or_default();
+
+        let (used_a, used_b, shares) = if pool.total_shares == 0 {
+            (amount_a, amount_b, isqrt(amount_a.saturating_mul(amount_b)))
+        } else {
+            let matched_b = amount_a * pool.reserve_b / pool.reserve_a;
+            let (used_a, used_b) = if matched_b <= amount_b {
+                (amount_a, matched_b)
+            } else {
+                (amount_b * pool.reserve_a / pool.reserve_b, amount_b)
+            };
+            let shares = (used_a * pool.total_shares / pool.reserve_a)
+                .min(used_b * pool.total_shares / pool.reserve_b);
+            (used_a, used_b, shares)
+        };
+        if shares <= 0 || used_a <= 0 || used_b <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let flow = Symbol::new(env, "pool_deposit");
+        let received_a =
+            TransferEnforcer::transfer_in_asset(env, &key.asset_a, provider, used_a, flow.clone())?;
+        let received_b =
+            TransferEnforcer::transfer_in_asset(env, &key.asset_b, provider, used_b, flow)?;
+        pool.reserve_a += received_a;
+        pool.reserve_b += received_b;
+        pool.total_shares += shares;
+        InternalPoolStorage::save(env, &key, &pool);
+        let held = InternalPoolStorage::get_shares(env, &key, provider);
+        InternalPoolStorage::save_shares(env, &key, provider, held + shares);
+
+        ProtocolEvent::AMMLiquidityAdded(
+            provider.clone(),
+            key.asset_a,
+            key.asset_b,
+            received_a,
+            received_b,
+        )
+        .emit(env);
+        Ok(shares)
+    }
+
+    /// Burn `shares` for their part of both reserves, returned as `(amount_a, amount_b)`
+    /// in the order the assets were passed
+    pub fn remove_liquidity(
+        env: &Env,
+        provider: &Address,
+        asset_a: &Address,
+        asset_b: &Address,
+        shares: i128,
+    ) -> Result<(i128, i128), ProtocolError> {
+        provider.require_auth();
+        if shares <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let _guard = ReentrancyScope::enter(env)?;
+        let key = PairKey::new(asset_a.clone(), asset_b.clone());
+        let mut pool = InternalPoolStorage::get(env, &key).ok_or(ProtocolError::NotFound)?;
+        let held = InternalPoolStorage::get_shares(env, &key, provider);
+        if shares > held {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let out_a = pool.reserve_a * shares / pool.total_shares;
+        let out_b = pool.reserve_b * shares / pool.total_shares;
+        pool.reserve_a -= out_a;
+        pool.reserve_b -= out_b;
+        pool.total_shares -= shares;
+        InternalPoolStorage::save(env, &key, &pool);
+        InternalPoolStorage::save_shares(env, &key, provider, held - shares);
+
+        let flow = Symbol::new(env, "pool_withdraw");
+        TransferEnforcer::transfer_out_asset(env, &key.asset_a, provider, out_a, flow.clone())?;
+        TransferEnforcer::transfer_out_asset(env, &key.asset_b, provider, out_b, flow)?;
+
+        ProtocolEvent::AMMLiquidityRemoved(
+            provider.clone(),
+            env.current_contract_address(),
+            shares,
+        )
+        .emit(env);
+        if key.asset_a == *asset_a {
+            Ok((out_a, out_b))
+        } else {
+            Ok((out_b, out_a))
+        }
+    }
+
+    /// Output of selling `amount_in` into the pool, `None` without a pool
+    pub fn quote(
+        env: &Env,
+        asset_in: &Address,
+        asset_out: &Address,
+        amount_in: i128,
+    ) -> Option<i128> {
+        let pool = Self::get(env, asset_in, asset_out)?;
+        let (reserve_in, reserve_out) = Self::oriented(asset_in, asset_out, &pool);
+        Some(amount_in * reserve_out / (reserve_in + amount_in))
+    }
+
+    /// Sell `amount_in` of contract-held `asset_in` into the pool. The tokens never leave
+    /// the contract: only the reserves move. Returns the output.
+    pub fn swap(
+        env: &Env,
+        asset_in: &Address,
+        asset_out: &Address,
+        amount_in: i128,
+    ) -> Result<i128, ProtocolError> {
+        let key = PairKey::new(asset_in.clone(), asset_out.clone());
+        let mut pool = Self::get(env, asset_in, asset_out).ok_or(ProtocolError::NotFound)?;
+        let (reserve_in, reserve_out) = Self::oriented(asset_in, asset_out, &pool);
+        let amount_out = amount_in * reserve_out / (reserve_in + amount_in);
+        if amount_out <= 0 {
+            return Err(ProtocolError::InsufficientLiquidity);
+        }
+        if key.asset_a == *asset_in {
+            pool.reserve_a += amount_in;
+            pool.reserve_b -= amount_out;
+        } else {
+            pool.reserve_b += amount_in;
+            pool.reserve_a -= amount_out;
+        }
+        InternalPoolStorage::save(env, &key, &pool);
+        Ok(amount_out)
+    }
+
+    /// `(reserve_in, reserve_out)` for a swap direction
+    fn oriented(asset_in: &Address, asset_out: &Address, pool: &InternalPool) -> (i128, i128) {
+        if asset_in < asset_out {
+            (pool.reserve_a, pool.reserve_b)
+        } else {
+            (pool.reserve_b, pool.reserve_a)
+        }
+    }
+}
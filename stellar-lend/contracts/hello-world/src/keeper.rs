@@ -0,0 +1,239 @@
+//! Keeper module for StellarLend protocol
+//! Registry of maintenance jobs that anyone may run once they are due, earning a bounty
+//! in the primary asset paid from its reserves. Job kinds:
+//! - `accrue_markets`: accrue one market, or every market when the job has no asset
+//! - `prune_events`: delete event log pages left outside a shortened retention window
+//! - `refresh_twap`: re-aggregate an asset's price feeds, recording a TWAP observation
+//! - `apply_params`: apply queued emergency parameter updates whose timelock has passed
+//!
+//! A job is due once its cooldown has passed since the last run. Runs that find nothing
+//! to do succeed without a bounty and do not restart the cooldown.
+
+use crate::oracle::Oracle;
+use crate::reserves::Reserves;
+use crate::watchdog::Watchdog;
+use crate::{
+    EmergencyManager, EventStorage, InterestRateManager, ProtocolConfig, ProtocolError,
+    TokenRegistry,
+};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol, Vec};
+
+/// Keeper errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum KeeperError {
+    JobNotDue = 14001,
+}
+
+impl From<KeeperError> for ProtocolError {
+    fn from(err: KeeperError) -> Self {
+        match err {
+            KeeperError::JobNotDue => ProtocolError::InvalidOperation,
+        }
+    }
+}
+
+/// Most jobs registered at once
+const MAX_JOBS: u32 = 20;
+
+const JOB_KINDS: [&str; 4] = [
+    "accrue_markets",
+    "prune_events",
+    "refresh_twap",
+    "apply_params",
+];
+
+/// A registered maintenance job
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct KeeperJob {
+    pub id: u32,
+    /// One of `accrue_markets`, `prune_events`, `refresh_twap` or `apply_params`
+    pub kind: Symbol,
+    /// Asset the job works on; required by `refresh_twap`, optional for `accrue_markets`
+    pub asset: Option<Address>,
+    /// Seconds between runs
+    pub cooldown: u64,
+    /// Primary-asset bounty paid to the caller of a run that did work
+    pub bounty: i128,
+    pub last_run: u64,
+    pub runs: u32,
+}
+
+/// Storage helper for keeper jobs
+pub struct KeeperStorage;
+
+impl KeeperStorage {
+    fn jobs_key(env: &Env) -> Symbol {
+        Symbol::new(env, "keeper_jobs")
+    }
+
+    fn next_id_key(env: &Env) -> Symbol {
+        Symbol::new(env, "keeper_job_id")
+    }
+
+    pub fn get_jobs(env: &Env) -> Vec<KeeperJob> {
+        env.storage()
+            .instance()
+            .get(&Self::jobs_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_jobs(env: &Env, jobs: &Vec<KeeperJob>) {
+        env.storage().instance().set(&Self::jobs_key(env), jobs);
+    }
+
+    pub fn next_id(env: &Env) -> u32 {
+        let id: u32 = env
+            .storage()
+            .instance()
+            .get(&Self::next_id_key(env))
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::next_id_key(env), &id);
+        id
+    }
+}
+
+/// Keeper job registry and execution
+pub struct Keepers;
+
+impl Keepers {
+    /// Register a job (admin only). Returns its id.
+    pub fn register_job(
+        env: &Env,
+        caller: &Address,
+        kind: Symbol,
+        asset: Option<Address>,
+        cooldown: u64,
+        bounty: i128,
+    ) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !JOB_KINDS.iter().any(|name| Symbol::new(env, name) == kind) {
+            return Err(ProtocolError::InvalidInput);
+        }
+        if kind == Symbol::new(env, "refresh_twap") && asset.is_none() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if cooldown == 0 || bounty < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut jobs = KeeperStorage::get_jobs(env);
+        if jobs.len() >= MAX_JOBS {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let id = KeeperStorage::next_id(env);
+        jobs.push_back(KeeperJob {
+            id,
+            kind,
+            asset,
+            cooldown,
+            bounty,
+            last_run: 0,
+            runs: 0,
+        });
+        KeeperStorage::save_jobs(env, &jobs);
+        Self::publish(env, "keeper_job_registered", id, 0);
+        Ok(id)
+    }
+
+    /// Remove a job (admin only)
+    pub fn remove_job(env: &Env, caller: &Address, id: u32) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut jobs = KeeperStorage::get_jobs(env);
+        let index = jobs
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or(ProtocolError::NotFound)?;
+        jobs.remove(index as u32);
+        KeeperStorage::save_jobs(env, &jobs);
+        Self::publish(env, "keeper_job_removed", id, 0);
+        Ok(())
+    }
+
+    pub fn get_jobs(env: &Env) -> Vec<KeeperJob> {
+        KeeperStorage::get_jobs(env)
+    }
+
+    pub fn get_job(env: &Env, id: u32) -> Option<KeeperJob> {
+        KeeperStorage::get_jobs(env).iter().find(|job| job.id == id)
+    }
+
+    /// Run a due job and pay its bounty to `keeper` if it did any work. Returns the
+    /// bounty paid, which is capped by the primary asset's reserves.
+    pub fn execute(env: &Env, keeper: &Address, id: u32) -> Result<i128, ProtocolError> {
+        keeper.require_auth();
+        // A silent protocol is paused instead; returning `Ok` keeps the pause
+        if Watchdog::trip_if_stale(env) {
+            return Ok(0);
+        }
+        let mut jobs = KeeperStorage::get_jobs(env);
+        let index = jobs
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or(ProtocolError::NotFound)? as u32;
+        let mut job = jobs.get(index).ok_or(ProtocolError::NotFound)?;
+        let now = env.ledger().timestamp();
+        if job.runs > 0 && now < job.last_run.saturating_add(job.cooldown) {
+            return Err(KeeperError::JobNotDue.into());
+        }
+
+        let work = Self::run(env, &job)?;
+        if work == 0 {
+            return Ok(0);
+        }
+        job.last_run = now;
+        job.runs += 1;
+        jobs.set(index, job.clone());
+        KeeperStorage::save_jobs(env, &jobs);
+        Watchdog::heartbeat(env, "keeper");
+
+        let bounty = match TokenRegistry::require_primary_asset(env) {
+            Ok(primary) if job.bounty > 0 => {
+                Reserves::pay(env, &primary, keeper, job.bounty, "keeper_bounty")?
+            }
+            _ => 0,
+        };
+        Self::publish(env, "keeper_job_executed", id, bounty);
+        Ok(bounty)
+    }
+
+    /// Do the job's work, returning how many items it touched
+    fn run(env: &Env, job: &KeeperJob) -> Result<u32, ProtocolError> {
+        if job.kind == Symbol::new(env, "accrue_markets") {
+            Ok(Self::accrue_markets(env, &job.asset))
+        } else if job.kind == Symbol::new(env, "prune_events") {
+            Ok(EventStorage::prune_expired(env))
+        } else if job.kind == Symbol::new(env, "refresh_twap") {
+            let asset = job.asset.clone().ok_or(ProtocolError::InvalidParameters)?;
+            Oracle::get_aggregated_price(env, &asset)?;
+            Ok(1)
+        } else {
+            EmergencyManager::apply_due_updates(env)
+        }
+    }
+
+    fn accrue_markets(env: &Env, asset: &Option<Address>) -> u32 {
+        match asset {
+            Some(asset) if InterestRateManager::has_market(env, asset) => {
+                InterestRateManager::accrue_market(env, asset);
+                1
+            }
+            Some(_) => 0,
+            None => InterestRateManager::accrue_all_markets(env),
+        }
+    }
+
+    fn publish(env: &Env, action: &str, id: u32, bounty: i128) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "job")),
+            (
+                Symbol::new(env, "id"),
+                id,
+                Symbol::new(env, "bounty"),
+                bounty,
+            ),
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Leverage module for StellarLend protocol
+//! Opens a leveraged multi-asset position in one call: after the initial deposit it loops
+//! borrow → swap via the AMM registry → credit as collateral until the collateral reaches
+//! the target multiple of the initial amount. Every round keeps the position at or above
+//! its minimum ratio and every swap is bounded by the oracle-implied output less the
+//! allowed slippage.
+
+use crate::amm::{AMMRegistry, SwapParams};
+use crate::analytics::AnalyticsModule;
+use crate::cross_asset::CrossAssets;
+use crate::deposit::DepositModule;
+use crate::gate::OperationGate;
+use crate::normalization::Normalization;
+use crate::oracle::Oracle;
+use crate::outflow_limit::OutflowLimits;
+use crate::{
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ReentrancyGuard,
+    StateHelper, UserManager,
+};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Leverage is expressed in basis points of the initial amount (10000 = 1x)
+const LEVERAGE_SCALE: i128 = 10_000;
+
+/// Highest target leverage accepted (10x)
+const MAX_LEVERAGE: i128 = 100_000;
+
+/// Borrow/swap rounds per call
+const MAX_ROUNDS: u32 = 8;
+
+/// Outcome of opening a leveraged position
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LeverageResult {
+    /// Collateral asset held by the position after the call
+    pub collateral: i128,
+    /// Debt asset owed by the position after the call
+    pub debt: i128,
+    /// Collateral the call put in as a multiple of the initial amount (10000 = 1x)
+    pub leverage: i128,
+    /// Borrow/swap rounds executed
+    pub rounds: u32,
+}
+
+/// Deposit → borrow → swap loops
+pub struct Leverage;
+
+impl Leverage {
+    /// Deposit `initial_amount` of `collateral_asset` and lever it up to `target_leverage`
+    /// by borrowing `debt_asset` and swapping it into more collateral, with each swap
+    /// accepting at most `max_slippage_bps` below the oracle price. Fails with
+    /// `InsufficientCollateralRatio` when the target cannot be reached within the minimum
+    /// ratio.
+    pub fn open(
+        env: &Env,
+        user: &Address,
+        collateral_asset: &Address,
+        debt_asset: &Address,
+        initial_amount: i128,
+        target_leverage: i128,
+        max_slippage_bps: i128,
+    ) -> Result<LeverageResult, ProtocolError> {
+        user.require_auth();
+        if initial_amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if collateral_asset == debt_asset
+            || target_leverage <= LEVERAGE_SCALE
+            || target_leverage > MAX_LEVERAGE
+            || !(0..=LEVERAGE_SCALE).contains(&max_slippage_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        // Deposit and swaps take the guard themselves, so hold it as a batch
+        ReentrancyGuard::enter_batch(env)?;
+        let result = Self::run(
+            env,
+            user,
+            collateral_asset,
+            debt_asset,
+            initial_amount,
+            target_leverage,
+            max_slippage_bps,
+        );
+        ReentrancyGuard::exit_batch(env);
+        result
+    }
+
+    fn run(
+        env: &Env,
+        user: &Address,
+        collateral_asset: &Address,
+        debt_asset: &Address,
+        initial_amount: i128,
+        target_leverage: i128,
+        max_slippage_bps: i128,
+    ) -> Result<LeverageResult, ProtocolError> {
+        DepositModule::deposit_collateral_asset(env, user, user, collateral_asset, initial_amount)?;
+        let collateral_price = CrossAssets::require_price(env, collateral_asset)?;
+        let debt_price = CrossAssets::require_price(env, debt_asset)?;
+        let target = initial_amount.saturating_mul(target_leverage) / LEVERAGE_SCALE;
+
+        let mut added = initial_amount;
+        let mut borrowed = 0i128;
+        let mut rounds = 0u32;
+        while added < target && rounds < MAX_ROUNDS {
+            InterestRateManager::accrue_market(env, collateral_asset);
+            InterestRateManager::accrue_market(env, debt_asset);
+            let mut position = StateHelper::get_cross_position(env, user)
+                .ok_or(ProtocolError::PositionNotFound)?;
+            InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
+
+            // Borrow what buys the missing collateral, capped by the ratio headroom
+            let health = CrossAssets::health(env, &position)?;
+            let headroom = health.collateral_value * 100 / health.min_ratio - health.debt_value;
+            let missing =
+                Normalization::value(env, collateral_asset, target - added, collateral_price);
+            let amount =
+                Normalization::amount_for_value(env, debt_asset, missing.min(headroom), debt_price);
+            if amount <= 0 {
+                break;
+            }
+            OperationGate::check(env, user, OperationKind::Borrow, Some(debt_asset), amount)?;
+            let liquidity =
+                TokenClient::new(env, debt_asset).balance(&env.current_contract_address());
+            if amount > liquidity {
+                return Err(ProtocolError::InsufficientLiquidity);
+            }
+            OutflowLimits::record(env, debt_asset, amount)?;
+
+            let debt = position.debt.get(debt_asset.clone()).unwrap_or(0);
+            if debt == 0 {
+                position.borrow_index.set(
+                    debt_asset.clone(),
+                    InterestRateStorage::current_borrow_index(env, debt_asset),
+                );
+            }
+            position.debt.set(debt_asset.clone(), debt + amount);
+
+            let expected = Normalization::amount_for_value(
+                env,
+                collateral_asset,
+                Normalization::value(env, debt_asset, amount, debt_price),
+                collateral_price,
+            );
+            let min_out = expected * (LEVERAGE_SCALE - max_slippage_bps) / LEVERAGE_SCALE;
+            let swap = AMMRegistry::execute_swap(
+                env,
+                SwapParams::new(
+                    user.clone(),
+                    debt_asset.clone(),
+                    collateral_asset.clone(),
+                    amount,
+                    min_out,
+                )
+                .with_slippage(max_slippage_bps),
+            )?
+            .require_executed()?;
+            let balance = position
+                .collateral
+                .get(collateral_asset.clone())
+                .unwrap_or(0);
+            position
+                .collateral
+                .set(collateral_asset.clone(), balance + swap.amount_out);
+
+            Oracle::ensure_position_fresh(env, &position)?;
+            if !CrossAssets::is_healthy(env, &position)? {
+                return Err(ProtocolError::InsufficientCollateralRatio);
+            }
+            StateHelper::save_cross_position(env, &position);
+            InterestRateStorage::apply_market_flow(env, debt_asset, 0, amount);
+            InterestRateStorage::apply_market_flow(env, collateral_asset, swap.amount_out, 0);
+            AnalyticsModule::record_activity(
+                env,
+                user,
+                "borrow",
+                amount,
+                Some(debt_asset.clone()),
+            )?;
+            UserManager::record_activity(env, user, OperationKind::Borrow, amount)?;
+
+            added += swap.amount_out;
+            borrowed += amount;
+            rounds += 1;
+        }
+        if added < target {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+
+        let position =
+            StateHelper::get_cross_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let result = LeverageResult {
+            collateral: position
+                .collateral
+                .get(collateral_asset.clone())
+                .unwrap_or(0),
+            debt: position.debt.get(debt_asset.clone()).unwrap_or(0),
+            leverage: added * LEVERAGE_SCALE / initial_amount,
+            rounds,
+        };
+        env.events().publish(
+            (
+                Symbol::new(env, "leveraged_position_opened"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "collateral_asset"),
+                collateral_asset.clone(),
+                Symbol::new(env, "debt_asset"),
+                debt_asset.clone(),
+                Symbol::new(env, "borrowed"),
+                borrowed,
+                Symbol::new(env, "leverage"),
+                result.leverage,
+            ),
+        );
+        Ok(result)
+    }
+}
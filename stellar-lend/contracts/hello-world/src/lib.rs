@@ -5,12 +5,14 @@
 
 #![no_std]
 extern crate alloc;
+#[cfg(test)]
+extern crate std;
 
-use alloc::format;
 use alloc::string::ToString;
 use soroban_sdk::token::TokenClient;
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, Address, Env, Map, String, Symbol, Vec,
+    contract, contracterror, contractimpl, contracttype, Address, Bytes, BytesN, Env, Map, String,
+    Symbol, Vec,
 };
 mod flash_loan;
 mod governance;
@@ -24,6 +26,14 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 pub struct AddressHelper;
 
 impl AddressHelper {
+    /// Length of a Stellar strkey (account `G...` or contract `C...`)
+    const STRKEY_LEN: usize = 56;
+    /// Decoded strkey length: version byte, 32-byte payload, 2-byte checksum
+    const DECODED_LEN: usize = 35;
+    /// Strkey version bytes for account and contract addresses
+    const VERSION_ACCOUNT: u8 = 6 << 3;
+    const VERSION_CONTRACT: u8 = 2 << 3;
+
     /// Safely construct an Address from a string with validation
     /// Returns InvalidAddress error for empty, malformed, or invalid inputs
     pub fn from_string_safe(_env: &Env, address_str: &String) -> Result<Address, ProtocolError> {
@@ -32,12 +42,9 @@ impl AddressHelper {
             return Err(ProtocolError::InvalidAddress);
         }
 
-        // Validate basic format requirements
+        // Validate the full strkey encoding so Address::from_string cannot panic
         Self::validate_address_format(address_str)?;
 
-        // Construct the address - in a real implementation, we would need to handle
-        // the potential panic from Address::from_string. For now, we assume the
-        // validation above catches most issues.
         Ok(Address::from_string(address_str))
     }
 
@@ -63,23 +70,69 @@ impl AddressHelper {
         Ok(addresses)
     }
 
-    /// Validate that an address string is not empty and has basic format requirements
+    /// Validate that an address string is a well-formed account or contract strkey:
+    /// exact length, base32 charset, a `G`/`C` prefix matching the version byte, and
+    /// a valid CRC16-XModem checksum
     pub fn validate_address_format(address_str: &String) -> Result<(), ProtocolError> {
-        if address_str.is_empty() {
+        if address_str.len() as usize != Self::STRKEY_LEN {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        let mut encoded = [0u8; Self::STRKEY_LEN];
+        address_str.copy_into_slice(&mut encoded);
+
+        let decoded = Self::decode_base32(&encoded).ok_or(ProtocolError::InvalidAddress)?;
+        let expected_version = match encoded[0] {
+            b'G' => Self::VERSION_ACCOUNT,
+            b'C' => Self::VERSION_CONTRACT,
+            _ => return Err(ProtocolError::InvalidAddress),
+        };
+        if decoded[0] != expected_version {
             return Err(ProtocolError::InvalidAddress);
         }
 
-        // Check for reasonable length bounds (Stellar addresses are typically 56 characters)
-        // But we'll be more permissive to handle different address formats
-        if address_str.len() > 256 {
+        let (body, checksum) = decoded.split_at(Self::DECODED_LEN - 2);
+        if Self::crc16_xmodem(body).to_le_bytes() != [checksum[0], checksum[1]] {
             return Err(ProtocolError::InvalidAddress);
         }
+        Ok(())
+    }
 
-        // Check for null bytes or other obviously invalid characters
-        // Note: In Soroban, we can't easily convert String to std::string::String
-        // This is a placeholder for more sophisticated validation that could be added
+    /// RFC 4648 base32 decode without padding; `None` on characters outside `A-Z2-7`
+    fn decode_base32(encoded: &[u8; Self::STRKEY_LEN]) -> Option<[u8; Self::DECODED_LEN]> {
+        let mut decoded = [0u8; Self::DECODED_LEN];
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+        let mut index = 0;
+        for &byte in encoded.iter() {
+            let value = match byte {
+                b'A'..=b'Z' => byte - b'A',
+                b'2'..=b'7' => byte - b'2' + 26,
+                _ => return None,
+            };
+            buffer = (buffer << 5) | value as u32;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                decoded[index] = (buffer >> bits) as u8;
+                index += 1;
+            }
+        }
+        Some(decoded)
+    }
 
-        Ok(())
+    fn crc16_xmodem(data: &[u8]) -> u16 {
+        let mut crc: u16 = 0;
+        for &byte in data {
+            crc ^= (byte as u16) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 {
+                    (crc << 1) ^ 0x1021
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        crc
     }
 
     /// Helper to safely convert string to address for public API functions
@@ -90,18 +143,98 @@ impl AddressHelper {
     ) -> Result<Address, ProtocolError> {
         Self::from_string_safe(env, address_str)
     }
+
+    /// Parse the address argument of a deprecated String entrypoint, rejecting the
+    /// call once the admin has turned off the legacy string API
+    pub fn require_legacy_address(
+        env: &Env,
+        address_str: &String,
+    ) -> Result<Address, ProtocolError> {
+        if !ProtocolConfig::is_legacy_string_api_enabled(env) {
+            return Err(ProtocolError::LegacyApiDisabled);
+        }
+        Self::require_valid_address(env, address_str)
+    }
 }
 
 #[cfg(test)]
 mod test;
+#[cfg(test)]
+mod test_invariants;
 
 // Core protocol modules
+mod account_data;
+mod admin_transfer;
 mod amm;
+mod amortization;
 mod analytics;
+mod attestation;
+mod auction;
+mod auth_adapter;
+mod auto_repay;
+mod batch;
+mod beneficiary;
+mod bloom;
+mod bootstrap;
 mod borrow;
+mod break_glass;
+mod bridge;
+mod circuit_breaker;
+mod collateral_swap;
+mod compliance;
+mod credit_delegation;
+mod cross_asset;
+mod deleverage;
 mod deposit;
+mod errors;
+mod fees;
+mod footprint;
+mod gate;
+mod guardian;
+mod health_index;
+mod insurance;
+mod internal_pool;
+mod keeper;
+mod leverage;
 mod liquidate;
+mod liquidation_grace;
+mod liquidity_guard;
+mod lp_pricing;
+mod migration;
+mod modules;
+mod netting;
+mod normalization;
+mod operators;
+mod origination;
+mod outflow_limit;
+mod permit;
+mod persistent;
+mod position_history;
+mod position_transfer;
+mod preview;
+mod quarantine;
+mod rate_dynamics;
+mod read_api;
+mod rebate;
+mod recovery;
+mod referral;
+mod release_schedule;
 mod repay;
+mod reserves;
+mod rewards;
+mod risk_matrix;
+mod safety_module;
+mod safety_rewards;
+mod soft_limits;
+mod solvency;
+mod stable_rate;
+mod subscribers;
+mod token_behavior;
+mod tvl_cap;
+mod unwind;
+mod upgrade;
+mod vesting;
+mod watchdog;
 mod withdraw;
 
 /// Supported emergency lifecycle states for the protocol
@@ -123,6 +256,27 @@ pub struct EmergencyParamUpdate {
     pub queued_at: u64,
 }
 
+/// An operation reopened at `enable_at` while the protocol is in Recovery
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UnwindStage {
+    pub operation: Symbol,
+    pub enable_at: u64,
+}
+
+/// Default wait before a queued emergency parameter update may be applied
+const DEFAULT_PARAM_TIMELOCK: u64 = 86_400;
+
+/// Timelock on queued emergency parameter updates
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ParamTimelock {
+    /// Seconds an update must wait after being queued before it can be applied
+    pub min_delay: u64,
+    /// Seconds after `min_delay` an update stays applicable before it expires, 0 for never
+    pub expiry: u64,
+}
+
 impl EmergencyParamUpdate {
     pub fn new(env: &Env, key: Symbol, value: i128, queued_by: Address) -> Self {
         Self {
@@ -169,6 +323,11 @@ pub struct EmergencyState {
     pub emergency_managers: Vec<Address>,
     pub pending_param_updates: Vec<EmergencyParamUpdate>,
     pub fund: EmergencyFund,
+    /// Operations scheduled to reopen during Recovery, cleared on leaving it
+    pub unwind_stages: Vec<UnwindStage>,
+    /// Highest utilization borrows and withdrawals may leave during Recovery, scaled by
+    /// 1e8; 0 for no cap
+    pub recovery_utilization_cap: i128,
 }
 
 impl EmergencyState {
@@ -184,6 +343,8 @@ impl EmergencyState {
             emergency_managers: Vec::new(env),
             pending_param_updates: Vec::new(env),
             fund: EmergencyFund::initial(env),
+            unwind_stages: Vec::new(env),
+            recovery_utilization_cap: 0,
         }
     }
 }
@@ -206,6 +367,26 @@ impl EmergencyStorage {
             .get::<Symbol, EmergencyState>(&Self::key(env))
             .unwrap_or_else(|| EmergencyState::default(env))
     }
+
+    fn timelock_key(env: &Env) -> Symbol {
+        Symbol::new(env, "emergency_param_timelock")
+    }
+
+    pub fn get_timelock(env: &Env) -> ParamTimelock {
+        env.storage()
+            .instance()
+            .get(&Self::timelock_key(env))
+            .unwrap_or(ParamTimelock {
+                min_delay: DEFAULT_PARAM_TIMELOCK,
+                expiry: 0,
+            })
+    }
+
+    pub fn save_timelock(env: &Env, timelock: &ParamTimelock) {
+        env.storage()
+            .instance()
+            .set(&Self::timelock_key(env), timelock);
+    }
 }
 
 /// Operation categories used when checking emergency restrictions
@@ -397,22 +578,31 @@ impl UserManager {
 
     fn ensure_profile(env: &Env, user: &Address) -> UserProfile {
         let key = Self::profile_key(user);
-        env.storage()
-            .instance()
-            .get::<UserStorageKey, UserProfile>(&key)
-            .unwrap_or_else(|| {
+        persistent::PersistentStore::get::<UserStorageKey, UserProfile>(env, &key).unwrap_or_else(
+            || {
                 let profile = UserProfile::new(env, user.clone());
-                env.storage().instance().set(&key, &profile);
+                persistent::PersistentStore::set(env, &key, &profile);
                 profile
-            })
+            },
+        )
     }
 
     fn save_profile(env: &Env, profile: &UserProfile) {
         let key = Self::profile_key(&profile.user);
-        env.storage().instance().set(&key, profile);
+        persistent::PersistentStore::set(env, &key, profile);
+    }
+
+    /// Stored profile without creating a default one
+    pub fn find_profile(env: &Env, user: &Address) -> Option<UserProfile> {
+        persistent::PersistentStore::get(env, &Self::profile_key(user))
+    }
+
+    /// Extend the TTL of a user's profile entry, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        persistent::PersistentStore::bump(env, &Self::profile_key(user)) as u32
     }
 
-    fn ensure_can_manage(
+    pub fn ensure_can_manage(
         env: &Env,
         caller: &Address,
         minimum_role: UserRole,
@@ -495,6 +685,25 @@ impl UserManager {
         );
     }
 
+    /// Drop a former admin's profile to the standard role after an admin transfer
+    pub fn retire_admin(env: &Env, former: &Address) {
+        let mut profile = Self::ensure_profile(env, former);
+        profile.role = UserRole::Standard;
+        Self::save_profile(env, &profile);
+        env.events().publish(
+            (
+                Symbol::new(env, "user_role_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                former.clone(),
+                Symbol::new(env, "role"),
+                UserRole::Standard.as_symbol(env),
+            ),
+        );
+    }
+
     pub fn set_role(
         env: &Env,
         caller: &Address,
@@ -505,6 +714,7 @@ impl UserManager {
         if matches!(role, UserRole::Admin) {
             Self::require_admin(env, caller)?;
         } else {
+            caller.require_auth();
             Self::ensure_can_manage(env, caller, UserRole::Manager)?;
         }
 
@@ -546,7 +756,15 @@ impl UserManager {
         user: &Address,
         status: VerificationStatus,
     ) -> Result<(), ProtocolError> {
+        caller.require_auth();
         Self::ensure_can_manage(env, caller, UserRole::Analyst)?;
+        Self::apply_verification_status(env, user, status);
+        Ok(())
+    }
+
+    /// Store a verification status already authorized by the caller, freezing rejected
+    /// users and unfreezing verified ones
+    pub fn apply_verification_status(env: &Env, user: &Address, status: VerificationStatus) {
         let mut profile = Self::ensure_profile(env, user);
         profile.verification = status.clone();
         if status == VerificationStatus::Rejected {
@@ -569,7 +787,6 @@ impl UserManager {
                 status_symbol,
             ),
         );
-        Ok(())
     }
 
     pub fn set_limits(
@@ -581,6 +798,7 @@ impl UserManager {
         max_withdraw: i128,
         daily_limit: i128,
     ) -> Result<(), ProtocolError> {
+        caller.require_auth();
         Self::ensure_can_manage(env, caller, UserRole::Manager)?;
         if max_deposit <= 0 || max_borrow <= 0 || max_withdraw <= 0 || daily_limit <= 0 {
             return Err(ProtocolError::InvalidParameters);
@@ -649,7 +867,10 @@ impl UserManager {
             }
         }
 
-        profile.limits.check_operation(operation, amount)
+        profile.limits.check_operation(operation, amount)?;
+        quarantine::Quarantine::ensure_allowed(env, user, operation, amount)?;
+
+        auth_adapter::AuthAdapter::ensure_approved(env, user, operation, amount)
     }
 
     pub fn record_activity(
@@ -691,6 +912,7 @@ impl UserManager {
     }
 
     pub fn freeze_user(env: &Env, caller: &Address, user: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
         Self::ensure_can_manage(env, caller, UserRole::Manager)?;
         let mut profile = Self::ensure_profile(env, user);
         profile.is_frozen = true;
@@ -711,6 +933,7 @@ impl UserManager {
     }
 
     pub fn unfreeze_user(env: &Env, caller: &Address, user: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
         Self::ensure_can_manage(env, caller, UserRole::Manager)?;
         let mut profile = Self::ensure_profile(env, user);
         profile.is_frozen = false;
@@ -733,7 +956,7 @@ impl UserManager {
         Ok(())
     }
 
-    fn operation_symbol(env: &Env, operation: OperationKind) -> Symbol {
+    pub fn operation_symbol(env: &Env, operation: OperationKind) -> Symbol {
         match operation {
             OperationKind::Deposit => Symbol::new(env, "deposit"),
             OperationKind::Borrow => Symbol::new(env, "borrow"),
@@ -832,7 +1055,31 @@ impl EventSummary {
     }
 }
 
-/// Persistent storage helper for protocol events
+/// Page of an event type's log, oldest event first
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EventPage {
+    pub event_type: Symbol,
+    pub page: u32,
+    pub events: Vec<EventRecord>,
+    /// Index of the page currently being appended to
+    pub latest_page: u32,
+    /// Events of this type ever logged, including those in pages no longer retained
+    pub total_events: u64,
+}
+
+/// Events held per persistent log page
+pub const EVENT_PAGE_SIZE: u32 = 32;
+
+/// Default number of most recent pages retained per event type
+const DEFAULT_EVENT_RETAINED_PAGES: u32 = 4;
+
+/// Upper bound on the configurable retention
+const MAX_EVENT_RETAINED_PAGES: u32 = 64;
+
+/// Persistent storage helper for protocol events. Each event type's log is split into
+/// fixed-size pages under their own persistent keys, so an append touches one page and a
+/// counter; only the most recent `retained_pages` pages are kept.
 pub struct EventStorage;
 
 impl EventStorage {
@@ -840,10 +1087,23 @@ impl EventStorage {
         Symbol::new(env, "event_aggregates")
     }
 
-    fn logs_key(env: &Env) -> Symbol {
+    /// Shared log map used before logs were paged
+    fn legacy_logs_key(env: &Env) -> Symbol {
         Symbol::new(env, "event_logs")
     }
 
+    fn count_key(env: &Env, event_type: &Symbol) -> (Symbol, Symbol) {
+        (Symbol::new(env, "event_count"), event_type.clone())
+    }
+
+    fn page_key(env: &Env, event_type: &Symbol, page: u32) -> (Symbol, Symbol, u32) {
+        (Symbol::new(env, "event_page"), event_type.clone(), page)
+    }
+
+    fn retention_key(env: &Env) -> Symbol {
+        Symbol::new(env, "event_retention")
+    }
+
     fn summary_key(env: &Env) -> Symbol {
         Symbol::new(env, "event_summary")
     }
@@ -861,15 +1121,175 @@ impl EventStorage {
             .set(&Self::aggregates_key(env), aggregates);
     }
 
-    pub fn get_logs(env: &Env) -> Map<Symbol, Vec<EventRecord>> {
+    /// Number of events of a type ever logged
+    pub fn get_count(env: &Env, event_type: &Symbol) -> u64 {
+        persistent::PersistentStore::get(env, &Self::count_key(env, event_type)).unwrap_or(0)
+    }
+
+    pub fn get_retained_pages(env: &Env) -> u32 {
         env.storage()
             .instance()
-            .get(&Self::logs_key(env))
-            .unwrap_or_else(|| Map::new(env))
+            .get(&Self::retention_key(env))
+            .unwrap_or(DEFAULT_EVENT_RETAINED_PAGES)
+    }
+
+    /// Set how many recent pages are kept per event type (admin only)
+    pub fn set_retained_pages(
+        env: &Env,
+        caller: &Address,
+        pages: u32,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if pages == 0 || pages > MAX_EVENT_RETAINED_PAGES {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        env.storage()
+            .instance()
+            .set(&Self::retention_key(env), &pages);
+        Ok(())
+    }
+
+    /// Page being appended to, or `None` before the first event of the type
+    fn latest_page(env: &Env, event_type: &Symbol) -> Option<u32> {
+        match Self::get_count(env, event_type) {
+            0 => None,
+            count => Some(((count - 1) / EVENT_PAGE_SIZE as u64) as u32),
+        }
+    }
+
+    /// Oldest page still inside the retention window
+    fn oldest_page(env: &Env, latest: u32) -> u32 {
+        (latest + 1).saturating_sub(Self::get_retained_pages(env))
+    }
+
+    /// Events stored on one page; empty for pages not yet written or outside retention
+    pub fn get_page(env: &Env, event_type: &Symbol, page: u32) -> EventPage {
+        let total_events = Self::get_count(env, event_type);
+        let latest_page = Self::latest_page(env, event_type).unwrap_or(0);
+        let events = if total_events > 0
+            && page <= latest_page
+            && page >= Self::oldest_page(env, latest_page)
+        {
+            persistent::PersistentStore::get(env, &Self::page_key(env, event_type, page))
+                .unwrap_or_else(|| Vec::new(env))
+        } else {
+            Vec::new(env)
+        };
+        EventPage {
+            event_type: event_type.clone(),
+            page,
+            events,
+            latest_page,
+            total_events,
+        }
+    }
+
+    /// Up to `limit` of the most recent retained events of a type, oldest first. A zero
+    /// limit returns one page's worth.
+    pub fn latest(env: &Env, event_type: &Symbol, limit: u32) -> Vec<EventRecord> {
+        let limit = if limit == 0 { EVENT_PAGE_SIZE } else { limit };
+        let mut events = Vec::new(env);
+        let Some(latest) = Self::latest_page(env, event_type) else {
+            return events;
+        };
+        let oldest = Self::oldest_page(env, latest);
+        let mut page = latest;
+        loop {
+            let mut merged = Self::get_page(env, event_type, page).events;
+            merged.append(&events);
+            events = merged;
+            if events.len() >= limit || page == oldest {
+                break;
+            }
+            page -= 1;
+        }
+        if events.len() > limit {
+            events = events.slice(events.len() - limit..);
+        }
+        events
+    }
+
+    /// Add a record to its type's current page, dropping the page that falls out of
+    /// retention when a new one is started
+    fn append_log(env: &Env, record: &EventRecord) {
+        let count = Self::get_count(env, &record.event_type);
+        let page = (count / EVENT_PAGE_SIZE as u64) as u32;
+        let key = Self::page_key(env, &record.event_type, page);
+        let mut events: Vec<EventRecord> =
+            persistent::PersistentStore::get(env, &key).unwrap_or_else(|| Vec::new(env));
+        events.push_back(record.clone());
+        persistent::PersistentStore::set(env, &key, &events);
+
+        let retained = Self::get_retained_pages(env);
+        if count.is_multiple_of(EVENT_PAGE_SIZE as u64) && page >= retained {
+            persistent::PersistentStore::remove(
+                env,
+                &Self::page_key(env, &record.event_type, page - retained),
+            );
+        }
+        persistent::PersistentStore::set(
+            env,
+            &Self::count_key(env, &record.event_type),
+            &(count + 1),
+        );
+    }
+
+    /// Retained pages of every logged event type, oldest first per type
+    pub fn retained_pages(env: &Env) -> Vec<EventPage> {
+        let mut pages = Vec::new(env);
+        for event_type in Self::get_aggregates(env).keys().iter() {
+            if let Some(latest) = Self::latest_page(env, &event_type) {
+                for page in Self::oldest_page(env, latest)..=latest {
+                    pages.push_back(Self::get_page(env, &event_type, page));
+                }
+            }
+        }
+        pages
+    }
+
+    /// Extend the TTL of every retained log page and counter, returning how many exist
+    pub fn bump(env: &Env) -> u32 {
+        let mut bumped = 0u32;
+        for event_type in Self::get_aggregates(env).keys().iter() {
+            bumped +=
+                persistent::PersistentStore::bump(env, &Self::count_key(env, &event_type)) as u32;
+            if let Some(latest) = Self::latest_page(env, &event_type) {
+                for page in Self::oldest_page(env, latest)..=latest {
+                    bumped += persistent::PersistentStore::bump(
+                        env,
+                        &Self::page_key(env, &event_type, page),
+                    ) as u32;
+                }
+            }
+        }
+        bumped
+    }
+
+    /// Drop the shared log map written before logs were paged
+    pub fn remove_legacy_logs(env: &Env) {
+        persistent::PersistentStore::remove(env, &Self::legacy_logs_key(env));
     }
 
-    pub fn save_logs(env: &Env, logs: &Map<Symbol, Vec<EventRecord>>) {
-        env.storage().instance().set(&Self::logs_key(env), logs);
+    /// Delete pages left behind outside the retention window after it was shortened,
+    /// returning how many were removed (at most `MAX_EVENT_RETAINED_PAGES` per call)
+    pub fn prune_expired(env: &Env) -> u32 {
+        let mut removed = 0u32;
+        for event_type in Self::get_aggregates(env).keys().iter() {
+            let Some(latest) = Self::latest_page(env, &event_type) else {
+                continue;
+            };
+            let mut page = Self::oldest_page(env, latest);
+            while page > 0 && removed < MAX_EVENT_RETAINED_PAGES {
+                page -= 1;
+                let key = Self::page_key(env, &event_type, page);
+                if !env.storage().persistent().has(&key) {
+                    break;
+                }
+                persistent::PersistentStore::remove(env, &key);
+                removed += 1;
+            }
+        }
+        removed
     }
 
     pub fn get_summary(env: &Env) -> EventSummary {
@@ -886,17 +1306,14 @@ impl EventStorage {
     }
 
     pub fn append_event(env: &Env, record: &EventRecord) {
-        let mut logs = Self::get_logs(env);
-        let mut events = logs
-            .get(record.event_type.clone())
-            .unwrap_or_else(|| Vec::new(env));
-        events.push_back(record.clone());
-        // Keep only the latest 32 events per type to cap storage use
-        if events.len() > 32 {
-            events = events.slice(events.len() - 32..);
+        if let Some(user) = &record.user {
+            bloom::ActivityBloom::insert(env, user, record.timestamp);
+        }
+        if let Some(asset) = &record.asset {
+            bloom::ActivityBloom::insert(env, asset, record.timestamp);
         }
-        logs.set(record.event_type.clone(), events);
-        Self::save_logs(env, &logs);
+
+        Self::append_log(env, record);
 
         let mut aggregates = Self::get_aggregates(env);
         let mut aggregate = aggregates
@@ -950,6 +1367,7 @@ impl EventTracker {
         }
         let record = EventRecord::new(env, event_type, topics, user, asset, amount);
         EventStorage::append_event(env, &record);
+        subscribers::Subscribers::notify(env, &record);
     }
 
     pub fn capture(env: &Env, event: &ProtocolEvent) {
@@ -1135,11 +1553,12 @@ impl EventTracker {
                 user = Some(addr.clone());
                 amount = if *status { 1 } else { 0 };
             }
-            ProtocolEvent::ComplianceAlert(addr, reason) => {
+            ProtocolEvent::ComplianceAlert(addr, reason, rule_id) => {
                 event_type = Symbol::new(env, "compliance_alert");
                 topics = Self::base_topics(env, &event_type);
                 topics.push_back(reason.clone());
                 user = Some(addr.clone());
+                amount = *rule_id as i128;
             }
             ProtocolEvent::MMIncentiveAccrued(user_addr, value) => {
                 event_type = Symbol::new(env, "mm_incentive_accrued");
@@ -1284,6 +1703,22 @@ impl EventTracker {
     }
 }
 
+/// Token registration errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum TokenError {
+    InterfaceInvalid = 11001,
+}
+
+impl From<TokenError> for ProtocolError {
+    fn from(err: TokenError) -> Self {
+        match err {
+            TokenError::InterfaceInvalid => ProtocolError::AssetNotSupported,
+        }
+    }
+}
+
 /// Registry for token assets supported by the protocol
 pub struct TokenRegistry;
 
@@ -1309,6 +1744,11 @@ impl TokenRegistry {
         Symbol::new(env, "primary_asset")
     }
 
+    fn decimals_key(env: &Env, token: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "token_decimals"), token.clone())
+    }
+
+    /// Register `token` under `key` once it passes the SEP-41 conformance checks
     pub fn set_asset(
         env: &Env,
         caller: &Address,
@@ -1316,16 +1756,77 @@ impl TokenRegistry {
         token: Address,
     ) -> Result<(), ProtocolError> {
         ProtocolConfig::require_admin(env, caller)?;
+        Self::register(env, key, token)
+    }
+
+    /// Register `token` under `key` for callers that already checked admin rights
+    pub fn register(env: &Env, key: Symbol, token: Address) -> Result<(), ProtocolError> {
+        let decimals = Self::verify_interface(env, &token)?;
+        Self::save_decimals(env, &token, decimals);
         let mut assets = Self::assets(env);
         assets.set(key, token);
         Self::save_assets(env, &assets);
         Ok(())
     }
 
+    /// Decimals cached when the token was registered or priced
+    pub fn decimals(env: &Env, token: &Address) -> Option<u32> {
+        env.storage()
+            .instance()
+            .get(&Self::decimals_key(env, token))
+    }
+
+    pub fn save_decimals(env: &Env, token: &Address, decimals: u32) {
+        env.storage()
+            .instance()
+            .set(&Self::decimals_key(env, token), &decimals);
+    }
+
+    /// Call `decimals`, `name` and `symbol` and dry-run a zero-amount self-transfer,
+    /// returning the decimals. The first check that fails is reported in a
+    /// `token_interface_rejected` event and fails with `AssetNotSupported`.
+    fn verify_interface(env: &Env, token: &Address) -> Result<u32, ProtocolError> {
+        let client = TokenClient::new(env, token);
+        let contract = env.current_contract_address();
+        let decimals = client.try_decimals().ok().and_then(|r| r.ok());
+        let failed = if decimals.is_none() {
+            Some("decimals")
+        } else if !matches!(client.try_name(), Ok(Ok(_))) {
+            Some("name")
+        } else if !matches!(client.try_symbol(), Ok(Ok(_))) {
+            Some("symbol")
+        } else if !matches!(client.try_transfer(&contract, &contract, &0), Ok(Ok(_))) {
+            Some("transfer")
+        } else {
+            None
+        };
+        if let Some(check) = failed {
+            env.events().publish(
+                (
+                    Symbol::new(env, "token_interface_rejected"),
+                    Symbol::new(env, "token"),
+                ),
+                (
+                    Symbol::new(env, "token"),
+                    token.clone(),
+                    Symbol::new(env, "failed_check"),
+                    Symbol::new(env, check),
+                ),
+            );
+            return Err(TokenError::InterfaceInvalid.into());
+        }
+        Ok(decimals.unwrap_or_default())
+    }
+
     pub fn get_asset(env: &Env, key: Symbol) -> Option<Address> {
         Self::assets(env).get(key)
     }
 
+    /// All registered assets by key, including the primary asset
+    pub fn list_assets(env: &Env) -> Map<Symbol, Address> {
+        Self::assets(env)
+    }
+
     pub fn set_primary_asset(
         env: &Env,
         caller: &Address,
@@ -1337,17 +1838,25 @@ impl TokenRegistry {
     pub fn require_primary_asset(env: &Env) -> Result<Address, ProtocolError> {
         Self::get_asset(env, Self::primary_key(env)).ok_or(ProtocolError::AssetNotSupported)
     }
+
+    /// Check that `asset` is a lending market: registered here, priced for multi-asset
+    /// positions, or a registered LP token
+    pub fn require_supported(env: &Env, asset: &Address) -> Result<(), ProtocolError> {
+        if Self::assets(env).values().contains(asset)
+            || cross_asset::CrossAssets::get_asset_price(env, asset).is_some()
+            || lp_pricing::LpTokenStorage::get(env, asset).is_some()
+        {
+            Ok(())
+        } else {
+            Err(ProtocolError::AssetNotSupported)
+        }
+    }
 }
 
 /// Utility enforcing token transfers with invariant checks
 pub struct TransferEnforcer;
 
 impl TransferEnforcer {
-    fn token_client(env: &Env) -> Result<(TokenClient<'_>, Address), ProtocolError> {
-        let asset = TokenRegistry::require_primary_asset(env)?;
-        Ok((TokenClient::new(env, &asset), asset))
-    }
-
     fn contract_address(env: &Env) -> Address {
         env.current_contract_address()
     }
@@ -1462,22 +1971,42 @@ impl TransferEnforcer {
         );
     }
 
+    /// Pull `amount` of a lending market's `asset` from the user. Returns the amount
+    /// actually received, which is what callers credit internally (less than `amount`
+    /// for fee-on-transfer tokens). Fails with `AssetNotSupported` for unknown assets.
     pub fn transfer_in(
         env: &Env,
+        asset: &Address,
+        user: &Address,
+        amount: i128,
+        flow: Symbol,
+    ) -> Result<i128, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        TokenRegistry::require_supported(env, asset)?;
+        Self::transfer_in_asset(env, asset, user, amount, flow)
+    }
+
+    /// `transfer_in` without the registry check, for tokens moved outside the lending
+    /// markets (AMM legs, pools, rewards, bridges)
+    pub fn transfer_in_asset(
+        env: &Env,
+        asset: &Address,
         user: &Address,
         amount: i128,
         flow: Symbol,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<i128, ProtocolError> {
         if amount <= 0 {
             return Err(ProtocolError::InvalidAmount);
         }
-        let (client, asset) = Self::token_client(env)?;
+        let client = TokenClient::new(env, asset);
         let contract = Self::contract_address(env);
 
         let before_contract = client.balance(&contract);
         let before_user = client.balance(user);
 
-        Self::emit_attempt(env, user, &contract, &asset, amount, &flow);
+        Self::emit_attempt(env, user, &contract, asset, amount, &flow);
 
         client.transfer(user, &contract, &amount);
 
@@ -1487,33 +2016,62 @@ impl TransferEnforcer {
         let contract_delta = after_contract.saturating_sub(before_contract);
         let user_delta = before_user.saturating_sub(after_user);
 
-        if contract_delta != amount || user_delta != amount {
-            Self::emit_failure(
-                env,
-                user,
-                &contract,
-                &asset,
-                amount,
-                &flow,
-                "invariant_violation",
-            );
-            return Err(ProtocolError::BalanceInvariantViolation);
-        }
+        let received = match token_behavior::TokenBehaviorRegistry::verify(
+            env,
+            asset,
+            amount,
+            user_delta,
+            contract_delta,
+        ) {
+            Some(received) => received,
+            None => {
+                Self::emit_failure(
+                    env,
+                    user,
+                    &contract,
+                    asset,
+                    amount,
+                    &flow,
+                    "invariant_violation",
+                );
+                return Err(ProtocolError::BalanceInvariantViolation);
+            }
+        };
 
-        Self::emit_success(env, user, &contract, &asset, amount, &flow);
-        Ok(())
+        Self::emit_success(env, user, &contract, asset, received, &flow);
+        Ok(received)
     }
 
+    /// Pay `amount` of a lending market's `asset` to the user. Returns the amount the
+    /// user actually received (less than `amount` for fee-on-transfer tokens). Fails with
+    /// `AssetNotSupported` for unknown assets.
     pub fn transfer_out(
         env: &Env,
+        asset: &Address,
         user: &Address,
         amount: i128,
         flow: Symbol,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<i128, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        TokenRegistry::require_supported(env, asset)?;
+        Self::transfer_out_asset(env, asset, user, amount, flow)
+    }
+
+    /// `transfer_out` without the registry check, for tokens moved outside the lending
+    /// markets (AMM legs, pools, rewards, treasury payouts)
+    pub fn transfer_out_asset(
+        env: &Env,
+        asset: &Address,
+        user: &Address,
+        amount: i128,
+        flow: Symbol,
+    ) -> Result<i128, ProtocolError> {
         if amount <= 0 {
             return Err(ProtocolError::InvalidAmount);
         }
-        let (client, asset) = Self::token_client(env)?;
+        let client = TokenClient::new(env, asset);
         let contract = Self::contract_address(env);
 
         let before_contract = client.balance(&contract);
@@ -1522,7 +2080,7 @@ impl TransferEnforcer {
                 env,
                 &contract,
                 user,
-                &asset,
+                asset,
                 amount,
                 &flow,
                 "insufficient_liquidity",
@@ -1531,7 +2089,7 @@ impl TransferEnforcer {
         }
         let before_user = client.balance(user);
 
-        Self::emit_attempt(env, &contract, user, &asset, amount, &flow);
+        Self::emit_attempt(env, &contract, user, asset, amount, &flow);
 
         client.transfer(&contract, user, &amount);
 
@@ -1541,21 +2099,91 @@ impl TransferEnforcer {
         let contract_delta = before_contract.saturating_sub(after_contract);
         let user_delta = after_user.saturating_sub(before_user);
 
-        if contract_delta != amount || user_delta != amount {
+        let delivered = match token_behavior::TokenBehaviorRegistry::verify(
+            env,
+            asset,
+            amount,
+            contract_delta,
+            user_delta,
+        ) {
+            Some(delivered) => delivered,
+            None => {
+                Self::emit_failure(
+                    env,
+                    &contract,
+                    user,
+                    asset,
+                    amount,
+                    &flow,
+                    "invariant_violation",
+                );
+                return Err(ProtocolError::BalanceInvariantViolation);
+            }
+        };
+
+        Self::emit_success(env, &contract, user, asset, delivered, &flow);
+        Ok(delivered)
+    }
+
+    /// Check that `expected` of `asset` reached the contract from `from` since it held
+    /// `before`, for transfers a counterparty makes itself (e.g. AMM adapters), allowing
+    /// the shortfall the asset's token behavior permits. Returns the amount received.
+    pub fn verify_received(
+        env: &Env,
+        asset: &Address,
+        from: &Address,
+        before: i128,
+        expected: i128,
+        flow: Symbol,
+    ) -> Result<i128, ProtocolError> {
+        let contract = Self::contract_address(env);
+        let received = TokenClient::new(env, asset)
+            .balance(&contract)
+            .saturating_sub(before);
+        if received < token_behavior::TokenBehaviorRegistry::min_received(env, asset, expected) {
             Self::emit_failure(
                 env,
+                from,
                 &contract,
-                user,
-                &asset,
-                amount,
+                asset,
+                expected,
                 &flow,
                 "invariant_violation",
             );
             return Err(ProtocolError::BalanceInvariantViolation);
         }
+        Self::emit_success(env, from, &contract, asset, received, &flow);
+        Ok(received)
+    }
+}
 
-        Self::emit_success(env, &contract, user, &asset, amount, &flow);
-        Ok(())
+/// Parameter keys accepted by queued emergency updates and governance proposals
+const PARAM_UPDATE_KEYS: [&str; 10] = [
+    "min_collateral_ratio",
+    "reserve_factor",
+    "base_rate",
+    "kink_utilization",
+    "multiplier",
+    "rate_ceiling",
+    "rate_floor",
+    "flash_fee_bps",
+    "close_factor",
+    "liquidation_incentive",
+];
+
+/// Emergency management errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum EmergencyError {
+    ParamUpdateTimelocked = 15001,
+}
+
+impl From<EmergencyError> for ProtocolError {
+    fn from(err: EmergencyError) -> Self {
+        match err {
+            EmergencyError::ParamUpdateTimelocked => ProtocolError::InvalidOperation,
+        }
     }
 }
 
@@ -1583,7 +2211,13 @@ impl EmergencyManager {
         false
     }
 
+    /// Addresses allowed to pause and run recovery besides the admin
+    pub fn managers(env: &Env) -> Vec<Address> {
+        EmergencyStorage::get(env).emergency_managers
+    }
+
     fn ensure_authorized(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
         if Self::is_authorized(env, caller) {
             Ok(())
         } else {
@@ -1595,8 +2229,15 @@ impl EmergencyManager {
         env: &Env,
         operation: OperationKind,
     ) -> Result<(), ProtocolError> {
-        let state = EmergencyStorage::get(env);
-        match state.status {
+        if !matches!(operation, OperationKind::Admin | OperationKind::Governance) {
+            watchdog::Watchdog::check(env)?;
+        }
+        Self::check_status(env, operation)
+    }
+
+    /// Whether the stored emergency status permits `operation`, ignoring the watchdog
+    pub fn check_status(env: &Env, operation: OperationKind) -> Result<(), ProtocolError> {
+        match EmergencyStorage::get(env).status {
             EmergencyStatus::Operational => Ok(()),
             EmergencyStatus::Paused => match operation {
                 OperationKind::Admin | OperationKind::Governance => Ok(()),
@@ -1607,6 +2248,7 @@ impl EmergencyManager {
                 | OperationKind::Deposit
                 | OperationKind::Governance
                 | OperationKind::Admin => Ok(()),
+                _ if unwind::RecoveryUnwind::is_reenabled(env, operation) => Ok(()),
                 _ => Err(ProtocolError::RecoveryModeRestricted),
             },
         }
@@ -1649,33 +2291,59 @@ impl EmergencyManager {
         Ok(())
     }
 
-    pub fn pause(env: &Env, caller: &Address, reason: Option<String>) -> Result<(), ProtocolError> {
+    /// Pause, recovery and parameter application need guardian confirmations while a
+    /// guardian set is configured
+    fn ensure_single_signer(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
         Self::ensure_authorized(env, caller)?;
-        let mut state = EmergencyStorage::get(env);
-        state.status = EmergencyStatus::Paused;
-        state.paused_by = Some(caller.clone());
-        state.paused_at = env.ledger().timestamp();
-        state.reason = reason.clone();
-        EmergencyStorage::save(env, &state);
-
-        ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "paused"), reason).emit(env);
+        if guardian::Guardians::is_enabled(env) {
+            return Err(ProtocolError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    pub fn pause(env: &Env, caller: &Address, reason: Option<String>) -> Result<(), ProtocolError> {
+        Self::ensure_single_signer(env, caller)?;
+        Self::set_paused(env, caller, reason);
         Ok(())
     }
 
+    pub fn set_paused(env: &Env, caller: &Address, reason: Option<String>) {
+        let mut state = EmergencyStorage::get(env);
+        state.status = EmergencyStatus::Paused;
+        state.paused_by = Some(caller.clone());
+        state.paused_at = env.ledger().timestamp();
+        state.reason = reason.clone();
+        EmergencyStorage::save(env, &state);
+
+        ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "paused"), reason).emit(env);
+    }
+
     pub fn enter_recovery(
         env: &Env,
         caller: &Address,
         plan: Option<String>,
     ) -> Result<(), ProtocolError> {
-        Self::ensure_authorized(env, caller)?;
+        Self::ensure_single_signer(env, caller)?;
+        Self::set_recovery(env, plan);
+        Ok(())
+    }
+
+    pub fn set_recovery(env: &Env, plan: Option<String>) {
         let mut state = EmergencyStorage::get(env);
+        let entering = state.status != EmergencyStatus::Recovery;
         state.status = EmergencyStatus::Recovery;
         state.recovery_plan = plan.clone();
+        if entering {
+            state.unwind_stages = Vec::new(env);
+            state.recovery_utilization_cap = 0;
+        }
         state.last_recovery_update = env.ledger().timestamp();
         EmergencyStorage::save(env, &state);
+        if entering {
+            recovery::RecoveryWithdrawals::start_round(env);
+        }
 
         ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "recovery"), plan).emit(env);
-        Ok(())
     }
 
     pub fn resume(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
@@ -1684,8 +2352,11 @@ impl EmergencyManager {
         state.status = EmergencyStatus::Operational;
         state.reason = None;
         state.recovery_plan = None;
+        state.unwind_stages = Vec::new(env);
+        state.recovery_utilization_cap = 0;
         state.last_recovery_update = env.ledger().timestamp();
         EmergencyStorage::save(env, &state);
+        watchdog::Watchdog::restart(env, "resume");
 
         ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "operational"), None).emit(env);
         Ok(())
@@ -1731,25 +2402,120 @@ impl EmergencyManager {
     }
 
     pub fn apply_param_updates(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
-        Self::ensure_authorized(env, caller)?;
-        let mut state = EmergencyStorage::get(env);
-        let updates = state.pending_param_updates;
-        let len = updates.len();
+        Self::ensure_single_signer(env, caller)?;
+        Self::apply_pending_updates(env)
+    }
 
-        for idx in 0..len {
-            if let Some(update) = updates.get(idx) {
-                Self::apply_single_update(env, &update)?;
-                ProtocolEvent::EmergencyParamUpdateApplied(update.key.clone(), update.value)
-                    .emit(env);
+    /// Apply every due queued update. Fails with `InvalidOperation` when updates are
+    /// queued but none has waited out the timelock yet.
+    pub fn apply_pending_updates(env: &Env) -> Result<(), ProtocolError> {
+        let applied = Self::apply_due_updates(env)?;
+        if applied == 0 && !EmergencyStorage::get(env).pending_param_updates.is_empty() {
+            return Err(EmergencyError::ParamUpdateTimelocked.into());
+        }
+        Ok(())
+    }
+
+    /// Apply the queued updates that have waited out the timelock, drop the ones past
+    /// their expiry and keep the rest queued. Returns how many were applied.
+    pub fn apply_due_updates(env: &Env) -> Result<u32, ProtocolError> {
+        let mut state = EmergencyStorage::get(env);
+        let timelock = EmergencyStorage::get_timelock(env);
+        let now = env.ledger().timestamp();
+        let mut remaining = Vec::new(env);
+        let mut applied = 0u32;
+        for update in state.pending_param_updates.iter() {
+            let age = now.saturating_sub(update.queued_at);
+            if timelock.expiry > 0 && age > timelock.min_delay.saturating_add(timelock.expiry) {
+                Self::publish_param_update(env, "emergency_param_update_expired", &update);
+                continue;
             }
+            if age < timelock.min_delay {
+                remaining.push_back(update);
+                continue;
+            }
+            Self::apply_single_update(env, &update)?;
+            ProtocolEvent::EmergencyParamUpdateApplied(update.key.clone(), update.value).emit(env);
+            applied += 1;
         }
+        state.pending_param_updates = remaining;
+        EmergencyStorage::save(env, &state);
+        Ok(applied)
+    }
 
-        state.pending_param_updates = Vec::new(env);
+    /// Drop every queued update of `key` (admin only). Returns how many were cancelled.
+    pub fn cancel_param_update(
+        env: &Env,
+        caller: &Address,
+        key: Symbol,
+    ) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut state = EmergencyStorage::get(env);
+        let mut remaining = Vec::new(env);
+        let mut cancelled = 0u32;
+        for update in state.pending_param_updates.iter() {
+            if update.key == key {
+                Self::publish_param_update(env, "emergency_param_update_cancelled", &update);
+                cancelled += 1;
+            } else {
+                remaining.push_back(update);
+            }
+        }
+        if cancelled == 0 {
+            return Err(ProtocolError::NotFound);
+        }
+        state.pending_param_updates = remaining;
         EmergencyStorage::save(env, &state);
+        Ok(cancelled)
+    }
+
+    /// Set the wait and expiry window of queued updates (admin only)
+    pub fn set_param_timelock(
+        env: &Env,
+        caller: &Address,
+        timelock: ParamTimelock,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        EmergencyStorage::save_timelock(env, &timelock);
+        env.events().publish(
+            (
+                Symbol::new(env, "param_timelock_updated"),
+                Symbol::new(env, "admin"),
+            ),
+            (
+                Symbol::new(env, "min_delay"),
+                timelock.min_delay,
+                Symbol::new(env, "expiry"),
+                timelock.expiry,
+            ),
+        );
         Ok(())
     }
 
-    fn apply_single_update(env: &Env, update: &EmergencyParamUpdate) -> Result<(), ProtocolError> {
+    fn publish_param_update(env: &Env, action: &str, update: &EmergencyParamUpdate) {
+        env.events().publish(
+            (Symbol::new(env, action), update.key.clone()),
+            (
+                Symbol::new(env, "parameter"),
+                update.key.clone(),
+                Symbol::new(env, "value"),
+                update.value,
+            ),
+        );
+    }
+
+    /// Whether `key` names a parameter that `apply_single_update` can set
+    pub fn is_param_key(env: &Env, key: &Symbol) -> bool {
+        PARAM_UPDATE_KEYS
+            .iter()
+            .any(|name| Symbol::new(env, name) == *key)
+    }
+
+    /// Apply one parameter update; also used by executed governance proposals
+    pub fn apply_single_update(
+        env: &Env,
+        update: &EmergencyParamUpdate,
+    ) -> Result<(), ProtocolError> {
         let key_min_collateral = Symbol::new(env, "min_collateral_ratio");
         let key_reserve_factor = Symbol::new(env, "reserve_factor");
         let key_base_rate = Symbol::new(env, "base_rate");
@@ -1758,6 +2524,8 @@ impl EmergencyManager {
         let key_rate_ceiling = Symbol::new(env, "rate_ceiling");
         let key_rate_floor = Symbol::new(env, "rate_floor");
         let key_flash_fee = Symbol::new(env, "flash_fee_bps");
+        let key_close_factor = Symbol::new(env, "close_factor");
+        let key_liquidation_incentive = Symbol::new(env, "liquidation_incentive");
 
         if update.key == key_min_collateral {
             let admin = ProtocolConfig::get_admin(env).ok_or(ProtocolError::ConfigurationError)?;
@@ -1765,6 +2533,23 @@ impl EmergencyManager {
             return Ok(());
         }
 
+        if update.key == key_close_factor || update.key == key_liquidation_incentive {
+            if update.value < 0 {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            let mut risk = RiskConfigStorage::get(env);
+            if update.key == key_close_factor {
+                risk.close_factor = update.value;
+            } else {
+                risk.liquidation_incentive = update.value;
+            }
+            risk.last_update = env.ledger().timestamp();
+            RiskConfigStorage::save(env, &risk);
+            ProtocolEvent::RiskParamsUpdated(risk.close_factor, risk.liquidation_incentive)
+                .emit(env);
+            return Ok(());
+        }
+
         let mut config = InterestRateStorage::get_config(env);
         if update.key == key_reserve_factor {
             config.reserve_factor = update.value;
@@ -1822,6 +2607,25 @@ impl EmergencyManager {
         ProtocolEvent::EmergencyFundUpdated(caller.clone(), delta, reserve_delta).emit(env);
         Ok(())
     }
+
+    /// Unreserved emergency fund balance
+    pub fn available_fund(env: &Env) -> i128 {
+        let fund = EmergencyStorage::get(env).fund;
+        fund.balance - fund.reserved
+    }
+
+    /// Pay `amount` out of the unreserved emergency fund, returning the fund's token
+    pub fn draw_fund(env: &Env, amount: i128) -> Result<Option<Address>, ProtocolError> {
+        if amount > Self::available_fund(env) {
+            return Err(ProtocolError::EmergencyFundInsufficient);
+        }
+        let mut state = EmergencyStorage::get(env);
+        state.fund.balance -= amount;
+        state.fund.last_update = env.ledger().timestamp();
+        let token = state.fund.token.clone();
+        EmergencyStorage::save(env, &state);
+        Ok(token)
+    }
 }
 
 /// Reentrancy guard for security
@@ -1831,13 +2635,26 @@ impl ReentrancyGuard {
     fn key(env: &Env) -> Symbol {
         Symbol::new(env, "reentrancy")
     }
-    pub fn enter(env: &Env) -> Result<(), ProtocolError> {
-        let entered = env
-            .storage()
+    fn batch_key(env: &Env) -> Symbol {
+        Symbol::new(env, "reentrancy_batch")
+    }
+    fn step_key(env: &Env) -> Symbol {
+        Symbol::new(env, "reentrancy_step")
+    }
+    fn flag(env: &Env, key: &Symbol) -> bool {
+        env.storage()
             .instance()
-            .get::<Symbol, bool>(&Self::key(env))
-            .unwrap_or(false);
+            .get::<Symbol, bool>(key)
+            .unwrap_or(false)
+    }
+    pub fn enter(env: &Env) -> Result<(), ProtocolError> {
+        let entered = Self::flag(env, &Self::key(env));
         if entered {
+            // Inside a batch each action may take the guard once, but not re-enter it
+            if Self::flag(env, &Self::batch_key(env)) && !Self::flag(env, &Self::step_key(env)) {
+                env.storage().instance().set(&Self::step_key(env), &true);
+                return Ok(());
+            }
             let error = ProtocolError::ReentrancyDetected;
             return Err(error);
         }
@@ -1845,8 +2662,23 @@ impl ReentrancyGuard {
         Ok(())
     }
     pub fn exit(env: &Env) {
+        if Self::flag(env, &Self::step_key(env)) {
+            env.storage().instance().remove(&Self::step_key(env));
+            return;
+        }
         env.storage().instance().set(&Self::key(env), &false);
     }
+    /// Hold the guard across several guarded steps (batches, collateral swaps)
+    pub fn enter_batch(env: &Env) -> Result<(), ProtocolError> {
+        Self::enter(env)?;
+        env.storage().instance().set(&Self::batch_key(env), &true);
+        Ok(())
+    }
+    pub fn exit_batch(env: &Env) {
+        env.storage().instance().remove(&Self::batch_key(env));
+        env.storage().instance().remove(&Self::step_key(env));
+        Self::exit(env);
+    }
 }
 
 /// RAII helper to ensure reentrancy guard exit on scope drop
@@ -1879,10 +2711,10 @@ pub struct Position {
     pub user: Address,
     /// The amount of collateral deposited
     pub collateral: i128,
-    /// The amount borrowed
+    /// Debt principal as of `borrow_index`; current debt grows with the market's index
     pub debt: i128,
-    /// Accrued borrow interest (scaled by 1e8)
-    pub borrow_interest: i128,
+    /// Market borrow index the debt was last rebased at (scaled by 1e18, 0 = unset)
+    pub borrow_index: i128,
     /// Accrued supply interest (scaled by 1e8)
     pub supply_interest: i128,
     /// Last time interest was accrued for this position
@@ -1896,13 +2728,58 @@ impl Position {
             user,
             collateral,
             debt,
-            borrow_interest: 0,
+            borrow_index: 0,
             supply_interest: 0,
             last_accrual_time: 0,
         }
     }
 }
 
+/// A primary position with its debt valued at the current borrow index
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PositionDetails {
+    pub position: Position,
+    /// Debt including interest accrued since the position was last written
+    pub current_debt: i128,
+    /// Collateral ratio at the current debt in percent; 0 without debt
+    pub collateral_ratio: i128,
+    /// Health factor at the current debt (100 = at the minimum ratio), `None` without debt
+    pub health_factor: Option<i128>,
+}
+
+/// Multi-asset position with per-asset collateral and debt balances
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CrossPosition {
+    /// The address of the user
+    pub user: Address,
+    /// Collateral balances by asset
+    pub collateral: Map<Address, i128>,
+    /// Debt principal by asset as of that asset's `borrow_index` snapshot
+    pub debt: Map<Address, i128>,
+    /// Market borrow index each debt balance was last rebased at (scaled by 1e18)
+    pub borrow_index: Map<Address, i128>,
+    /// Accrued supply interest by asset
+    pub supply_interest: Map<Address, i128>,
+    /// Last time interest was accrued for this position
+    pub last_accrual_time: u64,
+}
+
+impl CrossPosition {
+    /// Create an empty position
+    pub fn new(env: &Env, user: Address) -> Self {
+        Self {
+            user,
+            collateral: Map::new(env),
+            debt: Map::new(env),
+            borrow_index: Map::new(env),
+            supply_interest: Map::new(env),
+            last_accrual_time: 0,
+        }
+    }
+}
+
 /// Interest rate configuration parameters
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -1943,6 +2820,9 @@ impl Default for InterestRateConfig {
     }
 }
 
+/// Scale of market borrow indexes (1e18)
+pub const BORROW_INDEX_SCALE: i128 = 1_000_000_000_000_000_000;
+
 /// Current interest rate state
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -1961,6 +2841,8 @@ pub struct InterestRateState {
     pub last_accrual_time: u64,
     /// Smoothed borrow rate
     pub smoothed_borrow_rate: i128,
+    /// Cumulative borrow index, compounded at the borrow rate on every accrual (scaled by 1e18)
+    pub borrow_index: i128,
 }
 
 impl InterestRateState {
@@ -1974,6 +2856,7 @@ impl InterestRateState {
             total_supplied: 0,
             last_accrual_time: 0,
             smoothed_borrow_rate: 0,
+            borrow_index: BORROW_INDEX_SCALE,
         }
     }
 }
@@ -2076,9 +2959,170 @@ impl InterestRateStorage {
             .unwrap_or_else(InterestRateState::initial)
     }
 
+    fn market_config_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "market_config"), asset.clone())
+    }
+
+    fn market_state_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "market_state"), asset.clone())
+    }
+
+    fn markets_key(env: &Env) -> Symbol {
+        Symbol::new(env, "markets")
+    }
+
+    /// The global config and state belong to the primary asset's market
+    fn is_primary(env: &Env, asset: &Address) -> bool {
+        TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset)
+    }
+
+    fn track_market(env: &Env, asset: &Address) {
+        let mut markets = Self::get_markets(env);
+        if !markets.contains(asset) {
+            markets.push_back(asset.clone());
+            env.storage()
+                .instance()
+                .set(&Self::markets_key(env), &markets);
+        }
+    }
+
+    /// Extend the TTL of an asset's market entries, returning how many exist
+    pub fn bump_market(env: &Env, asset: &Address) -> u32 {
+        persistent::PersistentStore::bump(env, &Self::market_config_key(env, asset)) as u32
+            + persistent::PersistentStore::bump(env, &Self::market_state_key(env, asset)) as u32
+    }
+
+    /// Assets with their own market config or state
+    pub fn get_markets(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&Self::markets_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Market config of an asset; assets without their own config inherit the global one
+    pub fn get_market_config(env: &Env, asset: &Address) -> InterestRateConfig {
+        if Self::is_primary(env, asset) {
+            return Self::get_config(env);
+        }
+        persistent::PersistentStore::get(env, &Self::market_config_key(env, asset))
+            .unwrap_or_else(|| Self::get_config(env))
+    }
+
+    pub fn save_market_config(env: &Env, asset: &Address, config: &InterestRateConfig) {
+        if Self::is_primary(env, asset) {
+            return Self::save_config(env, config);
+        }
+        persistent::PersistentStore::set(env, &Self::market_config_key(env, asset), config);
+        Self::track_market(env, asset);
+    }
+
+    pub fn get_market_state(env: &Env, asset: &Address) -> InterestRateState {
+        if Self::is_primary(env, asset) {
+            return Self::get_state(env);
+        }
+        persistent::PersistentStore::get(env, &Self::market_state_key(env, asset))
+            .unwrap_or_else(InterestRateState::initial)
+    }
+
+    pub fn save_market_state(env: &Env, asset: &Address, state: &InterestRateState) {
+        if Self::is_primary(env, asset) {
+            return Self::save_state(env, state);
+        }
+        persistent::PersistentStore::set(env, &Self::market_state_key(env, asset), state);
+        Self::track_market(env, asset);
+    }
+
+    /// Move a market's supplied and borrowed totals and refresh its rates
+    pub fn apply_market_flow(
+        env: &Env,
+        asset: &Address,
+        supplied: i128,
+        borrowed: i128,
+    ) -> InterestRateState {
+        let mut state = Self::get_market_state(env, asset);
+        state.total_supplied = state.total_supplied.saturating_add(supplied).max(0);
+        state.total_borrowed = state.total_borrowed.saturating_add(borrowed).max(0);
+        Self::save_market_state(env, asset, &state);
+        Self::update_market_state(env, asset)
+    }
+
+    /// Recompute rates for an asset's market from its own utilization and config
+    pub fn update_market_state(env: &Env, asset: &Address) -> InterestRateState {
+        if Self::is_primary(env, asset) {
+            return Self::update_state(env);
+        }
+        let mut state = Self::get_market_state(env, asset);
+        Self::recompute(
+            env,
+            &mut state,
+            &Self::get_market_config(env, asset),
+            &rate_dynamics::RateDynamicsStorage::get(env, asset),
+        );
+        Self::save_market_state(env, asset, &state);
+        watchdog::Watchdog::heartbeat(env, "accrual");
+        state
+    }
+
+    /// Recompute rates for the primary asset's (global) market
     pub fn update_state(env: &Env) -> InterestRateState {
         let mut state = Self::get_state(env);
-        let config = Self::get_config(env);
+        let dynamics = TokenRegistry::require_primary_asset(env)
+            .map(|asset| rate_dynamics::RateDynamicsStorage::get(env, &asset))
+            .unwrap_or_default();
+        Self::recompute(env, &mut state, &Self::get_config(env), &dynamics);
+        Self::save_state(env, &state);
+        watchdog::Watchdog::heartbeat(env, "accrual");
+        state
+    }
+
+    /// Borrow index of an asset's market projected to the current ledger time, without
+    /// writing the accrual
+    pub fn current_borrow_index(env: &Env, asset: &Address) -> i128 {
+        let mut state = Self::get_market_state(env, asset);
+        Self::compound(env, &mut state);
+        state.borrow_index
+    }
+
+    /// Compound the borrow index and total borrowed at the current borrow rate over the
+    /// time since the last accrual
+    fn compound(env: &Env, state: &mut InterestRateState) {
+        let elapsed = env
+            .ledger()
+            .timestamp()
+            .saturating_sub(state.last_accrual_time);
+        if state.last_accrual_time == 0 || elapsed == 0 {
+            return;
+        }
+        let rate = state.current_borrow_rate;
+        state.borrow_index = state
+            .borrow_index
+            .saturating_add(InterestRateManager::interest(
+                state.borrow_index,
+                rate,
+                elapsed,
+            ));
+        state.total_borrowed = state
+            .total_borrowed
+            .saturating_add(InterestRateManager::interest(
+                state.total_borrowed,
+                rate,
+                elapsed,
+            ));
+    }
+
+    fn recompute(
+        env: &Env,
+        state: &mut InterestRateState,
+        config: &InterestRateConfig,
+        dynamics: &rate_dynamics::RateDynamicsConfig,
+    ) {
+        let elapsed = env
+            .ledger()
+            .timestamp()
+            .saturating_sub(state.last_accrual_time);
+        let first_update = state.last_accrual_time == 0;
+        Self::compound(env, state);
 
         // Units and scales:
         // - Rates are scaled by 1e8 (100000000) representing 1.0 = 1e8
@@ -2123,14 +3167,16 @@ impl InterestRateStorage {
             state.current_borrow_rate = config.rate_floor;
         }
 
-        // Smoothing for borrow rate: new = old*(s) + current*(1-s)
-        let s_bps = config.smoothing_bps; // 0..=10000
-        let old = state.smoothed_borrow_rate;
-        let cur = state.current_borrow_rate;
-        state.smoothed_borrow_rate = old
-            .saturating_mul(s_bps)
-            .saturating_add(cur.saturating_mul(10000 - s_bps))
-            .saturating_div(10000);
+        // Smoothing for borrow rate: new = old*(s) + current*(1-s), or time-decayed and
+        // capped per update when the market has rate dynamics set
+        state.smoothed_borrow_rate = rate_dynamics::RateSmoothing::smooth(
+            dynamics,
+            config.smoothing_bps,
+            state.smoothed_borrow_rate,
+            state.current_borrow_rate,
+            elapsed,
+            first_update,
+        );
 
         // Calculate supply rate from smoothed borrow rate
         state.current_supply_rate = state
@@ -2139,8 +3185,6 @@ impl InterestRateStorage {
             .saturating_div(100000000);
 
         state.last_accrual_time = env.ledger().timestamp();
-        Self::save_state(env, &state);
-        state
     }
 }
 
@@ -2148,18 +3192,196 @@ impl InterestRateStorage {
 pub struct InterestRateManager;
 
 impl InterestRateManager {
-    pub fn accrue_interest_for_position(
+    /// Give an asset its own interest rate model (admin only)
+    pub fn set_market_config(
         env: &Env,
-        position: &mut Position,
-        borrow_rate: i128,
-        supply_rate: i128,
-    ) {
+        caller: &Address,
+        asset: &Address,
+        mut config: InterestRateConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.base_rate < 0
+            || config.multiplier < 0
+            || config.rate_floor < 0
+            || config.rate_floor > config.rate_ceiling
+            || !(1..=100000000).contains(&config.kink_utilization)
+            || !(0..=100000000).contains(&config.reserve_factor)
+            || !(0..=10000).contains(&config.smoothing_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        config.last_update = env.ledger().timestamp();
+        InterestRateStorage::save_market_config(env, asset, &config);
+        env.events().publish(
+            (
+                Symbol::new(env, "market_config_updated"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "base_rate"),
+                config.base_rate,
+                Symbol::new(env, "multiplier"),
+                config.multiplier,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Compound an asset's market borrow index to now and refresh its rates
+    pub fn accrue_market(env: &Env, asset: &Address) -> InterestRateState {
+        InterestRateStorage::update_market_state(env, asset)
+    }
+
+    /// The primary asset followed by every asset with a market of its own
+    pub fn market_assets(env: &Env) -> Vec<Address> {
+        let mut assets = Vec::new(env);
+        if let Ok(primary) = TokenRegistry::require_primary_asset(env) {
+            assets.push_back(primary);
+        }
+        for market in InterestRateStorage::get_markets(env).iter() {
+            if !assets.contains(&market) {
+                assets.push_back(market);
+            }
+        }
+        assets
+    }
+
+    /// Accrue the primary market and every asset market, returning how many were accrued
+    pub fn accrue_all_markets(env: &Env) -> u32 {
+        let mut accrued = 0u32;
+        for asset in Self::market_assets(env).iter() {
+            if Self::has_market(env, &asset) {
+                Self::accrue_market(env, &asset);
+                accrued += 1;
+            }
+        }
+        accrued
+    }
+
+    /// Whether `asset` has a market that can be accrued: the primary asset, a priced
+    /// cross asset, or an asset with its own market config or state
+    pub fn has_market(env: &Env, asset: &Address) -> bool {
+        InterestRateStorage::is_primary(env, asset)
+            || cross_asset::CrossAssets::get_asset_price(env, asset).is_some()
+            || InterestRateStorage::get_markets(env).contains(asset)
+    }
+
+    /// Accrue each asset of a multi-asset position at its own market's rates
+    pub fn accrue_interest_for_cross_position(env: &Env, position: &mut CrossPosition) {
+        let current_time = env.ledger().timestamp();
+        let time_delta = current_time.saturating_sub(position.last_accrual_time);
+        let first_accrual = position.last_accrual_time == 0;
+        position.last_accrual_time = current_time;
+
+        let mut assets = position.collateral.keys();
+        for asset in position.debt.keys().iter() {
+            if !assets.contains(&asset) {
+                assets.push_back(asset);
+            }
+        }
+        for asset in assets.iter() {
+            let state = InterestRateStorage::update_market_state(env, &asset);
+            match position.debt.get(asset.clone()) {
+                Some(debt) => {
+                    let snapshot = position.borrow_index.get(asset.clone()).unwrap_or(0);
+                    let rebased = Self::rebase(debt, snapshot, state.borrow_index);
+                    reserves::Reserves::accrue(
+                        env,
+                        &position.user,
+                        &asset,
+                        rebased - debt,
+                        InterestRateStorage::get_market_config(env, &asset).reserve_factor,
+                    );
+                    position.debt.set(asset.clone(), rebased);
+                    position.borrow_index.set(asset.clone(), state.borrow_index);
+                }
+                None => {
+                    position.borrow_index.remove(asset.clone());
+                }
+            }
+            if first_accrual || time_delta == 0 {
+                continue;
+            }
+            let collateral = position.collateral.get(asset.clone()).unwrap_or(0);
+            if collateral > 0 {
+                let reserve_factor =
+                    InterestRateStorage::get_market_config(env, &asset).reserve_factor;
+                let rate =
+                    fees::FeeManager::supply_rate(env, &position.user, &state, reserve_factor);
+                let interest = Self::interest(collateral, rate, time_delta);
+                let total = position.supply_interest.get(asset.clone()).unwrap_or(0);
+                position
+                    .supply_interest
+                    .set(asset.clone(), total.saturating_add(interest));
+            }
+        }
+    }
+
+    /// Debt principal recorded at index `snapshot`, expressed at index `current`.
+    /// An unset (zero) snapshot means the debt has not accrued yet.
+    pub fn rebase(debt: i128, snapshot: i128, current: i128) -> i128 {
+        if debt <= 0 || snapshot <= 0 || current <= snapshot {
+            return debt;
+        }
+        debt.saturating_mul(current).saturating_div(snapshot)
+    }
+
+    /// Outstanding debt of a user in an asset at the current borrow index, computed
+    /// without writing the accrual. Includes the primary position when `asset` is the
+    /// primary asset.
+    pub fn get_current_debt(env: &Env, user: &Address, asset: &Address) -> i128 {
+        let index = InterestRateStorage::current_borrow_index(env, asset);
+        let mut debt = 0i128;
+        if InterestRateStorage::is_primary(env, asset) {
+            if let Some(position) = StateHelper::get_position(env, user) {
+                debt += stable_rate::StableRates::debt_at(env, &position, index);
+            }
+        }
+        if let Some(position) = StateHelper::get_cross_position(env, user) {
+            if let Some(principal) = position.debt.get(asset.clone()) {
+                let snapshot = position.borrow_index.get(asset.clone()).unwrap_or(0);
+                debt += Self::rebase(principal, snapshot, index);
+            }
+        }
+        debt
+    }
+
+    /// Simple interest on `principal` at an annualized 1e8-scaled `rate` over `time_delta` seconds
+    fn interest(principal: i128, rate: i128, time_delta: u64) -> i128 {
         // Units and scales:
-        // - borrow_rate and supply_rate are annualized rates scaled by 1e8
+        // - rates are annualized and scaled by 1e8, clamped to [0, 1e8]
         // - interest accrued = principal * rate * time_seconds / (SECONDS_PER_YEAR * 1e8)
         // - All arithmetic is saturating to avoid overflow
         const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
         const SCALE: i128 = 100000000; // 1e8
+        principal
+            .saturating_mul(rate.clamp(0, SCALE))
+            .saturating_mul(time_delta as i128)
+            .saturating_div(SECONDS_PER_YEAR.saturating_mul(SCALE))
+    }
+
+    /// Rebase a position's debt to the market borrow index in `state` and accrue supply
+    /// interest at its supply rate
+    pub fn accrue_interest_for_position(
+        env: &Env,
+        position: &mut Position,
+        state: &InterestRateState,
+    ) {
+        let rebased = stable_rate::StableRates::accrue_position(env, position, state.borrow_index);
+        if let Ok(asset) = TokenRegistry::require_primary_asset(env) {
+            let reserve_factor = InterestRateStorage::get_config(env).reserve_factor;
+            reserves::Reserves::accrue(
+                env,
+                &position.user,
+                &asset,
+                rebased - position.debt,
+                reserve_factor,
+            );
+        }
+        position.debt = rebased;
+        position.borrow_index = state.borrow_index;
 
         let current_time = env.ledger().timestamp();
         if position.last_accrual_time == 0 {
@@ -2172,37 +3394,13 @@ impl InterestRateManager {
             return;
         }
 
-        // Clamp rates to sensible bounds [0, 1e8]
-        let br = borrow_rate.clamp(0, SCALE);
-        let sr = supply_rate.clamp(0, SCALE);
-
-        // Accrue borrow interest
-        if position.debt > 0 {
-            let numerator = position
-                .debt
-                .saturating_mul(br)
-                .saturating_mul(time_delta as i128);
-            let denom = SECONDS_PER_YEAR.saturating_mul(SCALE);
-            let interest = if denom == 0 {
-                0
-            } else {
-                numerator.saturating_div(denom)
-            };
-            position.borrow_interest = position.borrow_interest.saturating_add(interest);
-        }
+        safety_rewards::SafetyRewards::on_accrual(env, position, time_delta);
 
-        // Accrue supply interest
+        // Accrue supply interest, at the user's loyalty-discounted spread
         if position.collateral > 0 {
-            let numerator = position
-                .collateral
-                .saturating_mul(sr)
-                .saturating_mul(time_delta as i128);
-            let denom = SECONDS_PER_YEAR.saturating_mul(SCALE);
-            let interest = if denom == 0 {
-                0
-            } else {
-                numerator.saturating_div(denom)
-            };
+            let reserve_factor = InterestRateStorage::get_config(env).reserve_factor;
+            let rate = fees::FeeManager::supply_rate(env, &position.user, state, reserve_factor);
+            let interest = Self::interest(position.collateral, rate, time_delta);
             position.supply_interest = position.supply_interest.saturating_add(interest);
         }
 
@@ -2210,29 +3408,155 @@ impl InterestRateManager {
     }
 }
 
+/// Storage key namespace for user positions
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PositionStorageKey {
+    Position(Address),
+    CrossPosition(Address),
+}
+
 /// State helper for managing user positions
 pub struct StateHelper;
 
 impl StateHelper {
-    fn position_key(env: &Env, _user: &Address) -> Symbol {
-        Symbol::new(env, &format!("position_{}", "user"))
+    fn position_key(user: &Address) -> PositionStorageKey {
+        PositionStorageKey::Position(user.clone())
     }
 
     pub fn save_position(env: &Env, position: &Position) {
-        let key = Self::position_key(env, &position.user);
-        env.storage().instance().set(&key, position);
+        let key = Self::position_key(&position.user);
+        let previous = migration::Migrations::read::<_, migration::VersionedPosition>(env, &key);
+        let previous_collateral = previous
+            .as_ref()
+            .map(|previous| previous.collateral)
+            .unwrap_or(0);
+        migration::Migrations::write::<_, migration::VersionedPosition>(
+            env,
+            &key,
+            position.clone(),
+        );
+        let delta = position.collateral - previous_collateral;
+        insurance::InsuranceCohorts::on_supply_change(env, &position.user, delta);
+        if let Ok(asset) = TokenRegistry::require_primary_asset(env) {
+            tvl_cap::TvlCaps::on_supply_change(env, &asset, delta);
+        }
+        solvency::Solvency::on_position_change(env, previous.as_ref(), position);
+        position_history::PositionHistory::on_position_change(env, position);
+        stable_rate::StableRates::on_position_change(env, position);
+        health_index::HealthIndex::on_position_change(env, position);
+        liquidation_grace::LiquidationGrace::on_position_change(env, position);
+        if let Ok(asset) = TokenRegistry::require_primary_asset(env) {
+            rewards::Rewards::sync(env, &position.user, &asset);
+        }
     }
 
+    /// The user's primary position, upgrading an entry stored in an older layout
     pub fn get_position(env: &Env, user: &Address) -> Option<Position> {
-        let key = Self::position_key(env, user);
-        env.storage().instance().get::<Symbol, Position>(&key)
+        migration::Migrations::read::<_, migration::VersionedPosition>(
+            env,
+            &Self::position_key(user),
+        )
     }
-}
 
-/// Protocol configuration
-pub struct ProtocolConfig;
+    fn cross_position_key(user: &Address) -> PositionStorageKey {
+        PositionStorageKey::CrossPosition(user.clone())
+    }
 
-impl ProtocolConfig {
+    pub fn save_cross_position(env: &Env, position: &CrossPosition) {
+        let key = Self::cross_position_key(&position.user);
+        let previous: Option<CrossPosition> = persistent::PersistentStore::get(env, &key);
+        persistent::PersistentStore::set(env, &key, position);
+        let mut assets = position.collateral.keys();
+        if let Some(previous) = previous.as_ref() {
+            for asset in previous.collateral.keys().iter() {
+                if !assets.contains(&asset) {
+                    assets.push_back(asset);
+                }
+            }
+        }
+        for asset in assets.iter() {
+            let before = previous
+                .as_ref()
+                .and_then(|previous| previous.collateral.get(asset.clone()))
+                .unwrap_or(0);
+            let after = position.collateral.get(asset.clone()).unwrap_or(0);
+            tvl_cap::TvlCaps::on_supply_change(env, &asset, after - before);
+        }
+        rewards::Rewards::sync_cross_position(env, &position.user);
+        health_index::HealthIndex::on_cross_position_change(env, position);
+    }
+
+    pub fn get_cross_position(env: &Env, user: &Address) -> Option<CrossPosition> {
+        persistent::PersistentStore::get(env, &Self::cross_position_key(user))
+    }
+
+    /// Extend the TTL of a user's position entries, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        persistent::PersistentStore::bump(env, &Self::position_key(user)) as u32
+            + persistent::PersistentStore::bump(env, &Self::cross_position_key(user)) as u32
+    }
+}
+
+/// Snapshot of the admin-set protocol configuration
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ProtocolConfigView {
+    pub admin: Option<Address>,
+    pub oracle: Option<Address>,
+    pub primary_asset: Option<Address>,
+    pub min_collateral_ratio: i128,
+    pub flash_loan_fee_bps: i128,
+    pub withdraw_fee_bps: i128,
+    pub legacy_string_api: bool,
+}
+
+/// Oracle aggregation and freshness settings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OracleSettings {
+    /// Default seconds a price stays fresh
+    pub heartbeat_ttl: u64,
+    /// Aggregation mode (0 = median, 1 = twap)
+    pub mode: i128,
+    pub deviation_bps: i128,
+    pub trim_count: i128,
+    pub max_jump_bps: i128,
+    pub price_cache_ttl: u64,
+    pub twap: oracle::TwapConfig,
+}
+
+/// Default protocol fees, in basis points
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeSettings {
+    pub flash_loan_fee_bps: i128,
+    pub withdraw_fee_bps: i128,
+    pub origination_fee_bps: i128,
+}
+
+/// Every protocol-wide configuration value in one view
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FullProtocolConfig {
+    pub protocol: ProtocolConfigView,
+    pub interest: InterestRateConfig,
+    pub risk: RiskConfig,
+    pub oracle: OracleSettings,
+    pub fees: FeeSettings,
+    pub emergency_status: EmergencyStatus,
+    /// Assets registered in the token registry
+    pub registered_assets: u32,
+    /// Assets with an interest rate market, the primary asset included
+    pub markets: u32,
+    /// Assets with admin-set price bounds
+    pub bounded_assets: u32,
+}
+
+/// Protocol configuration
+pub struct ProtocolConfig;
+
+impl ProtocolConfig {
     fn admin_key(env: &Env) -> Symbol {
         Symbol::new(env, "admin")
     }
@@ -2249,6 +3573,14 @@ impl ProtocolConfig {
         Symbol::new(env, "flash_fee_bps")
     }
 
+    fn withdraw_fee_bps_key(env: &Env) -> Symbol {
+        Symbol::new(env, "withdraw_fee_bps")
+    }
+
+    fn legacy_string_api_key(env: &Env) -> Symbol {
+        Symbol::new(env, "legacy_str_api")
+    }
+
     pub fn set_admin(env: &Env, admin: &Address) {
         env.storage().instance().set(&Self::admin_key(env), admin);
     }
@@ -2259,11 +3591,13 @@ impl ProtocolConfig {
             .get::<Symbol, Address>(&Self::admin_key(env))
     }
 
+    /// Check that `caller` is the admin and has authorized the call
     pub fn require_admin(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
         let admin = Self::get_admin(env).ok_or(ProtocolError::Unauthorized)?;
         if admin != *caller {
             return Err(ProtocolError::Unauthorized);
         }
+        caller.require_auth();
         Ok(())
     }
 
@@ -2273,6 +3607,12 @@ impl ProtocolConfig {
         Ok(())
     }
 
+    pub fn get_oracle(env: &Env) -> Option<Address> {
+        env.storage()
+            .instance()
+            .get::<Symbol, Address>(&Self::oracle_key(env))
+    }
+
     pub fn set_min_collateral_ratio(
         env: &Env,
         caller: &Address,
@@ -2316,6 +3656,89 @@ impl ProtocolConfig {
             .get::<Symbol, i128>(&Self::flash_fee_bps_key(env))
             .unwrap_or(5) // 0.05%
     }
+
+    pub fn set_withdraw_fee_bps(
+        env: &Env,
+        caller: &Address,
+        bps: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::require_admin(env, caller)?;
+        if !(0..=10000).contains(&bps) {
+            return Err(ProtocolError::InvalidInput);
+        }
+        env.storage()
+            .instance()
+            .set(&Self::withdraw_fee_bps_key(env), &bps);
+        Ok(())
+    }
+
+    pub fn get_withdraw_fee_bps(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get::<Symbol, i128>(&Self::withdraw_fee_bps_key(env))
+            .unwrap_or(0)
+    }
+
+    /// Enable or disable the deprecated String-addressed entrypoints (admin only)
+    pub fn set_legacy_string_api(
+        env: &Env,
+        caller: &Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        Self::require_admin(env, caller)?;
+        env.storage()
+            .instance()
+            .set(&Self::legacy_string_api_key(env), &enabled);
+        Ok(())
+    }
+
+    /// String entrypoints stay enabled until the admin opts into strict mode
+    pub fn is_legacy_string_api_enabled(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get::<Symbol, bool>(&Self::legacy_string_api_key(env))
+            .unwrap_or(true)
+    }
+
+    /// Every admin-set value in one view
+    pub fn snapshot(env: &Env) -> ProtocolConfigView {
+        ProtocolConfigView {
+            admin: Self::get_admin(env),
+            oracle: Self::get_oracle(env),
+            primary_asset: TokenRegistry::require_primary_asset(env).ok(),
+            min_collateral_ratio: Self::get_min_collateral_ratio(env),
+            flash_loan_fee_bps: Self::get_flash_loan_fee_bps(env),
+            withdraw_fee_bps: Self::get_withdraw_fee_bps(env),
+            legacy_string_api: Self::is_legacy_string_api_enabled(env),
+        }
+    }
+
+    /// `snapshot` with the interest, risk, oracle, fee and emergency settings
+    pub fn full_snapshot(env: &Env) -> FullProtocolConfig {
+        FullProtocolConfig {
+            protocol: Self::snapshot(env),
+            interest: InterestRateStorage::get_config(env),
+            risk: RiskConfigStorage::get(env),
+            oracle: OracleSettings {
+                heartbeat_ttl: oracle::OracleStorage::get_heartbeat_ttl(env),
+                mode: oracle::OracleStorage::get_mode(env),
+                deviation_bps: oracle::OracleStorage::get_deviation_bps(env),
+                trim_count: oracle::OracleStorage::get_trim_count(env),
+                max_jump_bps: oracle::OracleStorage::get_max_jump_bps(env),
+                price_cache_ttl: oracle::OracleStorage::get_price_cache_ttl(env),
+                twap: oracle::OracleStorage::get_twap_config(env),
+            },
+            fees: FeeSettings {
+                flash_loan_fee_bps: Self::get_flash_loan_fee_bps(env),
+                withdraw_fee_bps: Self::get_withdraw_fee_bps(env),
+                origination_fee_bps: origination::OriginationFeeStorage::get_default(env),
+            },
+            emergency_status: EmergencyStorage::get(env).status,
+            registered_assets: TokenRegistry::list_assets(env).len(),
+            markets: InterestRateManager::market_assets(env).len(),
+            bounded_assets: oracle::OracleStorage::get_bounded_assets(env).len(),
+        }
+    }
 }
 
 /// Protocol errors
@@ -2354,6 +3777,26 @@ pub enum ProtocolError {
     BalanceInvariantViolation = 29,
     InsufficientLiquidity = 30,
     SlippageProtectionTriggered = 31,
+    OperationNotApproved = 32,
+    LegacyApiDisabled = 33,
+    UserQuarantined = 34,
+    CircuitBreakerTripped = 35,
+    // Module-specific failures keep their namespaced code (see `errors` module)
+    DepositTvlCapExceeded = 1005,
+    WithdrawCollateralLocked = 4007,
+    WithdrawBeneficiaryNotAllowed = 4008,
+    AnalyticsInvalidTimeRange = 6001,
+    AnalyticsDataNotFound = 6002,
+    AmmPairNotRegistered = 7001,
+    AmmInvalidSwapParams = 7006,
+    AmmSwapFailed = 7008,
+    BridgeUnsupportedVersion = 8001,
+    BridgeNonceAlreadyUsed = 8004,
+    BridgeInsufficientSignatures = 8005,
+    OracleNoFeeds = 9001,
+    OracleNoValidPrice = 9002,
+    OracleDeviationExceeded = 9003,
+    OracleStalePrice = 9004,
 }
 
 /// Protocol events
@@ -2393,7 +3836,7 @@ pub enum ProtocolEvent {
     CacheUpdated(Symbol, Symbol), // cache_key, op (set/evict)
     // Compliance
     ComplianceKycUpdated(Address, bool),
-    ComplianceAlert(Address, Symbol),
+    ComplianceAlert(Address, Symbol, u32), // user, reason, rule id (0 when none)
     // Market making
     MMParamsUpdated(i128, i128),       // spread_bps, inventory_cap
     MMIncentiveAccrued(Address, i128), // user, amount
@@ -2987,6 +4430,22 @@ impl ProtocolEvent {
                     ),
                 );
             }
+            ProtocolEvent::ComplianceAlert(user, reason, rule_id) => {
+                env.events().publish(
+                    (
+                        Symbol::new(env, "compliance_alert"),
+                        Symbol::new(env, "user"),
+                    ),
+                    (
+                        Symbol::new(env, "user"),
+                        user.clone(),
+                        Symbol::new(env, "reason"),
+                        reason.clone(),
+                        Symbol::new(env, "rule"),
+                        *rule_id,
+                    ),
+                );
+            }
             // Add placeholder implementations for previously skipped event variants
             _ => {
                 env.events().publish(
@@ -3014,66 +4473,87 @@ fn _ensure_amount_positive(amount: i128) -> Result<(), ProtocolError> {
     Ok(())
 }
 
-/// Core protocol functions
-pub fn deposit_collateral(env: Env, depositor: String, amount: i128) -> Result<(), ProtocolError> {
-    // Check pause state first
-    let risk_config = RiskConfigStorage::get(&env);
-    risk_config.ensure_not_paused(OperationKind::Deposit)?;
+pub fn initialize(env: Env, admin: Address) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    if env
+        .storage()
+        .instance()
+        .has(&ProtocolConfig::admin_key(&env))
+    {
+        return Err(ProtocolError::AlreadyInitialized);
+    }
+    ProtocolConfig::set_admin(&env, &admin);
+    UserManager::bootstrap_admin(&env, &admin);
 
-    let depositor_addr = AddressHelper::require_valid_address(&env, &depositor)?;
-    deposit::DepositModule::deposit_collateral(&env, &depositor_addr, amount)
+    // Initialize interest rate system with default configuration
+    let config = InterestRateConfig::default();
+    InterestRateStorage::save_config(&env, &config);
+
+    let state = InterestRateState::initial();
+    InterestRateStorage::save_state(&env, &state);
+
+    // Initialize risk management system with default configuration
+    let risk_config = RiskConfig::default();
+    RiskConfigStorage::save(&env, &risk_config);
+    upgrade::Upgrade::on_initialize(&env);
+
+    Ok(())
 }
 
-pub fn borrow(env: Env, borrower: String, amount: i128) -> Result<(), ProtocolError> {
-    // Check pause state first
-    let risk_config = RiskConfigStorage::get(&env);
-    risk_config.ensure_not_paused(OperationKind::Borrow)?;
+/// Core protocol functions
+pub fn deposit_collateral(env: Env, depositor: Address, amount: i128) -> Result<(), ProtocolError> {
+    modules::ModuleRegistry::deposit_collateral(&env, &depositor, &depositor, amount)
+}
 
-    let borrower_addr = AddressHelper::require_valid_address(&env, &borrower)?;
-    borrow::BorrowModule::borrow(&env, &borrower_addr, amount)
+pub fn borrow(env: Env, borrower: Address, amount: i128) -> Result<(), ProtocolError> {
+    if soft_limits::SoftLimits::queue_if_exceeded(&env, &borrower, amount)? {
+        return Ok(());
+    }
+    modules::ModuleRegistry::borrow(&env, &borrower, amount)
 }
 
-pub fn repay(env: Env, repayer: String, amount: i128) -> Result<(), ProtocolError> {
-    // Check pause state first
-    let risk_config = RiskConfigStorage::get(&env);
-    risk_config.ensure_not_paused(OperationKind::Repay)?;
-    let repayer_addr = AddressHelper::require_valid_address(&env, &repayer)?;
-    repay::RepayModule::repay(&env, &repayer_addr, amount)
+pub fn repay(env: Env, repayer: Address, amount: i128) -> Result<(), ProtocolError> {
+    modules::ModuleRegistry::repay(&env, &repayer, &repayer, amount)
 }
 
-pub fn withdraw(env: Env, withdrawer: String, amount: i128) -> Result<(), ProtocolError> {
-    // Check pause state first
-    let risk_config = RiskConfigStorage::get(&env);
-    risk_config.ensure_not_paused(OperationKind::Withdraw)?;
-    let withdrawer_addr = AddressHelper::require_valid_address(&env, &withdrawer)?;
-    withdraw::WithdrawModule::withdraw(&env, &withdrawer_addr, amount)
+pub fn withdraw(
+    env: Env,
+    withdrawer: Address,
+    to: Option<Address>,
+    amount: i128,
+) -> Result<(), ProtocolError> {
+    modules::ModuleRegistry::withdraw(&env, &withdrawer, to, amount)
 }
 
 pub fn liquidate(
     env: Env,
-    liquidator: String,
-    user: String,
+    liquidator: Address,
+    user: Address,
     amount: i128,
     min_out: i128,
-) -> Result<(), ProtocolError> {
-    // Check pause state first
-    let risk_config = RiskConfigStorage::get(&env);
-    risk_config.ensure_not_paused(OperationKind::Liquidate)?;
-    let liquidator_addr = AddressHelper::require_valid_address(&env, &liquidator)?;
-    UserManager::ensure_operation_allowed(
-        &env,
-        &liquidator_addr,
-        OperationKind::Liquidate,
-        amount,
-    )?;
-    liquidate::LiquidationModule::liquidate(&env, &liquidator, &user, amount, min_out)?;
-    UserManager::record_activity(&env, &liquidator_addr, OperationKind::Liquidate, amount)?;
-    Ok(())
+) -> Result<liquidate::LiquidationResult, ProtocolError> {
+    gate::OperationGate::check(&env, &liquidator, OperationKind::Liquidate, None, amount)?;
+    let result = modules::ModuleRegistry::liquidate(&env, &liquidator, &user, amount, min_out)?;
+    UserManager::record_activity(&env, &liquidator, OperationKind::Liquidate, amount)?;
+    Ok(result)
+}
+
+pub fn liquidate_with_supply(
+    env: Env,
+    liquidator: Address,
+    user: Address,
+    amount: i128,
+    min_out: i128,
+) -> Result<liquidate::LiquidationResult, ProtocolError> {
+    gate::OperationGate::check(&env, &liquidator, OperationKind::Liquidate, None, amount)?;
+    let result =
+        modules::ModuleRegistry::liquidate_with_supply(&env, &liquidator, &user, amount, min_out)?;
+    UserManager::record_activity(&env, &liquidator, OperationKind::Liquidate, amount)?;
+    Ok(result)
 }
 
-pub fn get_position(env: Env, user: String) -> Result<(i128, i128, i128), ProtocolError> {
-    let user_addr = AddressHelper::require_valid_address(&env, &user)?;
-    match StateHelper::get_position(&env, &user_addr) {
+pub fn get_position(env: Env, user: Address) -> Result<(i128, i128, i128), ProtocolError> {
+    match StateHelper::get_position(&env, &user) {
         Some(position) => {
             let collateral_ratio = if position.debt > 0 {
                 (position.collateral * 100) / position.debt
@@ -3086,15 +4566,31 @@ pub fn get_position(env: Env, user: String) -> Result<(i128, i128, i128), Protoc
     }
 }
 
+pub fn get_position_details(env: Env, user: Address) -> Result<PositionDetails, ProtocolError> {
+    let position = StateHelper::get_position(&env, &user).ok_or(ProtocolError::PositionNotFound)?;
+    let mut current = position.clone();
+    current.debt = liquidate::LiquidationModule::accrued_debt(&env, &position);
+    let collateral_ratio = if current.debt > 0 {
+        (current.collateral * 100) / current.debt
+    } else {
+        0
+    };
+    Ok(PositionDetails {
+        position,
+        current_debt: current.debt,
+        collateral_ratio,
+        health_factor: safety_rewards::SafetyRewards::health_factor(&env, &current),
+    })
+}
+
 pub fn set_risk_params(
     env: Env,
-    caller: String,
+    caller: Address,
     close_factor: i128,
     liquidation_incentive: i128,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    ProtocolConfig::require_admin(&env, &caller_addr)?;
+    ProtocolConfig::require_admin(&env, &caller)?;
 
     let mut config = RiskConfigStorage::get(&env);
     config.close_factor = close_factor;
@@ -3108,15 +4604,14 @@ pub fn set_risk_params(
 
 pub fn set_pause_switches(
     env: Env,
-    caller: String,
+    caller: Address,
     pause_borrow: bool,
     pause_deposit: bool,
     pause_withdraw: bool,
     pause_liquidate: bool,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    ProtocolConfig::require_admin(&env, &caller_addr)?;
+    ProtocolConfig::require_admin(&env, &caller)?;
 
     let mut config = RiskConfigStorage::get(&env);
     config.pause_borrow = pause_borrow;
@@ -3177,75 +4672,66 @@ pub fn get_system_stats(env: Env) -> Result<(i128, i128, i128, i128), ProtocolEr
 
 pub fn set_emergency_manager(
     env: Env,
-    caller: String,
-    manager: String,
+    caller: Address,
+    manager: Address,
     enabled: bool,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    let manager_addr = AddressHelper::require_valid_address(&env, &manager)?;
-    EmergencyManager::set_manager(&env, &caller_addr, &manager_addr, enabled)
+    EmergencyManager::set_manager(&env, &caller, &manager, enabled)
 }
 
 pub fn trigger_emergency_pause(
     env: Env,
-    caller: String,
+    caller: Address,
     reason: Option<String>,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::pause(&env, &caller_addr, reason)
+    EmergencyManager::pause(&env, &caller, reason)
 }
 
 pub fn enter_recovery_mode(
     env: Env,
-    caller: String,
+    caller: Address,
     plan: Option<String>,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::enter_recovery(&env, &caller_addr, plan)
+    EmergencyManager::enter_recovery(&env, &caller, plan)
 }
 
-pub fn resume_operations(env: Env, caller: String) -> Result<(), ProtocolError> {
+pub fn resume_operations(env: Env, caller: Address) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::resume(&env, &caller_addr)
+    EmergencyManager::resume(&env, &caller)
 }
 
-pub fn record_recovery_step(env: Env, caller: String, step: String) -> Result<(), ProtocolError> {
+pub fn record_recovery_step(env: Env, caller: Address, step: String) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::record_recovery_step(&env, &caller_addr, step)
+    EmergencyManager::record_recovery_step(&env, &caller, step)
 }
 
 pub fn queue_emergency_param_update(
     env: Env,
-    caller: String,
+    caller: Address,
     parameter: Symbol,
     value: i128,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::queue_param_update(&env, &caller_addr, parameter, value)
+    EmergencyManager::queue_param_update(&env, &caller, parameter, value)
 }
 
-pub fn apply_emergency_param_updates(env: Env, caller: String) -> Result<(), ProtocolError> {
+pub fn apply_emergency_param_updates(env: Env, caller: Address) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::apply_param_updates(&env, &caller_addr)
+    EmergencyManager::apply_param_updates(&env, &caller)
 }
 
 pub fn adjust_emergency_fund(
     env: Env,
-    caller: String,
+    caller: Address,
     token: Option<Address>,
     delta: i128,
     reserve_delta: i128,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    EmergencyManager::adjust_fund(&env, &caller_addr, token, delta, reserve_delta)
+    EmergencyManager::adjust_fund(&env, &caller, token, delta, reserve_delta)
 }
 
 pub fn get_emergency_state(env: Env) -> Result<EmergencyState, ProtocolError> {
@@ -3265,15 +4751,23 @@ pub fn get_events_for_type(
     event_type: Symbol,
     limit: u32,
 ) -> Result<Vec<EventRecord>, ProtocolError> {
-    let logs = EventStorage::get_logs(&env);
-    let mut events = logs
-        .get(event_type.clone())
-        .unwrap_or_else(|| Vec::new(&env));
-    if limit > 0 && events.len() > limit {
-        let start = events.len() - limit;
-        events = events.slice(start..);
-    }
-    Ok(events)
+    Ok(EventStorage::latest(&env, &event_type, limit))
+}
+
+pub fn get_events_page(
+    env: Env,
+    event_type: Symbol,
+    page: u32,
+) -> Result<EventPage, ProtocolError> {
+    Ok(EventStorage::get_page(&env, &event_type, page))
+}
+
+pub fn set_event_retention(env: Env, caller: Address, pages: u32) -> Result<(), ProtocolError> {
+    EventStorage::set_retained_pages(&env, &caller, pages)
+}
+
+pub fn get_event_retention(env: Env) -> Result<u32, ProtocolError> {
+    Ok(EventStorage::get_retained_pages(&env))
 }
 
 pub fn get_recent_event_types(env: Env) -> Result<Vec<Symbol>, ProtocolError> {
@@ -3282,19 +4776,17 @@ pub fn get_recent_event_types(env: Env) -> Result<Vec<Symbol>, ProtocolError> {
 
 pub fn register_token_asset(
     env: Env,
-    caller: String,
+    caller: Address,
     key: Symbol,
     token: Address,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    TokenRegistry::set_asset(&env, &caller_addr, key, token)
+    TokenRegistry::set_asset(&env, &caller, key, token)
 }
 
-pub fn set_primary_asset(env: Env, caller: String, token: Address) -> Result<(), ProtocolError> {
+pub fn set_primary_asset(env: Env, caller: Address, token: Address) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    TokenRegistry::set_primary_asset(&env, &caller_addr, token)
+    TokenRegistry::set_primary_asset(&env, &caller, token)
 }
 
 pub fn get_registered_asset(env: Env, key: Symbol) -> Result<Option<Address>, ProtocolError> {
@@ -3303,29 +4795,27 @@ pub fn get_registered_asset(env: Env, key: Symbol) -> Result<Option<Address>, Pr
 
 pub fn set_user_role(
     env: Env,
-    caller: String,
+    caller: Address,
     user: Address,
     role: UserRole,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    UserManager::set_role(&env, &caller_addr, &user, role)
+    UserManager::set_role(&env, &caller, &user, role)
 }
 
 pub fn set_user_verification(
     env: Env,
-    caller: String,
+    caller: Address,
     user: Address,
     status: VerificationStatus,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    UserManager::set_verification_status(&env, &caller_addr, &user, status)
+    UserManager::set_verification_status(&env, &caller, &user, status)
 }
 
 pub fn set_user_limits(
     env: Env,
-    caller: String,
+    caller: Address,
     user: Address,
     max_deposit: i128,
     max_borrow: i128,
@@ -3333,10 +4823,9 @@ pub fn set_user_limits(
     daily_limit: i128,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
     UserManager::set_limits(
         &env,
-        &caller_addr,
+        &caller,
         &user,
         max_deposit,
         max_borrow,
@@ -3345,16 +4834,14 @@ pub fn set_user_limits(
     )
 }
 
-pub fn freeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
+pub fn freeze_user(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    UserManager::freeze_user(&env, &caller_addr, &user)
+    UserManager::freeze_user(&env, &caller, &user)
 }
 
-pub fn unfreeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
+pub fn unfreeze_user(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-    UserManager::unfreeze_user(&env, &caller_addr, &user)
+    UserManager::unfreeze_user(&env, &caller, &user)
 }
 
 pub fn get_user_profile(env: Env, user: Address) -> Result<UserProfile, ProtocolError> {
@@ -3365,30 +4852,8 @@ pub fn get_user_profile(env: Env, user: Address) -> Result<UserProfile, Protocol
 impl Contract {
     /// Initializes the contract and sets the admin address
     pub fn initialize(env: Env, admin: String) -> Result<(), ProtocolError> {
-        let _guard = ReentrancyScope::enter(&env)?;
-        let admin_addr = AddressHelper::require_valid_address(&env, &admin)?;
-        if env
-            .storage()
-            .instance()
-            .has(&ProtocolConfig::admin_key(&env))
-        {
-            return Err(ProtocolError::AlreadyInitialized);
-        }
-        ProtocolConfig::set_admin(&env, &admin_addr);
-        UserManager::bootstrap_admin(&env, &admin_addr);
-
-        // Initialize interest rate system with default configuration
-        let config = InterestRateConfig::default();
-        InterestRateStorage::save_config(&env, &config);
-
-        let state = InterestRateState::initial();
-        InterestRateStorage::save_state(&env, &state);
-
-        // Initialize risk management system with default configuration
-        let risk_config = RiskConfig::default();
-        RiskConfigStorage::save(&env, &risk_config);
-
-        Ok(())
+        let admin = AddressHelper::require_legacy_address(&env, &admin)?;
+        initialize(env, admin)
     }
 
     /// Set the minimum collateral ratio (admin only)
@@ -3397,9 +4862,8 @@ impl Contract {
         caller: String,
         ratio: i128,
     ) -> Result<(), ProtocolError> {
-        let caller_addr = AddressHelper::require_valid_address(&env, &caller)?;
-        ProtocolConfig::set_min_collateral_ratio(&env, &caller_addr, ratio)?;
-        Ok(())
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
+        ProtocolConfig::set_min_collateral_ratio(&env, &caller, ratio)
     }
 
     /// Deposit collateral into the protocol
@@ -3408,22 +4872,40 @@ impl Contract {
         depositor: String,
         amount: i128,
     ) -> Result<(), ProtocolError> {
-        deposit_collateral(env, depositor, amount)
+        let result =
+            AddressHelper::require_legacy_address(&env, &depositor).and_then(|depositor| {
+                depositor.require_auth();
+                deposit_collateral(env.clone(), depositor, amount)
+            });
+        analytics::AnalyticsModule::track(&env, "deposit_collateral", result)
     }
 
     /// Borrow assets from the protocol
     pub fn borrow(env: Env, borrower: String, amount: i128) -> Result<(), ProtocolError> {
-        borrow(env, borrower, amount)
+        let result = AddressHelper::require_legacy_address(&env, &borrower).and_then(|borrower| {
+            borrower.require_auth();
+            borrow(env.clone(), borrower, amount)
+        });
+        analytics::AnalyticsModule::track(&env, "borrow", result)
     }
 
     /// Repay borrowed assets
     pub fn repay(env: Env, repayer: String, amount: i128) -> Result<(), ProtocolError> {
-        repay(env, repayer, amount)
+        let result = AddressHelper::require_legacy_address(&env, &repayer).and_then(|repayer| {
+            repayer.require_auth();
+            repay(env.clone(), repayer, amount)
+        });
+        analytics::AnalyticsModule::track(&env, "repay", result)
     }
 
     /// Withdraw collateral from the protocol
     pub fn withdraw(env: Env, withdrawer: String, amount: i128) -> Result<(), ProtocolError> {
-        withdraw(env, withdrawer, amount)
+        let result =
+            AddressHelper::require_legacy_address(&env, &withdrawer).and_then(|withdrawer| {
+                withdrawer.require_auth();
+                withdraw(env.clone(), withdrawer, None, amount)
+            });
+        analytics::AnalyticsModule::track(&env, "withdraw", result)
     }
 
     /// Liquidate an undercollateralized position
@@ -3433,12 +4915,19 @@ impl Contract {
         user: String,
         amount: i128,
         min_out: i128,
-    ) -> Result<(), ProtocolError> {
-        liquidate(env, liquidator, user, amount, min_out)
+    ) -> Result<liquidate::LiquidationResult, ProtocolError> {
+        let result =
+            AddressHelper::require_legacy_address(&env, &liquidator).and_then(|liquidator| {
+                liquidator.require_auth();
+                let user = AddressHelper::require_legacy_address(&env, &user)?;
+                liquidate(env.clone(), liquidator, user, amount, min_out)
+            });
+        analytics::AnalyticsModule::track(&env, "liquidate", result)
     }
 
     /// Get user position
     pub fn get_position(env: Env, user: String) -> Result<(i128, i128, i128), ProtocolError> {
+        let user = AddressHelper::require_legacy_address(&env, &user)?;
         get_position(env, user)
     }
 
@@ -3449,6 +4938,7 @@ impl Contract {
         close_factor: i128,
         liquidation_incentive: i128,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         set_risk_params(env, caller, close_factor, liquidation_incentive)
     }
 
@@ -3461,6 +4951,7 @@ impl Contract {
         pause_withdraw: bool,
         pause_liquidate: bool,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         set_pause_switches(
             env,
             caller,
@@ -3496,6 +4987,8 @@ impl Contract {
         manager: String,
         enabled: bool,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
+        let manager = AddressHelper::require_legacy_address(&env, &manager)?;
         set_emergency_manager(env, caller, manager, enabled)
     }
 
@@ -3504,6 +4997,7 @@ impl Contract {
         caller: String,
         reason: Option<String>,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         trigger_emergency_pause(env, caller, reason)
     }
 
@@ -3512,10 +5006,12 @@ impl Contract {
         caller: String,
         plan: Option<String>,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         enter_recovery_mode(env, caller, plan)
     }
 
     pub fn resume_operations(env: Env, caller: String) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         resume_operations(env, caller)
     }
 
@@ -3524,6 +5020,7 @@ impl Contract {
         caller: String,
         step: String,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         record_recovery_step(env, caller, step)
     }
 
@@ -3533,10 +5030,12 @@ impl Contract {
         parameter: Symbol,
         value: i128,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         queue_emergency_param_update(env, caller, parameter, value)
     }
 
     pub fn apply_emergency_param_updates(env: Env, caller: String) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         apply_emergency_param_updates(env, caller)
     }
 
@@ -3547,6 +5046,7 @@ impl Contract {
         delta: i128,
         reserve_delta: i128,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         adjust_emergency_fund(env, caller, token, delta, reserve_delta)
     }
 
@@ -3570,6 +5070,22 @@ impl Contract {
         get_events_for_type(env, event_type, limit)
     }
 
+    pub fn get_events_page(
+        env: Env,
+        event_type: Symbol,
+        page: u32,
+    ) -> Result<EventPage, ProtocolError> {
+        get_events_page(env, event_type, page)
+    }
+
+    pub fn set_event_retention(env: Env, caller: Address, pages: u32) -> Result<(), ProtocolError> {
+        set_event_retention(env, caller, pages)
+    }
+
+    pub fn get_event_retention(env: Env) -> Result<u32, ProtocolError> {
+        get_event_retention(env)
+    }
+
     pub fn get_recent_event_types(env: Env) -> Result<Vec<Symbol>, ProtocolError> {
         get_recent_event_types(env)
     }
@@ -3580,6 +5096,7 @@ impl Contract {
         key: Symbol,
         token: Address,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         register_token_asset(env, caller, key, token)
     }
 
@@ -3588,6 +5105,7 @@ impl Contract {
         caller: String,
         token: Address,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         set_primary_asset(env, caller, token)
     }
 
@@ -3595,12 +5113,18 @@ impl Contract {
         get_registered_asset(env, key)
     }
 
+    /// Decimals of a registered token, read from the token when it was registered
+    pub fn get_token_decimals(env: Env, token: Address) -> Option<u32> {
+        TokenRegistry::decimals(&env, &token)
+    }
+
     pub fn set_user_role(
         env: Env,
         caller: String,
         user: Address,
         role: UserRole,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         set_user_role(env, caller, user, role)
     }
 
@@ -3610,6 +5134,7 @@ impl Contract {
         user: Address,
         status: VerificationStatus,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         set_user_verification(env, caller, user, status)
     }
 
@@ -3622,6 +5147,7 @@ impl Contract {
         max_withdraw: i128,
         daily_limit: i128,
     ) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         set_user_limits(
             env,
             caller,
@@ -3634,10 +5160,12 @@ impl Contract {
     }
 
     pub fn freeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         freeze_user(env, caller, user)
     }
 
     pub fn unfreeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
+        let caller = AddressHelper::require_legacy_address(&env, &caller)?;
         unfreeze_user(env, caller, user)
     }
 
@@ -3651,8 +5179,8 @@ impl Contract {
     }
 
     pub fn get_user_report(env: Env, user: String) -> Result<analytics::UserReport, ProtocolError> {
-        let user_addr = AddressHelper::require_valid_address(&env, &user)?;
-        analytics::AnalyticsModule::get_user_report(&env, &user_addr)
+        let user = AddressHelper::require_legacy_address(&env, &user)?;
+        analytics::AnalyticsModule::get_user_report(&env, &user)
     }
 
     pub fn get_asset_report(
@@ -3681,6 +5209,44 @@ impl Contract {
         analytics::AnalyticsModule::update_performance_metrics(&env, processing_time, success)
     }
 
+    /// Page through recorded liquidations between `from_ts` and `to_ts` (inclusive).
+    /// Pass `cursor = 0` for the first page and the returned `next_cursor` afterwards.
+    pub fn get_liquidation_history(
+        env: Env,
+        from_ts: u64,
+        to_ts: u64,
+        cursor: u64,
+    ) -> Result<analytics::LiquidationHistoryPage, ProtocolError> {
+        analytics::AnalyticsModule::get_liquidation_history(&env, from_ts, to_ts, cursor)
+    }
+
+    /// Capture today's protocol snapshot (keeper entrypoint, once per day)
+    pub fn snapshot_protocol(env: Env) -> Result<u64, ProtocolError> {
+        analytics::AnalyticsModule::snapshot_protocol(&env)
+    }
+
+    /// Set how many daily snapshots are retained (admin only)
+    pub fn set_snapshot_retention(
+        env: Env,
+        admin: Address,
+        retention: u32,
+    ) -> Result<(), ProtocolError> {
+        analytics::AnalyticsModule::set_snapshot_retention(&env, &admin, retention)
+    }
+
+    /// Get the snapshot taken on a day index (timestamp / 86400)
+    pub fn get_protocol_snapshot(
+        env: Env,
+        day: u64,
+    ) -> Result<analytics::ProtocolSnapshot, ProtocolError> {
+        analytics::AnalyticsModule::get_snapshot(&env, day)
+    }
+
+    /// Day indexes of retained snapshots, oldest first
+    pub fn get_snapshot_days(env: Env) -> Vec<u64> {
+        analytics::AnalyticsStorage::get_snapshot_days(&env)
+    }
+
     pub fn record_activity(
         env: Env,
         user: String,
@@ -3688,15 +5254,10 @@ impl Contract {
         amount: i128,
         asset: Option<Address>,
     ) -> Result<(), ProtocolError> {
-        let user_addr = AddressHelper::require_valid_address(&env, &user)?;
+        let user = AddressHelper::require_legacy_address(&env, &user)?;
+        user.require_auth();
         let activity = activity_type.to_string();
-        analytics::AnalyticsModule::record_activity(
-            &env,
-            &user_addr,
-            activity.as_str(),
-            amount,
-            asset,
-        )
+        analytics::AnalyticsModule::record_activity(&env, &user, activity.as_str(), amount, asset)
     }
 
     // ==================== AMM Registry and Swap Hooks ====================
@@ -3758,6 +5319,25 @@ impl Contract {
         amm::AMMRegistry::get_pair_info(&env, &asset_a, &asset_b)
     }
 
+    /// Quote a swap at the pair's registered AMM contract
+    ///
+    /// # Arguments
+    /// * `asset_in` - Asset to sell
+    /// * `asset_out` - Asset to buy
+    /// * `amount_in` - Amount of `asset_in` to sell
+    ///
+    /// # Returns
+    /// * Output the AMM quotes after the standard protocol swap fee
+    /// * Error if the pair is not registered or the AMM returns no quote
+    pub fn quote_swap(
+        env: Env,
+        asset_in: Address,
+        asset_out: Address,
+        amount_in: i128,
+    ) -> Result<i128, ProtocolError> {
+        amm::AMMRegistry::quote_swap(&env, &asset_in, &asset_out, amount_in)
+    }
+
     /// Execute a swap through registered AMM
     ///
     /// # Arguments
@@ -3765,13 +5345,15 @@ impl Contract {
     ///
     /// # Returns
     /// * Swap result with amounts and exchange rate
+    /// * A `SlippageRejected` result with `amount_in` refunded if the pair quotes below
+    ///   `min_amount_out`
     /// * Error if swap fails or parameters invalid
     pub fn execute_amm_swap(
         env: Env,
         params: amm::SwapParams,
     ) -> Result<amm::SwapResult, ProtocolError> {
-        let _guard = ReentrancyScope::enter(&env)?;
-        amm::AMMRegistry::execute_swap(&env, params)
+        // The swap takes the reentrancy guard itself
+        amm::AMMRegistry::swap_for_user(&env, params)
     }
 
     /// Swap hook for liquidation flows
@@ -3796,8 +5378,6 @@ impl Contract {
         collateral_amount: i128,
         min_debt_amount: i128,
     ) -> Result<amm::SwapResult, ProtocolError> {
-        let _guard = ReentrancyScope::enter(&env)?;
-
         amm::AMMRegistry::liquidation_swap_hook(
             &env,
             &liquidator,
@@ -3829,8 +5409,6 @@ impl Contract {
         sell_amount: i128,
         min_debt_repayment: i128,
     ) -> Result<amm::SwapResult, ProtocolError> {
-        let _guard = ReentrancyScope::enter(&env)?;
-
         amm::AMMRegistry::deleverage_swap_hook(
             &env,
             &user,
@@ -3865,6 +5443,25 @@ impl Contract {
         amm::AMMRegistry::get_swap_history(&env)
     }
 
+    /// Page through a pair's swap history, oldest first
+    ///
+    /// # Arguments
+    /// * `asset_a`, `asset_b` - The pair, in either order
+    /// * `cursor` - `0` for the first page, then the returned `next_cursor`
+    /// * `limit` - Records per page, capped at 50
+    ///
+    /// # Returns
+    /// * Records with their per-pair sequence numbers and the next cursor
+    pub fn get_swap_history_page(
+        env: Env,
+        asset_a: Address,
+        asset_b: Address,
+        cursor: u64,
+        limit: u32,
+    ) -> Result<amm::SwapHistoryPage, ProtocolError> {
+        amm::AMMRegistry::get_swap_history_page(&env, &asset_a, &asset_b, cursor, limit)
+    }
+
     /// Deactivate an AMM pair
     /// Admin-only function to disable a pair
     ///
@@ -3887,7 +5484,8 @@ impl Contract {
     }
 
     /// Reactivate an AMM pair
-    /// Admin-only function to re-enable a previously deactivated pair
+    /// Admin-only function to re-enable a previously deactivated pair and clear its
+    /// swap failure tracking
     ///
     /// # Arguments
     /// * `admin` - Admin address (must match contract admin)
@@ -3906,4 +5504,3353 @@ impl Contract {
 
         amm::AMMRegistry::activate_pair(&env, &asset_a, &asset_b)
     }
+
+    /// Set the swap failure thresholds that automatically deactivate AMM pairs
+    /// Admin-only; auto-deactivated pairs stay inactive until `activate_amm_pair`
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `config` - Consecutive-failure limit and failure-rate limit over a window
+    pub fn set_amm_pair_health_config(
+        env: Env,
+        admin: Address,
+        config: amm::PairHealthConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(&env, &admin)?;
+        amm::AMMRegistry::set_health_config(&env, config)
+    }
+
+    /// Swap failure tracking of an AMM pair
+    ///
+    /// # Arguments
+    /// * `asset_a` - First asset address
+    /// * `asset_b` - Second asset address
+    pub fn get_amm_pair_health(env: Env, asset_a: Address, asset_b: Address) -> amm::PairHealth {
+        amm::AMMRegistry::get_pair_health(&env, &asset_a, &asset_b)
+    }
+
+    // ==================== Position Netting ====================
+
+    /// Repay debt from the user's supplied balance in the same asset
+    ///
+    /// # Arguments
+    /// * `user` - Position owner (must authorize)
+    /// * `asset` - Asset held on both sides of the position
+    ///
+    /// # Returns
+    /// * Amount offset; fails unless the result strictly improves the collateral ratio
+    pub fn net_position(env: Env, user: Address, asset: Address) -> Result<i128, ProtocolError> {
+        netting::NettingModule::net_position(&env, &user, &asset)
+    }
+
+    // ==================== Storage Footprint ====================
+
+    /// Estimate ledger entries and value sizes attributable to a user
+    pub fn get_storage_footprint(env: Env, user: Address) -> footprint::StorageFootprint {
+        footprint::StorageFootprintReporter::user_footprint(&env, &user)
+    }
+
+    /// Estimate protocol-wide shared storage for rent budgeting
+    pub fn get_protocol_storage_footprint(env: Env) -> footprint::StorageFootprint {
+        footprint::StorageFootprintReporter::protocol_footprint(&env)
+    }
+
+    // ==================== Term Loan Previews ====================
+
+    /// Preview the installment schedule of a term loan at current rates (no state change)
+    ///
+    /// # Arguments
+    /// * `principal` - Amount to borrow
+    /// * `term` - Loan length in seconds
+    /// * `installments` - Number of equal installments (1-360)
+    /// * `rate_mode` - Which current borrow rate to price with
+    ///
+    /// # Returns
+    /// * Due dates, principal/interest split per installment and total cost
+    pub fn preview_term_loan(
+        env: Env,
+        principal: i128,
+        term: u64,
+        installments: u32,
+        rate_mode: amortization::RateMode,
+    ) -> Result<amortization::AmortizationSchedule, ProtocolError> {
+        amortization::Amortization::preview_term_loan(
+            &env,
+            principal,
+            term,
+            installments,
+            rate_mode,
+        )
+    }
+
+    // ==================== Custodial Auth Adapters ====================
+
+    /// Register a policy contract that must approve the user's operations
+    ///
+    /// # Arguments
+    /// * `user` - User opting into external approval (must authorize)
+    /// * `adapter` - Contract exposing `approve_operation(user, op, amount) -> bool`
+    pub fn set_auth_adapter(
+        env: Env,
+        user: Address,
+        adapter: Address,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        auth_adapter::AuthAdapter::set_adapter(&env, &user, &adapter)
+    }
+
+    /// Remove the user's auth adapter
+    ///
+    /// # Arguments
+    /// * `user` - User opting out of external approval (must authorize)
+    pub fn clear_auth_adapter(env: Env, user: Address) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        auth_adapter::AuthAdapter::clear_adapter(&env, &user)
+    }
+
+    /// Get the auth adapter registered for a user, if any
+    pub fn get_auth_adapter(env: Env, user: Address) -> Option<Address> {
+        auth_adapter::AuthAdapter::get_adapter(&env, &user)
+    }
+
+    // ==================== Break-Glass Operations ====================
+
+    /// Set how many distinct emergency managers must approve a break-glass call
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `quorum` - Required approvals, at least 2
+    pub fn set_break_glass_quorum(
+        env: Env,
+        admin: Address,
+        quorum: u32,
+    ) -> Result<(), ProtocolError> {
+        break_glass::BreakGlass::set_quorum(&env, &admin, quorum)
+    }
+
+    /// Propose a last-resort operation while the protocol is paused
+    ///
+    /// # Arguments
+    /// * `caller` - Admin or emergency manager (must authorize); counts as the first approval
+    /// * `action` - Break-glass action to execute on quorum
+    /// * `reason` - Mandatory justification copied into the incident record
+    ///
+    /// # Returns
+    /// * Proposal id (also the id of the resulting incident record)
+    pub fn break_glass_propose(
+        env: Env,
+        caller: Address,
+        action: break_glass::BreakGlassAction,
+        reason: String,
+    ) -> Result<u64, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        break_glass::BreakGlass::propose(&env, &caller, action, reason)
+    }
+
+    /// Approve a pending break-glass proposal, executing it once quorum is reached
+    ///
+    /// # Returns
+    /// * `true` if this approval executed the action
+    pub fn break_glass_approve(env: Env, caller: Address, id: u64) -> Result<bool, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        break_glass::BreakGlass::approve(&env, &caller, id)
+    }
+
+    /// Get a break-glass proposal by id
+    pub fn get_break_glass_proposal(env: Env, id: u64) -> Option<break_glass::BreakGlassProposal> {
+        break_glass::BreakGlass::get_proposal(&env, id)
+    }
+
+    /// Get the immutable incident record written when a break-glass call executed
+    pub fn get_incident_record(env: Env, id: u64) -> Option<break_glass::IncidentRecord> {
+        break_glass::BreakGlass::get_incident(&env, id)
+    }
+
+    // ==================== Guardians ====================
+
+    /// Configure the guardian set that must confirm pause, recovery and parameter
+    /// application; an empty set turns the scheme off (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `config` - Up to ten distinct guardians, a threshold between 1 and the set size,
+    ///   and the confirmation window in seconds
+    pub fn set_guardians(
+        env: Env,
+        admin: Address,
+        config: guardian::GuardianConfig,
+    ) -> Result<(), ProtocolError> {
+        guardian::Guardians::set_config(&env, &admin, config)
+    }
+
+    /// Current guardian configuration, if the scheme is on
+    pub fn get_guardians(env: Env) -> Option<guardian::GuardianConfig> {
+        guardian::Guardians::get_config(&env)
+    }
+
+    /// Confirm an emergency action as a guardian, executing it once the threshold of
+    /// distinct confirmations is reached within the window
+    ///
+    /// # Returns
+    /// * `true` if this confirmation executed the action
+    pub fn guardian_confirm(
+        env: Env,
+        guardian: Address,
+        action: guardian::GuardianAction,
+    ) -> Result<bool, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        guardian::Guardians::confirm(&env, &guardian, action)
+    }
+
+    /// Confirmations collected so far for an action kind
+    pub fn get_guardian_request(
+        env: Env,
+        action: guardian::GuardianAction,
+    ) -> Option<guardian::GuardianRequest> {
+        guardian::Guardians::get_request(&env, &action)
+    }
+
+    // ==================== Pairwise Collateral Matrix ====================
+
+    /// Override the minimum collateral ratio for a (collateral, debt) asset pair
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `collateral_asset` - Asset posted as collateral
+    /// * `debt_asset` - Asset borrowed against it
+    /// * `ratio` - Minimum collateral ratio in percent (same units as `set_min_collateral_ratio`)
+    pub fn set_pair_collateral_ratio(
+        env: Env,
+        admin: Address,
+        collateral_asset: Address,
+        debt_asset: Address,
+        ratio: i128,
+    ) -> Result<(), ProtocolError> {
+        risk_matrix::CollateralMatrix::set_pair_ratio(
+            &env,
+            &admin,
+            &collateral_asset,
+            &debt_asset,
+            ratio,
+        )
+    }
+
+    /// Remove a pair override so the pair uses the global minimum ratio
+    pub fn clear_pair_collateral_ratio(
+        env: Env,
+        admin: Address,
+        collateral_asset: Address,
+        debt_asset: Address,
+    ) -> Result<(), ProtocolError> {
+        risk_matrix::CollateralMatrix::clear_pair_ratio(
+            &env,
+            &admin,
+            &collateral_asset,
+            &debt_asset,
+        )
+    }
+
+    /// Get the pair override, if one is set
+    pub fn get_pair_collateral_ratio(
+        env: Env,
+        collateral_asset: Address,
+        debt_asset: Address,
+    ) -> Option<i128> {
+        risk_matrix::CollateralMatrix::get_pair_ratio(&env, &collateral_asset, &debt_asset)
+    }
+
+    // ==================== Liquidation Rebate Pool ====================
+
+    /// Set the share of liquidation penalties routed to the rebate pool
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `bps` - Share in basis points (0-10000)
+    pub fn set_rebate_penalty_share(
+        env: Env,
+        admin: Address,
+        bps: i128,
+    ) -> Result<(), ProtocolError> {
+        rebate::RebatePool::set_penalty_share(&env, &admin, bps)
+    }
+
+    /// Tag a verified oracle malfunction window that makes liquidations eligible for rebates
+    ///
+    /// # Returns
+    /// * Incident id
+    pub fn tag_oracle_incident(
+        env: Env,
+        admin: Address,
+        start: u64,
+        end: u64,
+        description: String,
+    ) -> Result<u64, ProtocolError> {
+        rebate::RebatePool::tag_incident(&env, &admin, start, end, description)
+    }
+
+    /// Claim a rebate for a liquidation (by liquidation history id) inside an incident window
+    ///
+    /// # Returns
+    /// * Claim id
+    pub fn submit_rebate_claim(
+        env: Env,
+        user: Address,
+        incident_id: u64,
+        liquidation_id: u64,
+    ) -> Result<u64, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        rebate::RebatePool::submit_claim(&env, &user, incident_id, liquidation_id)
+    }
+
+    /// Approve or reject a pending rebate claim; approved awards are credited to collateral
+    pub fn adjudicate_rebate_claim(
+        env: Env,
+        admin: Address,
+        claim_id: u64,
+        approve: bool,
+        award: i128,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        rebate::RebatePool::adjudicate_claim(&env, &admin, claim_id, approve, award)
+    }
+
+    /// Get rebate pool balances and configuration
+    pub fn get_rebate_pool(env: Env) -> rebate::RebatePoolState {
+        rebate::RebatePool::get_pool(&env)
+    }
+
+    /// Get a tagged oracle incident
+    pub fn get_oracle_incident(env: Env, id: u64) -> Option<rebate::OracleIncident> {
+        rebate::RebatePool::get_incident(&env, id)
+    }
+
+    /// Get a rebate claim
+    pub fn get_rebate_claim(env: Env, id: u64) -> Option<rebate::RebateClaim> {
+        rebate::RebatePool::get_claim(&env, id)
+    }
+
+    // ==================== Insurance Coverage Cohorts ====================
+
+    /// Opt in to or out of supplier insurance coverage (subject to a toggle cooldown)
+    ///
+    /// # Arguments
+    /// * `user` - Supplier changing their election (must authorize)
+    /// * `covered` - Whether the user's supply should be covered
+    pub fn set_insurance_coverage(
+        env: Env,
+        user: Address,
+        covered: bool,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        insurance::InsuranceCohorts::set_coverage(&env, &user, covered)
+    }
+
+    /// Set the minimum time between coverage toggles (admin only)
+    pub fn set_coverage_toggle_cooldown(
+        env: Env,
+        admin: Address,
+        secs: u64,
+    ) -> Result<(), ProtocolError> {
+        insurance::InsuranceCohorts::set_toggle_cooldown(&env, &admin, secs)
+    }
+
+    /// Add funds to an asset's insurance reserve (admin only)
+    pub fn fund_insurance_reserve(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        insurance::InsuranceCohorts::fund_reserve(&env, &admin, &asset, amount)
+    }
+
+    /// Apply a shortfall event to the covered or uncovered cohort of an asset (admin only)
+    ///
+    /// # Returns
+    /// * Amount absorbed by the reserve and amount written down against the cohort
+    pub fn apply_insurance_shortfall(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        covered_cohort: bool,
+        loss: i128,
+    ) -> Result<insurance::ShortfallOutcome, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        insurance::InsuranceCohorts::apply_shortfall(&env, &admin, &asset, covered_cohort, loss)
+    }
+
+    /// Get covered/uncovered cohort sizes and loss indexes for an asset
+    pub fn get_coverage_cohorts(env: Env, asset: Address) -> insurance::CoverageCohorts {
+        insurance::InsuranceCohorts::get_cohorts(&env, &asset)
+    }
+
+    /// Whether a user's supply is currently covered
+    pub fn is_insurance_covered(env: Env, user: Address) -> bool {
+        insurance::InsuranceCohorts::is_covered(&env, &user)
+    }
+
+    // ==================== Keeper Watchdog ====================
+
+    /// Enable or disable the liveness watchdog
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `enabled` - Whether silence should auto-pause the protocol
+    /// * `interval` - Maximum tolerated silence in seconds
+    pub fn configure_watchdog(
+        env: Env,
+        admin: Address,
+        enabled: bool,
+        interval: u64,
+    ) -> Result<(), ProtocolError> {
+        watchdog::Watchdog::configure(&env, &admin, enabled, interval)
+    }
+
+    /// Persist a watchdog pause if keepers have gone silent (callable by anyone)
+    ///
+    /// # Returns
+    /// * `true` if this call paused the protocol
+    pub fn poke_watchdog(env: Env) -> bool {
+        watchdog::Watchdog::trip_if_stale(&env)
+    }
+
+    /// Get watchdog configuration and last recorded activity
+    pub fn get_watchdog_state(env: Env) -> watchdog::WatchdogState {
+        watchdog::Watchdog::get_state(&env)
+    }
+
+    // ==================== Module Registry ====================
+
+    /// Select the implementation used by a core operation module
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `kind` - Module to reconfigure
+    /// * `implementation` - Implementation variant to activate
+    pub fn set_module_impl(
+        env: Env,
+        admin: Address,
+        kind: modules::ModuleKind,
+        implementation: modules::ModuleImpl,
+    ) -> Result<(), ProtocolError> {
+        modules::ModuleRegistry::set_active(&env, &admin, kind, implementation)
+    }
+
+    /// Get the implementation currently active for a module
+    pub fn get_module_impl(env: Env, kind: modules::ModuleKind) -> modules::ModuleImpl {
+        modules::ModuleRegistry::active(&env, kind)
+    }
+
+    // ==================== Bootstrap Campaigns ====================
+
+    /// Set the protocol withdrawal fee
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `bps` - Fee retained on withdrawals, in basis points
+    pub fn set_withdraw_fee_bps(env: Env, admin: Address, bps: i128) -> Result<(), ProtocolError> {
+        ProtocolConfig::set_withdraw_fee_bps(&env, &admin, bps)
+    }
+
+    /// Register a newly listed asset together with its bootstrap campaign
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `key` - Registry key for the asset
+    /// * `token` - Token contract address
+    /// * `duration` - Campaign window in seconds from now
+    /// * `reward_multiplier_bps` - Reward boost for early deposits (10000 = 1x, max 5x)
+    /// * `withdraw_fee_discount_bps` - Share of the withdrawal fee waived for participants
+    pub fn list_asset_with_campaign(
+        env: Env,
+        admin: Address,
+        key: Symbol,
+        token: Address,
+        duration: u64,
+        reward_multiplier_bps: i128,
+        withdraw_fee_discount_bps: i128,
+    ) -> Result<bootstrap::BootstrapCampaign, ProtocolError> {
+        bootstrap::Bootstrap::list_asset_with_campaign(
+            &env,
+            &admin,
+            key,
+            token,
+            duration,
+            reward_multiplier_bps,
+            withdraw_fee_discount_bps,
+        )
+    }
+
+    /// Get the bootstrap campaign for an asset, including expired ones
+    pub fn get_bootstrap_campaign(
+        env: Env,
+        asset: Address,
+    ) -> Option<bootstrap::BootstrapCampaign> {
+        bootstrap::Bootstrap::get_campaign(&env, &asset)
+    }
+
+    /// Get campaign analytics for an asset
+    pub fn get_bootstrap_stats(env: Env, asset: Address) -> bootstrap::BootstrapStats {
+        bootstrap::Bootstrap::get_stats(&env, &asset)
+    }
+
+    /// Get a depositor's campaign participation
+    pub fn get_bootstrap_participant(
+        env: Env,
+        asset: Address,
+        user: Address,
+    ) -> Option<bootstrap::BootstrapParticipant> {
+        bootstrap::Bootstrap::get_participant(&env, &asset, &user)
+    }
+
+    // ==================== Activity Bloom Index ====================
+
+    /// Check whether an address may have been involved in events during an epoch
+    ///
+    /// # Arguments
+    /// * `user` - Address to look up
+    /// * `epoch` - Epoch index (`timestamp / 86400`)
+    ///
+    /// # Returns
+    /// * `false` if the address definitely had no recorded activity; `true` may be a false positive
+    pub fn might_have_activity(env: Env, user: Address, epoch: u64) -> bool {
+        bloom::ActivityBloom::might_have_activity(&env, &user, epoch)
+    }
+
+    /// Get the epoch index for the current ledger time
+    pub fn get_activity_epoch(env: Env) -> u64 {
+        bloom::ActivityBloom::epoch_of(env.ledger().timestamp())
+    }
+
+    // ==================== Oracle Price Feeds ====================
+
+    /// Register a price feed for an asset; feeds are aggregated by median
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `asset` - Asset the feed prices
+    /// * `feed` - Contract exposing `get_price(asset) -> i128`
+    /// * `weight` - Relative weight of the feed (must be positive)
+    pub fn add_price_feed(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        feed: Address,
+        weight: i128,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::add_feed(&env, &admin, &asset, &feed, weight)
+    }
+
+    /// Remove a price feed from an asset
+    pub fn remove_price_feed(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        feed: Address,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::remove_feed(&env, &admin, &asset, &feed)
+    }
+
+    /// Set the largest move from the last accepted price, in bps (0 disables the check)
+    pub fn set_oracle_max_deviation(
+        env: Env,
+        admin: Address,
+        bps: i128,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::set_max_jump_bps(&env, &admin, bps)
+    }
+
+    /// Require an asset's price to be refreshed at least every `ttl` seconds (0 disables).
+    /// While stale, borrows and withdrawals against debt are blocked and liquidations pay
+    /// no bonus.
+    pub fn set_oracle_heartbeat(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        ttl: u64,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::set_asset_heartbeat(&env, &admin, &asset, ttl)
+    }
+
+    /// Last price update of an asset and whether it is past its heartbeat
+    pub fn get_price_freshness(env: Env, asset: Address) -> oracle::PriceFreshness {
+        oracle::Oracle::price_freshness(&env, &asset)
+    }
+
+    /// Set the TWAP window and whether multi-asset borrow and withdrawal health checks value
+    /// assets at their TWAP instead of the spot price (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `period` - Averaging window in seconds
+    /// * `use_for_risk` - Use the TWAP in position valuation
+    pub fn set_oracle_twap(
+        env: Env,
+        admin: Address,
+        period: u64,
+        use_for_risk: bool,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::set_twap_config(&env, &admin, period, use_for_risk)
+    }
+
+    /// Current TWAP settings
+    pub fn get_oracle_twap_config(env: Env) -> oracle::TwapConfig {
+        oracle::OracleStorage::get_twap_config(&env)
+    }
+
+    /// Time-weighted average price of an asset over the configured window, if it has
+    /// any recorded observations
+    pub fn get_twap_price(env: Env, asset: Address) -> Option<i128> {
+        oracle::Oracle::twap(&env, &asset)
+    }
+
+    /// Recorded price observations of an asset, oldest first
+    pub fn get_price_observations(env: Env, asset: Address) -> Vec<oracle::PriceObservation> {
+        oracle::Oracle::observations(&env, &asset)
+    }
+
+    /// Registered price feeds of an asset
+    pub fn get_price_feeds(env: Env, asset: Address) -> Vec<oracle::OracleSource> {
+        oracle::OracleStorage::get_sources(&env, &asset)
+    }
+
+    /// Median price across an asset's feeds
+    ///
+    /// # Returns
+    /// * The aggregated price, which becomes the new last accepted price
+    /// * `Oracle*` error codes (`9xxx`) giving the failure reason
+    pub fn get_aggregated_price(env: Env, asset: Address) -> Result<i128, ProtocolError> {
+        oracle::Oracle::get_aggregated_price(&env, &asset)
+    }
+
+    // ==================== Oracle Price Bounds ====================
+
+    /// Set hard floor/ceiling bounds for an asset's oracle price
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `asset` - Asset the bounds apply to
+    /// * `floor` - Lowest accepted price
+    /// * `ceiling` - Highest accepted price
+    pub fn set_price_bounds(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        floor: i128,
+        ceiling: i128,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::set_price_bounds(&env, &admin, &asset, floor, ceiling)
+    }
+
+    /// Remove an asset's oracle price bounds
+    pub fn clear_price_bounds(
+        env: Env,
+        admin: Address,
+        asset: Address,
+    ) -> Result<(), ProtocolError> {
+        oracle::Oracle::clear_price_bounds(&env, &admin, &asset)
+    }
+
+    /// List configured price bounds for all assets
+    pub fn get_price_bounds(env: Env) -> Vec<oracle::PriceBounds> {
+        oracle::Oracle::list_price_bounds(&env)
+    }
+
+    /// Get the most recent out-of-bounds price rejections (oldest first)
+    pub fn get_price_rejections(env: Env) -> Vec<oracle::PriceRejection> {
+        oracle::OracleStorage::get_rejections(&env)
+    }
+
+    // ==================== Token Behavior Profiles ====================
+
+    /// Declare how an asset's balances move on transfer
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `asset` - Token contract address
+    /// * `behavior` - Standard, fee-on-transfer with declared bps, or rebasing
+    pub fn set_token_behavior(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        behavior: token_behavior::TokenBehavior,
+    ) -> Result<(), ProtocolError> {
+        token_behavior::TokenBehaviorRegistry::set(&env, &admin, &asset, behavior)
+    }
+
+    /// Detect fee-on-transfer behavior by round-tripping a small probe from the admin
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address funding the probe
+    /// * `asset` - Token contract address
+    /// * `probe_amount` - Amount sent to the contract and returned
+    ///
+    /// # Returns
+    /// * The detected and stored behavior profile
+    pub fn detect_token_behavior(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        probe_amount: i128,
+    ) -> Result<token_behavior::TokenBehavior, ProtocolError> {
+        token_behavior::TokenBehaviorRegistry::detect(&env, &admin, &asset, probe_amount)
+    }
+
+    /// Get an asset's transfer behavior profile (standard if never set)
+    pub fn get_token_behavior(env: Env, asset: Address) -> token_behavior::TokenBehavior {
+        token_behavior::TokenBehaviorRegistry::get(&env, &asset)
+    }
+
+    // ==================== Auto-Repay From Deposits ====================
+
+    /// Route a share of future deposits to outstanding debt before collateral
+    ///
+    /// # Arguments
+    /// * `user` - Borrower setting the preference
+    /// * `enabled` - Whether auto-repay is active
+    /// * `share_bps` - Share of each deposit applied to debt (1..=10000 when enabled)
+    pub fn set_auto_repay(
+        env: Env,
+        user: Address,
+        enabled: bool,
+        share_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        auto_repay::AutoRepay::set_preference(&env, &user, enabled, share_bps)
+    }
+
+    /// Get a user's auto-repay preference and lifetime auto-repaid volume
+    pub fn get_auto_repay(env: Env, user: Address) -> auto_repay::AutoRepayPreference {
+        auto_repay::AutoRepay::get_preference(&env, &user)
+    }
+
+    /// Get protocol-wide auto-repaid volume
+    pub fn get_auto_repay_stats(env: Env) -> auto_repay::AutoRepayStats {
+        auto_repay::AutoRepay::get_stats(&env)
+    }
+
+    // ==================== Collateral Release Schedules ====================
+
+    /// Lock collateral into tranches that unlock at individual timestamps
+    ///
+    /// # Arguments
+    /// * `user` - Position owner
+    /// * `tranches` - Amounts and unlock timestamps; pending tranches may not exceed collateral
+    pub fn schedule_collateral_release(
+        env: Env,
+        user: Address,
+        tranches: Vec<release_schedule::ReleaseTranche>,
+    ) -> Result<(), ProtocolError> {
+        release_schedule::ReleaseSchedule::schedule(&env, &user, tranches)
+    }
+
+    /// Get a user's tranches that have not yet unlocked
+    pub fn get_collateral_release_schedule(
+        env: Env,
+        user: Address,
+    ) -> Vec<release_schedule::ReleaseTranche> {
+        release_schedule::ReleaseSchedule::get_schedule(&env, &user)
+    }
+
+    /// Get collateral currently locked by pending tranches
+    pub fn get_locked_collateral(env: Env, user: Address) -> i128 {
+        release_schedule::ReleaseSchedule::locked_amount(&env, &user)
+    }
+
+    // ==================== Guarded Launch TVL Caps ====================
+
+    /// Configure the protocol-wide TVL cap and its weekly growth, starting now
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `initial_cap` - Cap during the first week
+    /// * `weekly_increase` - Amount added to the cap each full week
+    /// * `max_cap` - Ceiling the schedule never exceeds
+    pub fn set_tvl_cap_schedule(
+        env: Env,
+        admin: Address,
+        initial_cap: i128,
+        weekly_increase: i128,
+        max_cap: i128,
+    ) -> Result<(), ProtocolError> {
+        tvl_cap::TvlCaps::set_schedule(&env, &admin, initial_cap, weekly_increase, max_cap)
+    }
+
+    /// Remove the protocol-wide TVL cap
+    pub fn clear_tvl_cap_schedule(env: Env, admin: Address) -> Result<(), ProtocolError> {
+        tvl_cap::TvlCaps::clear_schedule(&env, &admin)
+    }
+
+    /// Set an asset's TVL cap (`cap <= 0` clears it)
+    pub fn set_asset_tvl_cap(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        cap: i128,
+    ) -> Result<(), ProtocolError> {
+        tvl_cap::TvlCaps::set_asset_cap(&env, &admin, &asset, cap)
+    }
+
+    /// Get the configured global growth schedule
+    pub fn get_tvl_cap_schedule(env: Env) -> Option<tvl_cap::TvlCapSchedule> {
+        tvl_cap::TvlCapStorage::get_schedule(&env)
+    }
+
+    /// Get current TVL, caps in effect and remaining deposit headroom for an asset
+    pub fn get_tvl_headroom(env: Env, asset: Address) -> tvl_cap::TvlHeadroom {
+        tvl_cap::TvlCaps::headroom(&env, &asset)
+    }
+
+    // ==================== Supply-Claim Liquidation ====================
+
+    /// Liquidate an undercollateralized position by burning the liquidator's own supply
+    /// claim (sToken balance) in the same asset instead of paying in external tokens
+    ///
+    /// # Arguments
+    /// * `liquidator` - Liquidator whose supply claim covers the repayment
+    /// * `user` - Borrower being liquidated
+    /// * `amount` - Debt to repay, capped by the close factor
+    /// * `min_out` - Minimum seized collateral credited to the liquidator
+    ///
+    /// # Returns
+    /// The seized collateral, repaid debt and incentive applied
+    pub fn liquidate_with_supply(
+        env: Env,
+        liquidator: Address,
+        user: Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<liquidate::LiquidationResult, ProtocolError> {
+        let result = liquidate_with_supply(env.clone(), liquidator, user, amount, min_out);
+        analytics::AnalyticsModule::track(&env, "liquidate_with_supply", result)
+    }
+
+    // ==================== Multi-Asset Positions ====================
+
+    /// Register an asset's price for multi-asset health math (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `asset` - Token to price
+    /// * `price` - Price scaled by 1e8; must fall within the asset's oracle bounds
+    ///
+    /// # Returns
+    /// * `false` if the price was outside the bounds and was recorded as a rejection
+    ///   instead of registered
+    pub fn set_asset_price(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        price: i128,
+    ) -> Result<bool, ProtocolError> {
+        cross_asset::CrossAssets::set_asset_price(&env, &admin, &asset, price)
+    }
+
+    /// Registered price of an asset, if any
+    pub fn get_asset_price(env: Env, asset: Address) -> Option<i128> {
+        cross_asset::CrossAssets::get_asset_price(&env, &asset)
+    }
+
+    /// Supply collateral of a specific asset to the caller's multi-asset position
+    ///
+    /// # Arguments
+    /// * `user` - Depositor
+    /// * `asset` - Priced token to supply
+    /// * `amount` - Amount to transfer in
+    pub fn deposit_collateral_asset(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let result =
+            deposit::DepositModule::deposit_collateral_asset(&env, &user, &user, &asset, amount);
+        analytics::AnalyticsModule::track(&env, "deposit_collateral_asset", result)
+    }
+
+    /// Borrow a specific asset against all collateral in the multi-asset position
+    ///
+    /// # Arguments
+    /// * `user` - Borrower
+    /// * `asset` - Priced token to borrow
+    /// * `amount` - Amount to transfer out
+    pub fn borrow_asset(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let result = borrow::BorrowModule::borrow_asset(&env, &user, &asset, amount);
+        analytics::AnalyticsModule::track(&env, "borrow_asset", result)
+    }
+
+    /// Repay debt of a specific asset; amounts above the outstanding debt are capped
+    pub fn repay_asset(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let result =
+            repay::RepayModule::repay_asset(&env, &user, &user, &asset, amount).map(|_| ());
+        analytics::AnalyticsModule::track(&env, "repay_asset", result)
+    }
+
+    /// Withdraw collateral of a specific asset if the position stays healthy, optionally
+    /// to an allowlisted beneficiary
+    pub fn withdraw_asset(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+        to: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let result = withdraw::WithdrawModule::withdraw_asset(&env, &user, &asset, to, amount);
+        analytics::AnalyticsModule::track(&env, "withdraw_asset", result)
+    }
+
+    /// Per-asset collateral and debt balances of a user
+    pub fn get_cross_position(env: Env, user: Address) -> Option<CrossPosition> {
+        StateHelper::get_cross_position(&env, &user)
+    }
+
+    /// Collateral and debt value, ratio and required minimum of a multi-asset position
+    ///
+    /// # Returns
+    /// Valuation at registered prices; fails with `AssetNotSupported` if a held asset
+    /// has no registered price
+    pub fn get_cross_position_health(
+        env: Env,
+        user: Address,
+    ) -> Result<cross_asset::CrossPositionHealth, ProtocolError> {
+        let position = StateHelper::get_cross_position(&env, &user)
+            .unwrap_or_else(|| CrossPosition::new(&env, user.clone()));
+        cross_asset::CrossAssets::health(&env, &position)
+    }
+
+    // ==================== Interest Rate Markets ====================
+
+    /// Give an asset its own interest rate model (admin only). The primary asset's
+    /// market is the global interest config.
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `asset` - Market asset
+    /// * `config` - Rate model; `last_update` is set on save
+    pub fn set_market_config(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        config: InterestRateConfig,
+    ) -> Result<(), ProtocolError> {
+        InterestRateManager::set_market_config(&env, &admin, &asset, config)
+    }
+
+    /// Rate model of an asset's market (the global config if it has none of its own)
+    pub fn get_market_config(env: Env, asset: Address) -> InterestRateConfig {
+        InterestRateStorage::get_market_config(&env, &asset)
+    }
+
+    /// Compound an asset's market to the current time and refresh its utilization and
+    /// rates; callable by anyone, e.g. keepers keeping rate views fresh
+    ///
+    /// # Returns
+    /// * The updated market state
+    pub fn accrue_market(env: Env, asset: Address) -> Result<InterestRateState, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        if !InterestRateManager::has_market(&env, &asset) {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        Ok(InterestRateManager::accrue_market(&env, &asset))
+    }
+
+    /// Utilization, rates and totals of an asset's market
+    pub fn get_market_state(env: Env, asset: Address) -> InterestRateState {
+        InterestRateStorage::get_market_state(&env, &asset)
+    }
+
+    /// Time-decay the smoothed rate of an asset's market over `decay_period` seconds and
+    /// cap its move per update at `max_rate_delta_bps` (admin only; zeros disable each)
+    pub fn set_rate_dynamics(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        config: rate_dynamics::RateDynamicsConfig,
+    ) -> Result<(), ProtocolError> {
+        rate_dynamics::RateSmoothing::set_config(&env, &admin, &asset, config)
+    }
+
+    /// Effective smoothing parameters of an asset's market with its current and smoothed
+    /// borrow rates
+    pub fn get_rate_dynamics(env: Env, asset: Address) -> rate_dynamics::RateDynamics {
+        rate_dynamics::RateSmoothing::dynamics(&env, &asset)
+    }
+
+    /// Assets with a market of their own, besides the primary asset
+    pub fn get_markets(env: Env) -> Vec<Address> {
+        InterestRateStorage::get_markets(&env)
+    }
+
+    /// Current debt of a user in an asset, including interest accrued since the position
+    /// was last touched
+    ///
+    /// # Arguments
+    /// * `user` - Borrower
+    /// * `asset` - Debt asset; the primary asset includes the single-asset position
+    ///
+    /// # Returns
+    /// * Debt principal compounded by the market's borrow index up to now
+    pub fn get_current_debt(env: Env, user: Address, asset: Address) -> i128 {
+        InterestRateManager::get_current_debt(&env, &user, &asset)
+    }
+
+    // ==================== Flash Loans ====================
+
+    /// Flash-borrow several registered assets in one call
+    ///
+    /// # Arguments
+    /// * `initiator` - Verified user starting the loan
+    /// * `receiver` - Contract implementing `on_flash_loan_batch(loans, fees, initiator)`
+    /// * `loans` - Distinct (asset, amount) legs, at most eight
+    ///
+    /// # Returns
+    /// * Fee charged per leg; the receiver must have returned amount plus fee for every
+    ///   leg by the time its callback returns
+    pub fn flash_loan_batch(
+        env: Env,
+        initiator: Address,
+        receiver: Address,
+        loans: Vec<(Address, i128)>,
+    ) -> Result<Vec<i128>, ProtocolError> {
+        let result = flash_loan::FlashLoan::execute_batch(&env, &initiator, &receiver, loans);
+        analytics::AnalyticsModule::track(&env, "flash_loan_batch", result)
+    }
+
+    // ==================== Flash Loan Promos ====================
+
+    /// Grant a receiver contract fee-free flash loans for a time window (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `receiver` - Flash loan receiver contract
+    /// * `start` - First timestamp the promo applies
+    /// * `end` - Last timestamp the promo applies; it expires automatically afterwards
+    /// * `volume_cap` - Total principal that may be borrowed fee-free
+    pub fn grant_flash_loan_promo(
+        env: Env,
+        admin: Address,
+        receiver: Address,
+        start: u64,
+        end: u64,
+        volume_cap: i128,
+    ) -> Result<(), ProtocolError> {
+        flash_loan::FlashLoan::grant_promo(&env, &admin, &receiver, start, end, volume_cap)
+    }
+
+    /// Revoke a receiver's promo (admin only)
+    pub fn revoke_flash_loan_promo(
+        env: Env,
+        admin: Address,
+        receiver: Address,
+    ) -> Result<(), ProtocolError> {
+        flash_loan::FlashLoan::revoke_promo(&env, &admin, &receiver)
+    }
+
+    /// A receiver's active promo and its usage, if any
+    pub fn get_flash_loan_promo(env: Env, receiver: Address) -> Option<flash_loan::FlashLoanPromo> {
+        flash_loan::FlashLoan::get_promo(&env, &receiver)
+    }
+
+    // ==================== Address API ====================
+    //
+    // Address-typed counterparts of the String-addressed entrypoints. The String
+    // variants are deprecated and can be switched off with `set_legacy_string_api`.
+
+    /// Enable or disable the deprecated String-addressed entrypoints (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address
+    /// * `enabled` - `false` makes every String entrypoint fail with `LegacyApiDisabled`
+    pub fn set_legacy_string_api(
+        env: Env,
+        admin: Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::set_legacy_string_api(&env, &admin, enabled)
+    }
+
+    /// Whether the deprecated String-addressed entrypoints are still accepted
+    pub fn is_legacy_string_api_enabled(env: Env) -> bool {
+        ProtocolConfig::is_legacy_string_api_enabled(&env)
+    }
+
+    /// Initializes the contract and sets the admin address
+    pub fn initialize_addr(env: Env, admin: Address) -> Result<(), ProtocolError> {
+        initialize(env, admin)
+    }
+
+    /// Set the minimum collateral ratio (admin only)
+    pub fn set_min_collateral_ratio_addr(
+        env: Env,
+        caller: Address,
+        ratio: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::set_min_collateral_ratio(&env, &caller, ratio)
+    }
+
+    /// Deposit collateral into the protocol
+    pub fn deposit_collateral_addr(
+        env: Env,
+        depositor: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        depositor.require_auth();
+        let result = deposit_collateral(env.clone(), depositor, amount);
+        analytics::AnalyticsModule::track(&env, "deposit_collateral", result)
+    }
+
+    /// Borrow assets from the protocol
+    pub fn borrow_addr(env: Env, borrower: Address, amount: i128) -> Result<(), ProtocolError> {
+        borrower.require_auth();
+        let result = borrow(env.clone(), borrower, amount);
+        analytics::AnalyticsModule::track(&env, "borrow", result)
+    }
+
+    /// Repay borrowed assets
+    pub fn repay_addr(env: Env, repayer: Address, amount: i128) -> Result<(), ProtocolError> {
+        repayer.require_auth();
+        let result = repay(env.clone(), repayer, amount);
+        analytics::AnalyticsModule::track(&env, "repay", result)
+    }
+
+    /// Repay `borrower`'s debt with `payer`'s tokens; limits and verification apply to the
+    /// payer. Returns the amount repaid.
+    pub fn repay_on_behalf(
+        env: Env,
+        payer: Address,
+        borrower: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        payer.require_auth();
+        let result = repay::RepayModule::repay_on_behalf(&env, &payer, &borrower, amount);
+        analytics::AnalyticsModule::track(&env, "repay_on_behalf", result)
+    }
+
+    /// Withdraw collateral from the protocol, optionally to an allowlisted beneficiary
+    pub fn withdraw_addr(
+        env: Env,
+        withdrawer: Address,
+        amount: i128,
+        to: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        withdrawer.require_auth();
+        let result = withdraw(env.clone(), withdrawer, to, amount);
+        analytics::AnalyticsModule::track(&env, "withdraw", result)
+    }
+
+    /// Liquidate an undercollateralized position
+    pub fn liquidate_addr(
+        env: Env,
+        liquidator: Address,
+        user: Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<liquidate::LiquidationResult, ProtocolError> {
+        liquidator.require_auth();
+        let result = liquidate(env.clone(), liquidator, user, amount, min_out);
+        analytics::AnalyticsModule::track(&env, "liquidate", result)
+    }
+
+    pub fn set_risk_params_addr(
+        env: Env,
+        caller: Address,
+        close_factor: i128,
+        liquidation_incentive: i128,
+    ) -> Result<(), ProtocolError> {
+        set_risk_params(env, caller, close_factor, liquidation_incentive)
+    }
+
+    pub fn set_pause_switches_addr(
+        env: Env,
+        caller: Address,
+        pause_borrow: bool,
+        pause_deposit: bool,
+        pause_withdraw: bool,
+        pause_liquidate: bool,
+    ) -> Result<(), ProtocolError> {
+        set_pause_switches(
+            env,
+            caller,
+            pause_borrow,
+            pause_deposit,
+            pause_withdraw,
+            pause_liquidate,
+        )
+    }
+
+    pub fn set_emergency_manager_addr(
+        env: Env,
+        caller: Address,
+        manager: Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        set_emergency_manager(env, caller, manager, enabled)
+    }
+
+    pub fn trigger_emergency_pause_addr(
+        env: Env,
+        caller: Address,
+        reason: Option<String>,
+    ) -> Result<(), ProtocolError> {
+        trigger_emergency_pause(env, caller, reason)
+    }
+
+    pub fn enter_recovery_mode_addr(
+        env: Env,
+        caller: Address,
+        plan: Option<String>,
+    ) -> Result<(), ProtocolError> {
+        enter_recovery_mode(env, caller, plan)
+    }
+
+    pub fn resume_operations_addr(env: Env, caller: Address) -> Result<(), ProtocolError> {
+        resume_operations(env, caller)
+    }
+
+    pub fn record_recovery_step_addr(
+        env: Env,
+        caller: Address,
+        step: String,
+    ) -> Result<(), ProtocolError> {
+        record_recovery_step(env, caller, step)
+    }
+
+    pub fn queue_emergency_update_addr(
+        env: Env,
+        caller: Address,
+        parameter: Symbol,
+        value: i128,
+    ) -> Result<(), ProtocolError> {
+        queue_emergency_param_update(env, caller, parameter, value)
+    }
+
+    pub fn apply_emergency_updates_addr(env: Env, caller: Address) -> Result<(), ProtocolError> {
+        apply_emergency_param_updates(env, caller)
+    }
+
+    /// Drop every queued emergency update of `parameter` (admin only); returns how many
+    /// were cancelled
+    pub fn cancel_emergency_param_update(
+        env: Env,
+        admin: Address,
+        parameter: Symbol,
+    ) -> Result<u32, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        EmergencyManager::cancel_param_update(&env, &admin, parameter)
+    }
+
+    /// Set how long queued emergency updates wait before they can be applied and how long
+    /// they stay applicable after that (admin only)
+    pub fn set_emergency_param_timelock(
+        env: Env,
+        admin: Address,
+        timelock: ParamTimelock,
+    ) -> Result<(), ProtocolError> {
+        EmergencyManager::set_param_timelock(&env, &admin, timelock)
+    }
+
+    pub fn get_emergency_param_timelock(env: Env) -> ParamTimelock {
+        EmergencyStorage::get_timelock(&env)
+    }
+
+    /// Accrue every market to now while in Recovery (admin only); returns how many markets
+    /// were accrued
+    pub fn recovery_accrue_markets(env: Env, admin: Address) -> Result<u32, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        unwind::RecoveryUnwind::accrue_markets(&env, &admin)
+    }
+
+    /// Cap the utilization borrows and withdrawals may leave while in Recovery, scaled by
+    /// 1e8, or remove the cap with 0 (admin only)
+    pub fn set_recovery_utilization_cap(
+        env: Env,
+        admin: Address,
+        cap: i128,
+    ) -> Result<(), ProtocolError> {
+        unwind::RecoveryUnwind::set_utilization_cap(&env, &admin, cap)
+    }
+
+    /// Reopen `operation` during Recovery `delay` seconds from now (admin only); returns
+    /// when it reopens
+    pub fn schedule_recovery_reenable(
+        env: Env,
+        admin: Address,
+        operation: Symbol,
+        delay: u64,
+    ) -> Result<u64, ProtocolError> {
+        unwind::RecoveryUnwind::schedule_reenable(&env, &admin, operation, delay)
+    }
+
+    pub fn adjust_emergency_fund_addr(
+        env: Env,
+        caller: Address,
+        token: Option<Address>,
+        delta: i128,
+        reserve_delta: i128,
+    ) -> Result<(), ProtocolError> {
+        adjust_emergency_fund(env, caller, token, delta, reserve_delta)
+    }
+
+    pub fn register_token_asset_addr(
+        env: Env,
+        caller: Address,
+        key: Symbol,
+        token: Address,
+    ) -> Result<(), ProtocolError> {
+        register_token_asset(env, caller, key, token)
+    }
+
+    pub fn set_primary_asset_addr(
+        env: Env,
+        caller: Address,
+        token: Address,
+    ) -> Result<(), ProtocolError> {
+        set_primary_asset(env, caller, token)
+    }
+
+    pub fn set_user_role_addr(
+        env: Env,
+        caller: Address,
+        user: Address,
+        role: UserRole,
+    ) -> Result<(), ProtocolError> {
+        set_user_role(env, caller, user, role)
+    }
+
+    pub fn set_user_verification_addr(
+        env: Env,
+        caller: Address,
+        user: Address,
+        status: VerificationStatus,
+    ) -> Result<(), ProtocolError> {
+        set_user_verification(env, caller, user, status)
+    }
+
+    pub fn set_user_limits_addr(
+        env: Env,
+        caller: Address,
+        user: Address,
+        max_deposit: i128,
+        max_borrow: i128,
+        max_withdraw: i128,
+        daily_limit: i128,
+    ) -> Result<(), ProtocolError> {
+        set_user_limits(
+            env,
+            caller,
+            user,
+            max_deposit,
+            max_borrow,
+            max_withdraw,
+            daily_limit,
+        )
+    }
+
+    pub fn freeze_user_addr(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
+        freeze_user(env, caller, user)
+    }
+
+    pub fn unfreeze_user_addr(
+        env: Env,
+        caller: Address,
+        user: Address,
+    ) -> Result<(), ProtocolError> {
+        unfreeze_user(env, caller, user)
+    }
+
+    pub fn get_position_addr(env: Env, user: Address) -> Result<(i128, i128, i128), ProtocolError> {
+        get_position(env, user)
+    }
+
+    /// The user's full primary position with its current debt, collateral ratio and
+    /// health factor. `get_position_addr` keeps the (collateral, debt, ratio) tuple.
+    pub fn get_position_details(env: Env, user: Address) -> Result<PositionDetails, ProtocolError> {
+        get_position_details(env, user)
+    }
+
+    /// Up to `limit` of the user's most recent primary position snapshots (time,
+    /// collateral, debt, health factor), oldest first; 0 returns all kept snapshots
+    pub fn get_position_history(
+        env: Env,
+        user: Address,
+        limit: u32,
+    ) -> Vec<position_history::PositionSnapshot> {
+        position_history::PositionHistory::history(&env, &user, limit)
+    }
+
+    pub fn get_user_report_addr(
+        env: Env,
+        user: Address,
+    ) -> Result<analytics::UserReport, ProtocolError> {
+        analytics::AnalyticsModule::get_user_report(&env, &user)
+    }
+
+    pub fn record_activity_addr(
+        env: Env,
+        user: Address,
+        activity_type: String,
+        amount: i128,
+        asset: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        let activity = activity_type.to_string();
+        analytics::AnalyticsModule::record_activity(&env, &user, activity.as_str(), amount, asset)
+    }
+
+    // ==================== Storage TTL ====================
+
+    /// Refresh storage TTLs (admin only)
+    ///
+    /// Extends the instance and the shared persistent logs, plus the positions, profile,
+    /// analytics and beneficiaries of each user and the market and price entries of each asset.
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address
+    /// * `users` - Users whose entries to refresh
+    /// * `assets` - Assets whose entries to refresh
+    ///
+    /// # Returns
+    /// Number of persistent entries extended
+    pub fn bump_storage(
+        env: Env,
+        admin: Address,
+        users: Vec<Address>,
+        assets: Vec<Address>,
+    ) -> Result<u32, ProtocolError> {
+        persistent::StorageMaintenance::bump_storage(&env, &admin, users, assets)
+    }
+
+    // ==================== Withdrawal Beneficiaries ====================
+
+    /// Allowlist an address that `withdraw_addr` and `withdraw_asset` may pay out to
+    ///
+    /// # Arguments
+    /// * `user` - Position owner
+    /// * `beneficiary` - Recipient to allow; usable one day after being added
+    pub fn add_withdraw_beneficiary(
+        env: Env,
+        user: Address,
+        beneficiary: Address,
+    ) -> Result<(), ProtocolError> {
+        beneficiary::Beneficiaries::add(&env, &user, &beneficiary)
+    }
+
+    /// Remove an allowlisted recipient, effective immediately
+    pub fn remove_withdraw_beneficiary(
+        env: Env,
+        user: Address,
+        beneficiary: Address,
+    ) -> Result<(), ProtocolError> {
+        beneficiary::Beneficiaries::remove(&env, &user, &beneficiary)
+    }
+
+    /// Allowlisted withdrawal recipients of a user with their activation times
+    pub fn get_withdraw_beneficiaries(env: Env, user: Address) -> Vec<beneficiary::Beneficiary> {
+        beneficiary::Beneficiaries::get(&env, &user)
+    }
+
+    // ==================== Bridge Attestations ====================
+
+    /// Register or replace a bridge source network (admin only)
+    ///
+    /// # Arguments
+    /// * `network` - Network id, submitting adapter, ed25519 attester keys, signature
+    ///   threshold and fee in bps (max 10%)
+    pub fn register_bridge(
+        env: Env,
+        admin: Address,
+        network: bridge::BridgeNetwork,
+    ) -> Result<(), ProtocolError> {
+        bridge::Bridge::register_network(&env, &admin, network)
+    }
+
+    /// Credit bridged collateral from a signed attestation submitted by the network's adapter
+    ///
+    /// # Arguments
+    /// * `adapter` - Registered adapter for the attestation's source network; delivers the tokens
+    /// * `attestation` - Versioned payload and attester signatures over
+    ///   `bridge_attestation_message(payload)`
+    ///
+    /// # Returns
+    /// * Amount credited to the user's collateral after the bridge fee
+    pub fn bridge_deposit(
+        env: Env,
+        adapter: Address,
+        attestation: bridge::BridgeAttestation,
+    ) -> Result<i128, ProtocolError> {
+        bridge::Bridge::bridge_in(&env, &adapter, attestation)
+    }
+
+    /// Bytes attesters sign for a payload
+    pub fn bridge_attestation_message(env: Env, payload: bridge::AttestationPayload) -> Bytes {
+        bridge::AttestationVerifier::message(&env, &payload)
+    }
+
+    /// Bridge collateral out to a registered network
+    ///
+    /// # Arguments
+    /// * `user` - Owner of the collateral
+    /// * `network_id` - Destination network; its adapter receives the tokens to lock or burn
+    /// * `asset` - Collateral asset, primary or cross-asset
+    /// * `amount` - Collateral debited, including the network fee
+    ///
+    /// # Returns
+    /// * Amount sent to the adapter after the bridge fee
+    pub fn bridge_withdraw(
+        env: Env,
+        user: Address,
+        network_id: String,
+        asset: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        bridge::Bridge::bridge_out(&env, &user, &network_id, &asset, amount)
+    }
+
+    /// Set a network's per-transfer and daily limits; 0 leaves a limit off (admin only)
+    pub fn set_bridge_limits(
+        env: Env,
+        admin: Address,
+        network_id: String,
+        limits: bridge::BridgeLimits,
+    ) -> Result<(), ProtocolError> {
+        bridge::Bridge::set_limits(&env, &admin, &network_id, limits)
+    }
+
+    pub fn get_bridge_limits(env: Env, network_id: String) -> bridge::BridgeLimits {
+        bridge::Bridge::get_limits(&env, &network_id)
+    }
+
+    /// Inbound and outbound volume of a network in the current daily window
+    pub fn get_bridge_flow(env: Env, network_id: String) -> bridge::BridgeFlow {
+        bridge::Bridge::get_flow(&env, &network_id)
+    }
+
+    pub fn get_bridge(env: Env, network_id: String) -> Option<bridge::BridgeNetwork> {
+        bridge::Bridge::get_network(&env, &network_id)
+    }
+
+    pub fn is_bridge_nonce_used(env: Env, network_id: String, nonce: u64) -> bool {
+        bridge::Bridge::is_nonce_used(&env, &network_id, nonce)
+    }
+
+    // ==================== Safety Rewards ====================
+
+    /// Configure reward emissions on collateral with a health-factor multiplier curve
+    /// (admin only)
+    ///
+    /// # Arguments
+    /// * `emission_rate` - Annual points per unit of collateral (scaled by 1e8); 0 disables
+    /// * `curve` - Up to 8 points in strictly ascending health factor (100 = liquidation
+    ///   threshold), each with a multiplier in bps (10000 = 1x, max 5x)
+    pub fn set_safety_reward_config(
+        env: Env,
+        admin: Address,
+        emission_rate: i128,
+        curve: Vec<safety_rewards::RewardCurvePoint>,
+    ) -> Result<(), ProtocolError> {
+        safety_rewards::SafetyRewards::set_config(&env, &admin, emission_rate, curve)
+    }
+
+    pub fn get_safety_reward_config(env: Env) -> Option<safety_rewards::SafetyRewardConfig> {
+        safety_rewards::SafetyRewards::get_config(&env)
+    }
+
+    /// Points a user has earned and the multiplier applied at their last accrual
+    pub fn get_safety_rewards(env: Env, user: Address) -> safety_rewards::SafetyRewardAccount {
+        safety_rewards::SafetyRewards::get_account(&env, &user)
+    }
+
+    // ==================== Soft Borrow Limits ====================
+
+    /// Set a soft borrow limit for a user (manager or admin only). Borrows above it are
+    /// queued for manager approval instead of executing.
+    ///
+    /// # Arguments
+    /// * `caller` - Manager or admin
+    /// * `user` - User the limit applies to
+    /// * `limit` - Largest borrow executed without approval; 0 clears the limit
+    /// * `approval_window` - Seconds a queued borrow stays approvable
+    pub fn set_soft_borrow_limit(
+        env: Env,
+        caller: Address,
+        user: Address,
+        limit: i128,
+        approval_window: u64,
+    ) -> Result<(), ProtocolError> {
+        soft_limits::SoftLimits::set_limit(&env, &caller, &user, limit, approval_window)
+    }
+
+    pub fn get_soft_borrow_limit(env: Env, user: Address) -> Option<soft_limits::SoftLimit> {
+        soft_limits::SoftLimits::get_limit(&env, &user)
+    }
+
+    /// Approve a queued borrow within its window, executing it immediately
+    pub fn approve_soft_limit_request(
+        env: Env,
+        caller: Address,
+        request_id: u64,
+    ) -> Result<(), ProtocolError> {
+        soft_limits::SoftLimits::resolve(&env, &caller, request_id, true)
+    }
+
+    /// Reject a queued borrow
+    pub fn reject_soft_limit_request(
+        env: Env,
+        caller: Address,
+        request_id: u64,
+    ) -> Result<(), ProtocolError> {
+        soft_limits::SoftLimits::resolve(&env, &caller, request_id, false)
+    }
+
+    pub fn get_soft_limit_request(
+        env: Env,
+        request_id: u64,
+    ) -> Option<soft_limits::SoftLimitRequest> {
+        soft_limits::SoftLimits::get_request(&env, request_id)
+    }
+
+    /// Most recent borrow queued for a user, if any
+    pub fn get_user_soft_limit_request(
+        env: Env,
+        user: Address,
+    ) -> Option<soft_limits::SoftLimitRequest> {
+        soft_limits::SoftLimits::pending_request(&env, &user)
+    }
+
+    // ==================== Recovery Withdrawals ====================
+
+    /// Withdraw the caller's pro-rata share of an asset while the protocol is in Recovery.
+    /// The share is the user's supply scaled by the asset's liquid balance over its total
+    /// supply, snapshotted at the round's first claim; the rest stays in the position.
+    ///
+    /// # Arguments
+    /// * `user` - Supplier without outstanding debt; may claim once per asset per round
+    /// * `asset` - Supplied asset to withdraw
+    ///
+    /// # Returns
+    /// * Amount transferred to the user
+    pub fn recovery_withdraw(
+        env: Env,
+        user: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        recovery::RecoveryWithdrawals::withdraw(&env, &user, &asset)
+    }
+
+    /// A user's latest recovery claim for an asset
+    pub fn get_recovery_claim(
+        env: Env,
+        user: Address,
+        asset: Address,
+    ) -> Option<recovery::RecoveryClaim> {
+        recovery::RecoveryWithdrawals::get_claim(&env, &user, &asset)
+    }
+
+    // ==================== LP Token Collateral ====================
+
+    /// Accept an AMM LP token as multi-asset collateral, valued at its fair price (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `config` - LP token, its pool, the two priced underlying assets and the collateral
+    ///   factor (bps) applied to the fair value
+    pub fn register_lp_collateral(
+        env: Env,
+        admin: Address,
+        config: lp_pricing::LpTokenConfig,
+    ) -> Result<(), ProtocolError> {
+        lp_pricing::LpPricing::register(&env, &admin, config)
+    }
+
+    /// Registration of an LP token, if any
+    pub fn get_lp_collateral(env: Env, lp_token: Address) -> Option<lp_pricing::LpTokenConfig> {
+        lp_pricing::LpPricing::get_config(&env, &lp_token)
+    }
+
+    /// Fair price of one LP token (1e8 scale), before its collateral factor
+    pub fn get_lp_price(env: Env, lp_token: Address) -> Result<i128, ProtocolError> {
+        lp_pricing::LpPricing::fair_price(&env, &lp_token)
+    }
+
+    // ==================== Liquidation Auctions ====================
+
+    /// Set the length and collateral price range of liquidation auctions (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `config` - Duration and start/end collateral prices in bps of par; the price
+    ///   decays linearly from start to end
+    pub fn set_auction_config(
+        env: Env,
+        admin: Address,
+        config: auction::AuctionConfig,
+    ) -> Result<(), ProtocolError> {
+        auction::LiquidationAuctions::set_config(&env, &admin, config)
+    }
+
+    /// Current liquidation auction settings
+    pub fn get_auction_config(env: Env) -> auction::AuctionConfig {
+        auction::LiquidationAuctions::get_config(&env)
+    }
+
+    /// Start a dutch auction over part of an undercollateralized position's debt, as an
+    /// alternative to instant liquidation
+    ///
+    /// # Arguments
+    /// * `keeper` - Caller starting the auction
+    /// * `user` - Position to liquidate
+    /// * `debt_portion` - Debt offered, at most the close factor of the current debt
+    ///
+    /// # Returns
+    /// * Auction id
+    pub fn start_liquidation_auction(
+        env: Env,
+        keeper: Address,
+        user: Address,
+        debt_portion: i128,
+    ) -> Result<u64, ProtocolError> {
+        liquidate::LiquidationModule::start_auction(&env, &keeper, &user, debt_portion)
+    }
+
+    /// Repay part of a user's auctioned debt in the primary asset and receive collateral
+    /// at the auction's current price
+    ///
+    /// # Arguments
+    /// * `bidder` - Pays the debt and receives the collateral
+    /// * `user` - Position under auction
+    /// * `repay_amount` - Debt to repay; capped at the auction's remaining portion
+    ///
+    /// # Returns
+    /// * Debt repaid, collateral received and the price applied
+    pub fn bid_liquidation_auction(
+        env: Env,
+        bidder: Address,
+        user: Address,
+        repay_amount: i128,
+    ) -> Result<auction::AuctionFill, ProtocolError> {
+        liquidate::LiquidationModule::bid_auction(&env, &bidder, &user, repay_amount)
+    }
+
+    /// Auction by id; active auctions past expiry report `Expired`
+    pub fn get_liquidation_auction(env: Env, id: u64) -> Option<auction::LiquidationAuction> {
+        auction::LiquidationAuctions::get_auction(&env, id)
+    }
+
+    /// Latest liquidation auction of a user
+    pub fn get_user_liquidation_auction(
+        env: Env,
+        user: Address,
+    ) -> Option<auction::LiquidationAuction> {
+        auction::LiquidationAuctions::user_auction(&env, &user)
+    }
+
+    // ==================== Configuration Getters ====================
+
+    /// Current admin, if initialized
+    pub fn get_admin(env: Env) -> Option<Address> {
+        ProtocolConfig::get_admin(&env)
+    }
+
+    /// Oracle address set by the admin, if any
+    pub fn get_oracle(env: Env) -> Option<Address> {
+        ProtocolConfig::get_oracle(&env)
+    }
+
+    /// Global minimum collateral ratio in percent
+    pub fn get_min_collateral_ratio(env: Env) -> i128 {
+        ProtocolConfig::get_min_collateral_ratio(&env)
+    }
+
+    /// Flash loan fee in basis points, before promos
+    pub fn get_flash_loan_fee_bps(env: Env) -> i128 {
+        ProtocolConfig::get_flash_loan_fee_bps(&env)
+    }
+
+    /// Withdrawal fee in basis points
+    pub fn get_withdraw_fee_bps(env: Env) -> i128 {
+        ProtocolConfig::get_withdraw_fee_bps(&env)
+    }
+
+    /// Primary asset backing single-asset positions, if registered
+    pub fn get_primary_asset(env: Env) -> Option<Address> {
+        TokenRegistry::require_primary_asset(&env).ok()
+    }
+
+    /// Every asset registered with `register_token_asset`, by key
+    pub fn list_registered_assets(env: Env) -> Map<Symbol, Address> {
+        TokenRegistry::list_assets(&env)
+    }
+
+    /// Emergency managers allowed to pause and run recovery
+    pub fn list_emergency_managers(env: Env) -> Vec<Address> {
+        EmergencyManager::managers(&env)
+    }
+
+    /// All of the above admin-set values in one call
+    pub fn get_protocol_config(env: Env) -> ProtocolConfigView {
+        ProtocolConfig::snapshot(&env)
+    }
+
+    /// The protocol config with the interest, risk, oracle and fee settings, emergency
+    /// status and per-asset parameter counts, for indexers
+    pub fn get_full_config(env: Env) -> FullProtocolConfig {
+        ProtocolConfig::full_snapshot(&env)
+    }
+
+    // ==================== Liquidation Sizing ====================
+
+    /// Largest repayment a single `liquidate` call accepts for a user, capped at the close
+    /// factor of the current debt and at the collateral available to seize
+    ///
+    /// # Returns
+    /// * Repayable debt, collateral it would seize and the incentive applied; all zero
+    ///   when the position is not liquidatable
+    pub fn get_max_liquidatable(
+        env: Env,
+        user: Address,
+    ) -> Result<liquidate::LiquidationResult, ProtocolError> {
+        liquidate::LiquidationModule::max_liquidatable(&env, &user)
+    }
+
+    /// Make the liquidation incentive a curve over the health factor, scaled down by
+    /// collateral size tiers (admin only). Empty points restore the flat incentive.
+    pub fn set_liquidation_incentive_curve(
+        env: Env,
+        admin: Address,
+        curve: liquidate::IncentiveCurve,
+    ) -> Result<(), ProtocolError> {
+        liquidate::LiquidationModule::set_incentive_curve(&env, &admin, curve)
+    }
+
+    pub fn get_liquidation_incentive_curve(env: Env) -> liquidate::IncentiveCurve {
+        liquidate::LiquidationModule::get_incentive_curve(&env)
+    }
+
+    /// Incentive a liquidation of `user` would pay right now (scaled by 1e8)
+    pub fn get_liquidation_incentive(env: Env, user: Address) -> Result<i128, ProtocolError> {
+        liquidate::LiquidationModule::incentive_for(&env, &user)
+    }
+
+    // ==================== Health Factor Index ====================
+
+    /// Set the bounty paid per newly surfaced underwater position (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Protocol admin
+    /// * `bounty` - Amount of the emergency fund token paid per position; 0 disables it
+    pub fn set_underwater_report_bounty(
+        env: Env,
+        admin: Address,
+        bounty: i128,
+    ) -> Result<(), ProtocolError> {
+        health_index::HealthIndex::set_bounty(&env, &admin, bounty)
+    }
+
+    /// Bounty paid per newly surfaced underwater position
+    pub fn get_underwater_report_bounty(env: Env) -> i128 {
+        health_index::HealthIndex::get_bounty(&env)
+    }
+
+    /// Up to `limit` (at most 50) liquidatable positions from the health-factor index,
+    /// riskiest first, with the largest repayment each accepts
+    pub fn get_liquidatable_positions(
+        env: Env,
+        limit: u32,
+    ) -> Vec<health_index::LiquidatablePosition> {
+        health_index::HealthIndex::liquidatable(&env, limit)
+    }
+
+    /// Health-factor bucket of a user (10 points each, 100 = at the minimum ratio), or
+    /// `None` for positions without debt or at a health factor of 200 and above
+    pub fn get_health_bucket(env: Env, user: Address) -> Option<u32> {
+        health_index::HealthIndex::user_bucket(&env, false, &user)
+    }
+
+    /// Health-factor bucket of a user's multi-asset position, on the same scale as
+    /// `get_health_bucket`
+    pub fn get_cross_health_bucket(env: Env, user: Address) -> Option<u32> {
+        health_index::HealthIndex::user_bucket(&env, true, &user)
+    }
+
+    /// Up to `limit` (at most 50) users whose multi-asset position is below its minimum
+    /// ratio at current prices, riskiest first
+    pub fn get_underwater_cross_positions(env: Env, limit: u32) -> Vec<Address> {
+        health_index::HealthIndex::underwater_cross(&env, limit)
+    }
+
+    /// Re-index users at their current debt and collect the bounty for each one newly
+    /// found underwater
+    ///
+    /// # Arguments
+    /// * `caller` - Receives the bounty
+    /// * `users` - Up to 20 users to check
+    ///
+    /// # Returns
+    /// * Bounty paid from the emergency fund
+    pub fn report_underwater_positions(
+        env: Env,
+        caller: Address,
+        users: Vec<Address>,
+    ) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        health_index::HealthIndex::report(&env, &caller, users)
+    }
+
+    // ==================== Account Quarantine ====================
+
+    /// Set the quarantine review period and per-call borrow/withdraw allowances (admin only)
+    pub fn set_quarantine_config(
+        env: Env,
+        admin: Address,
+        config: quarantine::QuarantineConfig,
+    ) -> Result<(), ProtocolError> {
+        quarantine::Quarantine::set_config(&env, &admin, config)
+    }
+
+    /// Current quarantine settings
+    pub fn get_quarantine_config(env: Env) -> quarantine::QuarantineConfig {
+        quarantine::Quarantine::get_config(&env)
+    }
+
+    /// Quarantine a user: repay and deposit stay open, borrows and withdrawals above the
+    /// allowances fail with `UserQuarantined` until the review period ends
+    ///
+    /// # Arguments
+    /// * `caller` - Analyst, manager or admin
+    /// * `user` - Account to restrict
+    /// * `trigger` - Compliance alert, risk score or manual review
+    /// * `reason` - Reason code recorded and emitted with the quarantine
+    pub fn quarantine_user(
+        env: Env,
+        caller: Address,
+        user: Address,
+        trigger: quarantine::QuarantineTrigger,
+        reason: Symbol,
+    ) -> Result<(), ProtocolError> {
+        quarantine::Quarantine::quarantine(&env, &caller, &user, trigger, reason)
+    }
+
+    /// Keep a user's quarantine in place past its review period (manager or admin)
+    pub fn escalate_quarantine(
+        env: Env,
+        caller: Address,
+        user: Address,
+    ) -> Result<(), ProtocolError> {
+        quarantine::Quarantine::escalate(&env, &caller, &user)
+    }
+
+    /// Lift a user's quarantine (manager or admin)
+    pub fn release_quarantine(
+        env: Env,
+        caller: Address,
+        user: Address,
+    ) -> Result<(), ProtocolError> {
+        quarantine::Quarantine::release(&env, &caller, &user)
+    }
+
+    /// Latest quarantine record of a user with its reason code, active or not
+    pub fn get_quarantine(env: Env, user: Address) -> Option<quarantine::QuarantineRecord> {
+        quarantine::Quarantine::get_record(&env, &user)
+    }
+
+    /// Whether quarantine restrictions currently apply to a user
+    pub fn is_quarantined(env: Env, user: Address) -> bool {
+        quarantine::Quarantine::active_record(&env, &user).is_some()
+    }
+
+    // ==================== Governance ====================
+
+    /// Set the voting period, quorum and timelock for new proposals (admin only)
+    pub fn gov_set_config(
+        env: Env,
+        admin: Address,
+        config: governance::GovConfig,
+    ) -> Result<(), ProtocolError> {
+        governance::Governance::set_config(&env, &admin, config)
+    }
+
+    /// Current governance settings
+    pub fn gov_get_config(env: Env) -> governance::GovConfig {
+        governance::Governance::get_config(&env)
+    }
+
+    /// Choose where vote weight comes from: one per verified user, collateral share of the
+    /// primary asset, or a staked governance token (admin only)
+    pub fn gov_set_voting_power_source(
+        env: Env,
+        admin: Address,
+        source: governance::VotingPowerSource,
+    ) -> Result<(), ProtocolError> {
+        governance::Governance::set_power_source(&env, &admin, source)
+    }
+
+    /// Current vote weight source
+    pub fn gov_get_voting_power_source(env: Env) -> governance::VotingPowerSource {
+        governance::Governance::get_power_source(&env)
+    }
+
+    /// Stake governance tokens; they weigh on proposals created afterwards
+    ///
+    /// # Returns
+    /// * The user's staked balance
+    pub fn stake_gov_tokens(env: Env, user: Address, amount: i128) -> Result<i128, ProtocolError> {
+        governance::Governance::stake(&env, &user, amount)
+    }
+
+    /// Withdraw staked governance tokens
+    ///
+    /// # Returns
+    /// * The user's remaining staked balance
+    pub fn unstake_gov_tokens(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        governance::Governance::unstake(&env, &user, amount)
+    }
+
+    /// Governance tokens currently staked by a user
+    pub fn get_gov_stake(env: Env, user: Address) -> i128 {
+        governance::Governance::staked(&env, &user)
+    }
+
+    /// Delegate vote weight to `to`; delegating to yourself removes the delegation
+    pub fn delegate_votes(env: Env, delegator: Address, to: Address) -> Result<(), ProtocolError> {
+        governance::Governance::delegate(&env, &delegator, &to)
+    }
+
+    /// The address a user has delegated their votes to, if any
+    pub fn get_vote_delegate(env: Env, user: Address) -> Option<Address> {
+        governance::Governance::get_delegate(&env, &user)
+    }
+
+    /// Open a proposal to update protocol parameters
+    ///
+    /// # Arguments
+    /// * `proposer` - Verified user
+    /// * `title` - Non-empty description
+    /// * `actions` - Up to ten (key, value) parameter updates, using the keys accepted by
+    ///   queued emergency updates (interest model, risk config, collateral ratio, fees)
+    ///
+    /// # Returns
+    /// * The new proposal id
+    pub fn gov_create_proposal(
+        env: Env,
+        proposer: Address,
+        title: String,
+        actions: Vec<(Symbol, i128)>,
+    ) -> Result<u64, ProtocolError> {
+        governance::Governance::propose(&env, &proposer, title, actions).map(|p| p.id)
+    }
+
+    /// Vote for or against a proposal while voting is open; each voter votes once, carrying
+    /// the weight of delegators who have not voted
+    pub fn gov_vote(
+        env: Env,
+        voter: Address,
+        proposal_id: u64,
+        support: bool,
+    ) -> Result<(), ProtocolError> {
+        governance::Governance::vote(&env, proposal_id, &voter, support).map(|_| ())
+    }
+
+    /// Queue a passed proposal once voting has ended, starting its timelock
+    ///
+    /// # Returns
+    /// * Earliest execution timestamp
+    pub fn gov_queue(env: Env, caller: Address, proposal_id: u64) -> Result<u64, ProtocolError> {
+        governance::Governance::queue(&env, &caller, proposal_id).map(|p| p.queued_until)
+    }
+
+    /// Apply a queued proposal's parameter updates after its timelock
+    pub fn gov_execute(env: Env, caller: Address, proposal_id: u64) -> Result<(), ProtocolError> {
+        governance::Governance::execute(&env, &caller, proposal_id).map(|_| ())
+    }
+
+    /// A proposal with its vote totals and queue/execution state
+    pub fn gov_get_proposal(env: Env, proposal_id: u64) -> Option<governance::Proposal> {
+        governance::Governance::get_proposal(&env, proposal_id)
+    }
+
+    /// A voter's receipt for a proposal, if they voted
+    pub fn gov_get_receipt(
+        env: Env,
+        proposal_id: u64,
+        voter: Address,
+    ) -> Option<governance::VoteReceipt> {
+        governance::Governance::get_receipt(&env, proposal_id, &voter)
+    }
+
+    // ==================== Admin Transfer ====================
+
+    /// Set the delay between proposing and accepting an admin transfer (admin only)
+    pub fn set_admin_transfer_delay(
+        env: Env,
+        admin: Address,
+        delay: u64,
+    ) -> Result<(), ProtocolError> {
+        admin_transfer::AdminTransfer::set_delay(&env, &admin, delay)
+    }
+
+    /// Seconds a nominated admin must wait before accepting
+    pub fn get_admin_transfer_delay(env: Env) -> u64 {
+        admin_transfer::AdminTransfer::get_delay(&env)
+    }
+
+    /// Nominate a new admin, who may accept after the transfer delay
+    ///
+    /// # Arguments
+    /// * `admin` - Current admin (must authorize)
+    /// * `new_admin` - Successor; replaces any pending nomination
+    ///
+    /// # Returns
+    /// * Earliest acceptance timestamp
+    pub fn propose_admin_transfer(
+        env: Env,
+        admin: Address,
+        new_admin: Address,
+    ) -> Result<u64, ProtocolError> {
+        admin_transfer::AdminTransfer::propose(&env, &admin, &new_admin).map(|p| p.eta)
+    }
+
+    /// Accept a pending nomination as the new admin once the delay has passed
+    pub fn accept_admin_transfer(env: Env, new_admin: Address) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        admin_transfer::AdminTransfer::accept(&env, &new_admin)
+    }
+
+    /// Cancel the pending admin nomination (admin only)
+    pub fn cancel_admin_transfer(env: Env, admin: Address) -> Result<(), ProtocolError> {
+        admin_transfer::AdminTransfer::cancel(&env, &admin)
+    }
+
+    /// The pending admin nomination, if any
+    pub fn get_pending_admin_transfer(env: Env) -> Option<admin_transfer::PendingAdminTransfer> {
+        admin_transfer::AdminTransfer::get_pending(&env)
+    }
+
+    // ==================== Protocol Reserves ====================
+
+    /// Reserves accumulated for an asset from the reserve factor's cut of borrow interest
+    /// and from origination fees
+    pub fn get_reserves(env: Env, asset: Address) -> i128 {
+        reserves::Reserves::get(&env, &asset)
+    }
+
+    /// Per-asset token balances held, supplier claims, outstanding debt, reserves and the
+    /// resulting surplus or deficit, for verifying solvency on-chain
+    pub fn get_solvency_report(env: Env) -> solvency::SolvencyReport {
+        solvency::Solvency::report(&env)
+    }
+
+    /// Withdraw accumulated reserves to a treasury address
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must authorize)
+    /// * `asset` - Asset whose reserves are withdrawn
+    /// * `treasury` - Recipient
+    /// * `amount` - At most the asset's reserves
+    ///
+    /// # Returns
+    /// * Reserves left for the asset
+    pub fn withdraw_reserves(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        treasury: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        reserves::Reserves::withdraw(&env, &admin, &asset, &treasury, amount)
+    }
+
+    /// Set the origination fee charged on every borrow, at most 1000 bps (admin only)
+    pub fn set_origination_fee(
+        env: Env,
+        admin: Address,
+        fee_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        origination::OriginationFees::set_default(&env, &admin, fee_bps)
+    }
+
+    /// Override the origination fee of one asset, or clear the override with `None`
+    /// (admin only)
+    pub fn set_asset_origination_fee(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        fee_bps: Option<i128>,
+    ) -> Result<(), ProtocolError> {
+        origination::OriginationFees::set_override(&env, &admin, &asset, fee_bps)
+    }
+
+    /// Origination fee charged on borrows of `asset` (bps)
+    pub fn get_origination_fee(env: Env, asset: Address) -> i128 {
+        origination::OriginationFees::fee_bps(&env, &asset)
+    }
+
+    // ==================== Withdrawal Liquidity ====================
+
+    /// Set the highest utilization (scaled by 1e8) a withdrawal may leave an asset's market
+    /// at; 95% by default (admin only)
+    pub fn set_max_withdraw_utilization(
+        env: Env,
+        admin: Address,
+        max_utilization: i128,
+    ) -> Result<(), ProtocolError> {
+        liquidity_guard::LiquidityGuard::set_max_utilization(&env, &admin, max_utilization)
+    }
+
+    pub fn get_max_withdraw_utilization(env: Env) -> i128 {
+        liquidity_guard::LiquidityGuard::get_max_utilization(&env)
+    }
+
+    /// Largest amount of `asset` the user can withdraw now, bounded by their collateral,
+    /// locks and minimum ratio and by the asset's liquidity under the utilization cap.
+    /// The primary asset is withdrawn from the primary position, other assets from the
+    /// multi-asset position.
+    pub fn get_max_withdrawable(
+        env: Env,
+        user: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        liquidity_guard::LiquidityGuard::max_withdrawable(&env, &user, &asset)
+    }
+
+    // ==================== Account Data ====================
+
+    /// Risk summary of a user's primary and cross-asset positions in one call
+    ///
+    /// # Returns
+    /// * Collateral and debt value at registered prices, health factor (100 = at the
+    ///   liquidation threshold), remaining borrow capacity and the liquidation threshold
+    pub fn get_account_data(
+        env: Env,
+        user: Address,
+    ) -> Result<account_data::AccountData, ProtocolError> {
+        account_data::AccountView::get(&env, &user)
+    }
+
+    // ==================== Loyalty Fee Tiers ====================
+
+    /// Set fee discounts per loyalty tier (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `schedule` - One entry per tier (1-5) with flash loan, interest spread and AMM fee
+    ///   discounts in basis points of the fee; unlisted tiers get no discount
+    pub fn set_fee_tier_schedule(
+        env: Env,
+        admin: Address,
+        schedule: Vec<fees::TierDiscount>,
+    ) -> Result<(), ProtocolError> {
+        fees::FeeManager::set_schedule(&env, &admin, schedule)
+    }
+
+    /// Current fee discounts per loyalty tier
+    pub fn get_fee_tier_schedule(env: Env) -> Vec<fees::TierDiscount> {
+        fees::FeeManager::get_schedule(&env)
+    }
+
+    /// A user's loyalty tier, its discounts and the fees they pay after them
+    pub fn get_user_fee_schedule(env: Env, user: Address) -> fees::UserFeeSchedule {
+        fees::FeeManager::user_schedule(&env, &user)
+    }
+
+    // ==================== Referrals ====================
+
+    /// Set the share of a referee's protocol fees credited to their referrer (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `share_bps` - Share of each fee in basis points (0-10000)
+    pub fn set_referral_share(
+        env: Env,
+        admin: Address,
+        share_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        referral::Referrals::set_share(&env, &admin, share_bps)
+    }
+
+    /// Share of referees' fees credited to referrers, in basis points
+    pub fn get_referral_share(env: Env) -> i128 {
+        referral::Referrals::get_share(&env)
+    }
+
+    /// Register the address that referred `user` (once per user)
+    pub fn register_referrer(
+        env: Env,
+        user: Address,
+        referrer: Address,
+    ) -> Result<(), ProtocolError> {
+        referral::Referrals::register(&env, &user, &referrer)
+    }
+
+    /// Referrer registered by `user`, if any
+    pub fn get_referrer(env: Env, user: Address) -> Option<Address> {
+        referral::Referrals::get_referrer(&env, &user)
+    }
+
+    /// Referee count and rewards earned and claimed per asset for a referrer
+    pub fn get_referral_stats(env: Env, referrer: Address) -> referral::ReferrerStats {
+        referral::Referrals::get_stats(&env, &referrer)
+    }
+
+    /// Unclaimed referral rewards of a referrer in `asset`
+    pub fn get_referral_rewards(env: Env, referrer: Address, asset: Address) -> i128 {
+        referral::Referrals::claimable(&env, &referrer, &asset)
+    }
+
+    /// Pay out a referrer's unclaimed rewards in `asset`
+    ///
+    /// # Returns
+    /// * The amount transferred to the referrer
+    pub fn claim_referral_rewards(
+        env: Env,
+        referrer: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        referral::Referrals::claim(&env, &referrer, &asset)
+    }
+
+    // ==================== Liquidity Mining ====================
+
+    /// Set a market's reward emission schedule (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `asset` - Market whose suppliers and borrowers earn the emission
+    /// * `schedule` - Reward token, per-second rates for each side and the emission window.
+    ///   The reward token cannot change once the market has a schedule
+    pub fn set_emission_schedule(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        schedule: rewards::EmissionSchedule,
+    ) -> Result<(), ProtocolError> {
+        rewards::Rewards::set_schedule(&env, &admin, &asset, schedule)
+    }
+
+    /// Emission schedule, reward indexes and checkpointed totals of a market
+    pub fn get_reward_market(env: Env, asset: Address) -> Option<rewards::RewardMarket> {
+        rewards::Rewards::get_market(&env, &asset)
+    }
+
+    /// Unclaimed rewards of a user per reward token, including unsettled emissions
+    pub fn get_pending_rewards(env: Env, user: Address) -> Map<Address, i128> {
+        rewards::Rewards::pending(&env, &user)
+    }
+
+    /// Checkpoint a user's balances in a market so they start earning (positions are
+    /// otherwise checkpointed on their next write)
+    pub fn sync_rewards(env: Env, user: Address, asset: Address) {
+        rewards::Rewards::sync(&env, &user, &asset)
+    }
+
+    /// Settle and pay out all of a user's unclaimed rewards
+    ///
+    /// # Returns
+    /// * Amount paid per reward token
+    pub fn claim_rewards(env: Env, user: Address) -> Result<Map<Address, i128>, ProtocolError> {
+        rewards::Rewards::claim(&env, &user)
+    }
+
+    // ==================== Circuit Breakers ====================
+
+    /// Configure an asset's automatic circuit breaker (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `asset` - Asset whose borrows and/or withdrawals the breaker pauses
+    /// * `config` - Volatility index and utilization thresholds (0 = off, at least one set),
+    ///   cooldown and the operations paused while tripped
+    pub fn set_circuit_breaker(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        config: circuit_breaker::BreakerConfig,
+    ) -> Result<(), ProtocolError> {
+        circuit_breaker::CircuitBreakers::set_config(&env, &admin, &asset, config)
+    }
+
+    /// Remove an asset's circuit breaker (admin only)
+    pub fn clear_circuit_breaker(
+        env: Env,
+        admin: Address,
+        asset: Address,
+    ) -> Result<(), ProtocolError> {
+        circuit_breaker::CircuitBreakers::clear_config(&env, &admin, &asset)
+    }
+
+    /// Circuit breaker thresholds of an asset, if configured
+    pub fn get_circuit_breaker_config(
+        env: Env,
+        asset: Address,
+    ) -> Option<circuit_breaker::BreakerConfig> {
+        circuit_breaker::CircuitBreakers::get_config(&env, &asset)
+    }
+
+    /// Whether an asset's breaker is tripped at the current metrics, with the trigger,
+    /// cooldown end, volatility index and utilization
+    pub fn get_circuit_breaker_state(
+        env: Env,
+        asset: Address,
+    ) -> Option<circuit_breaker::BreakerState> {
+        circuit_breaker::CircuitBreakers::get_state(&env, &asset)
+    }
+
+    /// Re-evaluate an asset's breaker and persist a trip or resumption (callable by anyone)
+    pub fn poke_circuit_breaker(env: Env, asset: Address) -> Option<circuit_breaker::BreakerState> {
+        circuit_breaker::CircuitBreakers::refresh(&env, &asset)
+    }
+
+    // ==================== Outflow Limits ====================
+
+    /// Limit borrows plus withdrawals of an asset within a sliding window (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address (must match contract admin)
+    /// * `asset` - Asset whose outflows are limited
+    /// * `config` - Window length in seconds and the maximum outflow within it in basis
+    ///   points of the asset's liquidity
+    pub fn set_outflow_limit(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        config: outflow_limit::OutflowConfig,
+    ) -> Result<(), ProtocolError> {
+        outflow_limit::OutflowLimits::set_config(&env, &admin, &asset, config)
+    }
+
+    /// Remove an asset's outflow limit (admin only)
+    pub fn clear_outflow_limit(
+        env: Env,
+        admin: Address,
+        asset: Address,
+    ) -> Result<(), ProtocolError> {
+        outflow_limit::OutflowLimits::clear_config(&env, &admin, &asset)
+    }
+
+    /// Outflow limit of an asset, if configured
+    pub fn get_outflow_limit(env: Env, asset: Address) -> Option<outflow_limit::OutflowConfig> {
+        outflow_limit::OutflowLimits::get_config(&env, &asset)
+    }
+
+    /// Outflow used, the limit and the remaining capacity in an asset's current window
+    pub fn get_outflow_capacity(
+        env: Env,
+        asset: Address,
+    ) -> Option<outflow_limit::OutflowCapacity> {
+        outflow_limit::OutflowLimits::capacity(&env, &asset)
+    }
+
+    // ==================== Operators ====================
+
+    /// Approve an operator to act on the user's positions
+    ///
+    /// # Arguments
+    /// * `user` - Position owner (must authorize)
+    /// * `operator` - Address allowed to act for the user
+    /// * `permissions` - Bitmask of deposit (1), repay (2), borrow (4) and withdraw (8);
+    ///   replaces any earlier approval of the operator
+    pub fn approve_operator(
+        env: Env,
+        user: Address,
+        operator: Address,
+        permissions: u32,
+    ) -> Result<(), ProtocolError> {
+        operators::Operators::approve(&env, &user, &operator, permissions)
+    }
+
+    /// Revoke an operator's approval
+    pub fn revoke_operator(
+        env: Env,
+        user: Address,
+        operator: Address,
+    ) -> Result<(), ProtocolError> {
+        operators::Operators::revoke(&env, &user, &operator)
+    }
+
+    /// Permission bitmask the user granted an operator (0 = none)
+    pub fn get_operator_permissions(env: Env, user: Address, operator: Address) -> u32 {
+        operators::Operators::permissions(&env, &user, &operator)
+    }
+
+    /// Add collateral for `user` as their operator, paid from the operator's balance
+    ///
+    /// # Arguments
+    /// * `asset` - `None` for the primary position, otherwise the multi-asset position's asset
+    pub fn operator_deposit(
+        env: Env,
+        operator: Address,
+        user: Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let result = operators::Operators::deposit(&env, &operator, &user, asset, amount);
+        analytics::AnalyticsModule::track(&env, "operator_deposit", result)
+    }
+
+    /// Repay debt of `user` as their operator, paid from the operator's balance
+    pub fn operator_repay(
+        env: Env,
+        operator: Address,
+        user: Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let result = operators::Operators::repay(&env, &operator, &user, asset, amount);
+        analytics::AnalyticsModule::track(&env, "operator_repay", result)
+    }
+
+    /// Borrow against `user`'s position as their operator; the funds go to the user
+    pub fn operator_borrow(
+        env: Env,
+        operator: Address,
+        user: Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let result = operators::Operators::borrow(&env, &operator, &user, asset, amount);
+        analytics::AnalyticsModule::track(&env, "operator_borrow", result)
+    }
+
+    /// Withdraw `user`'s collateral as their operator; the funds go to the user
+    pub fn operator_withdraw(
+        env: Env,
+        operator: Address,
+        user: Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let result = operators::Operators::withdraw(&env, &operator, &user, asset, amount);
+        analytics::AnalyticsModule::track(&env, "operator_withdraw", result)
+    }
+
+    // ==================== Permits ====================
+
+    /// Deposit collateral from `user`'s balance under a permit they signed; any relayer
+    /// may submit it and pay the fees
+    pub fn permit_deposit(
+        env: Env,
+        user: Address,
+        permit: permit::Permit,
+    ) -> Result<(), ProtocolError> {
+        let result = permit::Permits::deposit(&env, &user, permit);
+        analytics::AnalyticsModule::track(&env, "permit_deposit", result)
+    }
+
+    /// Repay `user`'s debt from their balance under a permit they signed
+    pub fn permit_repay(
+        env: Env,
+        user: Address,
+        permit: permit::Permit,
+    ) -> Result<(), ProtocolError> {
+        let result = permit::Permits::repay(&env, &user, permit);
+        analytics::AnalyticsModule::track(&env, "permit_repay", result)
+    }
+
+    /// Nonce the user's next permit must carry
+    pub fn get_permit_nonce(env: Env, user: Address) -> u64 {
+        permit::Permits::nonce(&env, &user)
+    }
+
+    // ==================== Batch ====================
+
+    /// Execute deposit/borrow/repay/withdraw actions for `user` atomically in order,
+    /// returning the position balances after each action
+    pub fn batch_execute(
+        env: Env,
+        user: Address,
+        actions: Vec<batch::ProtocolAction>,
+    ) -> Result<Vec<batch::ActionResult>, ProtocolError> {
+        let result = batch::Batch::execute(&env, &user, actions);
+        analytics::AnalyticsModule::track(&env, "batch_execute", result)
+    }
+
+    // ==================== Collateral Swap ====================
+
+    /// Rotate `amount` of `from_asset` collateral into `to_asset` through the AMM registry,
+    /// receiving at least `min_out`; the position must stay healthy
+    pub fn swap_collateral(
+        env: Env,
+        user: Address,
+        from_asset: Address,
+        to_asset: Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<amm::SwapResult, ProtocolError> {
+        let result = collateral_swap::CollateralSwap::swap(
+            &env,
+            &user,
+            &from_asset,
+            &to_asset,
+            amount,
+            min_out,
+        );
+        analytics::AnalyticsModule::track(&env, "swap_collateral", result)
+    }
+
+    // ==================== Leverage ====================
+
+    /// Deposit `initial_amount` of `collateral_asset` and loop borrow → swap → collateral
+    /// through the AMM registry until collateral reaches `target_leverage` (bps of the
+    /// initial amount, 10000 = 1x); swaps accept at most `max_slippage` bps off the oracle
+    /// price
+    pub fn open_leveraged_position(
+        env: Env,
+        user: Address,
+        collateral_asset: Address,
+        debt_asset: Address,
+        initial_amount: i128,
+        target_leverage: i128,
+        max_slippage: i128,
+    ) -> Result<leverage::LeverageResult, ProtocolError> {
+        let result = leverage::Leverage::open(
+            &env,
+            &user,
+            &collateral_asset,
+            &debt_asset,
+            initial_amount,
+            target_leverage,
+            max_slippage,
+        );
+        analytics::AnalyticsModule::track(&env, "open_leveraged_position", result)
+    }
+
+    // ==================== Deleverage ====================
+
+    /// Sell primary collateral through the registered AMM pair and repay debt until the
+    /// position's health factor reaches `target_health_factor` (percent, 100 = at the
+    /// liquidation threshold)
+    pub fn deleverage_to_health(
+        env: Env,
+        user: Address,
+        target_health_factor: i128,
+    ) -> Result<deleverage::DeleverageResult, ProtocolError> {
+        let result = deleverage::Deleverage::to_health(&env, &user, target_health_factor);
+        analytics::AnalyticsModule::track(&env, "deleverage_to_health", result)
+    }
+
+    // ==================== Internal Pools ====================
+
+    /// Provide liquidity to the protocol's internal constant-product pool for a pair,
+    /// which backs swaps while no external AMM is registered for it
+    ///
+    /// # Arguments
+    /// * `provider` - Liquidity provider
+    /// * `asset_a`, `asset_b` - The pair
+    /// * `amount_a`, `amount_b` - Maximum amounts to deposit; only the amounts matching
+    ///   the pool's ratio are taken
+    ///
+    /// # Returns
+    /// Pool shares minted to the provider
+    pub fn add_internal_liquidity(
+        env: Env,
+        provider: Address,
+        asset_a: Address,
+        asset_b: Address,
+        amount_a: i128,
+        amount_b: i128,
+    ) -> Result<i128, ProtocolError> {
+        let result = internal_pool::InternalPools::add_liquidity(
+            &env, &provider, &asset_a, &asset_b, amount_a, amount_b,
+        );
+        analytics::AnalyticsModule::track(&env, "add_internal_liquidity", result)
+    }
+
+    /// Burn internal pool shares for their part of both reserves
+    ///
+    /// # Returns
+    /// `(amount_a, amount_b)` paid out, in the order the assets were passed
+    pub fn remove_internal_liquidity(
+        env: Env,
+        provider: Address,
+        asset_a: Address,
+        asset_b: Address,
+        shares: i128,
+    ) -> Result<(i128, i128), ProtocolError> {
+        let result = internal_pool::InternalPools::remove_liquidity(
+            &env, &provider, &asset_a, &asset_b, shares,
+        );
+        analytics::AnalyticsModule::track(&env, "remove_internal_liquidity", result)
+    }
+
+    /// Reserves and shares of a pair's internal pool, if it holds liquidity
+    pub fn get_internal_pool(
+        env: Env,
+        asset_a: Address,
+        asset_b: Address,
+    ) -> Option<internal_pool::InternalPool> {
+        internal_pool::InternalPools::get(&env, &asset_a, &asset_b)
+    }
+
+    /// Internal pool shares held by `provider`
+    pub fn get_internal_pool_shares(
+        env: Env,
+        asset_a: Address,
+        asset_b: Address,
+        provider: Address,
+    ) -> i128 {
+        internal_pool::InternalPools::shares(&env, &asset_a, &asset_b, &provider)
+    }
+
+    // ==================== Position Transfer ====================
+
+    /// Offer the caller's whole position to another address, which must accept it
+    ///
+    /// # Arguments
+    /// * `from` - Position owner (must authorize)
+    /// * `to` - Recipient; replaces any pending offer
+    pub fn transfer_position(env: Env, from: Address, to: Address) -> Result<(), ProtocolError> {
+        let result = position_transfer::PositionTransfers::offer(&env, &from, &to).map(|_| ());
+        analytics::AnalyticsModule::track(&env, "transfer_position", result)
+    }
+
+    /// Accept a pending position transfer. Both users' limits are checked and the position
+    /// must be healthy under its new owner.
+    pub fn accept_position_transfer(
+        env: Env,
+        to: Address,
+        from: Address,
+    ) -> Result<(), ProtocolError> {
+        let result = position_transfer::PositionTransfers::accept(&env, &to, &from);
+        analytics::AnalyticsModule::track(&env, "accept_position_transfer", result)
+    }
+
+    /// Withdraw a pending position transfer offer
+    pub fn cancel_position_transfer(env: Env, from: Address) -> Result<(), ProtocolError> {
+        position_transfer::PositionTransfers::cancel(&env, &from)
+    }
+
+    /// The pending transfer offered by `from`, if any
+    pub fn get_pending_position_transfer(
+        env: Env,
+        from: Address,
+    ) -> Option<position_transfer::PendingPositionTransfer> {
+        position_transfer::PositionTransfers::get_pending(&env, &from)
+    }
+
+    // ==================== Credit Delegation ====================
+
+    /// Let `borrower` draw up to `limit` of `asset` against the delegator's multi-asset
+    /// collateral; re-approving updates the limit and reinstates a revoked line
+    pub fn approve_credit_line(
+        env: Env,
+        delegator: Address,
+        borrower: Address,
+        asset: Address,
+        limit: i128,
+    ) -> Result<(), ProtocolError> {
+        credit_delegation::CreditDelegation::approve(&env, &delegator, &borrower, &asset, limit)
+            .map(|_| ())
+    }
+
+    /// Block new draws on a credit line; debt already drawn stays outstanding
+    pub fn revoke_credit_line(
+        env: Env,
+        delegator: Address,
+        borrower: Address,
+        asset: Address,
+    ) -> Result<(), ProtocolError> {
+        credit_delegation::CreditDelegation::revoke(&env, &delegator, &borrower, &asset)
+    }
+
+    /// Borrow on a credit line. The debt goes on the delegator's position, which must stay
+    /// healthy; the funds go to the borrower.
+    ///
+    /// # Returns
+    /// * The amount drawn and not yet repaid on the line
+    pub fn draw_credit_line(
+        env: Env,
+        borrower: Address,
+        delegator: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let result =
+            credit_delegation::CreditDelegation::draw(&env, &borrower, &delegator, &asset, amount)
+                .map(|line| line.drawn);
+        analytics::AnalyticsModule::track(&env, "draw_credit_line", result)
+    }
+
+    /// Repay debt drawn on a credit line from the borrower's balance
+    ///
+    /// # Returns
+    /// * The amount repaid
+    pub fn repay_credit_line(
+        env: Env,
+        borrower: Address,
+        delegator: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let result =
+            credit_delegation::CreditDelegation::repay(&env, &borrower, &delegator, &asset, amount);
+        analytics::AnalyticsModule::track(&env, "repay_credit_line", result)
+    }
+
+    /// A credit line's limit, utilization and status
+    pub fn get_credit_line(
+        env: Env,
+        delegator: Address,
+        borrower: Address,
+        asset: Address,
+    ) -> Option<credit_delegation::CreditLine> {
+        credit_delegation::CreditDelegation::get(&env, &delegator, &borrower, &asset)
+    }
+
+    // ==================== Vesting ====================
+
+    /// Create a linear vesting stream (admin only)
+    ///
+    /// # Arguments
+    /// * `schedule` - Recipient, token, total and the start/cliff/end timestamps
+    /// * `from_reserves` - Fund from the token's protocol reserves instead of the admin
+    ///
+    /// # Returns
+    /// * The stream id
+    pub fn create_vesting_stream(
+        env: Env,
+        admin: Address,
+        schedule: vesting::VestingSchedule,
+        from_reserves: bool,
+    ) -> Result<u64, ProtocolError> {
+        vesting::Vesting::create(&env, &admin, schedule, from_reserves)
+    }
+
+    /// Claim everything vested and not yet claimed from a stream
+    pub fn claim_vested(env: Env, recipient: Address, id: u64) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        let result = vesting::Vesting::claim(&env, &recipient, id);
+        analytics::AnalyticsModule::track(&env, "claim_vested", result)
+    }
+
+    pub fn get_vesting_stream(env: Env, id: u64) -> Option<vesting::VestingStream> {
+        vesting::Vesting::get(&env, id)
+    }
+
+    /// Vested amount of a stream not yet claimed
+    pub fn get_claimable_vested(env: Env, id: u64) -> i128 {
+        vesting::Vesting::claimable(&env, id)
+    }
+
+    /// Ids of the streams paying `recipient`
+    pub fn get_vesting_streams(env: Env, recipient: Address) -> Vec<u64> {
+        vesting::Vesting::get_ids(&env, &recipient)
+    }
+
+    // ==================== Safety Module ====================
+
+    /// Set the safety module's unstake cooldown, fee share and slash limit (admin only)
+    pub fn set_safety_module_config(
+        env: Env,
+        admin: Address,
+        config: safety_module::SafetyModuleConfig,
+    ) -> Result<(), ProtocolError> {
+        safety_module::SafetyModule::set_config(&env, &admin, config)
+    }
+
+    pub fn get_safety_module_config(env: Env) -> safety_module::SafetyModuleConfig {
+        safety_module::SafetyModule::get_config(&env)
+    }
+
+    /// Stake a registered token into its backstop pool
+    ///
+    /// # Returns
+    /// * Shares minted
+    pub fn safety_stake(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        let result = safety_module::SafetyModule::stake(&env, &user, &asset, amount);
+        analytics::AnalyticsModule::track(&env, "safety_stake", result)
+    }
+
+    /// Start the cooldown for redeeming `shares`
+    ///
+    /// # Returns
+    /// * Earliest redemption timestamp
+    pub fn safety_request_unstake(
+        env: Env,
+        user: Address,
+        asset: Address,
+        shares: i128,
+    ) -> Result<u64, ProtocolError> {
+        safety_module::SafetyModule::request_unstake(&env, &user, &asset, shares)
+    }
+
+    /// Redeem requested shares once their cooldown has passed
+    ///
+    /// # Returns
+    /// * Tokens paid out
+    pub fn safety_unstake(env: Env, user: Address, asset: Address) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        let result = safety_module::SafetyModule::unstake(&env, &user, &asset);
+        analytics::AnalyticsModule::track(&env, "safety_unstake", result)
+    }
+
+    /// Stake and shares of an asset's backstop pool
+    pub fn get_safety_pool(env: Env, asset: Address) -> safety_module::SafetyPool {
+        safety_module::SafetyModule::get_pool(&env, &asset)
+    }
+
+    /// Current token value of a user's staked shares
+    pub fn get_safety_stake(env: Env, asset: Address, user: Address) -> i128 {
+        safety_module::SafetyModule::staked_balance(&env, &asset, &user)
+    }
+
+    pub fn get_safety_unstake_request(
+        env: Env,
+        asset: Address,
+        user: Address,
+    ) -> Option<safety_module::UnstakeRequest> {
+        safety_module::SafetyModule::get_request(&env, &asset, &user)
+    }
+
+    // ==================== Upgrades ====================
+
+    /// Replace the contract code with an uploaded WASM (admin only). A
+    /// `contract_upgrade` event carries the pre-upgrade state checksum.
+    ///
+    /// # Returns
+    /// * The pre-upgrade state checksum
+    pub fn upgrade(
+        env: Env,
+        admin: Address,
+        wasm_hash: BytesN<32>,
+    ) -> Result<BytesN<32>, ProtocolError> {
+        upgrade::Upgrade::upgrade(&env, &admin, wasm_hash)
+    }
+
+    /// Run pending one-time storage migrations after an upgrade (admin only)
+    ///
+    /// # Returns
+    /// * The storage version reached
+    pub fn migrate(env: Env, admin: Address) -> Result<u32, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        upgrade::Upgrade::migrate(&env, &admin)
+    }
+
+    /// Storage schema version currently recorded
+    pub fn get_contract_version(env: Env) -> u32 {
+        upgrade::Upgrade::version(&env)
+    }
+
+    /// Checksum of the core configuration and state, as published before upgrades
+    pub fn get_state_checksum(env: Env) -> BytesN<32> {
+        upgrade::Upgrade::state_checksum(&env)
+    }
+
+    // ==================== Storage Migration ====================
+
+    /// Rewrite the listed users' primary positions in the latest storage layout (admin
+    /// only, at most 50 per call). Entries are otherwise upgraded when first read.
+    ///
+    /// # Returns
+    /// * Number of entries rewritten
+    pub fn migrate_positions(
+        env: Env,
+        admin: Address,
+        users: Vec<Address>,
+    ) -> Result<u32, ProtocolError> {
+        migration::Migrations::migrate_positions(&env, &admin, users)
+    }
+
+    /// Stored entries upgraded to a newer layout so far
+    pub fn get_migrated_record_count(env: Env) -> u32 {
+        migration::Migrations::migrated_count(&env)
+    }
+
+    // ==================== Operation Gate ====================
+
+    /// Whether `operation` (`deposit`, `borrow`, `repay`, `withdraw`, `liquidate` or
+    /// `flash_loan`) is open for `asset` (the primary asset when omitted), checking the
+    /// emergency status, pause switches, asset pause flags and circuit breaker. User
+    /// limits are not included.
+    pub fn get_operation_gate_status(
+        env: Env,
+        operation: Symbol,
+        asset: Option<Address>,
+    ) -> Result<gate::OperationGateStatus, ProtocolError> {
+        gate::OperationGate::status(&env, operation, asset)
+    }
+
+    /// Pause individual operations on one asset (admin or emergency manager)
+    pub fn set_asset_pause_flags(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        flags: gate::AssetPauseFlags,
+    ) -> Result<(), ProtocolError> {
+        gate::OperationGate::set_asset_pause_flags(&env, &caller, &asset, flags)
+    }
+
+    pub fn get_asset_pause_flags(env: Env, asset: Address) -> gate::AssetPauseFlags {
+        gate::OperationGate::get_asset_pause_flags(&env, &asset)
+    }
+
+    // ==================== Liquidation Grace ====================
+
+    /// Set the delay between a position being flagged liquidatable and its liquidation,
+    /// and the health factor below which no grace is given (admin only)
+    pub fn set_liquidation_grace_config(
+        env: Env,
+        admin: Address,
+        config: liquidation_grace::LiquidationGraceConfig,
+    ) -> Result<(), ProtocolError> {
+        liquidation_grace::LiquidationGrace::set_config(&env, &admin, config)
+    }
+
+    pub fn get_liquidation_grace_config(env: Env) -> liquidation_grace::LiquidationGraceConfig {
+        liquidation_grace::LiquidationGrace::get_config(&env)
+    }
+
+    /// Flag a liquidatable position, starting its grace window. Callable by anyone;
+    /// returns the time from which the position may be liquidated.
+    pub fn flag_liquidatable(env: Env, user: Address) -> Result<u64, ProtocolError> {
+        liquidation_grace::LiquidationGrace::flag(&env, &user)
+    }
+
+    /// When the user's position was flagged liquidatable, if it still is
+    pub fn get_liquidation_flagged_at(env: Env, user: Address) -> Option<u64> {
+        liquidation_grace::LiquidationGrace::flagged_at(&env, &user)
+    }
+
+    // ==================== Attestors ====================
+
+    /// Register a third-party attestor allowed to verify up to `max_verifications` users,
+    /// or update its cap (admin only)
+    pub fn set_attestor(
+        env: Env,
+        admin: Address,
+        attestor: Address,
+        max_verifications: u32,
+    ) -> Result<(), ProtocolError> {
+        attestation::Attestations::set_attestor(&env, &admin, &attestor, max_verifications)
+    }
+
+    /// Stop an attestor from setting verification statuses (admin only)
+    pub fn revoke_attestor(
+        env: Env,
+        admin: Address,
+        attestor: Address,
+    ) -> Result<(), ProtocolError> {
+        attestation::Attestations::revoke_attestor(&env, &admin, &attestor)
+    }
+
+    /// Set a standard user's verification status as a registered attestor
+    pub fn attest_user(
+        env: Env,
+        attestor: Address,
+        user: Address,
+        status: VerificationStatus,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        attestation::Attestations::attest(&env, &attestor, &user, status)
+    }
+
+    pub fn get_attestor(env: Env, attestor: Address) -> Option<attestation::Attestor> {
+        attestation::Attestations::get_attestor(&env, &attestor)
+    }
+
+    pub fn get_attestors(env: Env) -> Vec<Address> {
+        attestation::Attestations::get_attestors(&env)
+    }
+
+    /// The latest attestation made for a user, for audit
+    pub fn get_user_attestation(env: Env, user: Address) -> Option<attestation::AttestationRecord> {
+        attestation::Attestations::get_record(&env, &user)
+    }
+
+    // ==================== Compliance ====================
+
+    /// Add or remove a user from the deny list (manager role or above)
+    pub fn set_deny_listed(
+        env: Env,
+        caller: Address,
+        user: Address,
+        denied: bool,
+    ) -> Result<(), ProtocolError> {
+        compliance::Compliance::set_deny_listed(&env, &caller, &user, denied)
+    }
+
+    /// Tag a user with a jurisdiction or clear it with `None` (manager role or above)
+    pub fn set_user_jurisdiction(
+        env: Env,
+        caller: Address,
+        user: Address,
+        jurisdiction: Option<Symbol>,
+    ) -> Result<(), ProtocolError> {
+        compliance::Compliance::set_jurisdiction(&env, &caller, &user, jurisdiction)
+    }
+
+    /// Cap `operation` at `max_amount` per call for users tagged `jurisdiction`, or for
+    /// everyone when it is `None`; a cap of 0 blocks the operation (manager role or above).
+    /// Returns the rule id.
+    pub fn add_compliance_rule(
+        env: Env,
+        caller: Address,
+        jurisdiction: Option<Symbol>,
+        operation: Symbol,
+        max_amount: i128,
+    ) -> Result<u32, ProtocolError> {
+        compliance::Compliance::add_rule(&env, &caller, jurisdiction, operation, max_amount)
+    }
+
+    pub fn remove_compliance_rule(env: Env, caller: Address, id: u32) -> Result<(), ProtocolError> {
+        compliance::Compliance::remove_rule(&env, &caller, id)
+    }
+
+    pub fn get_compliance_rules(env: Env) -> Vec<compliance::ComplianceRule> {
+        compliance::Compliance::get_rules(&env)
+    }
+
+    pub fn is_deny_listed(env: Env, user: Address) -> bool {
+        compliance::Compliance::is_deny_listed(&env, &user)
+    }
+
+    pub fn get_user_jurisdiction(env: Env, user: Address) -> Option<Symbol> {
+        compliance::Compliance::get_jurisdiction(&env, &user)
+    }
+
+    // ==================== Subscribers ====================
+
+    /// Register a contract to receive `on_event(record)` for the event types in `topics`,
+    /// or for every event when `topics` is empty. Returns the subscriber id.
+    pub fn register_subscriber(
+        env: Env,
+        owner: Address,
+        target: Address,
+        topics: Vec<Symbol>,
+    ) -> Result<u32, ProtocolError> {
+        subscribers::Subscribers::register(&env, &owner, &target, topics)
+    }
+
+    /// Remove a subscriber (admin, or the subscriber's owner)
+    pub fn remove_subscriber(env: Env, caller: Address, id: u32) -> Result<(), ProtocolError> {
+        subscribers::Subscribers::remove(&env, &caller, id)
+    }
+
+    pub fn get_subscribers(env: Env) -> Vec<subscribers::Subscriber> {
+        subscribers::Subscribers::get_all(&env)
+    }
+
+    // ==================== Keeper Jobs ====================
+
+    /// Register a maintenance job (admin only): `kind` is `accrue_markets`, `prune_events`,
+    /// `refresh_twap` or `apply_params`; `bounty` is paid in the primary asset from its
+    /// reserves to whoever runs the job once `cooldown` seconds have passed. Returns the
+    /// job id.
+    pub fn register_keeper_job(
+        env: Env,
+        admin: Address,
+        kind: Symbol,
+        asset: Option<Address>,
+        cooldown: u64,
+        bounty: i128,
+    ) -> Result<u32, ProtocolError> {
+        keeper::Keepers::register_job(&env, &admin, kind, asset, cooldown, bounty)
+    }
+
+    pub fn remove_keeper_job(env: Env, admin: Address, job_id: u32) -> Result<(), ProtocolError> {
+        keeper::Keepers::remove_job(&env, &admin, job_id)
+    }
+
+    /// Seconds a queued emergency parameter update waits before `apply_params` jobs may
+    /// apply it (admin only). Sets the `min_delay` of the emergency parameter timelock,
+    /// keeping its expiry.
+    pub fn set_keeper_param_timelock(
+        env: Env,
+        admin: Address,
+        timelock: u64,
+    ) -> Result<(), ProtocolError> {
+        let current = EmergencyStorage::get_timelock(&env);
+        EmergencyManager::set_param_timelock(
+            &env,
+            &admin,
+            ParamTimelock {
+                min_delay: timelock,
+                ..current
+            },
+        )
+    }
+
+    /// `min_delay` of the emergency parameter timelock
+    pub fn get_keeper_param_timelock(env: Env) -> u64 {
+        EmergencyStorage::get_timelock(&env).min_delay
+    }
+
+    /// Run a due keeper job; returns the bounty paid to `keeper`
+    pub fn execute_keeper_job(
+        env: Env,
+        keeper: Address,
+        job_id: u32,
+    ) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        keeper::Keepers::execute(&env, &keeper, job_id)
+    }
+
+    pub fn get_keeper_job(env: Env, job_id: u32) -> Option<keeper::KeeperJob> {
+        keeper::Keepers::get_job(&env, job_id)
+    }
+
+    pub fn get_keeper_jobs(env: Env) -> Vec<keeper::KeeperJob> {
+        keeper::Keepers::get_jobs(&env)
+    }
+
+    // ==================== Previews ====================
+
+    /// Simulate a primary borrow of `amount` for `user`: the tokens received, the
+    /// origination fee and the resulting debt, collateral ratio and health factor. Fails
+    /// with the error the borrow would fail with. Nothing is written.
+    pub fn preview_borrow(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<preview::BorrowPreview, ProtocolError> {
+        preview::Previews::preview_borrow(&env, &user, amount)
+    }
+
+    /// Simulate a primary withdrawal of `amount`: the tokens received after the
+    /// withdrawal fee and the resulting position. Nothing is written.
+    pub fn preview_withdraw(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<preview::WithdrawPreview, ProtocolError> {
+        preview::Previews::preview_withdraw(&env, &user, amount)
+    }
+
+    /// Simulate liquidating `user` with a repayment of up to `amount`: the debt repaid,
+    /// collateral seized, the liquidator's share and the borrower's resulting position.
+    /// Nothing is written.
+    pub fn preview_liquidation(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<preview::LiquidationPreview, ProtocolError> {
+        preview::Previews::preview_liquidation(&env, &user, amount)
+    }
+
+    // ==================== Stable Rates ====================
+
+    /// Enable stable borrowing and set its premium over the variable rate and the
+    /// rebalance threshold (admin only)
+    pub fn set_stable_rate_config(
+        env: Env,
+        admin: Address,
+        config: stable_rate::StableRateConfig,
+    ) -> Result<(), ProtocolError> {
+        stable_rate::StableRates::set_config(&env, &admin, config)
+    }
+
+    pub fn get_stable_rate_config(env: Env) -> stable_rate::StableRateConfig {
+        stable_rate::StableRateStorage::get_config(&env)
+    }
+
+    /// Rate a stable borrow would lock now (scaled by 1e8)
+    pub fn get_stable_borrow_rate(env: Env) -> i128 {
+        stable_rate::StableRates::current_rate(&env)
+    }
+
+    /// Borrow from the primary position at a stable rate; returns the locked rate, blended
+    /// with any stable debt already held
+    pub fn borrow_stable_addr(
+        env: Env,
+        borrower: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        stable_rate::StableRates::borrow(&env, &borrower, amount)
+    }
+
+    /// Move all primary debt between the stable and variable rate; returns the new mode
+    pub fn swap_borrow_rate_mode(
+        env: Env,
+        user: Address,
+    ) -> Result<stable_rate::BorrowRateMode, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        stable_rate::StableRates::swap_mode(&env, &user)
+    }
+
+    /// Reset `user`'s locked rate to the current stable rate once market rates have moved
+    /// past the rebalance threshold; returns the new rate
+    pub fn rebalance_stable_rate(
+        env: Env,
+        caller: Address,
+        user: Address,
+    ) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        stable_rate::StableRates::rebalance(&env, &caller, &user)
+    }
+
+    pub fn get_borrow_rate_mode(env: Env, user: Address) -> stable_rate::BorrowRateMode {
+        stable_rate::StableRates::mode(&env, &user)
+    }
+
+    pub fn get_stable_debt(env: Env, user: Address) -> Option<stable_rate::StableDebt> {
+        stable_rate::StableRateStorage::get_debt(&env, &user)
+    }
+
+    // ==================== Error Stats ====================
+
+    /// Failure counts per entrypoint and `ProtocolError` code over a recent window
+    ///
+    /// Only failures handled on a committing path are counted; failed calls are
+    /// reverted and reported through `entrypoint_failed` events instead
+    ///
+    /// # Arguments
+    /// * `window` - Lookback in seconds (hourly granularity, at most 7 days)
+    ///
+    /// # Returns
+    /// One entry per (entrypoint, code) pair; pairs beyond the per-hour cardinality
+    /// bound are folded into an `overflow` entry
+    pub fn get_error_stats(
+        env: Env,
+        window: u64,
+    ) -> Result<Vec<analytics::ErrorStat>, ProtocolError> {
+        analytics::AnalyticsModule::get_error_stats(&env, window)
+    }
+
+    // ==================== Read Interface ====================
+
+    /// Answer many pure-read view queries in one call, in request order
+    ///
+    /// # Arguments
+    /// * `queries` - Up to 32 view queries
+    ///
+    /// # Returns
+    /// * One `ReadResult` per query; nothing is written to storage
+    pub fn batch_read(
+        env: Env,
+        queries: Vec<read_api::ReadQuery>,
+    ) -> Result<Vec<read_api::ReadResult>, ProtocolError> {
+        read_api::ReadApi::batch_read(&env, queries)
+    }
+
+    // ==================== Error Introspection ====================
+
+    /// Describe an error code returned by the contract
+    ///
+    /// # Returns
+    /// * Owning module and short name, or `None` for unknown codes
+    pub fn describe_error(env: Env, code: u32) -> Option<errors::ErrorDescription> {
+        errors::ErrorCatalog::describe(&env, code)
+    }
 }
@@ -1,12 +1,32 @@
 //! Liquidation module for StellarLend protocol
-//! Handles liquidation functionality and related operations
+//! Handles liquidation functionality and related operations. The liquidation incentive
+//! is the flat `RiskConfig` value unless the admin sets an incentive curve: the bonus is
+//! then interpolated from the position's health factor, so deeper shortfalls pay more,
+//! and scaled down by collateral size tiers so large liquidations are not over-rewarded.
 
 use crate::analytics::AnalyticsModule;
+use crate::auction::{AuctionFill, LiquidationAuctions};
+use crate::gate::OperationGate;
+use crate::liquidation_grace::LiquidationGrace;
+use crate::oracle::Oracle;
+use crate::rebate::RebatePool;
+use crate::risk_matrix::CollateralMatrix;
+use crate::safety_rewards::SafetyRewards;
+use crate::stable_rate::StableRates;
 use crate::{
-    EmergencyManager, OperationKind, ProtocolConfig, ProtocolError, ProtocolEvent, ReentrancyGuard,
-    RiskConfigStorage, StateHelper,
+    InterestRateManager, InterestRateStorage, OperationKind, Position, ProtocolConfig,
+    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol, Vec};
+
+/// Basis point scale (10000 = 1x)
+const BPS_SCALE: i128 = 10000;
+
+/// Highest incentive a curve point may grant (50%, scaled by 1e8)
+const MAX_INCENTIVE: i128 = 50000000;
+
+/// Maximum points on the incentive curve and maximum size tiers
+const MAX_CURVE_POINTS: u32 = 8;
 
 /// Liquidation-specific errors
 #[contracterror]
@@ -19,6 +39,7 @@ pub enum LiquidationError {
     PositionNotFound = 5004,
     NotEligibleForLiquidation = 5005,
     InsufficientLiquidationAmount = 5006,
+    GracePeriod = 5007,
 }
 
 impl From<LiquidationError> for ProtocolError {
@@ -30,6 +51,7 @@ impl From<LiquidationError> for ProtocolError {
             LiquidationError::PositionNotFound => ProtocolError::PositionNotFound,
             LiquidationError::NotEligibleForLiquidation => ProtocolError::NotEligibleForLiquidation,
             LiquidationError::InsufficientLiquidationAmount => ProtocolError::InvalidAmount,
+            LiquidationError::GracePeriod => ProtocolError::NotEligibleForLiquidation,
         }
     }
 }
@@ -83,6 +105,59 @@ impl LiquidationResult {
     }
 }
 
+/// Incentive paid at a health factor
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct IncentivePoint {
+    /// Health factor in percent (100 = at the liquidation threshold)
+    pub health_factor: i128,
+    /// Liquidation incentive at this health factor (scaled by 1e8)
+    pub incentive: i128,
+}
+
+/// Incentive scaling for positions holding at least `min_collateral`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct IncentiveSizeTier {
+    pub min_collateral: i128,
+    /// Share of the curve incentive paid (10000 = all of it)
+    pub scale_bps: i128,
+}
+
+/// Liquidation incentive curve
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct IncentiveCurve {
+    /// Points in ascending health factor; incentives are interpolated between points and
+    /// held flat beyond the ends. Empty uses the flat `RiskConfig` incentive.
+    pub points: Vec<IncentivePoint>,
+    /// Tiers in ascending `min_collateral`; the highest tier a position reaches applies
+    pub size_tiers: Vec<IncentiveSizeTier>,
+}
+
+/// Storage helper for the incentive curve
+pub struct IncentiveCurveStorage;
+
+impl IncentiveCurveStorage {
+    fn curve_key(env: &Env) -> Symbol {
+        Symbol::new(env, "liq_incentive_curve")
+    }
+
+    pub fn get(env: &Env) -> IncentiveCurve {
+        env.storage()
+            .instance()
+            .get(&Self::curve_key(env))
+            .unwrap_or_else(|| IncentiveCurve {
+                points: Vec::new(env),
+                size_tiers: Vec::new(env),
+            })
+    }
+
+    pub fn save(env: &Env, curve: &IncentiveCurve) {
+        env.storage().instance().set(&Self::curve_key(env), curve);
+    }
+}
+
 /// Liquidation module implementation
 pub struct LiquidationModule;
 
@@ -90,118 +165,441 @@ impl LiquidationModule {
     /// Liquidate an undercollateralized position
     pub fn liquidate(
         env: &Env,
-        liquidator: &String,
-        user: &String,
+        liquidator: &Address,
+        user: &Address,
         amount: i128,
         min_out: i128,
     ) -> Result<LiquidationResult, ProtocolError> {
         ReentrancyGuard::enter(env)?;
         let result = (|| -> Result<LiquidationResult, ProtocolError> {
             // Input validation
-            if liquidator.is_empty() || user.is_empty() {
-                return Err(LiquidationError::InvalidAddress.into());
-            }
             if amount <= 0 {
                 return Err(LiquidationError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Liquidate)?;
+            Self::ensure_allowed(env, liquidator, amount)?;
+            Self::execute(env, liquidator, user, amount, min_out, false)
+        })();
 
-            // Check if liquidation is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_liquidate {
-                return Err(LiquidationError::ProtocolPaused.into());
-            }
+        ReentrancyGuard::exit(env);
+        result
+    }
 
-            let liquidator_addr = crate::AddressHelper::require_valid_address(env, liquidator)?;
-            let user_addr = crate::AddressHelper::require_valid_address(env, user)?;
-
-            // Load user position
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => return Err(LiquidationError::PositionNotFound.into()),
-            };
-
-            // Check if position is eligible for liquidation
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let collateral_ratio = if position.debt > 0 {
-                (position.collateral * 100) / position.debt
-            } else {
-                0
-            };
-
-            if collateral_ratio >= min_ratio {
-                return Err(LiquidationError::NotEligibleForLiquidation.into());
+    /// Liquidate an undercollateralized position, repaying the debt from the liquidator's
+    /// own supply claim (sToken balance) in the same asset instead of external tokens.
+    /// The burned claim cancels the borrower's debt and the seized collateral is credited
+    /// to the liquidator's supply position.
+    pub fn liquidate_with_supply(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        liquidator.require_auth();
+        ReentrancyGuard::enter(env)?;
+        let result = (|| -> Result<LiquidationResult, ProtocolError> {
+            if amount <= 0 {
+                return Err(LiquidationError::InvalidAmount.into());
             }
-
-            // Calculate liquidation amount
-            let max_liquidation = (position.debt * risk_config.close_factor) / 100000000;
-            let liquidation_amount = if amount > max_liquidation {
-                max_liquidation
-            } else {
-                amount
-            };
-
-            // Calculate collateral to seize
-            let collateral_seized =
-                (liquidation_amount * (100000000 + risk_config.liquidation_incentive)) / 100000000;
-
-            // Slippage protection: ensure the liquidator receives at least `min_out` collateral
-            if min_out > 0 && collateral_seized < min_out {
-                // Emit an analytics/event record so indexers can surface the slippage protection trigger
-                // Use the EventTracker available from the main crate to record structured analytics
-                soroban_sdk::Env::events(env); // no-op to satisfy borrow checker usage
-                crate::EventTracker::record(
-                    env,
-                    soroban_sdk::Symbol::new(env, "slippage_protection"),
-                    {
-                        let mut topics = soroban_sdk::Vec::new(env);
-                        topics.push_back(soroban_sdk::Symbol::new(env, "liquidator"));
-                        topics.push_back(soroban_sdk::Symbol::new(env, "user"));
-                        topics
-                    },
-                    Some(liquidator_addr.clone()),
-                    Some(user_addr.clone()),
-                    collateral_seized,
-                );
-
-                return Err(ProtocolError::SlippageProtectionTriggered);
+            if liquidator == user {
+                return Err(ProtocolError::InvalidOperation);
             }
+            Self::ensure_allowed(env, liquidator, amount)?;
+            Self::execute(env, liquidator, user, amount, min_out, true)
+        })();
 
-            // Update position
-            position.debt -= liquidation_amount;
-            position.collateral -= collateral_seized;
-            StateHelper::save_position(env, &position);
+        ReentrancyGuard::exit(env);
+        result
+    }
 
-            let result = LiquidationResult::new(
-                collateral_seized,
-                liquidation_amount,
-                risk_config.liquidation_incentive,
+    /// Start a dutch auction over part of an unhealthy position's debt instead of
+    /// liquidating it instantly
+    pub fn start_auction(
+        env: &Env,
+        keeper: &Address,
+        user: &Address,
+        debt_portion: i128,
+    ) -> Result<u64, ProtocolError> {
+        ReentrancyGuard::enter(env)?;
+        let result = Self::ensure_allowed(env, keeper, debt_portion)
+            .and_then(|_| LiquidationAuctions::start(env, keeper, user, debt_portion));
+        ReentrancyGuard::exit(env);
+        result
+    }
+
+    /// Bid on a user's active liquidation auction at its current price
+    pub fn bid_auction(
+        env: &Env,
+        bidder: &Address,
+        user: &Address,
+        repay_amount: i128,
+    ) -> Result<AuctionFill, ProtocolError> {
+        ReentrancyGuard::enter(env)?;
+        let result = Self::ensure_allowed(env, bidder, repay_amount)
+            .and_then(|_| LiquidationAuctions::bid(env, bidder, user, repay_amount));
+        ReentrancyGuard::exit(env);
+        result
+    }
+
+    fn ensure_allowed(env: &Env, liquidator: &Address, amount: i128) -> Result<(), ProtocolError> {
+        OperationGate::check(env, liquidator, OperationKind::Liquidate, None, amount)
+    }
+
+    /// Shared liquidation path. With `from_supply` the repayment is burned from the
+    /// liquidator's supply claim rather than paid in externally.
+    fn execute(
+        env: &Env,
+        liquidator_addr: &Address,
+        user_addr: &Address,
+        amount: i128,
+        min_out: i128,
+        from_supply: bool,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        let risk_config = RiskConfigStorage::get(env);
+
+        // Load user position
+        let mut position = match StateHelper::get_position(env, user_addr) {
+            Some(pos) => pos,
+            None => return Err(LiquidationError::PositionNotFound.into()),
+        };
+
+        // Accrue interest so eligibility is judged on current debt
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+
+        // Check if position is eligible for liquidation
+        let min_ratio = CollateralMatrix::effective_min_ratio(env);
+        let collateral_ratio = if position.debt > 0 {
+            (position.collateral * 100) / position.debt
+        } else {
+            0
+        };
+
+        if collateral_ratio >= min_ratio {
+            return Err(LiquidationError::NotEligibleForLiquidation.into());
+        }
+        LiquidationGrace::ensure_liquidatable(env, &position)?;
+
+        // A stale primary price switches to the fallback mode: debt is still reduced, but
+        // no bonus is paid on collateral valued at an unreliable price
+        let fallback = Self::primary_price_stale(env);
+        let incentive = if fallback {
+            env.events().publish(
+                (
+                    Symbol::new(env, "liquidation_fallback"),
+                    Symbol::new(env, "user"),
+                ),
+                (Symbol::new(env, "user"), user_addr.clone()),
             );
+            0
+        } else {
+            Self::effective_incentive(env, &position)
+        };
+
+        // Cap the repayment per call and calculate collateral to seize
+        let (liquidation_amount, collateral_seized) = Self::capped_amounts(
+            position.debt,
+            position.collateral,
+            risk_config.close_factor,
+            incentive,
+            amount,
+        );
+        if liquidation_amount <= 0 {
+            return Err(LiquidationError::InsufficientLiquidationAmount.into());
+        }
+
+        // A configurable slice of the penalty is routed to the rebate pool
+        let rebate_cut = RebatePool::penalty_cut(env, collateral_seized - liquidation_amount);
+        let liquidator_payout = collateral_seized - rebate_cut;
 
-            // Emit liquidation event
-            ProtocolEvent::LiquidationExecuted(
-                liquidator_addr.clone(),
-                user_addr,
-                collateral_seized,
-                liquidation_amount,
-            )
-            .emit(env);
-
-            // Analytics
-            AnalyticsModule::record_activity(
+        // Slippage protection: ensure the liquidator receives at least `min_out` collateral
+        if min_out > 0 && liquidator_payout < min_out {
+            // Emit an analytics/event record so indexers can surface the slippage protection trigger
+            // Use the EventTracker available from the main crate to record structured analytics
+            soroban_sdk::Env::events(env); // no-op to satisfy borrow checker usage
+            crate::EventTracker::record(
                 env,
-                &liquidator_addr,
-                "liquidate",
-                liquidation_amount,
-                None,
-            )?;
+                soroban_sdk::Symbol::new(env, "slippage_protection"),
+                {
+                    let mut topics = soroban_sdk::Vec::new(env);
+                    topics.push_back(soroban_sdk::Symbol::new(env, "liquidator"));
+                    topics.push_back(soroban_sdk::Symbol::new(env, "user"));
+                    topics
+                },
+                Some(liquidator_addr.clone()),
+                Some(user_addr.clone()),
+                liquidator_payout,
+            );
 
-            Ok(result)
-        })();
+            return Err(ProtocolError::SlippageProtectionTriggered);
+        }
 
-        ReentrancyGuard::exit(env);
-        result
+        // The supply claim must cover the repayment before anything is burned
+        if from_supply {
+            let claim = StateHelper::get_position(env, liquidator_addr)
+                .map(|pos| pos.collateral)
+                .unwrap_or(0);
+            if claim < liquidation_amount {
+                return Err(ProtocolError::InsufficientCollateral);
+            }
+        }
+
+        // Update position
+        position.debt -= liquidation_amount;
+        position.collateral -= collateral_seized;
+        StateHelper::save_position(env, &position);
+        RebatePool::fund(env, rebate_cut);
+
+        if from_supply {
+            Self::settle_from_supply(env, liquidator_addr, liquidation_amount, liquidator_payout);
+        }
+
+        let result = LiquidationResult::new(collateral_seized, liquidation_amount, incentive);
+
+        // Persist replay data for risk calibration. Positions are single-asset, so
+        // collateral and debt carry the same oracle price.
+        let shortfall = (position.debt - position.collateral).max(0);
+        let price = crate::TokenRegistry::require_primary_asset(env)
+            .ok()
+            .and_then(|asset| Oracle::latest_price(env, &asset))
+            .unwrap_or(0);
+        AnalyticsModule::record_liquidation(
+            env,
+            liquidator_addr,
+            user_addr,
+            liquidation_amount,
+            collateral_seized,
+            price,
+            price,
+            min_ratio,
+            incentive,
+            shortfall,
+        );
+
+        // Emit liquidation event
+        ProtocolEvent::LiquidationExecuted(
+            liquidator_addr.clone(),
+            user_addr.clone(),
+            collateral_seized,
+            liquidation_amount,
+        )
+        .emit(env);
+
+        // Analytics
+        AnalyticsModule::record_activity(
+            env,
+            liquidator_addr,
+            "liquidate",
+            liquidation_amount,
+            None,
+        )?;
+
+        Ok(result)
+    }
+
+    /// Whether the primary asset's price is past its heartbeat
+    fn primary_price_stale(env: &Env) -> bool {
+        crate::TokenRegistry::require_primary_asset(env)
+            .map(|asset| Oracle::is_stale(env, &asset))
+            .unwrap_or(false)
+    }
+
+    /// Cap a requested repayment at the close factor of the debt and at what the
+    /// collateral covers including the incentive. Returns (repaid, seized).
+    fn capped_amounts(
+        debt: i128,
+        collateral: i128,
+        close_factor: i128,
+        incentive: i128,
+        amount: i128,
+    ) -> (i128, i128) {
+        let max_by_close_factor = (debt * close_factor) / 100000000;
+        let max_by_collateral = (collateral * 100000000) / (100000000 + incentive);
+        let repaid = amount
+            .min(max_by_close_factor)
+            .min(max_by_collateral)
+            .max(0);
+        (repaid, (repaid * (100000000 + incentive)) / 100000000)
+    }
+
+    /// Debt of a position at the current borrow index, with any stable part at its locked
+    /// rate, without writing the accrual
+    pub fn accrued_debt(env: &Env, position: &Position) -> i128 {
+        match crate::TokenRegistry::require_primary_asset(env) {
+            Ok(asset) => StableRates::debt_at(
+                env,
+                position,
+                InterestRateStorage::current_borrow_index(env, &asset),
+            ),
+            Err(_) => position.debt,
+        }
+    }
+
+    /// Largest repayment a single liquidation call accepts for a user right now, with the
+    /// collateral it would seize and the incentive applied. All zero when the position is
+    /// not liquidatable. Debt is valued at the current borrow index without writing it.
+    pub fn max_liquidatable(env: &Env, user: &Address) -> Result<LiquidationResult, ProtocolError> {
+        let mut position = match StateHelper::get_position(env, user) {
+            Some(pos) => pos,
+            None => return Err(LiquidationError::PositionNotFound.into()),
+        };
+        position.debt = Self::accrued_debt(env, &position);
+        let debt = position.debt;
+        if debt <= 0
+            || (position.collateral * 100) / debt >= CollateralMatrix::effective_min_ratio(env)
+        {
+            return Ok(LiquidationResult::new(0, 0, 0));
+        }
+
+        let risk_config = RiskConfigStorage::get(env);
+        let incentive = if Self::primary_price_stale(env) {
+            0
+        } else {
+            Self::effective_incentive(env, &position)
+        };
+        let (repaid, seized) = Self::capped_amounts(
+            debt,
+            position.collateral,
+            risk_config.close_factor,
+            incentive,
+            i128::MAX,
+        );
+        Ok(LiquidationResult::new(seized, repaid, incentive))
+    }
+
+    /// Set the incentive curve and size tiers (admin only). Empty points restore the
+    /// flat `RiskConfig` incentive.
+    pub fn set_incentive_curve(
+        env: &Env,
+        caller: &Address,
+        curve: IncentiveCurve,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if curve.points.len() > MAX_CURVE_POINTS || curve.size_tiers.len() > MAX_CURVE_POINTS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut previous: Option<i128> = None;
+        for point in curve.points.iter() {
+            if point.health_factor < 0
+                || !(0..=MAX_INCENTIVE).contains(&point.incentive)
+                || previous.is_some_and(|hf| point.health_factor <= hf)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            previous = Some(point.health_factor);
+        }
+        let mut previous: Option<i128> = None;
+        for tier in curve.size_tiers.iter() {
+            if tier.min_collateral < 0
+                || !(0..=BPS_SCALE).contains(&tier.scale_bps)
+                || previous.is_some_and(|min| tier.min_collateral <= min)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            previous = Some(tier.min_collateral);
+        }
+
+        IncentiveCurveStorage::save(env, &curve);
+        env.events().publish(
+            (
+                Symbol::new(env, "liquidation_incentive_curve"),
+                Symbol::new(env, "admin"),
+            ),
+            (
+                Symbol::new(env, "points"),
+                curve.points.len(),
+                Symbol::new(env, "size_tiers"),
+                curve.size_tiers.len(),
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_incentive_curve(env: &Env) -> IncentiveCurve {
+        IncentiveCurveStorage::get(env)
+    }
+
+    /// Incentive a liquidation of `position` would pay (scaled by 1e8): the curve value
+    /// at its health factor, or the flat incentive without a curve, scaled by its size
+    /// tier. `position` must carry its current debt.
+    pub fn effective_incentive(env: &Env, position: &Position) -> i128 {
+        let curve = IncentiveCurveStorage::get(env);
+        let base = if curve.points.is_empty() {
+            RiskConfigStorage::get(env).liquidation_incentive
+        } else {
+            Self::incentive_at(&curve.points, SafetyRewards::health_factor(env, position))
+        };
+        let scale_bps = curve
+            .size_tiers
+            .iter()
+            .filter(|tier| position.collateral >= tier.min_collateral)
+            .last()
+            .map(|tier| tier.scale_bps)
+            .unwrap_or(BPS_SCALE);
+        base * scale_bps / BPS_SCALE
+    }
+
+    /// Incentive a liquidation of `user` would pay right now; 0 while the primary price
+    /// is stale. Debt is valued at the current borrow index without writing it.
+    pub fn incentive_for(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
+        let mut position = match StateHelper::get_position(env, user) {
+            Some(pos) => pos,
+            None => return Err(LiquidationError::PositionNotFound.into()),
+        };
+        if Self::primary_price_stale(env) {
+            return Ok(0);
+        }
+        position.debt = Self::accrued_debt(env, &position);
+        Ok(Self::effective_incentive(env, &position))
+    }
+
+    /// Incentive on the curve at `health_factor`; debt-free positions take the last point
+    fn incentive_at(points: &Vec<IncentivePoint>, health_factor: Option<i128>) -> i128 {
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0,
+        };
+        let hf = match health_factor {
+            Some(hf) => hf,
+            None => return last.incentive,
+        };
+        if hf <= first.health_factor {
+            return first.incentive;
+        }
+        let mut lower = first;
+        for upper in points.iter().skip(1) {
+            if hf < upper.health_factor {
+                let span = upper.health_factor - lower.health_factor;
+                let delta = upper.incentive - lower.incentive;
+                return lower.incentive + delta * (hf - lower.health_factor) / span;
+            }
+            lower = upper;
+        }
+        last.incentive
+    }
+
+    /// Burn `burned` from the liquidator's supply claim and credit the seized `payout`
+    fn settle_from_supply(env: &Env, liquidator: &Address, burned: i128, payout: i128) {
+        let mut claim = StateHelper::get_position(env, liquidator)
+            .unwrap_or_else(|| Position::new(liquidator.clone(), 0, 0));
+        claim.collateral = claim.collateral - burned + payout;
+        StateHelper::save_position(env, &claim);
+
+        env.events().publish(
+            (
+                Symbol::new(env, "supply_claim_burned"),
+                Symbol::new(env, "liquidator"),
+            ),
+            (
+                Symbol::new(env, "liquidator"),
+                liquidator.clone(),
+                Symbol::new(env, "burned"),
+                burned,
+                Symbol::new(env, "credited"),
+                payout,
+            ),
+        );
     }
 
     /// Check if a position is eligible for liquidation
@@ -211,7 +609,7 @@ impl LiquidationModule {
             None => return Err(LiquidationError::PositionNotFound.into()),
         };
 
-        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        let min_ratio = CollateralMatrix::effective_min_ratio(env);
         let collateral_ratio = if position.debt > 0 {
             (position.collateral * 100) / position.debt
         } else {
@@ -272,7 +670,7 @@ impl LiquidationModule {
             None => return Err(LiquidationError::PositionNotFound.into()),
         };
 
-        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        let min_ratio = CollateralMatrix::effective_min_ratio(env);
         let collateral_ratio = if position.debt > 0 {
             (position.collateral * 100) / position.debt
         } else {
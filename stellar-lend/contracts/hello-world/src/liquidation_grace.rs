@@ -0,0 +1,169 @@
+//! Liquidation grace module for StellarLend protocol
+//! An optional window between a position becoming liquidatable and its liquidation. The
+//! first time a saved position is found below its minimum ratio it is flagged and a
+//! `RiskAlert` carrying its health factor is emitted; liquidators may act once the
+//! configured delay has passed since the flag, or at once when the health factor is below
+//! the hard threshold. The flag clears when the position is healthy again. Positions made
+//! liquidatable by price moves alone are flagged by anyone through `flag`.
+
+use crate::liquidate::LiquidationError;
+use crate::persistent::PersistentStore;
+use crate::safety_rewards::SafetyRewards;
+use crate::{
+    InterestRateManager, InterestRateStorage, Position, ProtocolConfig, ProtocolError,
+    ProtocolEvent, StateHelper,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Health factor of a position exactly at its minimum ratio
+const HEALTH_FACTOR_SCALE: i128 = 100;
+
+/// Default health factor below which no grace is given (80% of the minimum ratio)
+const DEFAULT_HARD_HEALTH_FACTOR: i128 = 80;
+
+/// Admin-set grace window
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationGraceConfig {
+    /// Seconds between the flag and the first allowed liquidation, 0 to disable the window
+    pub delay: u64,
+    /// Health factor below which a position can be liquidated without waiting
+    pub hard_health_factor: i128,
+}
+
+impl LiquidationGraceConfig {
+    pub fn default_config() -> Self {
+        Self {
+            delay: 0,
+            hard_health_factor: DEFAULT_HARD_HEALTH_FACTOR,
+        }
+    }
+}
+
+/// Storage helper for the grace window
+pub struct LiquidationGraceStorage;
+
+impl LiquidationGraceStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "liq_grace_config")
+    }
+
+    fn flag_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "liq_flagged_at"), user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> LiquidationGraceConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key(env))
+            .unwrap_or_else(LiquidationGraceConfig::default_config)
+    }
+
+    pub fn save_config(env: &Env, config: &LiquidationGraceConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get_flagged_at(env: &Env, user: &Address) -> Option<u64> {
+        PersistentStore::get(env, &Self::flag_key(env, user))
+    }
+
+    pub fn save_flagged_at(env: &Env, user: &Address, timestamp: u64) {
+        PersistentStore::set(env, &Self::flag_key(env, user), &timestamp);
+    }
+
+    pub fn remove_flag(env: &Env, user: &Address) {
+        PersistentStore::remove(env, &Self::flag_key(env, user));
+    }
+}
+
+/// Grace window checks and flagging
+pub struct LiquidationGrace;
+
+impl LiquidationGrace {
+    /// Set the delay and hard threshold (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: LiquidationGraceConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=HEALTH_FACTOR_SCALE).contains(&config.hard_health_factor) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        LiquidationGraceStorage::save_config(env, &config);
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env) -> LiquidationGraceConfig {
+        LiquidationGraceStorage::get_config(env)
+    }
+
+    /// When the user's position was flagged as liquidatable, if it still is
+    pub fn flagged_at(env: &Env, user: &Address) -> Option<u64> {
+        LiquidationGraceStorage::get_flagged_at(env, user)
+    }
+
+    /// Accrue and save the user's position so a liquidatable one is flagged. Returns the
+    /// time from which it may be liquidated.
+    pub fn flag(env: &Env, user: &Address) -> Result<u64, ProtocolError> {
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+        StateHelper::save_position(env, &position);
+        if !Self::is_unhealthy(env, &position) {
+            return Err(ProtocolError::NotEligibleForLiquidation);
+        }
+        let flagged_at = LiquidationGraceStorage::get_flagged_at(env, user)
+            .unwrap_or_else(|| env.ledger().timestamp());
+        Ok(flagged_at + LiquidationGraceStorage::get_config(env).delay)
+    }
+
+    /// Flag a position that turned liquidatable and clear the flag of one that recovered.
+    /// Does nothing while the window is disabled.
+    pub fn on_position_change(env: &Env, position: &Position) {
+        if LiquidationGraceStorage::get_config(env).delay == 0 {
+            return;
+        }
+        let flagged = LiquidationGraceStorage::get_flagged_at(env, &position.user).is_some();
+        match SafetyRewards::health_factor(env, position) {
+            Some(hf) if hf < HEALTH_FACTOR_SCALE => {
+                if !flagged {
+                    LiquidationGraceStorage::save_flagged_at(
+                        env,
+                        &position.user,
+                        env.ledger().timestamp(),
+                    );
+                    ProtocolEvent::RiskAlert(position.user.clone(), hf).emit(env);
+                }
+            }
+            _ => {
+                if flagged {
+                    LiquidationGraceStorage::remove_flag(env, &position.user);
+                }
+            }
+        }
+    }
+
+    /// Reject liquidating a position still inside its grace window. The position must
+    /// already be known to be liquidatable.
+    pub fn ensure_liquidatable(env: &Env, position: &Position) -> Result<(), ProtocolError> {
+        let config = LiquidationGraceStorage::get_config(env);
+        if config.delay == 0 {
+            return Ok(());
+        }
+        if SafetyRewards::health_factor(env, position)
+            .is_some_and(|hf| hf < config.hard_health_factor)
+        {
+            return Ok(());
+        }
+        match LiquidationGraceStorage::get_flagged_at(env, &position.user) {
+            Some(flagged_at) if env.ledger().timestamp() >= flagged_at + config.delay => Ok(()),
+            _ => Err(LiquidationError::GracePeriod.into()),
+        }
+    }
+
+    fn is_unhealthy(env: &Env, position: &Position) -> bool {
+        SafetyRewards::health_factor(env, position).is_some_and(|hf| hf < HEALTH_FACTOR_SCALE)
+    }
+}
@@ -0,0 +1,187 @@
+//! Liquidity guard module for StellarLend protocol
+//! Keeps withdrawals from draining the liquidity borrowers rely on. A market's utilization
+//! is its borrowed total over that total plus the contract's cash in the asset; a
+//! withdrawal may not push it above the admin-set maximum and fails with
+//! `InsufficientLiquidity` instead. `max_withdrawable` combines this limit with the
+//! user's own collateral, lock and ratio limits.
+
+use crate::cross_asset::CrossAssets;
+use crate::lp_pricing::LpPricing;
+use crate::normalization::Normalization;
+use crate::release_schedule::ReleaseSchedule;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{
+    InterestRateManager, InterestRateStorage, ProtocolConfig, ProtocolError, StateHelper,
+    TokenRegistry,
+};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Utilization scale (1e8 = 100%)
+const UTILIZATION_SCALE: i128 = 100_000_000;
+
+/// Default highest utilization a withdrawal may leave behind (95%)
+const DEFAULT_MAX_UTILIZATION: i128 = 95_000_000;
+
+/// Storage helper for the withdrawal utilization cap
+pub struct LiquidityGuardStorage;
+
+impl LiquidityGuardStorage {
+    fn key(env: &Env) -> Symbol {
+        Symbol::new(env, "max_withdraw_util")
+    }
+
+    pub fn get_max_utilization(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::key(env))
+            .unwrap_or(DEFAULT_MAX_UTILIZATION)
+    }
+
+    pub fn save_max_utilization(env: &Env, max_utilization: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::key(env), &max_utilization);
+    }
+}
+
+/// Utilization cap on withdrawals
+pub struct LiquidityGuard;
+
+impl LiquidityGuard {
+    /// Set the highest utilization a withdrawal may leave, scaled by 1e8 (admin only)
+    pub fn set_max_utilization(
+        env: &Env,
+        caller: &Address,
+        max_utilization: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if max_utilization <= 0 || max_utilization > UTILIZATION_SCALE {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        LiquidityGuardStorage::save_max_utilization(env, max_utilization);
+        env.events().publish(
+            (
+                Symbol::new(env, "max_withdraw_util_updated"),
+                Symbol::new(env, "admin"),
+            ),
+            (Symbol::new(env, "max_utilization"), max_utilization),
+        );
+        Ok(())
+    }
+
+    pub fn get_max_utilization(env: &Env) -> i128 {
+        LiquidityGuardStorage::get_max_utilization(env)
+    }
+
+    /// Cash in `asset` that can leave the contract without lifting its market above the
+    /// utilization cap
+    pub fn available_liquidity(env: &Env, asset: &Address) -> i128 {
+        Self::withdrawable_under(env, asset, LiquidityGuardStorage::get_max_utilization(env))
+    }
+
+    /// Cash in `asset` that can be withdrawn while utilization stays at or below
+    /// `max_utilization` (1e8 scale)
+    pub fn withdrawable_under(env: &Env, asset: &Address, max_utilization: i128) -> i128 {
+        let (cash, borrowed) = Self::cash_and_borrowed(env, asset);
+        if borrowed <= 0 || max_utilization >= UTILIZATION_SCALE {
+            return cash;
+        }
+        let min_total =
+            (borrowed.saturating_mul(UTILIZATION_SCALE) + max_utilization - 1) / max_utilization;
+        (cash + borrowed - min_total).clamp(0, cash)
+    }
+
+    /// Cash in `asset` that can be borrowed while utilization stays at or below
+    /// `max_utilization` (1e8 scale)
+    pub fn borrowable_under(env: &Env, asset: &Address, max_utilization: i128) -> i128 {
+        let (cash, borrowed) = Self::cash_and_borrowed(env, asset);
+        let max_borrowed = (cash + borrowed).saturating_mul(max_utilization) / UTILIZATION_SCALE;
+        (max_borrowed - borrowed).clamp(0, cash)
+    }
+
+    fn cash_and_borrowed(env: &Env, asset: &Address) -> (i128, i128) {
+        let cash = TokenClient::new(env, asset)
+            .balance(&env.current_contract_address())
+            .max(0);
+        let borrowed = InterestRateStorage::get_market_state(env, asset).total_borrowed;
+        (cash, borrowed.max(0))
+    }
+
+    /// Reject withdrawing `amount` of `asset` past the available liquidity
+    pub fn ensure_withdrawable(
+        env: &Env,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        if amount > Self::available_liquidity(env, asset) {
+            return Err(ProtocolError::InsufficientLiquidity);
+        }
+        Ok(())
+    }
+
+    /// Largest withdrawal of `asset` the user can make now: from the primary position for
+    /// the primary asset, otherwise from the multi-asset position
+    pub fn max_withdrawable(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+    ) -> Result<i128, ProtocolError> {
+        let own = if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset) {
+            Self::primary_withdrawable(env, user, asset)
+        } else {
+            Self::cross_withdrawable(env, user, asset)?
+        };
+        Ok(own.min(Self::available_liquidity(env, asset)).max(0))
+    }
+
+    fn primary_withdrawable(env: &Env, user: &Address, asset: &Address) -> i128 {
+        let position = match StateHelper::get_position(env, user) {
+            Some(position) => position,
+            None => return 0,
+        };
+        let unlocked = position.collateral - ReleaseSchedule::locked_amount(env, user);
+        let debt = InterestRateManager::rebase(
+            position.debt,
+            position.borrow_index,
+            InterestRateStorage::current_borrow_index(env, asset),
+        );
+        if debt <= 0 {
+            return unlocked;
+        }
+        let required = (debt * CollateralMatrix::effective_min_ratio(env) + 99) / 100;
+        unlocked.min(position.collateral - required)
+    }
+
+    fn cross_withdrawable(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+    ) -> Result<i128, ProtocolError> {
+        let position = match StateHelper::get_cross_position(env, user) {
+            Some(position) => position,
+            None => return Ok(0),
+        };
+        let balance = position.collateral.get(asset.clone()).unwrap_or(0);
+        if balance <= 0 || position.debt.is_empty() {
+            return Ok(balance);
+        }
+        let health = CrossAssets::health(env, &position)?;
+        let excess = health.collateral_value - (health.debt_value * health.min_ratio + 99) / 100;
+        if excess <= 0 {
+            return Ok(0);
+        }
+        let amount = match CrossAssets::valuation_price(env, asset) {
+            Some(price) => Normalization::amount_for_value(env, asset, excess, price),
+            None => {
+                let value = LpPricing::collateral_value(env, asset, balance)?;
+                if value <= 0 {
+                    balance
+                } else {
+                    balance.saturating_mul(excess) / value
+                }
+            }
+        };
+        Ok(amount.min(balance))
+    }
+}
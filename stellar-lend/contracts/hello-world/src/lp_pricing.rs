@@ -0,0 +1,164 @@
+//! LP token pricing module for StellarLend protocol
+//! Values AMM LP tokens from their pool's invariant and the underlying assets' registered
+//! prices rather than from spot reserves, so swaps that skew the reserves cannot inflate
+//! the collateral value. Registered LP tokens count as collateral at their own factor.
+
+use crate::cross_asset::{AssetPriceStorage, CrossAssets};
+use crate::normalization::Normalization;
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Val, Vec};
+
+/// Price scale (1e8)
+const PRICE_SCALE: i128 = 100_000_000;
+
+/// Basis point scale
+const BPS_SCALE: i128 = 10000;
+
+/// An LP token accepted as collateral
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LpTokenConfig {
+    pub lp_token: Address,
+    /// Pool exposing `get_reserves() -> (i128, i128)` and `total_supply() -> i128`
+    pub pool: Address,
+    pub asset_a: Address,
+    pub asset_b: Address,
+    /// Share of the fair value counted as collateral (bps)
+    pub collateral_factor_bps: i128,
+}
+
+/// Storage helper for registered LP tokens
+pub struct LpTokenStorage;
+
+impl LpTokenStorage {
+    fn key(env: &Env, lp_token: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "lp_token"), lp_token.clone())
+    }
+
+    pub fn get(env: &Env, lp_token: &Address) -> Option<LpTokenConfig> {
+        PersistentStore::get(env, &Self::key(env, lp_token))
+    }
+
+    pub fn set(env: &Env, lp_token: &Address, config: Option<LpTokenConfig>) {
+        match config {
+            Some(config) => PersistentStore::set(env, &Self::key(env, lp_token), &config),
+            None => PersistentStore::remove(env, &Self::key(env, lp_token)),
+        }
+    }
+}
+
+/// Fair-value pricing of LP tokens
+pub struct LpPricing;
+
+impl LpPricing {
+    /// Register or replace an LP token as collateral (admin only). Both underlying assets
+    /// must have registered prices.
+    pub fn register(
+        env: &Env,
+        caller: &Address,
+        config: LpTokenConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.asset_a == config.asset_b
+            || config.lp_token == config.asset_a
+            || config.lp_token == config.asset_b
+            || !(1..=BPS_SCALE).contains(&config.collateral_factor_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if AssetPriceStorage::get(env, &config.asset_a).is_none()
+            || AssetPriceStorage::get(env, &config.asset_b).is_none()
+        {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        Normalization::cache_decimals(env, &config.lp_token);
+        LpTokenStorage::set(env, &config.lp_token, Some(config.clone()));
+        env.events().publish(
+            (
+                Symbol::new(env, "lp_collateral_registered"),
+                Symbol::new(env, "lp_token"),
+            ),
+            (
+                Symbol::new(env, "lp_token"),
+                config.lp_token,
+                Symbol::new(env, "factor_bps"),
+                config.collateral_factor_bps,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env, lp_token: &Address) -> Option<LpTokenConfig> {
+        LpTokenStorage::get(env, lp_token)
+    }
+
+    /// Fair price of one LP token (1e8 scale): `2 * sqrt(k * p_a * p_b) / supply`.
+    /// Depends on the reserves only through their product, which swaps preserve.
+    pub fn fair_price(env: &Env, lp_token: &Address) -> Result<i128, ProtocolError> {
+        let config = LpTokenStorage::get(env, lp_token).ok_or(ProtocolError::AssetNotSupported)?;
+        let price_a = CrossAssets::valuation_price(env, &config.asset_a)
+            .ok_or(ProtocolError::AssetNotSupported)?;
+        let price_b = CrossAssets::valuation_price(env, &config.asset_b)
+            .ok_or(ProtocolError::AssetNotSupported)?;
+
+        let (reserve_a, reserve_b): (i128, i128) = env.invoke_contract(
+            &config.pool,
+            &Symbol::new(env, "get_reserves"),
+            Vec::<Val>::new(env),
+        );
+        let supply: i128 = env.invoke_contract(
+            &config.pool,
+            &Symbol::new(env, "total_supply"),
+            Vec::<Val>::new(env),
+        );
+        if reserve_a <= 0 || reserve_b <= 0 || supply <= 0 {
+            return Err(ProtocolError::OracleFailure);
+        }
+
+        // Reserves are compared in the LP token's own decimals
+        let decimals = Normalization::decimals(env, lp_token);
+        let reserve_a = Normalization::rescale(
+            reserve_a,
+            Normalization::decimals(env, &config.asset_a),
+            decimals,
+        );
+        let reserve_b = Normalization::rescale(
+            reserve_b,
+            Normalization::decimals(env, &config.asset_b),
+            decimals,
+        );
+        let value_a = reserve_a.saturating_mul(price_a) / PRICE_SCALE;
+        let value_b = reserve_b.saturating_mul(price_b) / PRICE_SCALE;
+        let root = match value_a.checked_mul(value_b) {
+            Some(product) => isqrt(product),
+            None => isqrt(value_a).saturating_mul(isqrt(value_b)),
+        };
+        Ok(root.saturating_mul(2 * PRICE_SCALE) / supply)
+    }
+
+    /// Collateral value of `amount` LP tokens after the token's collateral factor
+    pub fn collateral_value(
+        env: &Env,
+        lp_token: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let config = LpTokenStorage::get(env, lp_token).ok_or(ProtocolError::AssetNotSupported)?;
+        let value = Normalization::value(env, lp_token, amount, Self::fair_price(env, lp_token)?);
+        Ok(value * config.collateral_factor_bps / BPS_SCALE)
+    }
+}
+
+/// Integer square root (floor)
+pub(crate) fn isqrt(n: i128) -> i128 {
+    if n <= 0 {
+        return 0;
+    }
+    let mut x = n;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
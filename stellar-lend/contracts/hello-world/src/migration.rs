@@ -0,0 +1,177 @@
+//! Storage migration module for StellarLend protocol
+//! Stored contracttypes that gain or change fields are kept inside a versioned enum so
+//! older encodings stay readable. A `VersionedRecord` implementation recognizes every
+//! layout ever stored under its keys (earlier enum variants as well as the bare structs
+//! written before versioning) and lifts them to the current struct. Reads upgrade stale
+//! entries lazily and write them back, so each entry migrates once; the admin can also
+//! migrate entries in batches ahead of use.
+
+use crate::persistent::PersistentStore;
+use crate::{Position, PositionStorageKey, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec};
+
+/// Maximum entries migrated per batch call
+const MAX_BATCH: u32 = 50;
+
+/// A stored record with more than one known layout
+pub trait VersionedRecord: Sized + IntoVal<Env, Val> {
+    type Current: Clone;
+
+    /// Version of the layout written today
+    const LATEST: u32;
+
+    /// Recognize a stored value. The flag is false for bare structs written before the
+    /// record was versioned, which need rewriting even when their layout is current.
+    fn decode(env: &Env, raw: &Val) -> Option<(Self, bool)>;
+
+    fn version(&self) -> u32;
+
+    fn wrap(current: Self::Current) -> Self;
+
+    fn into_current(self) -> Self::Current;
+}
+
+/// Primary position layout before debt was tracked against the market borrow index
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PositionV1 {
+    pub user: Address,
+    pub collateral: i128,
+    pub debt: i128,
+    /// Interest accrued on `debt` and not yet folded into it
+    pub borrow_interest: i128,
+    pub supply_interest: i128,
+    pub last_accrual_time: u64,
+}
+
+/// Every stored primary position layout
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum VersionedPosition {
+    V1(PositionV1),
+    V2(Position),
+}
+
+impl VersionedRecord for VersionedPosition {
+    type Current = Position;
+
+    const LATEST: u32 = 2;
+
+    fn decode(env: &Env, raw: &Val) -> Option<(Self, bool)> {
+        if Vec::<Val>::try_from_val(env, raw).is_ok() {
+            return Self::try_from_val(env, raw)
+                .ok()
+                .map(|record| (record, true));
+        }
+        let fields = Map::<Symbol, Val>::try_from_val(env, raw).ok()?;
+        let record = if fields.contains_key(Symbol::new(env, "borrow_interest")) {
+            Self::V1(PositionV1::try_from_val(env, raw).ok()?)
+        } else {
+            Self::V2(Position::try_from_val(env, raw).ok()?)
+        };
+        Some((record, false))
+    }
+
+    fn version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+        }
+    }
+
+    fn wrap(current: Position) -> Self {
+        Self::V2(current)
+    }
+
+    /// V1 interest is folded into the debt, which is rebased from the current borrow index
+    /// on its next accrual
+    fn into_current(self) -> Position {
+        match self {
+            Self::V1(old) => Position {
+                user: old.user,
+                collateral: old.collateral,
+                debt: old.debt.saturating_add(old.borrow_interest),
+                borrow_index: 0,
+                supply_interest: old.supply_interest,
+                last_accrual_time: old.last_accrual_time,
+            },
+            Self::V2(position) => position,
+        }
+    }
+}
+
+/// Versioned reads and writes plus batch migration
+pub struct Migrations;
+
+impl Migrations {
+    fn migrated_key(env: &Env) -> Symbol {
+        Symbol::new(env, "migrated_records")
+    }
+
+    /// Read a record in its current layout, upgrading a stale entry in place
+    pub fn read<K, R>(env: &Env, key: &K) -> Option<R::Current>
+    where
+        K: IntoVal<Env, Val>,
+        R: VersionedRecord,
+    {
+        let raw: Val = PersistentStore::get(env, key)?;
+        let (record, wrapped) = R::decode(env, &raw)?;
+        if wrapped && record.version() == R::LATEST {
+            return Some(record.into_current());
+        }
+        let current = record.into_current();
+        PersistentStore::set(env, key, &R::wrap(current.clone()));
+        Self::record_migration(env);
+        Some(current)
+    }
+
+    /// Write a record in the latest layout
+    pub fn write<K, R>(env: &Env, key: &K, current: R::Current)
+    where
+        K: IntoVal<Env, Val>,
+        R: VersionedRecord,
+    {
+        PersistentStore::set(env, key, &R::wrap(current));
+    }
+
+    /// Upgrade the primary positions of `users` ahead of their next use (admin only).
+    /// Returns how many entries were rewritten.
+    pub fn migrate_positions(
+        env: &Env,
+        caller: &Address,
+        users: Vec<Address>,
+    ) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if users.len() > MAX_BATCH {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let before = Self::migrated_count(env);
+        for user in users.iter() {
+            Self::read::<_, VersionedPosition>(env, &PositionStorageKey::Position(user));
+        }
+        let migrated = Self::migrated_count(env) - before;
+        env.events().publish(
+            (
+                Symbol::new(env, "positions_migrated"),
+                Symbol::new(env, "admin"),
+            ),
+            (Symbol::new(env, "count"), migrated),
+        );
+        Ok(migrated)
+    }
+
+    /// Entries upgraded so far, lazily or in batches
+    pub fn migrated_count(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::migrated_key(env))
+            .unwrap_or(0)
+    }
+
+    fn record_migration(env: &Env) {
+        let count = Self::migrated_count(env) + 1;
+        env.storage()
+            .instance()
+            .set(&Self::migrated_key(env), &count);
+    }
+}
@@ -0,0 +1,256 @@
+//! Module boundaries for StellarLend protocol
+//! Traits describing the core operation modules and a registry selecting the active
+//! implementation for each, so alternative variants can be merged and toggled by config
+
+use crate::borrow::BorrowModule;
+use crate::deposit::DepositModule;
+use crate::liquidate::{LiquidationModule, LiquidationResult};
+use crate::repay::RepayModule;
+use crate::withdraw::WithdrawModule;
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Collateral deposit behaviour. `payer` funds the deposit credited to `depositor`.
+pub trait DepositOps {
+    fn deposit_collateral(
+        env: &Env,
+        payer: &Address,
+        depositor: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError>;
+}
+
+/// Borrow behaviour
+pub trait BorrowOps {
+    fn borrow(env: &Env, borrower: &Address, amount: i128) -> Result<(), ProtocolError>;
+}
+
+/// Repay behaviour. `payer` funds the repayment of `repayer`'s debt.
+pub trait RepayOps {
+    fn repay(
+        env: &Env,
+        payer: &Address,
+        repayer: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError>;
+}
+
+/// Withdraw behaviour
+pub trait WithdrawOps {
+    fn withdraw(
+        env: &Env,
+        withdrawer: &Address,
+        to: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError>;
+}
+
+/// Liquidation behaviour
+pub trait LiquidationOps {
+    fn liquidate(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError>;
+
+    fn liquidate_with_supply(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError>;
+}
+
+/// Core operation modules that can be swapped
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ModuleKind {
+    Deposit,
+    Borrow,
+    Repay,
+    Withdraw,
+    Liquidate,
+}
+
+/// Available implementations. New variants are added here alongside their trait impls.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ModuleImpl {
+    Standard,
+}
+
+/// Registry of the active implementation per module
+pub struct ModuleRegistry;
+
+impl ModuleRegistry {
+    fn impl_key(env: &Env, kind: ModuleKind) -> (Symbol, ModuleKind) {
+        (Symbol::new(env, "module_impl"), kind)
+    }
+
+    pub fn active(env: &Env, kind: ModuleKind) -> ModuleImpl {
+        env.storage()
+            .instance()
+            .get(&Self::impl_key(env, kind))
+            .unwrap_or(ModuleImpl::Standard)
+    }
+
+    /// Select the implementation used for a module (admin only)
+    pub fn set_active(
+        env: &Env,
+        caller: &Address,
+        kind: ModuleKind,
+        implementation: ModuleImpl,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        env.storage()
+            .instance()
+            .set(&Self::impl_key(env, kind), &implementation);
+        env.events().publish(
+            (
+                Symbol::new(env, "module_impl_updated"),
+                Symbol::new(env, "module"),
+            ),
+            (
+                Symbol::new(env, "module"),
+                kind,
+                Symbol::new(env, "implementation"),
+                implementation,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn deposit_collateral(
+        env: &Env,
+        payer: &Address,
+        depositor: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        match Self::active(env, ModuleKind::Deposit) {
+            ModuleImpl::Standard => {
+                <DepositModule as DepositOps>::deposit_collateral(env, payer, depositor, amount)
+            }
+        }
+    }
+
+    pub fn borrow(env: &Env, borrower: &Address, amount: i128) -> Result<(), ProtocolError> {
+        match Self::active(env, ModuleKind::Borrow) {
+            ModuleImpl::Standard => <BorrowModule as BorrowOps>::borrow(env, borrower, amount),
+        }
+    }
+
+    pub fn repay(
+        env: &Env,
+        payer: &Address,
+        repayer: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        match Self::active(env, ModuleKind::Repay) {
+            ModuleImpl::Standard => <RepayModule as RepayOps>::repay(env, payer, repayer, amount),
+        }
+    }
+
+    pub fn withdraw(
+        env: &Env,
+        withdrawer: &Address,
+        to: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        match Self::active(env, ModuleKind::Withdraw) {
+            ModuleImpl::Standard => {
+                <WithdrawModule as WithdrawOps>::withdraw(env, withdrawer, to, amount)
+            }
+        }
+    }
+
+    pub fn liquidate(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        match Self::active(env, ModuleKind::Liquidate) {
+            ModuleImpl::Standard => <LiquidationModule as LiquidationOps>::liquidate(
+                env, liquidator, user, amount, min_out,
+            ),
+        }
+    }
+
+    pub fn liquidate_with_supply(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        match Self::active(env, ModuleKind::Liquidate) {
+            ModuleImpl::Standard => <LiquidationModule as LiquidationOps>::liquidate_with_supply(
+                env, liquidator, user, amount, min_out,
+            ),
+        }
+    }
+}
+
+impl DepositOps for DepositModule {
+    fn deposit_collateral(
+        env: &Env,
+        payer: &Address,
+        depositor: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        DepositModule::deposit_collateral(env, payer, depositor, amount)
+    }
+}
+
+impl BorrowOps for BorrowModule {
+    fn borrow(env: &Env, borrower: &Address, amount: i128) -> Result<(), ProtocolError> {
+        BorrowModule::borrow(env, borrower, amount)
+    }
+}
+
+impl RepayOps for RepayModule {
+    fn repay(
+        env: &Env,
+        payer: &Address,
+        repayer: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        RepayModule::repay(env, payer, repayer, amount)
+    }
+}
+
+impl WithdrawOps for WithdrawModule {
+    fn withdraw(
+        env: &Env,
+        withdrawer: &Address,
+        to: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        WithdrawModule::withdraw(env, withdrawer, to, amount)
+    }
+}
+
+impl LiquidationOps for LiquidationModule {
+    fn liquidate(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        LiquidationModule::liquidate(env, liquidator, user, amount, min_out)
+    }
+    fn liquidate_with_supply(
+        env: &Env,
+        liquidator: &Address,
+        user: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        LiquidationModule::liquidate_with_supply(env, liquidator, user, amount, min_out)
+    }
+}
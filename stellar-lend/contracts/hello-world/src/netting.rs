@@ -0,0 +1,95 @@
+//! Netting module for StellarLend protocol
+//! Offsets a user's supply against their borrow in the same asset
+
+use crate::gate::OperationGate;
+use crate::{
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ProtocolEvent,
+    ReentrancyGuard, StateHelper, TokenRegistry, UserManager,
+};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Position netting implementation
+pub struct NettingModule;
+
+impl NettingModule {
+    /// Collateral ratio used to compare health before and after netting; debt-free is best
+    fn health(collateral: i128, debt: i128) -> i128 {
+        if debt == 0 {
+            i128::MAX
+        } else {
+            (collateral * 100) / debt
+        }
+    }
+
+    /// Repay debt from supplied balance in the same asset.
+    /// Only succeeds if the result strictly improves the position's health.
+    /// Returns the amount offset.
+    pub fn net_position(env: &Env, user: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        ReentrancyGuard::enter(env)?;
+        let result = (|| -> Result<i128, ProtocolError> {
+            user.require_auth();
+
+            if TokenRegistry::require_primary_asset(env)? != *asset {
+                return Err(ProtocolError::AssetNotSupported);
+            }
+
+            let mut position =
+                StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+
+            // Accrue interest so both sides are current
+            let state = InterestRateStorage::update_state(env);
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+
+            let offset = core::cmp::min(position.collateral, position.debt);
+            if offset <= 0 {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            OperationGate::check(env, user, OperationKind::Repay, Some(asset), offset)?;
+
+            let health_before = Self::health(position.collateral, position.debt);
+            let collateral_after = position.collateral - offset;
+            let debt_after = position.debt - offset;
+            if Self::health(collateral_after, debt_after) <= health_before {
+                return Err(ProtocolError::InvalidOperation);
+            }
+
+            position.collateral = collateral_after;
+            position.debt = debt_after;
+            StateHelper::save_position(env, &position);
+
+            let collateral_ratio = if position.debt > 0 {
+                (position.collateral * 100) / position.debt
+            } else {
+                0
+            };
+            ProtocolEvent::PositionUpdated(
+                user.clone(),
+                position.collateral,
+                position.debt,
+                collateral_ratio,
+            )
+            .emit(env);
+            env.events().publish(
+                (
+                    Symbol::new(env, "position_netted"),
+                    Symbol::new(env, "user"),
+                ),
+                (
+                    Symbol::new(env, "user"),
+                    user.clone(),
+                    Symbol::new(env, "asset"),
+                    asset.clone(),
+                    Symbol::new(env, "offset"),
+                    offset,
+                ),
+            );
+
+            UserManager::record_activity(env, user, OperationKind::Repay, offset)?;
+
+            Ok(offset)
+        })();
+
+        ReentrancyGuard::exit(env);
+        result
+    }
+}
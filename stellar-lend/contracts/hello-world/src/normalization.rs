@@ -0,0 +1,81 @@
+//! Normalization module for StellarLend protocol
+//! Converts token amounts between each asset's own decimals and a common 18-decimal base so
+//! values of assets with different decimals can be added and compared. Values are the
+//! normalized amount times the asset's 1e8-scaled price, i.e. 1e18 per unit of account.
+//! Decimals come from the token registry's cache; assets never read fall back to
+//! Stellar's 7.
+
+use crate::TokenRegistry;
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{Address, Env};
+
+/// Decimals of the common value base (1e18)
+pub const VALUE_DECIMALS: u32 = 18;
+
+/// Decimals assumed for assets whose token was never read
+pub const DEFAULT_DECIMALS: u32 = 7;
+
+/// Highest token decimals accepted
+pub const MAX_DECIMALS: u32 = 38;
+
+/// Price scale (1e8)
+const PRICE_SCALE: i128 = 100_000_000;
+
+/// Decimal-aware amount and value conversions
+pub struct Normalization;
+
+impl Normalization {
+    /// Decimals of an asset
+    pub fn decimals(env: &Env, asset: &Address) -> u32 {
+        TokenRegistry::decimals(env, asset).unwrap_or(DEFAULT_DECIMALS)
+    }
+
+    /// Read and cache the token's decimals unless already known. Addresses that do not
+    /// answer `decimals` keep the default.
+    pub fn cache_decimals(env: &Env, asset: &Address) {
+        if TokenRegistry::decimals(env, asset).is_some() {
+            return;
+        }
+        if let Ok(Ok(decimals)) = TokenClient::new(env, asset).try_decimals() {
+            if decimals <= MAX_DECIMALS {
+                TokenRegistry::save_decimals(env, asset, decimals);
+            }
+        }
+    }
+
+    /// Convert `amount` from `from` decimals to `to` decimals, rounding down
+    pub fn rescale(amount: i128, from: u32, to: u32) -> i128 {
+        if to >= from {
+            amount.saturating_mul(Self::pow10(to - from))
+        } else {
+            amount / Self::pow10(from - to)
+        }
+    }
+
+    /// `amount` of `asset` in the 18-decimal base
+    pub fn to_base(env: &Env, asset: &Address, amount: i128) -> i128 {
+        Self::rescale(amount, Self::decimals(env, asset), VALUE_DECIMALS)
+    }
+
+    /// 18-decimal base amount back in `asset` units, rounding down
+    pub fn from_base(env: &Env, asset: &Address, amount: i128) -> i128 {
+        Self::rescale(amount, VALUE_DECIMALS, Self::decimals(env, asset))
+    }
+
+    /// Value of `amount` of `asset` at `price` (1e8 scale), in the 1e18 base
+    pub fn value(env: &Env, asset: &Address, amount: i128, price: i128) -> i128 {
+        Self::to_base(env, asset, amount).saturating_mul(price) / PRICE_SCALE
+    }
+
+    /// Amount of `asset` worth `value` (1e18 base) at `price`, rounding down
+    pub fn amount_for_value(env: &Env, asset: &Address, value: i128, price: i128) -> i128 {
+        if price <= 0 {
+            return 0;
+        }
+        Self::from_base(env, asset, value.saturating_mul(PRICE_SCALE) / price)
+    }
+
+    fn pow10(exp: u32) -> i128 {
+        10i128.saturating_pow(exp)
+    }
+}
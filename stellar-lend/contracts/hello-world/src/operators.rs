@@ -0,0 +1,213 @@
+//! Operator module for StellarLend protocol
+//! Users approve operator addresses with a permission bitmask. An operator acts on the
+//! user's position within those permissions: deposits and repayments are funded by the
+//! operator, while borrowed and withdrawn funds always go to the user.
+
+use crate::borrow::BorrowModule;
+use crate::deposit::DepositModule;
+use crate::modules::ModuleRegistry;
+use crate::persistent::PersistentStore;
+use crate::repay::RepayModule;
+use crate::withdraw::WithdrawModule;
+use crate::ProtocolError;
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Operator may add collateral
+pub const PERMISSION_DEPOSIT: u32 = 1;
+/// Operator may repay debt
+pub const PERMISSION_REPAY: u32 = 1 << 1;
+/// Operator may borrow against the position
+pub const PERMISSION_BORROW: u32 = 1 << 2;
+/// Operator may withdraw collateral
+pub const PERMISSION_WITHDRAW: u32 = 1 << 3;
+
+const ALL_PERMISSIONS: u32 =
+    PERMISSION_DEPOSIT | PERMISSION_REPAY | PERMISSION_BORROW | PERMISSION_WITHDRAW;
+
+/// Storage helper for operator approvals
+pub struct OperatorStorage;
+
+impl OperatorStorage {
+    fn key(env: &Env, user: &Address, operator: &Address) -> (Symbol, Address, Address) {
+        (Symbol::new(env, "operator"), user.clone(), operator.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address, operator: &Address) -> u32 {
+        PersistentStore::get(env, &Self::key(env, user, operator)).unwrap_or(0)
+    }
+
+    pub fn set(env: &Env, user: &Address, operator: &Address, permissions: u32) {
+        PersistentStore::set(env, &Self::key(env, user, operator), &permissions);
+    }
+
+    pub fn remove(env: &Env, user: &Address, operator: &Address) {
+        PersistentStore::remove(env, &Self::key(env, user, operator));
+    }
+}
+
+/// Operator approvals and the actions they may take
+pub struct Operators;
+
+impl Operators {
+    /// Approve (or re-scope) an operator for the user's positions
+    pub fn approve(
+        env: &Env,
+        user: &Address,
+        operator: &Address,
+        permissions: u32,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if operator == user {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        if permissions == 0 || permissions & !ALL_PERMISSIONS != 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        OperatorStorage::set(env, user, operator, permissions);
+        Self::publish(env, "operator_approved", user, operator, permissions);
+        Ok(())
+    }
+
+    /// Withdraw an operator's approval
+    pub fn revoke(env: &Env, user: &Address, operator: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if OperatorStorage::get(env, user, operator) == 0 {
+            return Err(ProtocolError::NotFound);
+        }
+        OperatorStorage::remove(env, user, operator);
+        Self::publish(env, "operator_revoked", user, operator, 0);
+        Ok(())
+    }
+
+    pub fn permissions(env: &Env, user: &Address, operator: &Address) -> u32 {
+        OperatorStorage::get(env, user, operator)
+    }
+
+    /// Require the operator's signature and an approval from `user` covering `permission`
+    fn authorize(
+        env: &Env,
+        operator: &Address,
+        user: &Address,
+        permission: u32,
+    ) -> Result<(), ProtocolError> {
+        operator.require_auth();
+        if OperatorStorage::get(env, user, operator) & permission == 0 {
+            return Err(ProtocolError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Add collateral to the user's primary position (`asset` = None) or multi-asset
+    /// position, paid by the operator
+    pub fn deposit(
+        env: &Env,
+        operator: &Address,
+        user: &Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::authorize(env, operator, user, PERMISSION_DEPOSIT)?;
+        match &asset {
+            None => ModuleRegistry::deposit_collateral(env, operator, user, amount)?,
+            Some(asset) => {
+                DepositModule::deposit_collateral_asset(env, operator, user, asset, amount)?
+            }
+        }
+        Self::record(env, "deposit", operator, user, asset, amount);
+        Ok(())
+    }
+
+    /// Repay the user's debt, paid by the operator
+    pub fn repay(
+        env: &Env,
+        operator: &Address,
+        user: &Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::authorize(env, operator, user, PERMISSION_REPAY)?;
+        match &asset {
+            None => ModuleRegistry::repay(env, operator, user, amount)?,
+            Some(asset) => {
+                RepayModule::repay_asset(env, operator, user, asset, amount)?;
+            }
+        }
+        Self::record(env, "repay", operator, user, asset, amount);
+        Ok(())
+    }
+
+    /// Borrow against the user's position; the funds go to the user
+    pub fn borrow(
+        env: &Env,
+        operator: &Address,
+        user: &Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::authorize(env, operator, user, PERMISSION_BORROW)?;
+        match &asset {
+            None => crate::borrow(env.clone(), user.clone(), amount)?,
+            Some(asset) => BorrowModule::borrow_asset(env, user, asset, amount)?,
+        }
+        Self::record(env, "borrow", operator, user, asset, amount);
+        Ok(())
+    }
+
+    /// Withdraw the user's collateral; the funds go to the user
+    pub fn withdraw(
+        env: &Env,
+        operator: &Address,
+        user: &Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::authorize(env, operator, user, PERMISSION_WITHDRAW)?;
+        match &asset {
+            None => crate::withdraw(env.clone(), user.clone(), None, amount)?,
+            Some(asset) => WithdrawModule::withdraw_asset(env, user, asset, None, amount)?,
+        }
+        Self::record(env, "withdraw", operator, user, asset, amount);
+        Ok(())
+    }
+
+    /// Attribute an action to the operator that performed it for `user`
+    fn record(
+        env: &Env,
+        action: &str,
+        operator: &Address,
+        user: &Address,
+        asset: Option<Address>,
+        amount: i128,
+    ) {
+        env.events().publish(
+            (
+                Symbol::new(env, "operator_action"),
+                Symbol::new(env, action),
+            ),
+            (
+                Symbol::new(env, "operator"),
+                operator.clone(),
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "asset"),
+                asset,
+                Symbol::new(env, "amount"),
+                amount,
+            ),
+        );
+    }
+
+    fn publish(env: &Env, action: &str, user: &Address, operator: &Address, permissions: u32) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "operator")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "operator"),
+                operator.clone(),
+                Symbol::new(env, "permissions"),
+                permissions,
+            ),
+        );
+    }
+}
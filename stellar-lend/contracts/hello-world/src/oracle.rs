@@ -1,5 +1,32 @@
 #![allow(dead_code)]
-use soroban_sdk::{contracttype, vec, Address, Env, IntoVal, Symbol, Vec};
+use crate::persistent::PersistentStore;
+use soroban_sdk::{contracterror, contracttype, vec, Address, Env, IntoVal, Symbol, Vec};
+
+/// Reason-coded oracle failures
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum OracleError {
+    /// No price feed is registered for the asset
+    NoFeeds = 9001,
+    /// Every feed was stale, non-positive or out of bounds
+    NoValidPrice = 9002,
+    /// The aggregate moved more than the allowed deviation from the last accepted price
+    DeviationExceeded = 9003,
+    /// The asset's price has not been updated within its heartbeat
+    StalePrice = 9004,
+}
+
+impl From<OracleError> for crate::ProtocolError {
+    fn from(err: OracleError) -> Self {
+        match err {
+            OracleError::NoFeeds => crate::ProtocolError::OracleNoFeeds,
+            OracleError::NoValidPrice => crate::ProtocolError::OracleNoValidPrice,
+            OracleError::DeviationExceeded => crate::ProtocolError::OracleDeviationExceeded,
+            OracleError::StalePrice => crate::ProtocolError::OracleStalePrice,
+        }
+    }
+}
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -19,6 +46,77 @@ impl OracleSource {
     }
 }
 
+/// Governance-set sanity bounds for an asset's price
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceBounds {
+    pub asset: Address,
+    pub floor: i128,
+    pub ceiling: i128,
+}
+
+/// A price rejected for falling outside its asset's bounds
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceRejection {
+    pub asset: Address,
+    /// Source contract that reported the price, if it was read externally
+    pub source: Option<Address>,
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// Age of an asset's price relative to its heartbeat
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceFreshness {
+    pub asset: Address,
+    /// Last time a price for the asset was set or accepted (0 = never)
+    pub last_update: u64,
+    /// Maximum allowed age in seconds (0 = not enforced)
+    pub heartbeat: u64,
+    pub stale: bool,
+}
+
+/// A recorded price at a point in time
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceObservation {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// Fixed-size ring buffer of an asset's recent price observations
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceObservations {
+    pub entries: Vec<PriceObservation>,
+    /// Slot the next observation is written to once the buffer is full
+    pub next: u32,
+}
+
+/// Time-weighted average price settings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TwapConfig {
+    /// Averaging window in seconds
+    pub period: u64,
+    /// Value multi-asset positions at the TWAP instead of the spot price
+    pub use_for_risk: bool,
+}
+
+/// Number of recent rejections retained
+const MAX_REJECTIONS: u32 = 32;
+
+/// Maximum price feeds per asset
+const MAX_FEEDS: u32 = 10;
+
+/// Price observations retained per asset
+const MAX_OBSERVATIONS: u32 = 24;
+
+/// Default TWAP window (30 minutes)
+const DEFAULT_TWAP_PERIOD: u64 = 1800;
+
 pub struct OracleStorage;
 
 impl OracleStorage {
@@ -49,18 +147,42 @@ impl OracleStorage {
     fn price_cache_ttl_key(env: &Env) -> Symbol {
         Symbol::new(env, "oracle_price_cache_ttl")
     }
+    fn bounds_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "oracle_bounds"), asset.clone())
+    }
+    fn bounded_assets_key(env: &Env) -> Symbol {
+        Symbol::new(env, "oracle_bounded_assets")
+    }
+    fn rejections_key(env: &Env) -> Symbol {
+        Symbol::new(env, "oracle_rejections")
+    }
+    fn max_jump_bps_key(env: &Env) -> Symbol {
+        Symbol::new(env, "oracle_max_jump_bps")
+    }
+    fn last_accepted_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "oracle_last_price"), asset.clone())
+    }
+    fn asset_heartbeat_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "oracle_asset_heartbeat"), asset.clone())
+    }
+    fn updated_at_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "oracle_updated_at"), asset.clone())
+    }
+    fn observations_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "oracle_observations"), asset.clone())
+    }
+    fn twap_config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "oracle_twap_cfg")
+    }
 
     pub fn get_sources(env: &Env, asset: &Address) -> Vec<OracleSource> {
         let key = (Self::sources_key(env), asset.clone());
-        env.storage()
-            .instance()
-            .get(&key)
-            .unwrap_or_else(|| Vec::new(env))
+        PersistentStore::get(env, &key).unwrap_or_else(|| Vec::new(env))
     }
 
     pub fn put_sources(env: &Env, asset: &Address, sources: &Vec<OracleSource>) {
         let key = (Self::sources_key(env), asset.clone());
-        env.storage().instance().set(&key, sources);
+        PersistentStore::set(env, &key, sources);
     }
 
     pub fn get_heartbeat_ttl(env: &Env) -> u64 {
@@ -169,6 +291,132 @@ impl OracleStorage {
             .instance()
             .set(&Self::price_cache_ttl_key(env), &ttl);
     }
+
+    // Price bounds, with an index of bounded assets for listing
+    pub fn get_bounds(env: &Env, asset: &Address) -> Option<PriceBounds> {
+        PersistentStore::get(env, &Self::bounds_key(env, asset))
+    }
+    pub fn put_bounds(env: &Env, bounds: &PriceBounds) {
+        PersistentStore::set(env, &Self::bounds_key(env, &bounds.asset), bounds);
+        let mut assets = Self::get_bounded_assets(env);
+        if !assets.contains(&bounds.asset) {
+            assets.push_back(bounds.asset.clone());
+            env.storage()
+                .instance()
+                .set(&Self::bounded_assets_key(env), &assets);
+        }
+    }
+    pub fn remove_bounds(env: &Env, asset: &Address) {
+        PersistentStore::remove(env, &Self::bounds_key(env, asset));
+        let mut assets = Self::get_bounded_assets(env);
+        if let Some(index) = assets.first_index_of(asset) {
+            assets.remove(index);
+            env.storage()
+                .instance()
+                .set(&Self::bounded_assets_key(env), &assets);
+        }
+    }
+    pub fn get_bounded_assets(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&Self::bounded_assets_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Largest move from the last accepted price, in bps (0 disables the check)
+    pub fn get_max_jump_bps(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::max_jump_bps_key(env))
+            .unwrap_or(0)
+    }
+    pub fn set_max_jump_bps(env: &Env, bps: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::max_jump_bps_key(env), &bps);
+    }
+
+    /// Last aggregated price that passed every check, with its timestamp
+    pub fn get_last_accepted(env: &Env, asset: &Address) -> Option<(i128, u64)> {
+        PersistentStore::get(env, &Self::last_accepted_key(env, asset))
+    }
+    pub fn set_last_accepted(env: &Env, asset: &Address, price: i128, timestamp: u64) {
+        PersistentStore::set(
+            env,
+            &Self::last_accepted_key(env, asset),
+            &(price, timestamp),
+        );
+    }
+
+    /// Per-asset heartbeat in seconds (0 = not enforced)
+    pub fn get_asset_heartbeat(env: &Env, asset: &Address) -> u64 {
+        PersistentStore::get(env, &Self::asset_heartbeat_key(env, asset)).unwrap_or(0)
+    }
+    pub fn set_asset_heartbeat(env: &Env, asset: &Address, ttl: u64) {
+        PersistentStore::set(env, &Self::asset_heartbeat_key(env, asset), &ttl);
+    }
+
+    pub fn get_updated_at(env: &Env, asset: &Address) -> u64 {
+        PersistentStore::get(env, &Self::updated_at_key(env, asset)).unwrap_or(0)
+    }
+    pub fn set_updated_at(env: &Env, asset: &Address, timestamp: u64) {
+        PersistentStore::set(env, &Self::updated_at_key(env, asset), &timestamp);
+    }
+
+    pub fn get_observations(env: &Env, asset: &Address) -> PriceObservations {
+        PersistentStore::get(env, &Self::observations_key(env, asset)).unwrap_or_else(|| {
+            PriceObservations {
+                entries: Vec::new(env),
+                next: 0,
+            }
+        })
+    }
+    pub fn set_observations(env: &Env, asset: &Address, observations: &PriceObservations) {
+        PersistentStore::set(env, &Self::observations_key(env, asset), observations);
+    }
+
+    pub fn get_twap_config(env: &Env) -> TwapConfig {
+        env.storage()
+            .instance()
+            .get(&Self::twap_config_key(env))
+            .unwrap_or(TwapConfig {
+                period: DEFAULT_TWAP_PERIOD,
+                use_for_risk: false,
+            })
+    }
+    pub fn set_twap_config(env: &Env, config: &TwapConfig) {
+        env.storage()
+            .instance()
+            .set(&Self::twap_config_key(env), config);
+    }
+
+    pub fn get_rejections(env: &Env) -> Vec<PriceRejection> {
+        env.storage()
+            .instance()
+            .get(&Self::rejections_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+    pub fn push_rejection(env: &Env, rejection: PriceRejection) {
+        let mut rejections = Self::get_rejections(env);
+        rejections.push_back(rejection);
+        if rejections.len() > MAX_REJECTIONS {
+            rejections = rejections.slice(rejections.len() - MAX_REJECTIONS..);
+        }
+        env.storage()
+            .instance()
+            .set(&Self::rejections_key(env), &rejections);
+    }
+
+    /// Extend the TTL of an asset's sources, bounds, prices and observations,
+    /// returning how many entries exist
+    pub fn bump_asset(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &(Self::sources_key(env), asset.clone())) as u32
+            + PersistentStore::bump(env, &Self::bounds_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::last_accepted_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::asset_heartbeat_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::updated_at_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::observations_key(env, asset)) as u32
+    }
 }
 
 pub struct Oracle;
@@ -182,6 +430,11 @@ impl Oracle {
         source: OracleSource,
     ) -> Result<(), crate::ProtocolError> {
         crate::UserManager::require_admin(env, caller)?;
+        Self::put_source(env, asset, source);
+        Ok(())
+    }
+
+    fn put_source(env: &Env, asset: &Address, source: OracleSource) {
         let list = OracleStorage::get_sources(env, asset);
         // Replace if exists
         let mut replaced = false;
@@ -198,7 +451,7 @@ impl Oracle {
             out.push_back(source);
         }
         OracleStorage::put_sources(env, asset, &out);
-        Ok(())
+        crate::watchdog::Watchdog::heartbeat(env, "oracle");
     }
 
     /// Remove a source
@@ -209,6 +462,11 @@ impl Oracle {
         addr: &Address,
     ) -> Result<(), crate::ProtocolError> {
         crate::UserManager::require_admin(env, caller)?;
+        Self::drop_source(env, asset, addr);
+        Ok(())
+    }
+
+    fn drop_source(env: &Env, asset: &Address, addr: &Address) {
         let list = OracleStorage::get_sources(env, asset);
         let mut out: Vec<OracleSource> = Vec::new(env);
         for s in list.iter() {
@@ -217,13 +475,387 @@ impl Oracle {
             }
         }
         OracleStorage::put_sources(env, asset, &out);
+    }
+
+    /// Add a price feed for an asset (admin only). Feeds expose `get_price(asset) -> i128`.
+    pub fn add_feed(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        feed: &Address,
+        weight: i128,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        let sources = OracleStorage::get_sources(env, asset);
+        if weight <= 0 {
+            return Err(crate::ProtocolError::InvalidParameters);
+        }
+        if sources.iter().any(|s| s.addr == *feed) {
+            return Err(crate::ProtocolError::AlreadyExists);
+        }
+        if sources.len() >= MAX_FEEDS {
+            return Err(crate::ProtocolError::StorageLimitExceeded);
+        }
+        let source = OracleSource::new(feed.clone(), weight, env.ledger().timestamp());
+        Self::put_source(env, asset, source);
         Ok(())
     }
 
+    /// Remove a price feed from an asset (admin only)
+    pub fn remove_feed(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        feed: &Address,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        if !OracleStorage::get_sources(env, asset)
+            .iter()
+            .any(|s| s.addr == *feed)
+        {
+            return Err(crate::ProtocolError::NotFound);
+        }
+        Self::drop_source(env, asset, feed);
+        Ok(())
+    }
+
+    /// Set the largest move allowed from the last accepted price, in bps (admin only, 0 disables)
+    pub fn set_max_jump_bps(
+        env: &Env,
+        caller: &Address,
+        bps: i128,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        if !(0..=10000).contains(&bps) {
+            return Err(crate::ProtocolError::InvalidParameters);
+        }
+        OracleStorage::set_max_jump_bps(env, bps);
+        Ok(())
+    }
+
+    /// Median of the asset's feeds, checked against the last accepted price.
+    /// Failures carry a reason code from the `oracle` error namespace.
+    pub fn get_aggregated_price(env: &Env, asset: &Address) -> Result<i128, crate::ProtocolError> {
+        if OracleStorage::get_sources(env, asset).is_empty() {
+            return Err(OracleError::NoFeeds.into());
+        }
+        let price = Self::aggregate_price(env, asset).ok_or(OracleError::NoValidPrice)?;
+
+        let max_jump_bps = OracleStorage::get_max_jump_bps(env);
+        if let Some((last, _)) = OracleStorage::get_last_accepted(env, asset) {
+            let max_diff = last.saturating_mul(max_jump_bps) / 10000;
+            if max_jump_bps > 0 && (price - last).abs() > max_diff {
+                // Drop the cached aggregate so the next read refetches the feeds
+                let mut cache = OracleStorage::get_price_cache(env);
+                cache.remove(asset.clone());
+                OracleStorage::put_price_cache(env, &cache);
+                return Err(OracleError::DeviationExceeded.into());
+            }
+        }
+        OracleStorage::set_last_accepted(env, asset, price, env.ledger().timestamp());
+        Self::record_price_update(env, asset, price);
+        Ok(price)
+    }
+
+    /// Require an asset's price to be updated at least every `ttl` seconds (admin only,
+    /// 0 disables). Stale prices block borrows and withdrawals against debt and switch
+    /// liquidations to the no-bonus fallback.
+    pub fn set_asset_heartbeat(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        ttl: u64,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        OracleStorage::set_asset_heartbeat(env, asset, ttl);
+        env.events().publish(
+            (
+                Symbol::new(env, "oracle_heartbeat_set"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "ttl"),
+                ttl,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Mark an asset's price as updated now and record it as an observation
+    pub fn record_price_update(env: &Env, asset: &Address, price: i128) {
+        let now = env.ledger().timestamp();
+        OracleStorage::set_updated_at(env, asset, now);
+
+        let mut observations = OracleStorage::get_observations(env, asset);
+        let observation = PriceObservation {
+            price,
+            timestamp: now,
+        };
+        let len = observations.entries.len();
+        let latest = if len == 0 {
+            None
+        } else {
+            Some((observations.next + len - 1) % len)
+        };
+        match latest {
+            // Several updates in one ledger keep only the last price
+            Some(i) if observations.entries.get(i).map(|o| o.timestamp) == Some(now) => {
+                observations.entries.set(i, observation);
+            }
+            _ if len < MAX_OBSERVATIONS => observations.entries.push_back(observation),
+            _ => {
+                observations.entries.set(observations.next, observation);
+                observations.next = (observations.next + 1) % MAX_OBSERVATIONS;
+            }
+        }
+        OracleStorage::set_observations(env, asset, &observations);
+        crate::circuit_breaker::CircuitBreakers::refresh(env, asset);
+    }
+
+    /// Most recent price recorded for an asset, from its feeds or its registered price
+    pub fn latest_price(env: &Env, asset: &Address) -> Option<i128> {
+        Self::observations(env, asset)
+            .last()
+            .map(|observation| observation.price)
+    }
+
+    /// Observations of an asset, oldest first
+    pub fn observations(env: &Env, asset: &Address) -> Vec<PriceObservation> {
+        let observations = OracleStorage::get_observations(env, asset);
+        let len = observations.entries.len();
+        let mut ordered = Vec::new(env);
+        for i in 0..len {
+            if let Some(entry) = observations.entries.get((observations.next + i) % len) {
+                ordered.push_back(entry);
+            }
+        }
+        ordered
+    }
+
+    /// Time-weighted average over the configured window. Each observation holds until the
+    /// next one; the window is shortened to the first observation when history is shorter.
+    pub fn twap(env: &Env, asset: &Address) -> Option<i128> {
+        let observations = Self::observations(env, asset);
+        let last = observations.last()?;
+        let now = env.ledger().timestamp();
+        let start = now.saturating_sub(OracleStorage::get_twap_config(env).period);
+
+        let mut weighted: i128 = 0;
+        let mut elapsed: u64 = 0;
+        for i in 0..observations.len() {
+            let current = observations.get(i)?;
+            let end = observations
+                .get(i + 1)
+                .map(|next| next.timestamp)
+                .unwrap_or(now);
+            let from = current.timestamp.max(start);
+            if end <= from {
+                continue;
+            }
+            weighted = weighted.saturating_add(current.price.saturating_mul((end - from) as i128));
+            elapsed += end - from;
+        }
+        if elapsed == 0 {
+            return Some(last.price);
+        }
+        Some(weighted / elapsed as i128)
+    }
+
+    /// Volatility index: the price range over the TWAP window in basis points of the
+    /// window's lowest price (0 with fewer than two prices in the window)
+    pub fn volatility_bps(env: &Env, asset: &Address) -> i128 {
+        let observations = Self::observations(env, asset);
+        let now = env.ledger().timestamp();
+        let start = now.saturating_sub(OracleStorage::get_twap_config(env).period);
+
+        let mut low = i128::MAX;
+        let mut high = 0i128;
+        for (i, current) in observations.iter().enumerate() {
+            let end = observations
+                .get(i as u32 + 1)
+                .map(|next| next.timestamp)
+                .unwrap_or(now);
+            // Observations superseded before the window opened do not count
+            if end < start || current.price <= 0 {
+                continue;
+            }
+            low = low.min(current.price);
+            high = high.max(current.price);
+        }
+        if high <= low {
+            return 0;
+        }
+        (high - low).saturating_mul(10_000) / low
+    }
+
+    /// Set the TWAP window and whether position valuation uses it (admin only)
+    pub fn set_twap_config(
+        env: &Env,
+        caller: &Address,
+        period: u64,
+        use_for_risk: bool,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        if period == 0 {
+            return Err(crate::ProtocolError::InvalidParameters);
+        }
+        OracleStorage::set_twap_config(
+            env,
+            &TwapConfig {
+                period,
+                use_for_risk,
+            },
+        );
+        Ok(())
+    }
+
+    /// Price to value positions with: the TWAP when enabled for risk checks and
+    /// available, otherwise `spot`
+    pub fn risk_price(env: &Env, asset: &Address, spot: i128) -> i128 {
+        if !OracleStorage::get_twap_config(env).use_for_risk {
+            return spot;
+        }
+        Self::twap(env, asset).unwrap_or(spot)
+    }
+
+    pub fn price_freshness(env: &Env, asset: &Address) -> PriceFreshness {
+        let last_update = OracleStorage::get_updated_at(env, asset);
+        let heartbeat = OracleStorage::get_asset_heartbeat(env, asset);
+        let stale =
+            heartbeat > 0 && env.ledger().timestamp().saturating_sub(last_update) > heartbeat;
+        PriceFreshness {
+            asset: asset.clone(),
+            last_update,
+            heartbeat,
+            stale,
+        }
+    }
+
+    pub fn is_stale(env: &Env, asset: &Address) -> bool {
+        Self::price_freshness(env, asset).stale
+    }
+
+    /// Reject operations that depend on a stale price
+    pub fn ensure_fresh(env: &Env, asset: &Address) -> Result<(), crate::ProtocolError> {
+        if Self::is_stale(env, asset) {
+            return Err(OracleError::StalePrice.into());
+        }
+        Ok(())
+    }
+
+    /// `ensure_fresh` for the primary asset, if one is configured
+    pub fn ensure_primary_fresh(env: &Env) -> Result<(), crate::ProtocolError> {
+        match crate::TokenRegistry::require_primary_asset(env) {
+            Ok(asset) => Self::ensure_fresh(env, &asset),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// `ensure_fresh` for every asset a multi-asset position holds or owes
+    pub fn ensure_position_fresh(
+        env: &Env,
+        position: &crate::CrossPosition,
+    ) -> Result<(), crate::ProtocolError> {
+        for asset in position.collateral.keys().iter() {
+            Self::ensure_fresh(env, &asset)?;
+        }
+        for asset in position.debt.keys().iter() {
+            Self::ensure_fresh(env, &asset)?;
+        }
+        Ok(())
+    }
+
+    /// Set hard floor/ceiling bounds for an asset's price (admin only)
+    pub fn set_price_bounds(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        floor: i128,
+        ceiling: i128,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        if floor <= 0 || ceiling < floor {
+            return Err(crate::ProtocolError::InvalidParameters);
+        }
+        OracleStorage::put_bounds(
+            env,
+            &PriceBounds {
+                asset: asset.clone(),
+                floor,
+                ceiling,
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove an asset's price bounds (admin only)
+    pub fn clear_price_bounds(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+    ) -> Result<(), crate::ProtocolError> {
+        crate::UserManager::require_admin(env, caller)?;
+        OracleStorage::remove_bounds(env, asset);
+        Ok(())
+    }
+
+    /// Bounds for every asset that has them configured
+    pub fn list_price_bounds(env: &Env) -> Vec<PriceBounds> {
+        let mut out = Vec::new(env);
+        for asset in OracleStorage::get_bounded_assets(env).iter() {
+            if let Some(bounds) = OracleStorage::get_bounds(env, &asset) {
+                out.push_back(bounds);
+            }
+        }
+        out
+    }
+
+    /// Whether a pushed or externally read price is within the asset's bounds. An
+    /// outlier is recorded and flagged; callers skip it rather than fail, since a failed
+    /// call would revert the record.
+    pub fn check_price(env: &Env, asset: &Address, source: Option<Address>, price: i128) -> bool {
+        let bounds = match OracleStorage::get_bounds(env, asset) {
+            Some(bounds) => bounds,
+            None => return true,
+        };
+        if price >= bounds.floor && price <= bounds.ceiling {
+            return true;
+        }
+        OracleStorage::push_rejection(
+            env,
+            PriceRejection {
+                asset: asset.clone(),
+                source,
+                price,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+        env.events().publish(
+            (
+                Symbol::new(env, "price_rejected"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "price"),
+                price,
+                Symbol::new(env, "floor"),
+                bounds.floor,
+                Symbol::new(env, "ceiling"),
+                bounds.ceiling,
+            ),
+        );
+        false
+    }
+
     /// Fetch prices from all sources (stubbed as calling `get_price()` on source contracts)
     /// Policies:
     /// - Staleness: drop sources whose last_heartbeat is older than TTL
     /// - Non-positive prices are ignored
+    /// - Prices outside the asset's bounds are rejected and flagged
     pub fn fetch_prices(env: &Env, asset: &Address) -> Vec<i128> {
         let list = OracleStorage::get_sources(env, asset);
         let ttl = OracleStorage::get_heartbeat_ttl(env);
@@ -236,7 +868,7 @@ impl Oracle {
             // Try calling a standard oracle interface: fn get_price(asset: Address) -> i128
             let args = vec![env, asset.clone().into_val(env)];
             let price: i128 = env.invoke_contract(&s.addr, &Symbol::new(env, "get_price"), args);
-            if price > 0 {
+            if price > 0 && Self::check_price(env, asset, Some(s.addr.clone()), price) {
                 prices.push_back(price);
             }
         }
@@ -252,7 +884,11 @@ impl Oracle {
         let now = env.ledger().timestamp();
         let mut cache = OracleStorage::get_price_cache(env);
         if let Some((cached, ts)) = cache.get(asset.clone()) {
-            if now.saturating_sub(ts) <= ttl {
+            // Bounds may have been tightened since the price was cached
+            let in_bounds = OracleStorage::get_bounds(env, asset)
+                .map(|b| cached >= b.floor && cached <= b.ceiling)
+                .unwrap_or(true);
+            if in_bounds && now.saturating_sub(ts) <= ttl {
                 // cache hit
                 crate::ProtocolEvent::CacheUpdated(
                     Symbol::new(env, "oracle_price_cache"),
@@ -278,6 +914,7 @@ impl Oracle {
         if n_usize == 0 {
             return None;
         }
+        crate::watchdog::Watchdog::heartbeat(env, "oracle");
         let mode = OracleStorage::get_mode(env);
         if mode == 1 {
             // TWAP approximation: simple average for now; window size informs minimal sample need
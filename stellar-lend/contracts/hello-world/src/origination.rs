@@ -0,0 +1,143 @@
+//! Origination fee module for StellarLend protocol
+//! A one-time fee charged when debt is drawn. The fee is added to the borrower's debt on
+//! top of the amount they receive and credited to the asset's protocol reserves, so it is
+//! collected as the debt is repaid. The admin sets a protocol-wide rate and may override
+//! it per asset.
+
+use crate::persistent::PersistentStore;
+use crate::reserves::Reserves;
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Fees are basis points of the amount drawn
+const BPS_SCALE: i128 = 10_000;
+
+/// Highest origination fee the admin may set (10%)
+const MAX_FEE_BPS: i128 = 1_000;
+
+/// Storage helper for origination fee rates
+pub struct OriginationFeeStorage;
+
+impl OriginationFeeStorage {
+    fn default_key(env: &Env) -> Symbol {
+        Symbol::new(env, "origination_fee")
+    }
+
+    fn asset_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "origination_fee"), asset.clone())
+    }
+
+    pub fn get_default(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::default_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn save_default(env: &Env, fee_bps: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::default_key(env), &fee_bps);
+    }
+
+    pub fn get_override(env: &Env, asset: &Address) -> Option<i128> {
+        PersistentStore::get(env, &Self::asset_key(env, asset))
+    }
+
+    pub fn save_override(env: &Env, asset: &Address, fee_bps: Option<i128>) {
+        let key = Self::asset_key(env, asset);
+        match fee_bps {
+            Some(fee_bps) => PersistentStore::set(env, &key, &fee_bps),
+            None => PersistentStore::remove(env, &key),
+        }
+    }
+}
+
+/// Origination fee rates and collection
+pub struct OriginationFees;
+
+impl OriginationFees {
+    /// Set the protocol-wide fee (admin only)
+    pub fn set_default(env: &Env, caller: &Address, fee_bps: i128) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        Self::validate(fee_bps)?;
+        OriginationFeeStorage::save_default(env, fee_bps);
+        Self::publish_update(env, None, fee_bps);
+        Ok(())
+    }
+
+    /// Set or, with `None`, clear an asset's fee override (admin only)
+    pub fn set_override(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        fee_bps: Option<i128>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let Some(fee_bps) = fee_bps {
+            Self::validate(fee_bps)?;
+        }
+        OriginationFeeStorage::save_override(env, asset, fee_bps);
+        Self::publish_update(env, Some(asset), Self::fee_bps(env, asset));
+        Ok(())
+    }
+
+    /// Fee charged on `asset`: its override, else the protocol-wide rate
+    pub fn fee_bps(env: &Env, asset: &Address) -> i128 {
+        OriginationFeeStorage::get_override(env, asset)
+            .unwrap_or_else(|| OriginationFeeStorage::get_default(env))
+    }
+
+    /// Fee owed for drawing `amount` of `asset`, rounded up
+    pub fn fee_for(env: &Env, asset: &Address, amount: i128) -> i128 {
+        let fee_bps = Self::fee_bps(env, asset);
+        if fee_bps == 0 || amount <= 0 {
+            return 0;
+        }
+        (amount.saturating_mul(fee_bps) + BPS_SCALE - 1) / BPS_SCALE
+    }
+
+    /// Credit a charged `fee` to the asset's reserves
+    pub fn collect(env: &Env, user: &Address, asset: &Address, fee: i128) {
+        if fee <= 0 {
+            return;
+        }
+        Reserves::credit(env, asset, fee);
+        env.events().publish(
+            (
+                Symbol::new(env, "origination_fee"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "fee"),
+                fee,
+            ),
+        );
+    }
+
+    fn validate(fee_bps: i128) -> Result<(), ProtocolError> {
+        if !(0..=MAX_FEE_BPS).contains(&fee_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        Ok(())
+    }
+
+    fn publish_update(env: &Env, asset: Option<&Address>, fee_bps: i128) {
+        env.events().publish(
+            (
+                Symbol::new(env, "origination_fee_updated"),
+                Symbol::new(env, "admin"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.cloned(),
+                Symbol::new(env, "fee_bps"),
+                fee_bps,
+            ),
+        );
+    }
+}
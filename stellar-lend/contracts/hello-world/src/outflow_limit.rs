@@ -0,0 +1,196 @@
+//! Outflow limit module for StellarLend protocol
+//! Per-asset rate limits on borrows plus withdrawals: the amount leaving within a sliding
+//! window cannot exceed a configured share of the asset's liquidity, blunting bank runs
+//! and exploit drains.
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, TokenRegistry};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol, Vec};
+
+/// Limits are basis points of liquidity (10000 = all of it)
+const BPS_SCALE: i128 = 10_000;
+
+/// Buckets a window is split into; outflows age out one bucket at a time
+const BUCKETS: u64 = 12;
+
+/// Outflow-limit errors
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum OutflowError {
+    LimitExceeded = 10001,
+}
+
+impl From<OutflowError> for ProtocolError {
+    fn from(err: OutflowError) -> Self {
+        match err {
+            OutflowError::LimitExceeded => ProtocolError::InsufficientLiquidity,
+        }
+    }
+}
+
+/// Outflow limit of one asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OutflowConfig {
+    /// Sliding window length in seconds
+    pub window: u64,
+    /// Maximum outflow within the window in basis points of liquidity
+    pub max_outflow_bps: i128,
+}
+
+/// Window capacity of an asset at the current time
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OutflowCapacity {
+    pub asset: Address,
+    pub window: u64,
+    /// Borrowed and withdrawn within the window
+    pub used: i128,
+    /// Limit over the liquidity held before the window's outflows
+    pub limit: i128,
+    pub remaining: i128,
+}
+
+/// Storage helper for outflow configs and per-bucket totals
+pub struct OutflowStorage;
+
+impl OutflowStorage {
+    fn config_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "outflow_config"), asset.clone())
+    }
+    fn log_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "outflow_log"), asset.clone())
+    }
+
+    pub fn get_config(env: &Env, asset: &Address) -> Option<OutflowConfig> {
+        PersistentStore::get(env, &Self::config_key(env, asset))
+    }
+
+    pub fn save_config(env: &Env, asset: &Address, config: &OutflowConfig) {
+        PersistentStore::set(env, &Self::config_key(env, asset), config);
+    }
+
+    /// Outflow per bucket as `(bucket start, amount)`, oldest first
+    pub fn get_log(env: &Env, asset: &Address) -> Vec<(u64, i128)> {
+        PersistentStore::get(env, &Self::log_key(env, asset)).unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_log(env: &Env, asset: &Address, log: &Vec<(u64, i128)>) {
+        PersistentStore::set(env, &Self::log_key(env, asset), log);
+    }
+
+    pub fn remove(env: &Env, asset: &Address) {
+        PersistentStore::remove(env, &Self::config_key(env, asset));
+        PersistentStore::remove(env, &Self::log_key(env, asset));
+    }
+
+    pub fn bump(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::config_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::log_key(env, asset)) as u32
+    }
+}
+
+/// Sliding-window outflow limits
+pub struct OutflowLimits;
+
+impl OutflowLimits {
+    /// Set an asset's outflow limit (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        config: OutflowConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.window == 0 || !(1..=BPS_SCALE).contains(&config.max_outflow_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        OutflowStorage::save_config(env, asset, &config);
+        Ok(())
+    }
+
+    /// Remove an asset's outflow limit and its window history (admin only)
+    pub fn clear_config(env: &Env, caller: &Address, asset: &Address) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        OutflowStorage::remove(env, asset);
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env, asset: &Address) -> Option<OutflowConfig> {
+        OutflowStorage::get_config(env, asset)
+    }
+
+    fn bucket_len(config: &OutflowConfig) -> u64 {
+        (config.window / BUCKETS).max(1)
+    }
+
+    /// Buckets still inside the window ending now
+    fn live_log(env: &Env, asset: &Address, config: &OutflowConfig) -> Vec<(u64, i128)> {
+        let now = env.ledger().timestamp();
+        let mut live = Vec::new(env);
+        for (start, amount) in OutflowStorage::get_log(env, asset).iter() {
+            if start + Self::bucket_len(config) + config.window > now {
+                live.push_back((start, amount));
+            }
+        }
+        live
+    }
+
+    fn capacity_of(
+        env: &Env,
+        asset: &Address,
+        config: &OutflowConfig,
+        log: &Vec<(u64, i128)>,
+    ) -> OutflowCapacity {
+        let used = log.iter().fold(0i128, |sum, (_, amount)| sum + amount);
+        let balance = TokenClient::new(env, asset).balance(&env.current_contract_address());
+        let limit = (balance + used).saturating_mul(config.max_outflow_bps) / BPS_SCALE;
+        OutflowCapacity {
+            asset: asset.clone(),
+            window: config.window,
+            used,
+            limit,
+            remaining: (limit - used).max(0),
+        }
+    }
+
+    /// Remaining outflow capacity of an asset, `None` without a limit
+    pub fn capacity(env: &Env, asset: &Address) -> Option<OutflowCapacity> {
+        let config = OutflowStorage::get_config(env, asset)?;
+        let log = Self::live_log(env, asset, &config);
+        Some(Self::capacity_of(env, asset, &config, &log))
+    }
+
+    /// Count a borrow or withdrawal of `amount` against the asset's window, failing with
+    /// `InsufficientLiquidity` when it would go over the limit
+    pub fn record(env: &Env, asset: &Address, amount: i128) -> Result<(), ProtocolError> {
+        let config = match OutflowStorage::get_config(env, asset) {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+        let mut log = Self::live_log(env, asset, &config);
+        if amount > Self::capacity_of(env, asset, &config, &log).remaining {
+            return Err(OutflowError::LimitExceeded.into());
+        }
+
+        let now = env.ledger().timestamp();
+        let bucket = now - now % Self::bucket_len(&config);
+        let last = log.len().checked_sub(1);
+        match last.and_then(|i| log.get(i).map(|entry| (i, entry))) {
+            Some((i, (start, total))) if start == bucket => log.set(i, (start, total + amount)),
+            _ => log.push_back((bucket, amount)),
+        }
+        OutflowStorage::save_log(env, asset, &log);
+        Ok(())
+    }
+
+    /// `record` for the primary asset, when one is registered
+    pub fn record_primary(env: &Env, amount: i128) -> Result<(), ProtocolError> {
+        match TokenRegistry::require_primary_asset(env) {
+            Ok(asset) => Self::record(env, &asset, amount),
+            Err(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Permit module for StellarLend protocol
+//! Meta-transactions for deposits and repayments: the user signs a permit covering the
+//! action, asset, amount, nonce and deadline, and any relayer can submit it while paying
+//! the transaction fees. The user's address authorizes exactly those structured arguments
+//! and each nonce can be used once.
+
+use crate::deposit::DepositModule;
+use crate::modules::ModuleRegistry;
+use crate::persistent::PersistentStore;
+use crate::repay::RepayModule;
+use crate::ProtocolError;
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Symbol};
+
+/// Terms a user signs for a relayed deposit or repayment
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Permit {
+    /// Asset of the multi-asset position, `None` for the primary position
+    pub asset: Option<Address>,
+    pub amount: i128,
+    /// Must equal the user's current permit nonce
+    pub nonce: u64,
+    /// Last ledger timestamp the permit can be submitted at
+    pub deadline: u64,
+}
+
+/// Storage helper for permit nonces
+pub struct PermitStorage;
+
+impl PermitStorage {
+    fn nonce_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "permit_nonce"), user.clone())
+    }
+
+    pub fn get_nonce(env: &Env, user: &Address) -> u64 {
+        PersistentStore::get(env, &Self::nonce_key(env, user)).unwrap_or(0)
+    }
+
+    pub fn save_nonce(env: &Env, user: &Address, nonce: u64) {
+        PersistentStore::set(env, &Self::nonce_key(env, user), &nonce);
+    }
+
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::nonce_key(env, user)) as u32
+    }
+}
+
+/// Relayed deposits and repayments authorized by signed permits
+pub struct Permits;
+
+impl Permits {
+    pub fn nonce(env: &Env, user: &Address) -> u64 {
+        PermitStorage::get_nonce(env, user)
+    }
+
+    /// Check the user's signature over `(action, permit)` and consume the permit's nonce.
+    /// Replayed or out-of-order nonces fail with `InvalidParameters`, expired permits
+    /// with `InvalidOperation`.
+    fn consume(
+        env: &Env,
+        action: &str,
+        user: &Address,
+        permit: &Permit,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth_for_args((Symbol::new(env, action), permit.clone()).into_val(env));
+        if env.ledger().timestamp() > permit.deadline {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let nonce = PermitStorage::get_nonce(env, user);
+        if permit.nonce != nonce {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        PermitStorage::save_nonce(env, user, nonce + 1);
+        Ok(())
+    }
+
+    /// Deposit collateral from the user's balance under a signed permit
+    pub fn deposit(env: &Env, user: &Address, permit: Permit) -> Result<(), ProtocolError> {
+        Self::consume(env, "permit_deposit", user, &permit)?;
+        match &permit.asset {
+            None => ModuleRegistry::deposit_collateral(env, user, user, permit.amount)?,
+            Some(asset) => {
+                DepositModule::deposit_collateral_asset(env, user, user, asset, permit.amount)?
+            }
+        }
+        Self::publish(env, "deposit", user, &permit);
+        Ok(())
+    }
+
+    /// Repay debt from the user's balance under a signed permit
+    pub fn repay(env: &Env, user: &Address, permit: Permit) -> Result<(), ProtocolError> {
+        Self::consume(env, "permit_repay", user, &permit)?;
+        match &permit.asset {
+            None => ModuleRegistry::repay(env, user, user, permit.amount)?,
+            Some(asset) => {
+                RepayModule::repay_asset(env, user, user, asset, permit.amount)?;
+            }
+        }
+        Self::publish(env, "repay", user, &permit);
+        Ok(())
+    }
+
+    fn publish(env: &Env, action: &str, user: &Address, permit: &Permit) {
+        env.events().publish(
+            (Symbol::new(env, "permit_used"), Symbol::new(env, action)),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "asset"),
+                permit.asset.clone(),
+                Symbol::new(env, "amount"),
+                permit.amount,
+                Symbol::new(env, "nonce"),
+                permit.nonce,
+            ),
+        );
+    }
+}
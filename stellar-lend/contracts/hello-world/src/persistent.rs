@@ -0,0 +1,170 @@
+//! Persistent storage layer for StellarLend protocol
+//! Per-user and per-asset entries live in persistent storage so they do not count against
+//! the instance size limit. Reads and writes extend the entry's TTL; `bump_storage` lets the
+//! admin refresh entries that have not been touched recently.
+
+use crate::analytics::AnalyticsStorage;
+use crate::auction::AuctionStorage;
+use crate::auth_adapter::AuthAdapterStorage;
+use crate::auto_repay::AutoRepayStorage;
+use crate::beneficiary::BeneficiaryStorage;
+use crate::bootstrap::BootstrapStorage;
+use crate::circuit_breaker::BreakerStorage;
+use crate::cross_asset::AssetPriceStorage;
+use crate::governance::GovStorage;
+use crate::health_index::HealthIndexStorage;
+use crate::insurance::InsuranceStorage;
+use crate::oracle::OracleStorage;
+use crate::outflow_limit::OutflowStorage;
+use crate::permit::PermitStorage;
+use crate::position_history::PositionHistoryStorage;
+use crate::quarantine::QuarantineStorage;
+use crate::rebate::RebateStorage;
+use crate::referral::ReferralStorage;
+use crate::release_schedule::ReleaseScheduleStorage;
+use crate::reserves::ReserveStorage;
+use crate::rewards::RewardStorage;
+use crate::risk_matrix::CollateralMatrixStorage;
+use crate::safety_rewards::SafetyRewardStorage;
+use crate::soft_limits::SoftLimitStorage;
+use crate::stable_rate::StableRateStorage;
+use crate::tvl_cap::TvlCapStorage;
+use crate::{
+    EventStorage, InterestRateStorage, ProtocolConfig, ProtocolError, StateHelper, UserManager,
+};
+use soroban_sdk::{Address, Env, IntoVal, TryFromVal, Val, Vec};
+
+/// Ledgers per day at a 5 second close time
+pub const DAY_IN_LEDGERS: u32 = 17280;
+
+/// Entries are extended once their remaining TTL drops below this
+pub const TTL_THRESHOLD: u32 = 7 * DAY_IN_LEDGERS;
+
+/// TTL an entry is extended to
+pub const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+
+/// Maximum users plus assets refreshed per `bump_storage` call
+const MAX_BUMP_TARGETS: u32 = 50;
+
+/// Persistent storage access with TTL extension on every touch
+pub struct PersistentStore;
+
+impl PersistentStore {
+    pub fn get<K, V>(env: &Env, key: &K) -> Option<V>
+    where
+        K: IntoVal<Env, Val>,
+        V: TryFromVal<Env, Val>,
+    {
+        let value = env.storage().persistent().get(key);
+        if value.is_some() {
+            Self::extend(env, key);
+        }
+        value
+    }
+
+    pub fn set<K, V>(env: &Env, key: &K, value: &V)
+    where
+        K: IntoVal<Env, Val>,
+        V: IntoVal<Env, Val>,
+    {
+        env.storage().persistent().set(key, value);
+        Self::extend(env, key);
+    }
+
+    pub fn remove<K>(env: &Env, key: &K)
+    where
+        K: IntoVal<Env, Val>,
+    {
+        env.storage().persistent().remove(key);
+    }
+
+    /// Extend an existing entry's TTL; returns false when the entry does not exist
+    pub fn bump<K>(env: &Env, key: &K) -> bool
+    where
+        K: IntoVal<Env, Val>,
+    {
+        if !env.storage().persistent().has(key) {
+            return false;
+        }
+        Self::extend(env, key);
+        true
+    }
+
+    pub fn bump_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+
+    fn extend<K>(env: &Env, key: &K)
+    where
+        K: IntoVal<Env, Val>,
+    {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
+/// Admin TTL maintenance
+pub struct StorageMaintenance;
+
+impl StorageMaintenance {
+    /// Refresh the instance, the shared persistent logs, and every entry held for the
+    /// given users and assets (admin only). Returns the number of persistent entries bumped.
+    pub fn bump_storage(
+        env: &Env,
+        caller: &Address,
+        users: Vec<Address>,
+        assets: Vec<Address>,
+    ) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if users.len() + assets.len() > MAX_BUMP_TARGETS {
+            return Err(ProtocolError::InvalidInput);
+        }
+
+        PersistentStore::bump_instance(env);
+        let mut bumped = EventStorage::bump(env) + AnalyticsStorage::bump_shared(env);
+        bumped += crate::amm::AMMStorage::bump(env) + RebateStorage::bump(env);
+        for user in users.iter() {
+            bumped += StateHelper::bump(env, &user)
+                + UserManager::bump(env, &user)
+                + AnalyticsStorage::bump_user(env, &user)
+                + BeneficiaryStorage::bump(env, &user)
+                + SoftLimitStorage::bump(env, &user)
+                + SafetyRewardStorage::bump(env, &user)
+                + AuctionStorage::bump(env, &user)
+                + HealthIndexStorage::bump(env, &user)
+                + QuarantineStorage::bump(env, &user)
+                + GovStorage::bump(env, &user)
+                + ReferralStorage::bump(env, &user)
+                + RewardStorage::bump(env, &user)
+                + PermitStorage::bump(env, &user)
+                + PositionHistoryStorage::bump(env, &user)
+                + StableRateStorage::bump(env, &user)
+                + AuthAdapterStorage::bump(env, &user)
+                + AutoRepayStorage::bump(env, &user)
+                + ReleaseScheduleStorage::bump(env, &user)
+                + InsuranceStorage::bump(env, &user);
+            for asset in assets.iter() {
+                bumped += BootstrapStorage::bump_participant(env, &asset, &user);
+            }
+        }
+        for asset in assets.iter() {
+            bumped += InterestRateStorage::bump_market(env, &asset)
+                + AssetPriceStorage::bump(env, &asset)
+                + ReserveStorage::bump(env, &asset)
+                + RewardStorage::bump_market(env, &asset)
+                + BreakerStorage::bump(env, &asset)
+                + OutflowStorage::bump(env, &asset)
+                + OracleStorage::bump_asset(env, &asset)
+                + TvlCapStorage::bump_asset(env, &asset)
+                + InsuranceStorage::bump_asset(env, &asset)
+                + BootstrapStorage::bump_asset(env, &asset);
+            for debt in assets.iter() {
+                bumped += CollateralMatrixStorage::bump(env, &asset, &debt);
+            }
+        }
+        Ok(bumped)
+    }
+}
@@ -0,0 +1,106 @@
+//! Position history module for StellarLend protocol
+//! Every write of a user's primary position appends a compact snapshot (time, collateral,
+//! debt and health factor) to a per-user ring buffer of the last `MAX_SNAPSHOTS` entries,
+//! so users can see how their position evolved. Several writes in one ledger keep only
+//! the last state.
+
+use crate::persistent::PersistentStore;
+use crate::safety_rewards::SafetyRewards;
+use crate::Position;
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Snapshots kept per user
+const MAX_SNAPSHOTS: u32 = 30;
+
+/// A position's state after one write
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PositionSnapshot {
+    pub timestamp: u64,
+    pub collateral: i128,
+    pub debt: i128,
+    /// Health factor in percent (100 = at the minimum ratio), `None` without debt
+    pub health_factor: Option<i128>,
+}
+
+/// Fixed-size ring buffer of a user's recent position snapshots
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PositionHistoryBuffer {
+    pub entries: Vec<PositionSnapshot>,
+    /// Slot the next snapshot is written to once the buffer is full
+    pub next: u32,
+}
+
+/// Storage helper for position history
+pub struct PositionHistoryStorage;
+
+impl PositionHistoryStorage {
+    fn key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "position_history"), user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> PositionHistoryBuffer {
+        PersistentStore::get(env, &Self::key(env, user)).unwrap_or_else(|| PositionHistoryBuffer {
+            entries: Vec::new(env),
+            next: 0,
+        })
+    }
+
+    pub fn save(env: &Env, user: &Address, buffer: &PositionHistoryBuffer) {
+        PersistentStore::set(env, &Self::key(env, user), buffer);
+    }
+
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::key(env, user)) as u32
+    }
+}
+
+/// Per-user position history
+pub struct PositionHistory;
+
+impl PositionHistory {
+    /// Record the position's new state; called whenever a primary position is saved
+    pub fn on_position_change(env: &Env, position: &Position) {
+        let now = env.ledger().timestamp();
+        let snapshot = PositionSnapshot {
+            timestamp: now,
+            collateral: position.collateral,
+            debt: position.debt,
+            health_factor: SafetyRewards::health_factor(env, position),
+        };
+        let mut buffer = PositionHistoryStorage::get(env, &position.user);
+        let len = buffer.entries.len();
+        let latest = if len == 0 {
+            None
+        } else {
+            Some((buffer.next + len - 1) % len)
+        };
+        match latest {
+            Some(i) if buffer.entries.get(i).map(|s| s.timestamp) == Some(now) => {
+                buffer.entries.set(i, snapshot);
+            }
+            _ if len < MAX_SNAPSHOTS => buffer.entries.push_back(snapshot),
+            _ => {
+                buffer.entries.set(buffer.next, snapshot);
+                buffer.next = (buffer.next + 1) % MAX_SNAPSHOTS;
+            }
+        }
+        PositionHistoryStorage::save(env, &position.user, &buffer);
+    }
+
+    /// Up to `limit` of the user's most recent snapshots, oldest first. A zero limit
+    /// returns every kept snapshot.
+    pub fn history(env: &Env, user: &Address, limit: u32) -> Vec<PositionSnapshot> {
+        let buffer = PositionHistoryStorage::get(env, user);
+        let len = buffer.entries.len();
+        let count = if limit == 0 { len } else { limit.min(len) };
+        let mut ordered = Vec::new(env);
+        for i in (len - count)..len {
+            if let Some(entry) = buffer.entries.get((buffer.next + i) % len) {
+                ordered.push_back(entry);
+            }
+        }
+        ordered
+    }
+}
@@ -0,0 +1,203 @@
+//! Position transfer module for StellarLend protocol
+//! Hands a user's whole position (primary and multi-asset collateral and debt) to another
+//! address. The owner offers the transfer and the recipient accepts it; on acceptance both
+//! users' limits are checked and the position must still be healthy under its new owner.
+
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::oracle::Oracle;
+use crate::persistent::PersistentStore;
+use crate::release_schedule::ReleaseSchedule;
+use crate::safety_rewards::SafetyRewards;
+use crate::{
+    CrossPosition, InterestRateManager, InterestRateStorage, OperationKind, Position,
+    ProtocolError, ReentrancyScope, StateHelper,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Transfer offered by a position owner, waiting for the recipient
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PendingPositionTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub offered_at: u64,
+}
+
+/// Storage helper for pending transfers, one per owner
+pub struct PositionTransferStorage;
+
+impl PositionTransferStorage {
+    fn key(env: &Env, from: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "pos_transfer"), from.clone())
+    }
+
+    pub fn get(env: &Env, from: &Address) -> Option<PendingPositionTransfer> {
+        PersistentStore::get(env, &Self::key(env, from))
+    }
+
+    pub fn save(env: &Env, pending: &PendingPositionTransfer) {
+        PersistentStore::set(env, &Self::key(env, &pending.from), pending);
+    }
+
+    pub fn remove(env: &Env, from: &Address) {
+        PersistentStore::remove(env, &Self::key(env, from));
+    }
+}
+
+/// Offer/accept handover of whole positions
+pub struct PositionTransfers;
+
+impl PositionTransfers {
+    /// Offer `from`'s position to `to`, replacing any earlier offer
+    pub fn offer(
+        env: &Env,
+        from: &Address,
+        to: &Address,
+    ) -> Result<PendingPositionTransfer, ProtocolError> {
+        from.require_auth();
+        if from == to {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if !Self::holds_position(env, from) {
+            return Err(ProtocolError::PositionNotFound);
+        }
+        let pending = PendingPositionTransfer {
+            from: from.clone(),
+            to: to.clone(),
+            offered_at: env.ledger().timestamp(),
+        };
+        PositionTransferStorage::save(env, &pending);
+        Self::publish(env, "position_transfer_offered", from, to);
+        Ok(pending)
+    }
+
+    /// Withdraw the pending offer
+    pub fn cancel(env: &Env, from: &Address) -> Result<(), ProtocolError> {
+        from.require_auth();
+        let pending = PositionTransferStorage::get(env, from).ok_or(ProtocolError::NotFound)?;
+        PositionTransferStorage::remove(env, from);
+        Self::publish(env, "position_transfer_cancelled", from, &pending.to);
+        Ok(())
+    }
+
+    /// Take over `from`'s position as the recipient of its pending offer. The recipient
+    /// must not hold a position of its own and the owner may have no locked collateral.
+    pub fn accept(env: &Env, to: &Address, from: &Address) -> Result<(), ProtocolError> {
+        to.require_auth();
+        let pending = PositionTransferStorage::get(env, from).ok_or(ProtocolError::NotFound)?;
+        if pending.to != *to {
+            return Err(ProtocolError::Unauthorized);
+        }
+        if Self::holds_position(env, to) || ReleaseSchedule::locked_amount(env, from) > 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let _guard = ReentrancyScope::enter(env)?;
+
+        let mut primary = StateHelper::get_position(env, from);
+        if let Some(position) = primary.as_mut() {
+            let state = InterestRateStorage::update_state(env);
+            InterestRateManager::accrue_interest_for_position(env, position, &state);
+        }
+        let mut cross = StateHelper::get_cross_position(env, from);
+        if let Some(position) = cross.as_mut() {
+            InterestRateManager::accrue_interest_for_cross_position(env, position);
+        }
+
+        // The owner gives up everything; the recipient takes on all of it
+        let (collateral, debt) = primary
+            .as_ref()
+            .map(|p| (p.collateral, p.debt))
+            .unwrap_or((0, 0));
+        let (cross_collateral, cross_debt) = match cross.as_ref() {
+            Some(position) => (
+                position.collateral.values().iter().sum::<i128>(),
+                position.debt.values().iter().sum::<i128>(),
+            ),
+            None => (0, 0),
+        };
+        OperationGate::check(
+            env,
+            from,
+            OperationKind::Withdraw,
+            None,
+            collateral + cross_collateral,
+        )?;
+        OperationGate::check(
+            env,
+            to,
+            OperationKind::Deposit,
+            None,
+            collateral + cross_collateral,
+        )?;
+        if debt + cross_debt > 0 {
+            OperationGate::check(env, to, OperationKind::Borrow, None, debt + cross_debt)?;
+        }
+
+        // Revalidate health under the new owner
+        if let Some(position) = primary.as_ref() {
+            if SafetyRewards::health_factor(env, position).is_some_and(|hf| hf < 100) {
+                return Err(ProtocolError::InsufficientCollateralRatio);
+            }
+        }
+        if let Some(position) = cross.as_ref() {
+            if !position.debt.is_empty() {
+                Oracle::ensure_position_fresh(env, position)?;
+            }
+            if !CrossAssets::is_healthy(env, position)? {
+                return Err(ProtocolError::InsufficientCollateralRatio);
+            }
+        }
+
+        PositionTransferStorage::remove(env, from);
+        if let Some(position) = primary {
+            StateHelper::save_position(env, &Position::new(from.clone(), 0, 0));
+            StateHelper::save_position(
+                env,
+                &Position {
+                    user: to.clone(),
+                    ..position
+                },
+            );
+        }
+        if let Some(position) = cross {
+            StateHelper::save_cross_position(env, &CrossPosition::new(env, from.clone()));
+            StateHelper::save_cross_position(
+                env,
+                &CrossPosition {
+                    user: to.clone(),
+                    ..position
+                },
+            );
+        }
+        Self::publish(env, "position_transferred", from, to);
+        Ok(())
+    }
+
+    pub fn get_pending(env: &Env, from: &Address) -> Option<PendingPositionTransfer> {
+        PositionTransferStorage::get(env, from)
+    }
+
+    /// Whether the user has any collateral or debt
+    fn holds_position(env: &Env, user: &Address) -> bool {
+        let primary = StateHelper::get_position(env, user)
+            .is_some_and(|position| position.collateral > 0 || position.debt > 0);
+        let cross = StateHelper::get_cross_position(env, user).is_some_and(|position| {
+            position.collateral.values().iter().any(|amount| amount > 0)
+                || position.debt.values().iter().any(|amount| amount > 0)
+        });
+        primary || cross
+    }
+
+    fn publish(env: &Env, action: &str, from: &Address, to: &Address) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "from"),
+                from.clone(),
+                Symbol::new(env, "to"),
+                to.clone(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Preview module for StellarLend protocol
+//! Read-only simulations of a primary borrow, withdrawal or liquidation. Each preview
+//! accrues the position's debt to now without writing it, applies the same fees and
+//! checks as the real call and reports the resulting position, so wallets can show what
+//! a transaction will do and bots can skip calls that would fail. A preview fails with
+//! the error the call would fail with; pauses and other operation gate layers are
+//! reported separately by `get_operation_gate_status`.
+
+use crate::auction::LiquidationAuctions;
+use crate::bootstrap::Bootstrap;
+use crate::liquidate::LiquidationModule;
+use crate::liquidation_grace::LiquidationGrace;
+use crate::liquidity_guard::LiquidityGuard;
+use crate::origination::OriginationFees;
+use crate::rebate::RebatePool;
+use crate::release_schedule::ReleaseSchedule;
+use crate::risk_matrix::CollateralMatrix;
+use crate::safety_rewards::SafetyRewards;
+use crate::{Position, ProtocolError, StateHelper, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env};
+
+/// Outcome of a borrow
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BorrowPreview {
+    /// Tokens sent to the borrower
+    pub received: i128,
+    /// Origination fee added to the debt
+    pub fee: i128,
+    pub collateral: i128,
+    /// Debt after the borrow, including accrued interest and the fee
+    pub debt: i128,
+    /// Collateral ratio after the borrow in percent
+    pub collateral_ratio: i128,
+    /// Health factor after the borrow (100 = at the minimum ratio)
+    pub health_factor: Option<i128>,
+}
+
+/// Outcome of a withdrawal
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WithdrawPreview {
+    /// Tokens sent to the recipient
+    pub received: i128,
+    /// Withdrawal fee kept by the pool
+    pub fee: i128,
+    /// Part of the fee waived by a bootstrap campaign
+    pub fee_waived: i128,
+    pub collateral: i128,
+    pub debt: i128,
+    /// Collateral ratio after the withdrawal in percent; 0 without debt
+    pub collateral_ratio: i128,
+    pub health_factor: Option<i128>,
+}
+
+/// Outcome of a liquidation
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationPreview {
+    /// Debt repaid, after the close factor and collateral caps
+    pub debt_repaid: i128,
+    pub collateral_seized: i128,
+    /// Collateral paid to the liquidator
+    pub liquidator_receives: i128,
+    /// Part of the penalty routed to the rebate pool
+    pub rebate_cut: i128,
+    /// Incentive applied (scaled by 1e8); 0 while the primary price is stale
+    pub incentive: i128,
+    /// Borrower's collateral after the liquidation
+    pub collateral: i128,
+    /// Borrower's debt after the liquidation
+    pub debt: i128,
+    pub health_factor: Option<i128>,
+}
+
+/// Read-only operation previews
+pub struct Previews;
+
+impl Previews {
+    pub fn preview_borrow(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<BorrowPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut position = Self::accrued_position(env, user)?;
+        let asset = TokenRegistry::require_primary_asset(env)?;
+        let fee = OriginationFees::fee_for(env, &asset, amount);
+        position.debt += amount + fee;
+        let collateral_ratio = Self::collateral_ratio(&position);
+        if collateral_ratio < CollateralMatrix::effective_min_ratio(env) {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+        Ok(BorrowPreview {
+            received: amount,
+            fee,
+            collateral: position.collateral,
+            debt: position.debt,
+            collateral_ratio,
+            health_factor: SafetyRewards::health_factor(env, &position),
+        })
+    }
+
+    pub fn preview_withdraw(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<WithdrawPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut position = Self::accrued_position(env, user)?;
+        if position.collateral < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        position.collateral -= amount;
+        if position.collateral
+            < ReleaseSchedule::locked_amount(env, user)
+                + LiquidationAuctions::escrowed_collateral(env, user)
+        {
+            return Err(ProtocolError::WithdrawCollateralLocked);
+        }
+        let collateral_ratio = Self::collateral_ratio(&position);
+        if position.debt > 0 && collateral_ratio < CollateralMatrix::effective_min_ratio(env) {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+        let asset = TokenRegistry::require_primary_asset(env)?;
+        let (fee, fee_waived) = Bootstrap::quote_withdraw_fee(env, user, &asset, amount);
+        LiquidityGuard::ensure_withdrawable(env, &asset, amount - fee)?;
+        Ok(WithdrawPreview {
+            received: amount - fee,
+            fee,
+            fee_waived,
+            collateral: position.collateral,
+            debt: position.debt,
+            collateral_ratio,
+            health_factor: SafetyRewards::health_factor(env, &position),
+        })
+    }
+
+    pub fn preview_liquidation(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<LiquidationPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut position = Self::accrued_position(env, user)?;
+        if position.debt <= 0
+            || Self::collateral_ratio(&position) >= CollateralMatrix::effective_min_ratio(env)
+        {
+            return Err(ProtocolError::NotEligibleForLiquidation);
+        }
+        LiquidationGrace::ensure_liquidatable(env, &position)?;
+
+        let result = LiquidationModule::max_liquidatable(env, user)?;
+        let debt_repaid = amount.min(result.debt_repaid);
+        if debt_repaid <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let collateral_seized =
+            (debt_repaid * (100000000 + result.liquidation_incentive)) / 100000000;
+        let rebate_cut = RebatePool::penalty_cut(env, collateral_seized - debt_repaid);
+        position.debt -= debt_repaid;
+        position.collateral -= collateral_seized;
+        Ok(LiquidationPreview {
+            debt_repaid,
+            collateral_seized,
+            liquidator_receives: collateral_seized - rebate_cut,
+            rebate_cut,
+            incentive: result.liquidation_incentive,
+            collateral: position.collateral,
+            debt: position.debt,
+            health_factor: SafetyRewards::health_factor(env, &position),
+        })
+    }
+
+    /// The user's primary position with its debt accrued to now, not written back
+    fn accrued_position(env: &Env, user: &Address) -> Result<Position, ProtocolError> {
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        position.debt = LiquidationModule::accrued_debt(env, &position);
+        Ok(position)
+    }
+
+    fn collateral_ratio(position: &Position) -> i128 {
+        if position.debt > 0 {
+            (position.collateral * 100) / position.debt
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Quarantine module for StellarLend protocol
+//! A graduated restriction between normal operation and a full freeze. Quarantined users
+//! can still repay and deposit, but borrows and withdrawals above small thresholds are
+//! rejected. Quarantine lifts on its own after a review period unless a manager escalates
+//! it, in which case it holds until released.
+
+use crate::persistent::PersistentStore;
+use crate::{OperationKind, ProtocolConfig, ProtocolError, ProtocolEvent, UserManager, UserRole};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// What raised a quarantine
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum QuarantineTrigger {
+    ComplianceAlert,
+    RiskScore,
+    Manual,
+}
+
+/// Review period and per-call allowances while quarantined
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct QuarantineConfig {
+    /// Seconds until an unescalated quarantine expires
+    pub review_period: u64,
+    /// Largest borrow allowed per call while quarantined
+    pub max_borrow: i128,
+    /// Largest withdrawal allowed per call while quarantined
+    pub max_withdraw: i128,
+}
+
+impl QuarantineConfig {
+    pub fn default_config() -> Self {
+        Self {
+            review_period: 7 * 24 * 60 * 60,
+            max_borrow: 100,
+            max_withdraw: 100,
+        }
+    }
+}
+
+/// A user's latest quarantine
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct QuarantineRecord {
+    pub trigger: QuarantineTrigger,
+    /// Reason code supplied by the caller
+    pub reason: Symbol,
+    pub quarantined_by: Address,
+    pub started_at: u64,
+    /// Expiry unless escalated
+    pub expires_at: u64,
+    pub escalated: bool,
+    pub released: bool,
+}
+
+impl QuarantineRecord {
+    /// Whether restrictions currently apply
+    pub fn is_active(&self, now: u64) -> bool {
+        !self.released && (self.escalated || now < self.expires_at)
+    }
+}
+
+/// Storage helper for quarantine records
+pub struct QuarantineStorage;
+
+impl QuarantineStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "quarantine_cfg")
+    }
+    fn record_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "quarantine"), user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> QuarantineConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key(env))
+            .unwrap_or_else(QuarantineConfig::default_config)
+    }
+
+    pub fn save_config(env: &Env, config: &QuarantineConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Option<QuarantineRecord> {
+        PersistentStore::get(env, &Self::record_key(env, user))
+    }
+
+    pub fn save(env: &Env, user: &Address, record: &QuarantineRecord) {
+        PersistentStore::set(env, &Self::record_key(env, user), record);
+    }
+
+    /// Extend the TTL of a user's quarantine record, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::record_key(env, user)) as u32
+    }
+}
+
+/// Quarantine lifecycle and enforcement
+pub struct Quarantine;
+
+impl Quarantine {
+    /// Set the review period and allowances (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: QuarantineConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.review_period == 0 || config.max_borrow < 0 || config.max_withdraw < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        QuarantineStorage::save_config(env, &config);
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env) -> QuarantineConfig {
+        QuarantineStorage::get_config(env)
+    }
+
+    fn publish(env: &Env, action: &str, user: &Address, reason: &Symbol) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "reason"),
+                reason.clone(),
+            ),
+        );
+    }
+
+    /// Quarantine a user for the configured review period (analyst role or above).
+    /// Re-quarantining an active user restarts the review period and keeps escalation.
+    pub fn quarantine(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        trigger: QuarantineTrigger,
+        reason: Symbol,
+    ) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::ensure_can_manage(env, caller, UserRole::Analyst)?;
+        let now = env.ledger().timestamp();
+        let escalated = Self::active_record(env, user).is_some_and(|record| record.escalated);
+        let record = QuarantineRecord {
+            trigger,
+            reason: reason.clone(),
+            quarantined_by: caller.clone(),
+            started_at: now,
+            expires_at: now + QuarantineStorage::get_config(env).review_period,
+            escalated,
+            released: false,
+        };
+        QuarantineStorage::save(env, user, &record);
+        if trigger == QuarantineTrigger::ComplianceAlert {
+            ProtocolEvent::ComplianceAlert(user.clone(), reason.clone(), 0).emit(env);
+        }
+        Self::publish(env, "user_quarantined", user, &reason);
+        Ok(())
+    }
+
+    /// Keep an active quarantine in place past its review period (manager role or above)
+    pub fn escalate(env: &Env, caller: &Address, user: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::ensure_can_manage(env, caller, UserRole::Manager)?;
+        let mut record = Self::active_record(env, user).ok_or(ProtocolError::NotFound)?;
+        record.escalated = true;
+        QuarantineStorage::save(env, user, &record);
+        Self::publish(env, "quarantine_escalated", user, &record.reason);
+        Ok(())
+    }
+
+    /// Lift an active quarantine (manager role or above)
+    pub fn release(env: &Env, caller: &Address, user: &Address) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::ensure_can_manage(env, caller, UserRole::Manager)?;
+        let mut record = Self::active_record(env, user).ok_or(ProtocolError::NotFound)?;
+        record.released = true;
+        QuarantineStorage::save(env, user, &record);
+        Self::publish(env, "quarantine_released", user, &record.reason);
+        Ok(())
+    }
+
+    /// Latest quarantine record of a user, active or not
+    pub fn get_record(env: &Env, user: &Address) -> Option<QuarantineRecord> {
+        QuarantineStorage::get(env, user)
+    }
+
+    /// The user's quarantine if restrictions currently apply
+    pub fn active_record(env: &Env, user: &Address) -> Option<QuarantineRecord> {
+        let now = env.ledger().timestamp();
+        QuarantineStorage::get(env, user).filter(|record| record.is_active(now))
+    }
+
+    /// Reject borrows and withdrawals above the quarantine allowances
+    pub fn ensure_allowed(
+        env: &Env,
+        user: &Address,
+        operation: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let allowance = match operation {
+            OperationKind::Borrow => QuarantineStorage::get_config(env).max_borrow,
+            OperationKind::Withdraw => QuarantineStorage::get_config(env).max_withdraw,
+            _ => return Ok(()),
+        };
+        if amount > allowance && Self::active_record(env, user).is_some() {
+            return Err(ProtocolError::UserQuarantined);
+        }
+        Ok(())
+    }
+}
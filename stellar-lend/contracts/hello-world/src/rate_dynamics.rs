@@ -0,0 +1,150 @@
+//! Rate dynamics module for StellarLend protocol
+//! Controls how fast a market's smoothed borrow rate follows its utilization-driven rate.
+//! Without settings the smoothed rate is the `smoothing_bps` EWMA of the market config.
+//! With a decay period, the weight given to the current rate grows with the time since
+//! the last update (full weight after one period), so bursts of updates cannot drag the
+//! rate and a long gap catches up. A per-update cap bounds how far the smoothed rate may
+//! move in one update.
+
+use crate::{InterestRateStorage, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Basis point scale (10000 = 100%)
+const BPS_SCALE: i128 = 10000;
+
+/// Rate units per basis point (rates are scaled by 1e8)
+const RATE_PER_BPS: i128 = 10000;
+
+/// Smoothing settings of one market
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct RateDynamicsConfig {
+    /// Largest move of the smoothed rate per update, in bps of rate; 0 for no cap
+    pub max_rate_delta_bps: i128,
+    /// Seconds after which the current rate gets full weight; 0 keeps the fixed
+    /// `smoothing_bps` blend
+    pub decay_period: u64,
+}
+
+/// A market's effective smoothing parameters and rates
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RateDynamics {
+    pub asset: Address,
+    pub smoothing_bps: i128,
+    pub max_rate_delta_bps: i128,
+    pub decay_period: u64,
+    /// Weight an update now would give the current rate (bps)
+    pub current_weight_bps: i128,
+    pub current_borrow_rate: i128,
+    pub smoothed_borrow_rate: i128,
+    pub last_update: u64,
+}
+
+/// Storage helper for rate dynamics
+pub struct RateDynamicsStorage;
+
+impl RateDynamicsStorage {
+    fn config_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "rate_dynamics"), asset.clone())
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> RateDynamicsConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key(env, asset))
+            .unwrap_or_default()
+    }
+
+    pub fn save(env: &Env, asset: &Address, config: &RateDynamicsConfig) {
+        env.storage()
+            .instance()
+            .set(&Self::config_key(env, asset), config);
+    }
+}
+
+/// Time-decayed, capped rate smoothing
+pub struct RateSmoothing;
+
+impl RateSmoothing {
+    /// Set a market's decay period and per-update cap (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        config: RateDynamicsConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=BPS_SCALE).contains(&config.max_rate_delta_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        RateDynamicsStorage::save(env, asset, &config);
+        env.events().publish(
+            (Symbol::new(env, "rate_dynamics"), Symbol::new(env, "asset")),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "max_rate_delta_bps"),
+                config.max_rate_delta_bps,
+                Symbol::new(env, "decay_period"),
+                config.decay_period,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Effective smoothing parameters and rates of an asset's market
+    pub fn dynamics(env: &Env, asset: &Address) -> RateDynamics {
+        let config = RateDynamicsStorage::get(env, asset);
+        let market = InterestRateStorage::get_market_config(env, asset);
+        let state = InterestRateStorage::get_market_state(env, asset);
+        let elapsed = env
+            .ledger()
+            .timestamp()
+            .saturating_sub(state.last_accrual_time);
+        RateDynamics {
+            asset: asset.clone(),
+            smoothing_bps: market.smoothing_bps,
+            max_rate_delta_bps: config.max_rate_delta_bps,
+            decay_period: config.decay_period,
+            current_weight_bps: Self::weight_bps(&config, market.smoothing_bps, elapsed),
+            current_borrow_rate: state.current_borrow_rate,
+            smoothed_borrow_rate: state.smoothed_borrow_rate,
+            last_update: state.last_accrual_time,
+        }
+    }
+
+    /// Weight in bps given to the current rate by an update `elapsed` seconds after the
+    /// previous one
+    pub fn weight_bps(config: &RateDynamicsConfig, smoothing_bps: i128, elapsed: u64) -> i128 {
+        if config.decay_period == 0 {
+            return BPS_SCALE - smoothing_bps;
+        }
+        ((elapsed as i128).saturating_mul(BPS_SCALE) / config.decay_period as i128).min(BPS_SCALE)
+    }
+
+    /// Blend the previous smoothed rate toward `current` and apply the per-update cap.
+    /// With dynamics set, the first update of a market takes the current rate as is.
+    pub fn smooth(
+        config: &RateDynamicsConfig,
+        smoothing_bps: i128,
+        previous: i128,
+        current: i128,
+        elapsed: u64,
+        first_update: bool,
+    ) -> i128 {
+        if first_update && *config != RateDynamicsConfig::default() {
+            return current;
+        }
+        let weight = Self::weight_bps(config, smoothing_bps, elapsed);
+        let blended = previous
+            .saturating_mul(BPS_SCALE - weight)
+            .saturating_add(current.saturating_mul(weight))
+            .saturating_div(BPS_SCALE);
+        if config.max_rate_delta_bps == 0 {
+            return blended;
+        }
+        let cap = config.max_rate_delta_bps * RATE_PER_BPS;
+        blended.clamp(previous.saturating_sub(cap), previous.saturating_add(cap))
+    }
+}
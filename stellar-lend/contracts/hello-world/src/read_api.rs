@@ -0,0 +1,139 @@
+//! Read interface for StellarLend protocol
+//! Batches pure-read views into one call so RPC gateways can serve dashboards from a
+//! single simulation. Nothing here writes to storage; persistent reads only extend TTLs.
+
+use crate::analytics::{AnalyticsStorage, ProtocolMetrics, UserAnalytics};
+use crate::auto_repay::{AutoRepay, AutoRepayPreference};
+use crate::bloom::ActivityBloom;
+use crate::bootstrap::{Bootstrap, BootstrapStats};
+use crate::footprint::{StorageFootprint, StorageFootprintReporter};
+use crate::insurance::{CoverageCohorts, InsuranceCohorts};
+use crate::modules::{ModuleImpl, ModuleKind, ModuleRegistry};
+use crate::oracle::{Oracle, PriceBounds};
+use crate::rebate::{RebatePool, RebatePoolState};
+use crate::token_behavior::{TokenBehavior, TokenBehaviorRegistry};
+use crate::watchdog::{Watchdog, WatchdogState};
+use crate::{
+    EmergencyState, EmergencyStorage, InterestRateState, InterestRateStorage, Position,
+    ProtocolConfig, ProtocolConfigView, ProtocolError, RiskConfig, RiskConfigStorage, StateHelper,
+};
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+/// Maximum queries served per batch
+const MAX_BATCH: u32 = 32;
+
+/// A single view call
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ReadQuery {
+    Position(Address),
+    UserAnalytics(Address),
+    AutoRepay(Address),
+    StorageFootprint(Address),
+    MightHaveActivity(Address, u64),
+    ProtocolMetrics,
+    InterestState,
+    MarketState(Address),
+    RiskConfig,
+    EmergencyState,
+    WatchdogState,
+    RebatePool,
+    PriceBounds,
+    CoverageCohorts(Address),
+    BootstrapStats(Address),
+    TokenBehavior(Address),
+    ModuleImpl(ModuleKind),
+    ProtocolConfig,
+}
+
+/// Result of the matching `ReadQuery`, in request order
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ReadResult {
+    /// The queried entry does not exist
+    Missing,
+    Position(Position),
+    UserAnalytics(UserAnalytics),
+    AutoRepay(AutoRepayPreference),
+    StorageFootprint(StorageFootprint),
+    MightHaveActivity(bool),
+    ProtocolMetrics(ProtocolMetrics),
+    InterestState(InterestRateState),
+    MarketState(InterestRateState),
+    RiskConfig(RiskConfig),
+    EmergencyState(EmergencyState),
+    WatchdogState(WatchdogState),
+    RebatePool(RebatePoolState),
+    PriceBounds(Vec<PriceBounds>),
+    CoverageCohorts(CoverageCohorts),
+    BootstrapStats(BootstrapStats),
+    TokenBehavior(TokenBehavior),
+    ModuleImpl(ModuleImpl),
+    ProtocolConfig(ProtocolConfigView),
+}
+
+/// Batch read dispatcher
+pub struct ReadApi;
+
+impl ReadApi {
+    /// Answer every query in order
+    pub fn batch_read(
+        env: &Env,
+        queries: Vec<ReadQuery>,
+    ) -> Result<Vec<ReadResult>, ProtocolError> {
+        if queries.len() > MAX_BATCH {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let mut results = Vec::new(env);
+        for query in queries.iter() {
+            results.push_back(Self::read(env, query));
+        }
+        Ok(results)
+    }
+
+    fn read(env: &Env, query: ReadQuery) -> ReadResult {
+        match query {
+            ReadQuery::Position(user) => StateHelper::get_position(env, &user)
+                .map_or(ReadResult::Missing, ReadResult::Position),
+            ReadQuery::UserAnalytics(user) => AnalyticsStorage::find_user_analytics(env, &user)
+                .map_or(ReadResult::Missing, ReadResult::UserAnalytics),
+            ReadQuery::AutoRepay(user) => {
+                ReadResult::AutoRepay(AutoRepay::get_preference(env, &user))
+            }
+            ReadQuery::StorageFootprint(user) => {
+                ReadResult::StorageFootprint(StorageFootprintReporter::user_footprint(env, &user))
+            }
+            ReadQuery::MightHaveActivity(user, epoch) => {
+                ReadResult::MightHaveActivity(ActivityBloom::might_have_activity(env, &user, epoch))
+            }
+            ReadQuery::ProtocolMetrics => {
+                ReadResult::ProtocolMetrics(AnalyticsStorage::get_protocol_metrics(env))
+            }
+            ReadQuery::InterestState => {
+                ReadResult::InterestState(InterestRateStorage::get_state(env))
+            }
+            ReadQuery::MarketState(asset) => {
+                ReadResult::MarketState(InterestRateStorage::get_market_state(env, &asset))
+            }
+            ReadQuery::RiskConfig => ReadResult::RiskConfig(RiskConfigStorage::get(env)),
+            ReadQuery::EmergencyState => ReadResult::EmergencyState(EmergencyStorage::get(env)),
+            ReadQuery::WatchdogState => ReadResult::WatchdogState(Watchdog::get_state(env)),
+            ReadQuery::RebatePool => ReadResult::RebatePool(RebatePool::get_pool(env)),
+            ReadQuery::PriceBounds => ReadResult::PriceBounds(Oracle::list_price_bounds(env)),
+            ReadQuery::CoverageCohorts(asset) => {
+                ReadResult::CoverageCohorts(InsuranceCohorts::get_cohorts(env, &asset))
+            }
+            ReadQuery::BootstrapStats(asset) => {
+                ReadResult::BootstrapStats(Bootstrap::get_stats(env, &asset))
+            }
+            ReadQuery::TokenBehavior(asset) => {
+                ReadResult::TokenBehavior(TokenBehaviorRegistry::get(env, &asset))
+            }
+            ReadQuery::ModuleImpl(kind) => {
+                ReadResult::ModuleImpl(ModuleRegistry::active(env, kind))
+            }
+            ReadQuery::ProtocolConfig => ReadResult::ProtocolConfig(ProtocolConfig::snapshot(env)),
+        }
+    }
+}
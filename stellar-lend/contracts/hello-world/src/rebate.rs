@@ -0,0 +1,362 @@
+//! Rebate module for StellarLend protocol
+//! Goodwill pool funded by a slice of liquidation penalties, refunding borrowers
+//! liquidated during verified oracle malfunction windows
+
+use crate::analytics::AnalyticsStorage;
+use crate::persistent::PersistentStore;
+use crate::{Position, ProtocolConfig, ProtocolError, StateHelper};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol};
+
+/// Rebate pool accounting
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RebatePoolState {
+    /// Share of each liquidation penalty routed to the pool (basis points)
+    pub penalty_share_bps: i128,
+    /// Funds available for rebates
+    pub balance: i128,
+    /// Lifetime penalties routed to the pool
+    pub total_funded: i128,
+    /// Lifetime rebates paid out
+    pub total_paid: i128,
+}
+
+impl RebatePoolState {
+    pub fn new() -> Self {
+        Self {
+            penalty_share_bps: 0,
+            balance: 0,
+            total_funded: 0,
+            total_paid: 0,
+        }
+    }
+}
+
+impl Default for RebatePoolState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Oracle malfunction window tagged by governance
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct OracleIncident {
+    pub id: u64,
+    pub start: u64,
+    pub end: u64,
+    pub description: String,
+    pub tagged_by: Address,
+}
+
+/// Lifecycle of a rebate claim
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RebateClaimStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Borrower claim against the rebate pool for a single liquidation
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RebateClaim {
+    pub id: u64,
+    pub user: Address,
+    pub incident_id: u64,
+    pub liquidation_id: u64,
+    /// Penalty paid in the liquidation (upper bound for the rebate)
+    pub requested: i128,
+    pub awarded: i128,
+    pub status: RebateClaimStatus,
+    pub submitted_at: u64,
+}
+
+/// Most recent incidents and claims refreshed by `bump_storage`
+const MAX_BUMPED_REBATE_RECORDS: u64 = 100;
+
+/// Storage helper for the rebate pool, incidents and claims
+pub struct RebateStorage;
+
+impl RebateStorage {
+    fn pool_key(env: &Env) -> Symbol {
+        Symbol::new(env, "rebate_pool")
+    }
+    fn incident_count_key(env: &Env) -> Symbol {
+        Symbol::new(env, "rebate_incident_count")
+    }
+    fn incident_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "rebate_incident"), id)
+    }
+    fn claim_count_key(env: &Env) -> Symbol {
+        Symbol::new(env, "rebate_claim_count")
+    }
+    fn claim_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "rebate_claim"), id)
+    }
+    fn claimed_liquidation_key(env: &Env, liquidation_id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "rebate_claimed"), liquidation_id)
+    }
+
+    pub fn get_pool(env: &Env) -> RebatePoolState {
+        env.storage()
+            .instance()
+            .get(&Self::pool_key(env))
+            .unwrap_or_default()
+    }
+
+    pub fn save_pool(env: &Env, pool: &RebatePoolState) {
+        env.storage().instance().set(&Self::pool_key(env), pool);
+    }
+
+    fn next_id(env: &Env, key: &Symbol) -> u64 {
+        let id = Self::count(env, key) + 1;
+        env.storage().instance().set(key, &id);
+        id
+    }
+
+    pub fn get_incident(env: &Env, id: u64) -> Option<OracleIncident> {
+        PersistentStore::get(env, &Self::incident_key(env, id))
+    }
+
+    pub fn save_incident(env: &Env, incident: &OracleIncident) {
+        PersistentStore::set(env, &Self::incident_key(env, incident.id), incident);
+    }
+
+    pub fn get_claim(env: &Env, id: u64) -> Option<RebateClaim> {
+        PersistentStore::get(env, &Self::claim_key(env, id))
+    }
+
+    pub fn save_claim(env: &Env, claim: &RebateClaim) {
+        PersistentStore::set(env, &Self::claim_key(env, claim.id), claim);
+    }
+
+    pub fn is_liquidation_claimed(env: &Env, liquidation_id: u64) -> bool {
+        PersistentStore::get(env, &Self::claimed_liquidation_key(env, liquidation_id))
+            .unwrap_or(false)
+    }
+
+    pub fn mark_liquidation_claimed(env: &Env, liquidation_id: u64) {
+        PersistentStore::set(
+            env,
+            &Self::claimed_liquidation_key(env, liquidation_id),
+            &true,
+        );
+    }
+
+    fn count(env: &Env, key: &Symbol) -> u64 {
+        env.storage().instance().get(key).unwrap_or(0)
+    }
+
+    /// Extend the TTL of the most recent incidents and claims (with their liquidation
+    /// markers), returning how many entries exist
+    pub fn bump(env: &Env) -> u32 {
+        let mut bumped = 0u32;
+        let incidents = Self::count(env, &Self::incident_count_key(env));
+        for id in incidents.saturating_sub(MAX_BUMPED_REBATE_RECORDS) + 1..=incidents {
+            bumped += PersistentStore::bump(env, &Self::incident_key(env, id)) as u32;
+        }
+        let claims = Self::count(env, &Self::claim_count_key(env));
+        for id in claims.saturating_sub(MAX_BUMPED_REBATE_RECORDS) + 1..=claims {
+            if let Some(claim) = Self::get_claim(env, id) {
+                bumped += 1 + PersistentStore::bump(
+                    env,
+                    &Self::claimed_liquidation_key(env, claim.liquidation_id),
+                ) as u32;
+            }
+        }
+        bumped
+    }
+}
+
+/// Rebate pool operations
+pub struct RebatePool;
+
+impl RebatePool {
+    /// Set the share of liquidation penalties routed to the pool (admin only)
+    pub fn set_penalty_share(env: &Env, caller: &Address, bps: i128) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=10000).contains(&bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut pool = RebateStorage::get_pool(env);
+        pool.penalty_share_bps = bps;
+        RebateStorage::save_pool(env, &pool);
+        Ok(())
+    }
+
+    /// Portion of a liquidation penalty that should be routed to the pool
+    pub fn penalty_cut(env: &Env, penalty: i128) -> i128 {
+        if penalty <= 0 {
+            return 0;
+        }
+        (penalty * RebateStorage::get_pool(env).penalty_share_bps) / 10000
+    }
+
+    /// Credit the pool with a penalty cut taken during liquidation
+    pub fn fund(env: &Env, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+        let mut pool = RebateStorage::get_pool(env);
+        pool.balance += amount;
+        pool.total_funded += amount;
+        RebateStorage::save_pool(env, &pool);
+    }
+
+    /// Tag a verified oracle malfunction window (admin only)
+    pub fn tag_incident(
+        env: &Env,
+        caller: &Address,
+        start: u64,
+        end: u64,
+        description: String,
+    ) -> Result<u64, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if end < start || description.is_empty() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let incident = OracleIncident {
+            id: RebateStorage::next_id(env, &RebateStorage::incident_count_key(env)),
+            start,
+            end,
+            description,
+            tagged_by: caller.clone(),
+        };
+        RebateStorage::save_incident(env, &incident);
+        env.events().publish(
+            (
+                Symbol::new(env, "oracle_incident_tagged"),
+                Symbol::new(env, "incident"),
+            ),
+            (
+                Symbol::new(env, "id"),
+                incident.id,
+                Symbol::new(env, "start"),
+                start,
+                Symbol::new(env, "end"),
+                end,
+            ),
+        );
+        Ok(incident.id)
+    }
+
+    /// Submit a claim for a liquidation that happened inside a tagged incident window
+    pub fn submit_claim(
+        env: &Env,
+        user: &Address,
+        incident_id: u64,
+        liquidation_id: u64,
+    ) -> Result<u64, ProtocolError> {
+        user.require_auth();
+        let incident =
+            RebateStorage::get_incident(env, incident_id).ok_or(ProtocolError::NotFound)?;
+        let record = AnalyticsStorage::get_liquidation_record(env, liquidation_id)
+            .ok_or(ProtocolError::NotFound)?;
+        if record.user != *user {
+            return Err(ProtocolError::Unauthorized);
+        }
+        if record.timestamp < incident.start || record.timestamp > incident.end {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        if RebateStorage::is_liquidation_claimed(env, liquidation_id) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+
+        let claim = RebateClaim {
+            id: RebateStorage::next_id(env, &RebateStorage::claim_count_key(env)),
+            user: user.clone(),
+            incident_id,
+            liquidation_id,
+            requested: (record.collateral_seized - record.debt_repaid).max(0),
+            awarded: 0,
+            status: RebateClaimStatus::Pending,
+            submitted_at: env.ledger().timestamp(),
+        };
+        RebateStorage::mark_liquidation_claimed(env, liquidation_id);
+        RebateStorage::save_claim(env, &claim);
+        env.events().publish(
+            (
+                Symbol::new(env, "rebate_claim_submitted"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "claim"),
+                claim.id,
+                Symbol::new(env, "requested"),
+                claim.requested,
+            ),
+        );
+        Ok(claim.id)
+    }
+
+    /// Approve (with an award up to the requested amount) or reject a pending claim (admin only).
+    /// Approved rebates are credited back to the borrower's collateral.
+    pub fn adjudicate_claim(
+        env: &Env,
+        caller: &Address,
+        claim_id: u64,
+        approve: bool,
+        award: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut claim = RebateStorage::get_claim(env, claim_id).ok_or(ProtocolError::NotFound)?;
+        if claim.status != RebateClaimStatus::Pending {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        if approve {
+            if award <= 0 || award > claim.requested {
+                return Err(ProtocolError::InvalidAmount);
+            }
+            let mut pool = RebateStorage::get_pool(env);
+            if award > pool.balance {
+                return Err(ProtocolError::InsufficientLiquidity);
+            }
+            pool.balance -= award;
+            pool.total_paid += award;
+            RebateStorage::save_pool(env, &pool);
+
+            let mut position = StateHelper::get_position(env, &claim.user)
+                .unwrap_or_else(|| Position::new(claim.user.clone(), 0, 0));
+            position.collateral += award;
+            StateHelper::save_position(env, &position);
+
+            claim.awarded = award;
+            claim.status = RebateClaimStatus::Approved;
+        } else {
+            claim.status = RebateClaimStatus::Rejected;
+        }
+        RebateStorage::save_claim(env, &claim);
+        env.events().publish(
+            (
+                Symbol::new(env, "rebate_claim_adjudicated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                claim.user.clone(),
+                Symbol::new(env, "claim"),
+                claim.id,
+                Symbol::new(env, "awarded"),
+                claim.awarded,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_pool(env: &Env) -> RebatePoolState {
+        RebateStorage::get_pool(env)
+    }
+
+    pub fn get_incident(env: &Env, id: u64) -> Option<OracleIncident> {
+        RebateStorage::get_incident(env, id)
+    }
+
+    pub fn get_claim(env: &Env, id: u64) -> Option<RebateClaim> {
+        RebateStorage::get_claim(env, id)
+    }
+}
@@ -0,0 +1,222 @@
+//! Recovery withdrawal module for StellarLend protocol
+//! While the protocol is in Recovery, suppliers may pull out their pro-rata share of an
+//! asset's liquid balance once per recovery round. Each round snapshots the asset's liquid
+//! balance against total supply at its first claim, so every claimant is paid at the same
+//! ratio regardless of claim order. Any unpaid remainder stays in the user's position.
+
+use crate::persistent::PersistentStore;
+use crate::{
+    EmergencyStatus, EmergencyStorage, InterestRateManager, InterestRateStorage, ProtocolError,
+    ReentrancyScope, StateHelper, TokenRegistry, TransferEnforcer,
+};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Liquidity ratio of an asset fixed at the first claim of a recovery round
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RecoverySnapshot {
+    pub round: u32,
+    /// Contract balance of the asset when the snapshot was taken
+    pub liquid: i128,
+    /// Total supplied of the asset when the snapshot was taken
+    pub total_supplied: i128,
+}
+
+/// A user's latest recovery claim for an asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RecoveryClaim {
+    pub round: u32,
+    /// Supplied balance the entitlement was computed from
+    pub supplied: i128,
+    /// Amount paid out
+    pub amount: i128,
+    pub claimed_at: u64,
+}
+
+/// Storage helper for recovery rounds, snapshots and claims
+pub struct RecoveryStorage;
+
+impl RecoveryStorage {
+    fn round_key(env: &Env) -> Symbol {
+        Symbol::new(env, "recovery_round")
+    }
+    fn snapshot_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "recovery_snapshot"), asset.clone())
+    }
+    fn claim_key(env: &Env, user: &Address, asset: &Address) -> (Symbol, Address, Address) {
+        (
+            Symbol::new(env, "recovery_claim"),
+            user.clone(),
+            asset.clone(),
+        )
+    }
+
+    pub fn get_round(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::round_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn set_round(env: &Env, round: u32) {
+        env.storage().instance().set(&Self::round_key(env), &round);
+    }
+
+    pub fn get_snapshot(env: &Env, asset: &Address) -> Option<RecoverySnapshot> {
+        env.storage()
+            .instance()
+            .get(&Self::snapshot_key(env, asset))
+    }
+
+    pub fn save_snapshot(env: &Env, asset: &Address, snapshot: &RecoverySnapshot) {
+        env.storage()
+            .instance()
+            .set(&Self::snapshot_key(env, asset), snapshot);
+    }
+
+    pub fn get_claim(env: &Env, user: &Address, asset: &Address) -> Option<RecoveryClaim> {
+        PersistentStore::get(env, &Self::claim_key(env, user, asset))
+    }
+
+    pub fn save_claim(env: &Env, user: &Address, asset: &Address, claim: &RecoveryClaim) {
+        PersistentStore::set(env, &Self::claim_key(env, user, asset), claim);
+    }
+}
+
+/// Pro-rata emergency withdrawals during Recovery
+pub struct RecoveryWithdrawals;
+
+impl RecoveryWithdrawals {
+    /// Open a new claim round; called when the protocol enters Recovery
+    pub fn start_round(env: &Env) {
+        RecoveryStorage::set_round(env, RecoveryStorage::get_round(env) + 1);
+    }
+
+    /// Total supplied of an asset: tracked TVL for the primary asset, market supply otherwise
+    fn total_supplied(env: &Env, asset: &Address, is_primary: bool) -> i128 {
+        if is_primary {
+            crate::tvl_cap::TvlCapStorage::get_asset_tvl(env, asset)
+        } else {
+            InterestRateStorage::get_market_state(env, asset).total_supplied
+        }
+    }
+
+    /// This round's snapshot for an asset, taking it on first use
+    fn snapshot(env: &Env, asset: &Address, is_primary: bool, round: u32) -> RecoverySnapshot {
+        if let Some(snapshot) = RecoveryStorage::get_snapshot(env, asset) {
+            if snapshot.round == round {
+                return snapshot;
+            }
+        }
+        let snapshot = RecoverySnapshot {
+            round,
+            liquid: TokenClient::new(env, asset).balance(&env.current_contract_address()),
+            total_supplied: Self::total_supplied(env, asset, is_primary),
+        };
+        RecoveryStorage::save_snapshot(env, asset, &snapshot);
+        snapshot
+    }
+
+    /// Share of `supplied` covered by the snapshot's liquidity, never more than `supplied`
+    pub fn entitlement(supplied: i128, snapshot: &RecoverySnapshot) -> i128 {
+        if supplied <= 0 || snapshot.total_supplied <= 0 || snapshot.liquid <= 0 {
+            return 0;
+        }
+        if snapshot.liquid >= snapshot.total_supplied {
+            return supplied;
+        }
+        supplied.saturating_mul(snapshot.liquid) / snapshot.total_supplied
+    }
+
+    /// Withdraw the user's pro-rata share of an asset's liquid balance. Only available in
+    /// Recovery, once per user and asset per round, and to positions without debt.
+    pub fn withdraw(env: &Env, user: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        let _guard = ReentrancyScope::enter(env)?;
+        if EmergencyStorage::get(env).status != EmergencyStatus::Recovery {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let round = RecoveryStorage::get_round(env);
+        if RecoveryStorage::get_claim(env, user, asset).is_some_and(|claim| claim.round == round) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+
+        let is_primary = TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset);
+        let snapshot = Self::snapshot(env, asset, is_primary, round);
+        let (supplied, amount) = if is_primary {
+            let mut position =
+                StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+            let state = InterestRateStorage::update_state(env);
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+            if position.debt > 0 {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            let supplied = position.collateral;
+            let amount = Self::entitlement(supplied, &snapshot);
+            position.collateral -= amount;
+            StateHelper::save_position(env, &position);
+            (supplied, amount)
+        } else {
+            let mut position = StateHelper::get_cross_position(env, user)
+                .ok_or(ProtocolError::PositionNotFound)?;
+            if !position.debt.is_empty() {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            let supplied = position.collateral.get(asset.clone()).unwrap_or(0);
+            let amount = Self::entitlement(supplied, &snapshot);
+            if amount == supplied {
+                position.collateral.remove(asset.clone());
+            } else {
+                position.collateral.set(asset.clone(), supplied - amount);
+            }
+            StateHelper::save_cross_position(env, &position);
+            InterestRateStorage::apply_market_flow(env, asset, -amount, 0);
+            (supplied, amount)
+        };
+        if amount <= 0 {
+            return Err(ProtocolError::InsufficientLiquidity);
+        }
+
+        TransferEnforcer::transfer_out_asset(
+            env,
+            asset,
+            user,
+            amount,
+            Symbol::new(env, "recovery_withdraw"),
+        )?;
+        RecoveryStorage::save_claim(
+            env,
+            user,
+            asset,
+            &RecoveryClaim {
+                round,
+                supplied,
+                amount,
+                claimed_at: env.ledger().timestamp(),
+            },
+        );
+        env.events().publish(
+            (
+                Symbol::new(env, "recovery_withdraw"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+                Symbol::new(env, "round"),
+                round,
+            ),
+        );
+        Ok(amount)
+    }
+
+    pub fn get_claim(env: &Env, user: &Address, asset: &Address) -> Option<RecoveryClaim> {
+        RecoveryStorage::get_claim(env, user, asset)
+    }
+}
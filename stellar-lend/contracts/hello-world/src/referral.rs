@@ -0,0 +1,199 @@
+//! Referral module for StellarLend protocol
+//! Users register the address that referred them once. A configurable share of the
+//! protocol fees their activity generates (the reserve cut of their interest, flash loan
+//! fees and withdrawal fees) accrues to the referrer, who claims it per asset.
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, TransferEnforcer};
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol};
+
+/// Shares are basis points of the fee (10000 = whole fee)
+const BPS_SCALE: i128 = 10_000;
+
+/// Referral totals of one referrer
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ReferrerStats {
+    /// Users who registered this referrer
+    pub referees: u32,
+    /// Rewards credited per asset
+    pub earned: Map<Address, i128>,
+    /// Rewards paid out per asset
+    pub claimed: Map<Address, i128>,
+}
+
+/// Storage helper for referral links, referrer totals and the fee share
+pub struct ReferralStorage;
+
+impl ReferralStorage {
+    fn referrer_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "referrer"), user.clone())
+    }
+    fn stats_key(env: &Env, referrer: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "referral_stats"), referrer.clone())
+    }
+    fn share_key(env: &Env) -> Symbol {
+        Symbol::new(env, "referral_share")
+    }
+
+    pub fn get_referrer(env: &Env, user: &Address) -> Option<Address> {
+        PersistentStore::get(env, &Self::referrer_key(env, user))
+    }
+
+    pub fn save_referrer(env: &Env, user: &Address, referrer: &Address) {
+        PersistentStore::set(env, &Self::referrer_key(env, user), referrer);
+    }
+
+    pub fn get_stats(env: &Env, referrer: &Address) -> ReferrerStats {
+        PersistentStore::get(env, &Self::stats_key(env, referrer)).unwrap_or(ReferrerStats {
+            referees: 0,
+            earned: Map::new(env),
+            claimed: Map::new(env),
+        })
+    }
+
+    pub fn save_stats(env: &Env, referrer: &Address, stats: &ReferrerStats) {
+        PersistentStore::set(env, &Self::stats_key(env, referrer), stats);
+    }
+
+    pub fn get_share(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::share_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn save_share(env: &Env, share_bps: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::share_key(env), &share_bps);
+    }
+
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::referrer_key(env, user)) as u32
+            + PersistentStore::bump(env, &Self::stats_key(env, user)) as u32
+    }
+}
+
+/// Referral registration, fee sharing and claims
+pub struct Referrals;
+
+impl Referrals {
+    /// Set the share of referees' fees credited to their referrers (admin only)
+    pub fn set_share(env: &Env, caller: &Address, share_bps: i128) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=BPS_SCALE).contains(&share_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        ReferralStorage::save_share(env, share_bps);
+        Ok(())
+    }
+
+    pub fn get_share(env: &Env) -> i128 {
+        ReferralStorage::get_share(env)
+    }
+
+    /// Record `referrer` as the user's referrer. A user registers once and cannot refer
+    /// themselves or the address that referred them.
+    pub fn register(env: &Env, user: &Address, referrer: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if user == referrer || ReferralStorage::get_referrer(env, referrer).as_ref() == Some(user) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if ReferralStorage::get_referrer(env, user).is_some() {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        ReferralStorage::save_referrer(env, user, referrer);
+        let mut stats = ReferralStorage::get_stats(env, referrer);
+        stats.referees += 1;
+        ReferralStorage::save_stats(env, referrer, &stats);
+        env.events().publish(
+            (
+                Symbol::new(env, "referral_registered"),
+                Symbol::new(env, "referrer"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "referrer"),
+                referrer.clone(),
+                Symbol::new(env, "referees"),
+                stats.referees,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_referrer(env: &Env, user: &Address) -> Option<Address> {
+        ReferralStorage::get_referrer(env, user)
+    }
+
+    pub fn get_stats(env: &Env, referrer: &Address) -> ReferrerStats {
+        ReferralStorage::get_stats(env, referrer)
+    }
+
+    /// Credit the referrer's share of a `fee` in `asset` generated by `user`. Returns the
+    /// amount credited, 0 when the user has no referrer.
+    pub fn on_fee(env: &Env, user: &Address, asset: &Address, fee: i128) -> i128 {
+        let share = ReferralStorage::get_share(env);
+        if fee <= 0 || share == 0 {
+            return 0;
+        }
+        let referrer = match ReferralStorage::get_referrer(env, user) {
+            Some(referrer) => referrer,
+            None => return 0,
+        };
+        let reward = fee.saturating_mul(share) / BPS_SCALE;
+        if reward <= 0 {
+            return 0;
+        }
+        let mut stats = ReferralStorage::get_stats(env, &referrer);
+        let earned = stats.earned.get(asset.clone()).unwrap_or(0);
+        stats
+            .earned
+            .set(asset.clone(), earned.saturating_add(reward));
+        ReferralStorage::save_stats(env, &referrer, &stats);
+        reward
+    }
+
+    /// Rewards in `asset` the referrer has not claimed yet
+    pub fn claimable(env: &Env, referrer: &Address, asset: &Address) -> i128 {
+        let stats = ReferralStorage::get_stats(env, referrer);
+        stats.earned.get(asset.clone()).unwrap_or(0) - stats.claimed.get(asset.clone()).unwrap_or(0)
+    }
+
+    /// Pay out the referrer's unclaimed rewards in `asset`. Returns the amount paid.
+    pub fn claim(env: &Env, referrer: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        referrer.require_auth();
+        let mut stats = ReferralStorage::get_stats(env, referrer);
+        let claimed = stats.claimed.get(asset.clone()).unwrap_or(0);
+        let amount = stats.earned.get(asset.clone()).unwrap_or(0) - claimed;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        stats.claimed.set(asset.clone(), claimed + amount);
+        ReferralStorage::save_stats(env, referrer, &stats);
+        TransferEnforcer::transfer_out_asset(
+            env,
+            asset,
+            referrer,
+            amount,
+            Symbol::new(env, "referral_claim"),
+        )?;
+        env.events().publish(
+            (
+                Symbol::new(env, "referral_claimed"),
+                Symbol::new(env, "referrer"),
+            ),
+            (
+                Symbol::new(env, "referrer"),
+                referrer.clone(),
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+            ),
+        );
+        Ok(amount)
+    }
+}
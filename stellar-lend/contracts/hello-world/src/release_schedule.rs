@@ -0,0 +1,125 @@
+//! Collateral release schedule module for StellarLend protocol
+//! Vesting-style locks: collateral tranches that unlock at individual timestamps
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolError, StateHelper};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Maximum pending tranches per user
+const MAX_TRANCHES: u32 = 32;
+
+/// Collateral locked until `unlock_at`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ReleaseTranche {
+    pub amount: i128,
+    pub unlock_at: u64,
+}
+
+/// Storage helper for release schedules
+pub struct ReleaseScheduleStorage;
+
+impl ReleaseScheduleStorage {
+    fn key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "release_sched"), user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Vec<ReleaseTranche> {
+        PersistentStore::get(env, &Self::key(env, user)).unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save(env: &Env, user: &Address, tranches: &Vec<ReleaseTranche>) {
+        if tranches.is_empty() {
+            PersistentStore::remove(env, &Self::key(env, user));
+        } else {
+            PersistentStore::set(env, &Self::key(env, user), tranches);
+        }
+    }
+
+    /// Extend the TTL of a user's schedule entry, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::key(env, user)) as u32
+    }
+}
+
+/// Scheduled collateral releases
+pub struct ReleaseSchedule;
+
+impl ReleaseSchedule {
+    /// Tranches that have not yet unlocked
+    fn pending(env: &Env, user: &Address) -> Vec<ReleaseTranche> {
+        let now = env.ledger().timestamp();
+        let mut pending = Vec::new(env);
+        for tranche in ReleaseScheduleStorage::get(env, user).iter() {
+            if tranche.unlock_at > now {
+                pending.push_back(tranche);
+            }
+        }
+        pending
+    }
+
+    /// Lock existing collateral into tranches. Previously scheduled tranches are kept;
+    /// all pending tranches together may not exceed the position's collateral.
+    pub fn schedule(
+        env: &Env,
+        user: &Address,
+        tranches: Vec<ReleaseTranche>,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if tranches.is_empty() {
+            return Err(ProtocolError::InvalidInput);
+        }
+
+        let now = env.ledger().timestamp();
+        let mut schedule = Self::pending(env, user);
+        for tranche in tranches.iter() {
+            if tranche.amount <= 0 {
+                return Err(ProtocolError::InvalidAmount);
+            }
+            if tranche.unlock_at <= now {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            schedule.push_back(tranche);
+        }
+        if schedule.len() > MAX_TRANCHES {
+            return Err(ProtocolError::StorageLimitExceeded);
+        }
+
+        let collateral = StateHelper::get_position(env, user)
+            .map(|position| position.collateral)
+            .unwrap_or(0);
+        let locked: i128 = schedule.iter().map(|tranche| tranche.amount).sum();
+        if locked > collateral {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+
+        ReleaseScheduleStorage::save(env, user, &schedule);
+        env.events().publish(
+            (
+                Symbol::new(env, "collateral_release_scheduled"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "locked"),
+                locked,
+                Symbol::new(env, "tranches"),
+                schedule.len(),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Collateral still locked by pending tranches
+    pub fn locked_amount(env: &Env, user: &Address) -> i128 {
+        Self::pending(env, user)
+            .iter()
+            .map(|tranche| tranche.amount)
+            .sum()
+    }
+
+    pub fn get_schedule(env: &Env, user: &Address) -> Vec<ReleaseTranche> {
+        Self::pending(env, user)
+    }
+}
@@ -2,9 +2,10 @@
 //! Handles debt repayment functionality and related operations
 
 use crate::analytics::AnalyticsModule;
+use crate::gate::OperationGate;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolError,
-    ProtocolEvent, ReentrancyGuard, StateHelper, TransferEnforcer, UserManager,
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ProtocolEvent,
+    ReentrancyGuard, StateHelper, TokenRegistry, TransferEnforcer, UserManager,
 };
 use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
 
@@ -75,17 +76,60 @@ impl RepayParams {
 pub struct RepayModule;
 
 impl RepayModule {
-    /// Repay borrowed assets
-    pub fn repay(env: &Env, repayer: &Address, amount: i128) -> Result<(), ProtocolError> {
+    /// Repay borrowed assets, funded by `payer` (the repayer unless an operator repays on
+    /// their behalf)
+    pub fn repay(
+        env: &Env,
+        payer: &Address,
+        repayer: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::settle(env, payer, repayer, repayer, amount).map(|_| ())
+    }
+
+    /// Repay `borrower`'s debt from `payer`'s tokens. Limits, verification and activity
+    /// apply to the payer; the borrower only sees their debt go down. Returns the amount
+    /// repaid.
+    pub fn repay_on_behalf(
+        env: &Env,
+        payer: &Address,
+        borrower: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let repaid = Self::settle(env, payer, borrower, payer, amount)?;
+        env.events().publish(
+            (
+                Symbol::new(env, "repay_on_behalf"),
+                Symbol::new(env, "borrower"),
+            ),
+            (
+                Symbol::new(env, "payer"),
+                payer.clone(),
+                Symbol::new(env, "borrower"),
+                borrower.clone(),
+                Symbol::new(env, "amount"),
+                repaid,
+            ),
+        );
+        Ok(repaid)
+    }
+
+    /// Reduce `repayer`'s debt with tokens from `payer`, checking limits and recording
+    /// activity for `actor`
+    fn settle(
+        env: &Env,
+        payer: &Address,
+        repayer: &Address,
+        actor: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
         ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<(), ProtocolError> {
+        let result = (|| -> Result<i128, ProtocolError> {
             if amount <= 0 {
                 return Err(RepayError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Repay)?;
-
-            UserManager::ensure_operation_allowed(env, repayer, OperationKind::Repay, amount)?;
+            OperationGate::check(env, actor, OperationKind::Repay, None, amount)?;
 
             // Load user position
             let mut position = match StateHelper::get_position(env, repayer) {
@@ -95,12 +139,7 @@ impl RepayModule {
 
             // Accrue interest
             let state = InterestRateStorage::update_state(env);
-            InterestRateManager::accrue_interest_for_position(
-                env,
-                &mut position,
-                state.current_borrow_rate,
-                state.current_supply_rate,
-            );
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
 
             // Check if user has debt to repay
             if position.debt == 0 {
@@ -110,7 +149,14 @@ impl RepayModule {
             // Update position
             let repay_amount = core::cmp::min(amount, position.debt);
 
-            TransferEnforcer::transfer_in(env, repayer, repay_amount, Symbol::new(env, "repay"))?;
+            let asset = TokenRegistry::require_primary_asset(env)?;
+            let repay_amount = TransferEnforcer::transfer_in(
+                env,
+                &asset,
+                payer,
+                repay_amount,
+                Symbol::new(env, "repay"),
+            )?;
 
             position.debt -= repay_amount;
             StateHelper::save_position(env, &position);
@@ -131,59 +177,76 @@ impl RepayModule {
             .emit(env);
 
             // Analytics
-            AnalyticsModule::record_activity(env, repayer, "repay", repay_amount, None)?;
-            UserManager::record_activity(env, repayer, OperationKind::Repay, repay_amount)?;
+            AnalyticsModule::record_activity(env, actor, "repay", repay_amount, None)?;
+            UserManager::record_activity(env, actor, OperationKind::Repay, repay_amount)?;
 
-            Ok(())
+            Ok(repay_amount)
         })();
 
         ReentrancyGuard::exit(env);
         result
     }
 
-    /// Repay debt for a specific asset
-    pub fn _repay_asset(
+    /// Repay debt of a specific asset in the user's multi-asset position, funded by `payer`.
+    /// Returns the amount credited against the debt.
+    pub fn repay_asset(
         env: &Env,
-        user: &String,
+        payer: &Address,
+        user: &Address,
         asset: &Address,
         amount: i128,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<i128, ProtocolError> {
         ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(RepayError::InvalidAddress.into());
-            }
+        let result = (|| -> Result<i128, ProtocolError> {
             if amount <= 0 {
                 return Err(RepayError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Repay)?;
+            OperationGate::check(env, payer, OperationKind::Repay, Some(asset), amount)?;
 
-            let user_addr = crate::AddressHelper::require_valid_address(env, user)?;
+            // Bring the market current before this flow moves its totals
+            InterestRateManager::accrue_market(env, asset);
 
-            // For cross-asset repayment, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
+            let mut position = match StateHelper::get_cross_position(env, user) {
                 Some(pos) => pos,
                 None => return Err(RepayError::PositionNotFound.into()),
             };
 
-            if position.debt == 0 {
+            InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
+
+            let debt = position.debt.get(asset.clone()).unwrap_or(0);
+            if debt == 0 {
                 return Err(RepayError::InvalidOperation.into());
             }
 
-            let repay_amount = if amount > position.debt {
-                position.debt
+            // Credit what actually arrived, never more than the outstanding debt
+            let repay_amount = TransferEnforcer::transfer_in(
+                env,
+                asset,
+                payer,
+                amount.min(debt),
+                Symbol::new(env, "repay"),
+            )?;
+            if repay_amount == debt {
+                position.debt.remove(asset.clone());
             } else {
-                amount
-            };
-            position.debt -= repay_amount;
-            StateHelper::save_position(env, &position);
+                position.debt.set(asset.clone(), debt - repay_amount);
+            }
+            StateHelper::save_cross_position(env, &position);
+            InterestRateStorage::apply_market_flow(env, asset, 0, -repay_amount);
 
-            // Emit cross-asset repay event
-            ProtocolEvent::CrossRepay(user_addr, asset.clone(), repay_amount).emit(env);
+            ProtocolEvent::CrossRepay(user.clone(), asset.clone(), repay_amount).emit(env);
 
-            Ok(())
+            AnalyticsModule::record_activity(
+                env,
+                user,
+                "repay",
+                repay_amount,
+                Some(asset.clone()),
+            )?;
+            UserManager::record_activity(env, user, OperationKind::Repay, repay_amount)?;
+
+            Ok(repay_amount)
         })();
 
         ReentrancyGuard::exit(env);
@@ -208,12 +271,7 @@ impl RepayModule {
 
             // Accrue interest
             let state = InterestRateStorage::update_state(env);
-            InterestRateManager::accrue_interest_for_position(
-                env,
-                &mut position,
-                state.current_borrow_rate,
-                state.current_supply_rate,
-            );
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
 
             let total_debt = position.debt;
             if total_debt == 0 {
@@ -0,0 +1,144 @@
+//! Reserves module for StellarLend protocol
+//! Whenever a position's debt is rebased to its market's borrow index, `reserve_factor`
+//! of the interest added is credited to that asset's protocol reserves, less the share
+//! owed to the borrower's referrer and the safety module's stakers. Origination fees are
+//! credited in full. The admin withdraws reserves to a treasury address, and keeper job
+//! bounties are paid out of them.
+
+use crate::persistent::PersistentStore;
+use crate::referral::Referrals;
+use crate::safety_module::SafetyModule;
+use crate::{ProtocolConfig, ProtocolError, TransferEnforcer};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Scale of `reserve_factor` (1e8 = 100%)
+const FACTOR_SCALE: i128 = 100_000_000;
+
+/// Storage helper for per-asset reserves
+pub struct ReserveStorage;
+
+impl ReserveStorage {
+    fn key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "reserves"), asset.clone())
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> i128 {
+        PersistentStore::get(env, &Self::key(env, asset)).unwrap_or(0)
+    }
+
+    pub fn save(env: &Env, asset: &Address, amount: i128) {
+        PersistentStore::set(env, &Self::key(env, asset), &amount);
+    }
+
+    pub fn bump(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::key(env, asset)) as u32
+    }
+}
+
+/// Reserve accounting and treasury withdrawals
+pub struct Reserves;
+
+impl Reserves {
+    /// Credit the reserve cut of `interest` newly accrued on `user`'s debt
+    pub fn accrue(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        interest: i128,
+        reserve_factor: i128,
+    ) {
+        let cut = interest
+            .saturating_mul(reserve_factor.clamp(0, FACTOR_SCALE))
+            .saturating_div(FACTOR_SCALE);
+        let cut = cut - Referrals::on_fee(env, user, asset, cut);
+        let cut = cut - SafetyModule::on_fee(env, asset, cut);
+        Self::credit(env, asset, cut);
+    }
+
+    /// Add `amount` to the asset's reserves
+    pub fn credit(env: &Env, asset: &Address, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+        let total = ReserveStorage::get(env, asset).saturating_add(amount);
+        ReserveStorage::save(env, asset, total);
+        env.events().publish(
+            (
+                Symbol::new(env, "reserves_accrued"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+                Symbol::new(env, "total"),
+                total,
+            ),
+        );
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> i128 {
+        ReserveStorage::get(env, asset)
+    }
+
+    /// Pay up to `amount` of the asset's reserves to `recipient`, returning what was paid
+    pub fn pay(
+        env: &Env,
+        asset: &Address,
+        recipient: &Address,
+        amount: i128,
+        flow: &str,
+    ) -> Result<i128, ProtocolError> {
+        let reserves = ReserveStorage::get(env, asset);
+        let paid = amount.min(reserves);
+        if paid <= 0 {
+            return Ok(0);
+        }
+        ReserveStorage::save(env, asset, reserves - paid);
+        TransferEnforcer::transfer_out_asset(env, asset, recipient, paid, Symbol::new(env, flow))?;
+        Ok(paid)
+    }
+
+    /// Send accumulated reserves of an asset to `treasury` (admin only). Returns the
+    /// reserves left.
+    pub fn withdraw(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        treasury: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let reserves = ReserveStorage::get(env, asset);
+        if amount <= 0 || amount > reserves {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let remaining = reserves - amount;
+        ReserveStorage::save(env, asset, remaining);
+        TransferEnforcer::transfer_out_asset(
+            env,
+            asset,
+            treasury,
+            amount,
+            Symbol::new(env, "reserve_withdraw"),
+        )?;
+        env.events().publish(
+            (
+                Symbol::new(env, "reserves_withdrawn"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "treasury"),
+                treasury.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+                Symbol::new(env, "remaining"),
+                remaining,
+            ),
+        );
+        Ok(remaining)
+    }
+}
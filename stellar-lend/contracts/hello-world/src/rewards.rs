@@ -0,0 +1,339 @@
+//! Rewards module for StellarLend protocol
+//! Liquidity mining: each market can stream a reward token to its suppliers and borrowers
+//! at admin-set per-second rates. Emissions are distributed through a reward index per
+//! market side; every position write checkpoints the user's balances and settles what they
+//! earned since their last snapshot.
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, StateHelper, TokenRegistry, TransferEnforcer};
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol};
+
+/// Scale of the reward indexes (1e18)
+const INDEX_SCALE: i128 = 1_000_000_000_000_000_000;
+
+/// Reward emission of one market
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EmissionSchedule {
+    pub reward_token: Address,
+    /// Reward tokens per second shared by the market's suppliers
+    pub supply_per_second: i128,
+    /// Reward tokens per second shared by the market's borrowers
+    pub borrow_per_second: i128,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Reward accounting of one market
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardMarket {
+    pub schedule: EmissionSchedule,
+    /// Rewards per unit supplied since the market was created (scaled by 1e18)
+    pub supply_index: i128,
+    /// Rewards per unit borrowed since the market was created (scaled by 1e18)
+    pub borrow_index: i128,
+    /// Checkpointed supply of all users earning in this market
+    pub total_supply: i128,
+    /// Checkpointed debt of all users earning in this market
+    pub total_borrow: i128,
+    pub last_update: u64,
+}
+
+/// A user's checkpointed balances in a market and the indexes they were settled at
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardPosition {
+    pub supply: i128,
+    pub borrow: i128,
+    pub supply_index: i128,
+    pub borrow_index: i128,
+}
+
+/// A user's reward checkpoints and unclaimed rewards per reward token
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UserRewards {
+    pub positions: Map<Address, RewardPosition>,
+    pub accrued: Map<Address, i128>,
+}
+
+/// Storage helper for reward markets and user checkpoints
+pub struct RewardStorage;
+
+impl RewardStorage {
+    fn market_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "reward_market"), asset.clone())
+    }
+    fn user_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "user_rewards"), user.clone())
+    }
+
+    pub fn get_market(env: &Env, asset: &Address) -> Option<RewardMarket> {
+        PersistentStore::get(env, &Self::market_key(env, asset))
+    }
+
+    pub fn save_market(env: &Env, asset: &Address, market: &RewardMarket) {
+        PersistentStore::set(env, &Self::market_key(env, asset), market);
+    }
+
+    pub fn get_user(env: &Env, user: &Address) -> UserRewards {
+        PersistentStore::get(env, &Self::user_key(env, user)).unwrap_or(UserRewards {
+            positions: Map::new(env),
+            accrued: Map::new(env),
+        })
+    }
+
+    pub fn save_user(env: &Env, user: &Address, rewards: &UserRewards) {
+        PersistentStore::set(env, &Self::user_key(env, user), rewards);
+    }
+
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::user_key(env, user)) as u32
+    }
+
+    pub fn bump_market(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::market_key(env, asset)) as u32
+    }
+}
+
+/// Reward emissions, checkpoints and claims
+pub struct Rewards;
+
+impl Rewards {
+    /// Set a market's emission schedule (admin only). Emissions under the previous
+    /// schedule are accounted up to now; a market keeps the reward token it started with.
+    pub fn set_schedule(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        schedule: EmissionSchedule,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if schedule.supply_per_second < 0
+            || schedule.borrow_per_second < 0
+            || schedule.end <= schedule.start
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let market = match RewardStorage::get_market(env, asset) {
+            Some(market) => {
+                if market.schedule.reward_token != schedule.reward_token {
+                    return Err(ProtocolError::InvalidParameters);
+                }
+                RewardMarket {
+                    schedule: schedule.clone(),
+                    ..Self::advance(env, market)
+                }
+            }
+            None => RewardMarket {
+                schedule: schedule.clone(),
+                supply_index: 0,
+                borrow_index: 0,
+                total_supply: 0,
+                total_borrow: 0,
+                last_update: env.ledger().timestamp(),
+            },
+        };
+        RewardStorage::save_market(env, asset, &market);
+        env.events().publish(
+            (
+                Symbol::new(env, "emission_schedule_set"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "reward_token"),
+                schedule.reward_token,
+                Symbol::new(env, "supply_per_second"),
+                schedule.supply_per_second,
+                Symbol::new(env, "borrow_per_second"),
+                schedule.borrow_per_second,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_market(env: &Env, asset: &Address) -> Option<RewardMarket> {
+        RewardStorage::get_market(env, asset)
+    }
+
+    /// Market with its indexes moved forward to now; emissions only count inside the
+    /// schedule window and while the side has checkpointed balances
+    fn advance(env: &Env, mut market: RewardMarket) -> RewardMarket {
+        let now = env.ledger().timestamp();
+        let from = market.last_update.max(market.schedule.start);
+        let to = now.min(market.schedule.end);
+        if to > from {
+            let elapsed = (to - from) as i128;
+            if market.total_supply > 0 {
+                market.supply_index += market
+                    .schedule
+                    .supply_per_second
+                    .saturating_mul(elapsed)
+                    .saturating_mul(INDEX_SCALE)
+                    / market.total_supply;
+            }
+            if market.total_borrow > 0 {
+                market.borrow_index += market
+                    .schedule
+                    .borrow_per_second
+                    .saturating_mul(elapsed)
+                    .saturating_mul(INDEX_SCALE)
+                    / market.total_borrow;
+            }
+        }
+        market.last_update = now;
+        market
+    }
+
+    /// Rewards a checkpoint earned between its indexes and the market's
+    fn earned(position: &RewardPosition, market: &RewardMarket) -> i128 {
+        let supply = position
+            .supply
+            .saturating_mul(market.supply_index - position.supply_index)
+            / INDEX_SCALE;
+        let borrow = position
+            .borrow
+            .saturating_mul(market.borrow_index - position.borrow_index)
+            / INDEX_SCALE;
+        supply + borrow
+    }
+
+    /// A user's supply and debt in `asset` across their primary and cross positions
+    fn balances(env: &Env, user: &Address, asset: &Address) -> (i128, i128) {
+        let (mut supply, mut borrow) = (0i128, 0i128);
+        if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset) {
+            if let Some(position) = StateHelper::get_position(env, user) {
+                supply += position.collateral;
+                borrow += position.debt;
+            }
+        }
+        if let Some(position) = StateHelper::get_cross_position(env, user) {
+            supply += position.collateral.get(asset.clone()).unwrap_or(0);
+            borrow += position.debt.get(asset.clone()).unwrap_or(0);
+        }
+        (supply, borrow)
+    }
+
+    /// Settle the user's rewards in `asset` and checkpoint their current balances. Markets
+    /// without a schedule are skipped.
+    pub fn sync(env: &Env, user: &Address, asset: &Address) {
+        let mut market = match RewardStorage::get_market(env, asset) {
+            Some(market) => Self::advance(env, market),
+            None => return,
+        };
+        let mut rewards = RewardStorage::get_user(env, user);
+        if let Some(previous) = rewards.positions.get(asset.clone()) {
+            let earned = Self::earned(&previous, &market);
+            if earned > 0 {
+                let token = market.schedule.reward_token.clone();
+                let total = rewards.accrued.get(token.clone()).unwrap_or(0);
+                rewards.accrued.set(token, total.saturating_add(earned));
+            }
+            market.total_supply -= previous.supply;
+            market.total_borrow -= previous.borrow;
+        }
+        let (supply, borrow) = Self::balances(env, user, asset);
+        market.total_supply += supply;
+        market.total_borrow += borrow;
+        if supply > 0 || borrow > 0 {
+            rewards.positions.set(
+                asset.clone(),
+                RewardPosition {
+                    supply,
+                    borrow,
+                    supply_index: market.supply_index,
+                    borrow_index: market.borrow_index,
+                },
+            );
+        } else {
+            rewards.positions.remove(asset.clone());
+        }
+        RewardStorage::save_market(env, asset, &market);
+        RewardStorage::save_user(env, user, &rewards);
+    }
+
+    /// Checkpoint every asset of a user's cross position plus the markets they were
+    /// earning in before the write
+    pub fn sync_cross_position(env: &Env, user: &Address) {
+        let mut assets = RewardStorage::get_user(env, user).positions.keys();
+        if let Some(position) = StateHelper::get_cross_position(env, user) {
+            for asset in position
+                .collateral
+                .keys()
+                .iter()
+                .chain(position.debt.keys().iter())
+            {
+                if !assets.contains(&asset) {
+                    assets.push_back(asset);
+                }
+            }
+        }
+        for asset in assets.iter() {
+            Self::sync(env, user, &asset);
+        }
+    }
+
+    /// Unclaimed rewards per reward token including emissions not yet settled, without
+    /// writing
+    pub fn pending(env: &Env, user: &Address) -> Map<Address, i128> {
+        let rewards = RewardStorage::get_user(env, user);
+        let mut pending = rewards.accrued.clone();
+        for (asset, position) in rewards.positions.iter() {
+            if let Some(market) = RewardStorage::get_market(env, &asset) {
+                let market = Self::advance(env, market);
+                let token = market.schedule.reward_token.clone();
+                let total = pending.get(token.clone()).unwrap_or(0);
+                pending.set(token, total + Self::earned(&position, &market));
+            }
+        }
+        pending
+    }
+
+    /// Settle every market the user earns in and pay out all unclaimed rewards. Returns
+    /// the amount paid per reward token.
+    pub fn claim(env: &Env, user: &Address) -> Result<Map<Address, i128>, ProtocolError> {
+        user.require_auth();
+        for asset in RewardStorage::get_user(env, user).positions.keys().iter() {
+            Self::sync(env, user, &asset);
+        }
+        let mut rewards = RewardStorage::get_user(env, user);
+        let mut paid = Map::new(env);
+        for (token, amount) in rewards.accrued.iter() {
+            if amount > 0 {
+                paid.set(token, amount);
+            }
+        }
+        if paid.is_empty() {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        rewards.accrued = Map::new(env);
+        RewardStorage::save_user(env, user, &rewards);
+        for (token, amount) in paid.iter() {
+            TransferEnforcer::transfer_out_asset(
+                env,
+                &token,
+                user,
+                amount,
+                Symbol::new(env, "reward_claim"),
+            )?;
+            env.events().publish(
+                (
+                    Symbol::new(env, "rewards_claimed"),
+                    Symbol::new(env, "user"),
+                ),
+                (
+                    Symbol::new(env, "user"),
+                    user.clone(),
+                    Symbol::new(env, "reward_token"),
+                    token,
+                    Symbol::new(env, "amount"),
+                    amount,
+                ),
+            );
+        }
+        Ok(paid)
+    }
+}
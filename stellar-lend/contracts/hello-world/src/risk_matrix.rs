@@ -0,0 +1,120 @@
+//! Risk matrix module for StellarLend protocol
+//! Sparse pairwise (collateral, debt) collateral requirements overriding the global minimum ratio
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, TokenRegistry};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Storage helper for pairwise collateral requirements.
+/// Only pairs with an explicit entry are stored; everything else uses the global `min_ratio`.
+pub struct CollateralMatrixStorage;
+
+impl CollateralMatrixStorage {
+    fn pair_key(env: &Env, collateral: &Address, debt: &Address) -> (Symbol, Address, Address) {
+        (
+            Symbol::new(env, "cf_pair"),
+            collateral.clone(),
+            debt.clone(),
+        )
+    }
+
+    pub fn get(env: &Env, collateral: &Address, debt: &Address) -> Option<i128> {
+        PersistentStore::get(env, &Self::pair_key(env, collateral, debt))
+    }
+
+    pub fn set(env: &Env, collateral: &Address, debt: &Address, ratio: i128) {
+        PersistentStore::set(env, &Self::pair_key(env, collateral, debt), &ratio);
+    }
+
+    pub fn remove(env: &Env, collateral: &Address, debt: &Address) {
+        PersistentStore::remove(env, &Self::pair_key(env, collateral, debt));
+    }
+
+    /// Extend the TTL of a pair's entry, returning how many exist
+    pub fn bump(env: &Env, collateral: &Address, debt: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::pair_key(env, collateral, debt)) as u32
+    }
+}
+
+/// Pairwise collateral-factor matrix used by health math
+pub struct CollateralMatrix;
+
+impl CollateralMatrix {
+    /// Set the minimum collateral ratio (percent, same units as `min_ratio`) for a pair
+    pub fn set_pair_ratio(
+        env: &Env,
+        caller: &Address,
+        collateral: &Address,
+        debt: &Address,
+        ratio: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if ratio <= 0 {
+            return Err(ProtocolError::InvalidInput);
+        }
+        CollateralMatrixStorage::set(env, collateral, debt, ratio);
+        env.events().publish(
+            (
+                Symbol::new(env, "collateral_pair_updated"),
+                Symbol::new(env, "pair"),
+            ),
+            (
+                Symbol::new(env, "collateral"),
+                collateral.clone(),
+                Symbol::new(env, "debt"),
+                debt.clone(),
+                Symbol::new(env, "ratio"),
+                Some(ratio),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Remove a pair entry so the pair falls back to the global minimum ratio
+    pub fn clear_pair_ratio(
+        env: &Env,
+        caller: &Address,
+        collateral: &Address,
+        debt: &Address,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if CollateralMatrixStorage::get(env, collateral, debt).is_none() {
+            return Err(ProtocolError::NotFound);
+        }
+        CollateralMatrixStorage::remove(env, collateral, debt);
+        env.events().publish(
+            (
+                Symbol::new(env, "collateral_pair_updated"),
+                Symbol::new(env, "pair"),
+            ),
+            (
+                Symbol::new(env, "collateral"),
+                collateral.clone(),
+                Symbol::new(env, "debt"),
+                debt.clone(),
+                Symbol::new(env, "ratio"),
+                None::<i128>,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn get_pair_ratio(env: &Env, collateral: &Address, debt: &Address) -> Option<i128> {
+        CollateralMatrixStorage::get(env, collateral, debt)
+    }
+
+    /// Minimum collateral ratio for a pair, falling back to the global default
+    pub fn min_ratio_for_pair(env: &Env, collateral: &Address, debt: &Address) -> i128 {
+        CollateralMatrixStorage::get(env, collateral, debt)
+            .unwrap_or_else(|| ProtocolConfig::get_min_collateral_ratio(env))
+    }
+
+    /// Minimum collateral ratio applied to positions.
+    /// Positions hold the primary asset on both sides, so the (primary, primary) entry applies.
+    pub fn effective_min_ratio(env: &Env) -> i128 {
+        match TokenRegistry::require_primary_asset(env) {
+            Ok(asset) => Self::min_ratio_for_pair(env, &asset, &asset),
+            Err(_) => ProtocolConfig::get_min_collateral_ratio(env),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Safety module for StellarLend protocol
+//! Users stake a registered token into that token's backstop pool for shares. A configured
+//! share of the reserve cut of interest in the token is added to the pool, raising the
+//! value of every share. When a shortfall is applied the pool is slashed first, up to
+//! `max_slash_bps` of its stake, before the insurance reserve or supplier funds are
+//! touched. Unstaking takes a request followed by a cooldown; shares keep backing the
+//! pool (and can be slashed) until they are redeemed.
+
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, TransferEnforcer};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Fee share and slash limits are basis points
+const BPS_SCALE: i128 = 10_000;
+
+/// Default wait between an unstake request and redemption (10 days)
+const DEFAULT_COOLDOWN: u64 = 10 * 86400;
+
+/// Default largest part of a pool one shortfall may slash (30%)
+const DEFAULT_MAX_SLASH_BPS: i128 = 3_000;
+
+/// Admin-set safety module parameters
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SafetyModuleConfig {
+    /// Seconds between an unstake request and redemption
+    pub cooldown: u64,
+    /// Share of the reserve cut paid to the asset's stakers (bps)
+    pub fee_share_bps: i128,
+    /// Largest part of a pool's stake a single shortfall may slash (bps)
+    pub max_slash_bps: i128,
+}
+
+impl SafetyModuleConfig {
+    pub fn default_config() -> Self {
+        Self {
+            cooldown: DEFAULT_COOLDOWN,
+            fee_share_bps: 0,
+            max_slash_bps: DEFAULT_MAX_SLASH_BPS,
+        }
+    }
+}
+
+/// Stake and shares of one asset's backstop pool
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct SafetyPool {
+    /// Tokens backing the pool, including earned fees and net of slashes
+    pub total_staked: i128,
+    pub total_shares: i128,
+}
+
+/// Shares a staker asked to redeem
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UnstakeRequest {
+    pub shares: i128,
+    /// Earliest redemption time
+    pub available_at: u64,
+}
+
+/// Storage helper for the safety module
+pub struct SafetyModuleStorage;
+
+impl SafetyModuleStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "safety_config")
+    }
+
+    fn pool_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "safety_pool"), asset.clone())
+    }
+
+    fn shares_key(env: &Env, asset: &Address, user: &Address) -> (Symbol, Address, Address) {
+        (
+            Symbol::new(env, "safety_shares"),
+            asset.clone(),
+            user.clone(),
+        )
+    }
+
+    fn request_key(env: &Env, asset: &Address, user: &Address) -> (Symbol, Address, Address) {
+        (
+            Symbol::new(env, "safety_unstake"),
+            asset.clone(),
+            user.clone(),
+        )
+    }
+
+    pub fn get_config(env: &Env) -> SafetyModuleConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key(env))
+            .unwrap_or_else(SafetyModuleConfig::default_config)
+    }
+
+    pub fn save_config(env: &Env, config: &SafetyModuleConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get_pool(env: &Env, asset: &Address) -> SafetyPool {
+        PersistentStore::get(env, &Self::pool_key(env, asset)).unwrap_or_default()
+    }
+
+    pub fn save_pool(env: &Env, asset: &Address, pool: &SafetyPool) {
+        PersistentStore::set(env, &Self::pool_key(env, asset), pool);
+    }
+
+    pub fn get_shares(env: &Env, asset: &Address, user: &Address) -> i128 {
+        PersistentStore::get(env, &Self::shares_key(env, asset, user)).unwrap_or(0)
+    }
+
+    pub fn save_shares(env: &Env, asset: &Address, user: &Address, shares: i128) {
+        let key = Self::shares_key(env, asset, user);
+        if shares == 0 {
+            PersistentStore::remove(env, &key);
+        } else {
+            PersistentStore::set(env, &key, &shares);
+        }
+    }
+
+    pub fn get_request(env: &Env, asset: &Address, user: &Address) -> Option<UnstakeRequest> {
+        PersistentStore::get(env, &Self::request_key(env, asset, user))
+    }
+
+    pub fn save_request(env: &Env, asset: &Address, user: &Address, request: &UnstakeRequest) {
+        PersistentStore::set(env, &Self::request_key(env, asset, user), request);
+    }
+
+    pub fn remove_request(env: &Env, asset: &Address, user: &Address) {
+        PersistentStore::remove(env, &Self::request_key(env, asset, user));
+    }
+}
+
+/// Stake-to-backstop pools
+pub struct SafetyModule;
+
+impl SafetyModule {
+    /// Set the cooldown, fee share and slash limit (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: SafetyModuleConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=BPS_SCALE).contains(&config.fee_share_bps)
+            || !(0..=BPS_SCALE).contains(&config.max_slash_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        SafetyModuleStorage::save_config(env, &config);
+        Ok(())
+    }
+
+    pub fn get_config(env: &Env) -> SafetyModuleConfig {
+        SafetyModuleStorage::get_config(env)
+    }
+
+    /// Stake `amount` of a registered token. Returns the shares minted.
+    pub fn stake(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        // Only registered tokens pass the transfer enforcer
+        let received =
+            TransferEnforcer::transfer_in(env, asset, user, amount, Symbol::new(env, "stake"))?;
+
+        let mut pool = SafetyModuleStorage::get_pool(env, asset);
+        let shares = if pool.total_shares == 0 || pool.total_staked == 0 {
+            received
+        } else {
+            received * pool.total_shares / pool.total_staked
+        };
+        if shares <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        pool.total_staked += received;
+        pool.total_shares += shares;
+        SafetyModuleStorage::save_pool(env, asset, &pool);
+        let held = SafetyModuleStorage::get_shares(env, asset, user);
+        SafetyModuleStorage::save_shares(env, asset, user, held + shares);
+        Self::publish(env, "safety_staked", asset, user, received);
+        Ok(shares)
+    }
+
+    /// Ask to redeem `shares` once the cooldown has passed, replacing any earlier request.
+    /// Returns the earliest redemption time.
+    pub fn request_unstake(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        shares: i128,
+    ) -> Result<u64, ProtocolError> {
+        user.require_auth();
+        if shares <= 0 || shares > SafetyModuleStorage::get_shares(env, asset, user) {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let available_at = env.ledger().timestamp() + SafetyModuleStorage::get_config(env).cooldown;
+        SafetyModuleStorage::save_request(
+            env,
+            asset,
+            user,
+            &UnstakeRequest {
+                shares,
+                available_at,
+            },
+        );
+        Self::publish(env, "safety_unstake_requested", asset, user, shares);
+        Ok(available_at)
+    }
+
+    /// Redeem the requested shares at their current value after the cooldown. Returns the
+    /// tokens paid out.
+    pub fn unstake(env: &Env, user: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        let request =
+            SafetyModuleStorage::get_request(env, asset, user).ok_or(ProtocolError::NotFound)?;
+        if env.ledger().timestamp() < request.available_at {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let held = SafetyModuleStorage::get_shares(env, asset, user);
+        let shares = request.shares.min(held);
+        let mut pool = SafetyModuleStorage::get_pool(env, asset);
+        let amount = if pool.total_shares == 0 {
+            0
+        } else {
+            shares * pool.total_staked / pool.total_shares
+        };
+        pool.total_staked -= amount;
+        pool.total_shares -= shares;
+        SafetyModuleStorage::save_pool(env, asset, &pool);
+        SafetyModuleStorage::save_shares(env, asset, user, held - shares);
+        SafetyModuleStorage::remove_request(env, asset, user);
+        if amount > 0 {
+            TransferEnforcer::transfer_out(env, asset, user, amount, Symbol::new(env, "unstake"))?;
+        }
+        Self::publish(env, "safety_unstaked", asset, user, amount);
+        Ok(amount)
+    }
+
+    /// Credit the stakers' share of a reserve `fee` in `asset`. Returns the amount taken
+    /// from the fee.
+    pub fn on_fee(env: &Env, asset: &Address, fee: i128) -> i128 {
+        let share = SafetyModuleStorage::get_config(env).fee_share_bps;
+        if fee <= 0 || share == 0 {
+            return 0;
+        }
+        let mut pool = SafetyModuleStorage::get_pool(env, asset);
+        if pool.total_shares == 0 {
+            return 0;
+        }
+        let reward = fee.saturating_mul(share) / BPS_SCALE;
+        if reward <= 0 {
+            return 0;
+        }
+        pool.total_staked = pool.total_staked.saturating_add(reward);
+        SafetyModuleStorage::save_pool(env, asset, &pool);
+        reward
+    }
+
+    /// Slash the asset's pool to cover up to `loss`, bounded by the slash limit. The
+    /// slashed tokens stay with the protocol. Returns the amount covered.
+    pub fn cover_shortfall(env: &Env, asset: &Address, loss: i128) -> i128 {
+        let mut pool = SafetyModuleStorage::get_pool(env, asset);
+        let limit =
+            pool.total_staked * SafetyModuleStorage::get_config(env).max_slash_bps / BPS_SCALE;
+        let slashed = loss.min(limit).max(0);
+        if slashed == 0 {
+            return 0;
+        }
+        pool.total_staked -= slashed;
+        SafetyModuleStorage::save_pool(env, asset, &pool);
+        env.events().publish(
+            (
+                Symbol::new(env, "safety_slashed"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "amount"),
+                slashed,
+                Symbol::new(env, "remaining"),
+                pool.total_staked,
+            ),
+        );
+        slashed
+    }
+
+    pub fn get_pool(env: &Env, asset: &Address) -> SafetyPool {
+        SafetyModuleStorage::get_pool(env, asset)
+    }
+
+    /// Current token value of a staker's shares
+    pub fn staked_balance(env: &Env, asset: &Address, user: &Address) -> i128 {
+        let pool = SafetyModuleStorage::get_pool(env, asset);
+        if pool.total_shares == 0 {
+            return 0;
+        }
+        SafetyModuleStorage::get_shares(env, asset, user) * pool.total_staked / pool.total_shares
+    }
+
+    pub fn get_request(env: &Env, asset: &Address, user: &Address) -> Option<UnstakeRequest> {
+        SafetyModuleStorage::get_request(env, asset, user)
+    }
+
+    fn publish(env: &Env, action: &str, asset: &Address, user: &Address, amount: i128) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+            ),
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Safety reward module for StellarLend protocol
+//! Reward points emitted on supplied collateral at accrual time, scaled by a multiplier
+//! read off a health-factor curve so safe positions earn more than ones near liquidation.
+
+use crate::persistent::PersistentStore;
+use crate::risk_matrix::CollateralMatrix;
+use crate::{InterestRateManager, Position, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Basis point scale (10000 = 1x)
+const BPS_SCALE: i128 = 10000;
+
+/// Highest multiplier a curve point may grant (5x)
+const MAX_MULTIPLIER_BPS: i128 = 50000;
+
+/// Maximum points on the curve
+const MAX_CURVE_POINTS: u32 = 8;
+
+/// A point on the multiplier curve
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardCurvePoint {
+    /// Health factor in percent (100 = at the liquidation threshold)
+    pub health_factor: i128,
+    /// Reward multiplier at this health factor (10000 = 1x)
+    pub multiplier_bps: i128,
+}
+
+/// Emission settings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SafetyRewardConfig {
+    /// Annual points per unit of collateral (scaled by 1e8, at most 1e8); 0 disables emissions
+    pub emission_rate: i128,
+    /// Curve points in ascending health factor; multipliers are interpolated between
+    /// points and held flat beyond the ends
+    pub curve: Vec<RewardCurvePoint>,
+}
+
+/// A user's accumulated safety rewards
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct SafetyRewardAccount {
+    pub points: i128,
+    /// Multiplier applied at the last accrual
+    pub multiplier_bps: i128,
+    pub updated_at: u64,
+}
+
+/// Storage helper for safety rewards
+pub struct SafetyRewardStorage;
+
+impl SafetyRewardStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "safety_reward_cfg")
+    }
+    fn account_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "safety_points"), user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> Option<SafetyRewardConfig> {
+        env.storage().instance().get(&Self::config_key(env))
+    }
+
+    pub fn save_config(env: &Env, config: &SafetyRewardConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get_account(env: &Env, user: &Address) -> SafetyRewardAccount {
+        PersistentStore::get(env, &Self::account_key(env, user)).unwrap_or_default()
+    }
+
+    pub fn save_account(env: &Env, user: &Address, account: &SafetyRewardAccount) {
+        PersistentStore::set(env, &Self::account_key(env, user), account);
+    }
+
+    /// Extend the TTL of a user's reward account, returning how many entries exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::account_key(env, user)) as u32
+    }
+}
+
+/// Health-factor-aware reward emissions
+pub struct SafetyRewards;
+
+impl SafetyRewards {
+    /// Set the emission rate and multiplier curve (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        emission_rate: i128,
+        curve: Vec<RewardCurvePoint>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=100000000).contains(&emission_rate)
+            || curve.is_empty()
+            || curve.len() > MAX_CURVE_POINTS
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut previous: Option<i128> = None;
+        for point in curve.iter() {
+            if point.health_factor < 0
+                || !(0..=MAX_MULTIPLIER_BPS).contains(&point.multiplier_bps)
+                || previous.is_some_and(|hf| point.health_factor <= hf)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            previous = Some(point.health_factor);
+        }
+
+        SafetyRewardStorage::save_config(
+            env,
+            &SafetyRewardConfig {
+                emission_rate,
+                curve,
+            },
+        );
+        env.events().publish(
+            (
+                Symbol::new(env, "safety_rewards_updated"),
+                Symbol::new(env, "admin"),
+            ),
+            (Symbol::new(env, "emission_rate"), emission_rate),
+        );
+        Ok(())
+    }
+
+    /// Health factor of a position in percent (100 = at the liquidation threshold),
+    /// or `None` when it carries no debt
+    pub fn health_factor(env: &Env, position: &Position) -> Option<i128> {
+        if position.debt <= 0 {
+            return None;
+        }
+        let min_ratio = CollateralMatrix::effective_min_ratio(env);
+        if min_ratio <= 0 {
+            return None;
+        }
+        let collateral_ratio = (position.collateral * 100) / position.debt;
+        Some((collateral_ratio * 100) / min_ratio)
+    }
+
+    /// Multiplier on the curve at `health_factor`; debt-free positions take the top point
+    pub fn multiplier_at(curve: &Vec<RewardCurvePoint>, health_factor: Option<i128>) -> i128 {
+        let (first, last) = match (curve.first(), curve.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return BPS_SCALE,
+        };
+        let hf = match health_factor {
+            Some(hf) => hf,
+            None => return last.multiplier_bps,
+        };
+        if hf <= first.health_factor {
+            return first.multiplier_bps;
+        }
+        let mut lower = first;
+        for upper in curve.iter().skip(1) {
+            if hf < upper.health_factor {
+                let span = upper.health_factor - lower.health_factor;
+                let delta = upper.multiplier_bps - lower.multiplier_bps;
+                return lower.multiplier_bps + delta * (hf - lower.health_factor) / span;
+            }
+            lower = upper;
+        }
+        last.multiplier_bps
+    }
+
+    /// Credit points for the elapsed interval; called from position accrual
+    pub fn on_accrual(env: &Env, position: &Position, time_delta: u64) {
+        let config = match SafetyRewardStorage::get_config(env) {
+            Some(config) if config.emission_rate > 0 => config,
+            _ => return,
+        };
+        if position.collateral <= 0 || time_delta == 0 {
+            return;
+        }
+
+        let multiplier_bps = Self::multiplier_at(&config.curve, Self::health_factor(env, position));
+        let base =
+            InterestRateManager::interest(position.collateral, config.emission_rate, time_delta);
+        let points = base.saturating_mul(multiplier_bps) / BPS_SCALE;
+
+        let mut account = SafetyRewardStorage::get_account(env, &position.user);
+        account.points = account.points.saturating_add(points);
+        account.multiplier_bps = multiplier_bps;
+        account.updated_at = env.ledger().timestamp();
+        SafetyRewardStorage::save_account(env, &position.user, &account);
+    }
+
+    pub fn get_config(env: &Env) -> Option<SafetyRewardConfig> {
+        SafetyRewardStorage::get_config(env)
+    }
+
+    pub fn get_account(env: &Env, user: &Address) -> SafetyRewardAccount {
+        SafetyRewardStorage::get_account(env, user)
+    }
+}
@@ -0,0 +1,256 @@
+//! Soft borrow limit module for StellarLend protocol
+//! Borrows above a user's soft limit are queued as approval requests instead of being
+//! rejected. A manager approving the request within its window executes the borrow.
+
+use crate::gate::OperationGate;
+use crate::persistent::PersistentStore;
+use crate::{modules, OperationKind, ProtocolError, UserManager};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Per-user soft borrow limit
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SoftLimit {
+    /// Largest borrow executed without approval
+    pub limit: i128,
+    /// Seconds a manager has to approve a queued borrow
+    pub approval_window: u64,
+}
+
+/// Lifecycle of a queued borrow
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum SoftLimitRequestStatus {
+    Pending,
+    Executed,
+    Rejected,
+    Expired,
+}
+
+/// A borrow above the soft limit awaiting manager approval
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SoftLimitRequest {
+    pub id: u64,
+    pub user: Address,
+    pub amount: i128,
+    pub requested_at: u64,
+    pub expires_at: u64,
+    pub status: SoftLimitRequestStatus,
+}
+
+/// Storage helper for soft limits and queued requests
+pub struct SoftLimitStorage;
+
+impl SoftLimitStorage {
+    fn limit_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "soft_limit"), user.clone())
+    }
+    fn pending_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "soft_pending"), user.clone())
+    }
+    fn request_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "soft_request"), id)
+    }
+    fn request_count_key(env: &Env) -> Symbol {
+        Symbol::new(env, "soft_request_count")
+    }
+
+    pub fn get_limit(env: &Env, user: &Address) -> Option<SoftLimit> {
+        PersistentStore::get(env, &Self::limit_key(env, user))
+    }
+
+    pub fn set_limit(env: &Env, user: &Address, limit: Option<SoftLimit>) {
+        match limit {
+            Some(limit) => PersistentStore::set(env, &Self::limit_key(env, user), &limit),
+            None => PersistentStore::remove(env, &Self::limit_key(env, user)),
+        }
+    }
+
+    pub fn get_pending(env: &Env, user: &Address) -> Option<u64> {
+        PersistentStore::get(env, &Self::pending_key(env, user))
+    }
+
+    pub fn set_pending(env: &Env, user: &Address, id: Option<u64>) {
+        match id {
+            Some(id) => PersistentStore::set(env, &Self::pending_key(env, user), &id),
+            None => PersistentStore::remove(env, &Self::pending_key(env, user)),
+        }
+    }
+
+    pub fn get_request(env: &Env, id: u64) -> Option<SoftLimitRequest> {
+        PersistentStore::get(env, &Self::request_key(env, id))
+    }
+
+    pub fn save_request(env: &Env, request: &SoftLimitRequest) {
+        PersistentStore::set(env, &Self::request_key(env, request.id), request);
+    }
+
+    /// Extend the TTL of a user's limit and pending request id, returning how many exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::limit_key(env, user)) as u32
+            + PersistentStore::bump(env, &Self::pending_key(env, user)) as u32
+    }
+
+    pub fn next_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get::<Symbol, u64>(&Self::request_count_key(env))
+            .unwrap_or(0)
+            + 1;
+        env.storage()
+            .instance()
+            .set(&Self::request_count_key(env), &id);
+        id
+    }
+}
+
+/// Soft borrow limits with manager override approvals
+pub struct SoftLimits;
+
+impl SoftLimits {
+    /// Set or clear (`limit <= 0`) a user's soft borrow limit (manager or admin only)
+    pub fn set_limit(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        limit: i128,
+        approval_window: u64,
+    ) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::require_manager(env, caller)?;
+        if limit > 0 && approval_window == 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let soft_limit = if limit > 0 {
+            Some(SoftLimit {
+                limit,
+                approval_window,
+            })
+        } else {
+            None
+        };
+        SoftLimitStorage::set_limit(env, user, soft_limit);
+        env.events().publish(
+            (
+                Symbol::new(env, "soft_limit_updated"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "limit"),
+                limit.max(0),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Queue a borrow that exceeds the user's soft limit. Returns `true` when the borrow
+    /// was queued and must not execute now. Hard limits and verification still apply.
+    pub fn queue_if_exceeded(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<bool, ProtocolError> {
+        let soft_limit = match SoftLimitStorage::get_limit(env, user) {
+            Some(soft_limit) if amount > soft_limit.limit => soft_limit,
+            _ => return Ok(false),
+        };
+        OperationGate::check(env, user, OperationKind::Borrow, None, amount)?;
+        if let Some(pending) = Self::pending_request(env, user) {
+            if pending.status == SoftLimitRequestStatus::Pending {
+                return Err(ProtocolError::AlreadyExists);
+            }
+        }
+
+        let now = env.ledger().timestamp();
+        let request = SoftLimitRequest {
+            id: SoftLimitStorage::next_id(env),
+            user: user.clone(),
+            amount,
+            requested_at: now,
+            expires_at: now + soft_limit.approval_window,
+            status: SoftLimitRequestStatus::Pending,
+        };
+        SoftLimitStorage::save_request(env, &request);
+        SoftLimitStorage::set_pending(env, user, Some(request.id));
+        env.events().publish(
+            (
+                Symbol::new(env, "soft_limit_request"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "request"),
+                request.id,
+                Symbol::new(env, "amount"),
+                amount,
+            ),
+        );
+        Ok(true)
+    }
+
+    /// Approve (executing the queued borrow) or reject a pending request within its
+    /// window (manager or admin only)
+    pub fn resolve(
+        env: &Env,
+        caller: &Address,
+        id: u64,
+        approve: bool,
+    ) -> Result<(), ProtocolError> {
+        caller.require_auth();
+        UserManager::require_manager(env, caller)?;
+        let mut request = Self::get_request(env, id).ok_or(ProtocolError::NotFound)?;
+        if request.status != SoftLimitRequestStatus::Pending {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        if approve {
+            modules::ModuleRegistry::borrow(env, &request.user, request.amount)?;
+            request.status = SoftLimitRequestStatus::Executed;
+        } else {
+            request.status = SoftLimitRequestStatus::Rejected;
+        }
+        SoftLimitStorage::save_request(env, &request);
+        SoftLimitStorage::set_pending(env, &request.user, None);
+        env.events().publish(
+            (
+                Symbol::new(env, "soft_limit_resolved"),
+                Symbol::new(env, "user"),
+            ),
+            (
+                Symbol::new(env, "user"),
+                request.user.clone(),
+                Symbol::new(env, "request"),
+                id,
+                Symbol::new(env, "approved"),
+                approve,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Request by id; pending requests past their window report `Expired`
+    pub fn get_request(env: &Env, id: u64) -> Option<SoftLimitRequest> {
+        SoftLimitStorage::get_request(env, id).map(|mut request| {
+            if request.status == SoftLimitRequestStatus::Pending
+                && env.ledger().timestamp() > request.expires_at
+            {
+                request.status = SoftLimitRequestStatus::Expired;
+            }
+            request
+        })
+    }
+
+    /// Latest request queued for a user
+    pub fn pending_request(env: &Env, user: &Address) -> Option<SoftLimitRequest> {
+        SoftLimitStorage::get_pending(env, user).and_then(|id| Self::get_request(env, id))
+    }
+
+    pub fn get_limit(env: &Env, user: &Address) -> Option<SoftLimit> {
+        SoftLimitStorage::get_limit(env, user)
+    }
+}
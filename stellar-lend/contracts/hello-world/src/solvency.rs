@@ -0,0 +1,143 @@
+//! Solvency module for StellarLend protocol
+//! On-chain solvency report per market asset: the tokens the contract holds, what it is
+//! owed by borrowers, what it owes suppliers and the reserves it keeps. Borrower debt on
+//! primary positions is tracked as a running total of principal scaled to the primary
+//! borrow index, updated on every position write, so the report needs no scan over users.
+
+use crate::reserves::Reserves;
+use crate::tvl_cap::TvlCapStorage;
+use crate::{
+    InterestRateManager, InterestRateStorage, Position, TokenRegistry, BORROW_INDEX_SCALE,
+};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Solvency of one market asset, in its token's units
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetSolvency {
+    pub asset: Address,
+    /// Live token balance held by the contract
+    pub cash: i128,
+    /// Supplied balances owed to depositors
+    pub supplier_claims: i128,
+    /// Outstanding borrower debt at the current borrow index
+    pub debt: i128,
+    pub reserves: i128,
+    /// `cash + debt - supplier_claims - reserves`; negative for a deficit
+    pub surplus: i128,
+}
+
+/// Solvency of every market asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SolvencyReport {
+    pub assets: Vec<AssetSolvency>,
+    /// Number of assets in deficit
+    pub deficit_assets: u32,
+    pub generated_at: u64,
+}
+
+/// Storage helper for the primary debt total
+pub struct SolvencyStorage;
+
+impl SolvencyStorage {
+    fn scaled_debt_key(env: &Env) -> Symbol {
+        Symbol::new(env, "primary_scaled_debt")
+    }
+
+    pub fn get_scaled_debt(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::scaled_debt_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn save_scaled_debt(env: &Env, scaled_debt: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::scaled_debt_key(env), &scaled_debt);
+    }
+}
+
+/// Solvency tracking and reporting
+pub struct Solvency;
+
+impl Solvency {
+    /// Move the primary debt total by the change between a position's previous and new
+    /// debt; called whenever a primary position is saved
+    pub fn on_position_change(env: &Env, previous: Option<&Position>, position: &Position) {
+        let before = previous.map(|p| Self::scaled_debt(env, p)).unwrap_or(0);
+        let after = Self::scaled_debt(env, position);
+        if before == after {
+            return;
+        }
+        let total = SolvencyStorage::get_scaled_debt(env) - before + after;
+        SolvencyStorage::save_scaled_debt(env, total.max(0));
+    }
+
+    /// Debt owed on primary positions at the current borrow index
+    pub fn primary_debt(env: &Env, asset: &Address) -> i128 {
+        SolvencyStorage::get_scaled_debt(env)
+            .saturating_mul(InterestRateStorage::current_borrow_index(env, asset))
+            / BORROW_INDEX_SCALE
+    }
+
+    pub fn report(env: &Env) -> SolvencyReport {
+        let primary = TokenRegistry::require_primary_asset(env).ok();
+        let mut assets = Vec::new(env);
+        let mut deficit_assets = 0u32;
+        for asset in InterestRateManager::market_assets(env).iter() {
+            let entry = Self::asset_solvency(env, &asset, primary.as_ref() == Some(&asset));
+            if entry.surplus < 0 {
+                deficit_assets += 1;
+            }
+            assets.push_back(entry);
+        }
+        SolvencyReport {
+            assets,
+            deficit_assets,
+            generated_at: env.ledger().timestamp(),
+        }
+    }
+
+    fn asset_solvency(env: &Env, asset: &Address, is_primary: bool) -> AssetSolvency {
+        let cash = TokenClient::new(env, asset).balance(&env.current_contract_address());
+        let market = InterestRateStorage::get_market_state(env, asset);
+        let index = InterestRateStorage::current_borrow_index(env, asset);
+        let mut debt = if market.borrow_index > 0 {
+            market.total_borrowed.saturating_mul(index) / market.borrow_index
+        } else {
+            market.total_borrowed
+        };
+        // Primary TVL already counts multi-asset supply of the primary asset
+        let mut supplier_claims = market.total_supplied;
+        if is_primary {
+            debt += Self::primary_debt(env, asset);
+            supplier_claims = TvlCapStorage::get_asset_tvl(env, asset);
+        }
+        let reserves = Reserves::get(env, asset);
+        AssetSolvency {
+            asset: asset.clone(),
+            cash,
+            supplier_claims,
+            debt,
+            reserves,
+            surplus: cash + debt - supplier_claims - reserves,
+        }
+    }
+
+    /// Debt principal expressed at the base borrow index. Debt that has not accrued yet
+    /// is taken at the market's current index.
+    fn scaled_debt(env: &Env, position: &Position) -> i128 {
+        if position.debt <= 0 {
+            return 0;
+        }
+        let index = if position.borrow_index > 0 {
+            position.borrow_index
+        } else {
+            InterestRateStorage::get_state(env).borrow_index.max(1)
+        };
+        position.debt.saturating_mul(BORROW_INDEX_SCALE) / index
+    }
+}
@@ -0,0 +1,306 @@
+//! Stable rate module for StellarLend protocol
+//! Primary borrowers may hold part of their debt at a stable rate locked when it is drawn:
+//! the market's variable borrow rate plus an admin-set premium. A position's `debt` stays
+//! its total debt; the stable bucket records how much of it grows at the locked rate
+//! instead of the variable borrow index. Repayments reduce the variable part first.
+//! When market rates move further than the rebalance threshold from a locked rate,
+//! anyone may rebalance it to the current stable rate.
+
+use crate::origination::OriginationFees;
+use crate::persistent::PersistentStore;
+use crate::soft_limits::SoftLimits;
+use crate::{
+    InterestRateManager, InterestRateStorage, Position, ProtocolConfig, ProtocolError, StateHelper,
+    TokenRegistry,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Rate scale (1.0 = 1e8)
+const RATE_SCALE: i128 = 100000000;
+
+/// Which rate a borrower's debt accrues at
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum BorrowRateMode {
+    Variable,
+    Stable,
+}
+
+/// Stable rate settings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StableRateConfig {
+    /// Whether new stable debt may be taken on
+    pub enabled: bool,
+    /// Spread over the variable borrow rate charged for a stable rate (scaled by 1e8)
+    pub premium: i128,
+    /// Distance between a locked rate and the current stable rate past which the locked
+    /// rate may be rebalanced (scaled by 1e8)
+    pub rebalance_threshold: i128,
+}
+
+impl Default for StableRateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            premium: 2000000,             // 2%
+            rebalance_threshold: 5000000, // 5%
+        }
+    }
+}
+
+/// The stable part of a user's primary debt
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StableDebt {
+    /// Stable debt including interest up to `updated_at`
+    pub amount: i128,
+    /// Locked annual rate (scaled by 1e8)
+    pub rate: i128,
+    pub updated_at: u64,
+}
+
+/// Storage helper for stable debt
+pub struct StableRateStorage;
+
+impl StableRateStorage {
+    fn config_key(env: &Env) -> Symbol {
+        Symbol::new(env, "stable_rate_cfg")
+    }
+
+    fn debt_key(env: &Env, user: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "stable_debt"), user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> StableRateConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key(env))
+            .unwrap_or_default()
+    }
+
+    pub fn save_config(env: &Env, config: &StableRateConfig) {
+        env.storage().instance().set(&Self::config_key(env), config);
+    }
+
+    pub fn get_debt(env: &Env, user: &Address) -> Option<StableDebt> {
+        PersistentStore::get(env, &Self::debt_key(env, user))
+    }
+
+    pub fn save_debt(env: &Env, user: &Address, debt: Option<&StableDebt>) {
+        match debt {
+            Some(debt) => PersistentStore::set(env, &Self::debt_key(env, user), debt),
+            None => PersistentStore::remove(env, &Self::debt_key(env, user)),
+        }
+    }
+
+    /// Extend the TTL of a user's stable debt, returning how many entries exist
+    pub fn bump(env: &Env, user: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::debt_key(env, user)) as u32
+    }
+}
+
+/// Stable borrow rates
+pub struct StableRates;
+
+impl StableRates {
+    /// Set the stable rate settings (admin only)
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: StableRateConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=RATE_SCALE).contains(&config.premium)
+            || !(0..=RATE_SCALE).contains(&config.rebalance_threshold)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        StableRateStorage::save_config(env, &config);
+        env.events().publish(
+            (
+                Symbol::new(env, "stable_rate_config"),
+                Symbol::new(env, "admin"),
+            ),
+            (
+                Symbol::new(env, "enabled"),
+                config.enabled,
+                Symbol::new(env, "premium"),
+                config.premium,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Rate a stable borrow would lock now (scaled by 1e8)
+    pub fn current_rate(env: &Env) -> i128 {
+        InterestRateStorage::get_state(env)
+            .current_borrow_rate
+            .saturating_add(StableRateStorage::get_config(env).premium)
+    }
+
+    /// Borrow `amount` of the primary asset with the drawn debt and its origination fee
+    /// at a stable rate. Adding to existing stable debt blends the rates by amount.
+    /// Returns the locked rate.
+    pub fn borrow(env: &Env, user: &Address, amount: i128) -> Result<i128, ProtocolError> {
+        user.require_auth();
+        if !StableRateStorage::get_config(env).enabled {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        // Large borrows waiting on soft limit approval are drawn at the variable rate
+        if SoftLimits::get_limit(env, user).is_some_and(|soft| amount > soft.limit) {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let asset = TokenRegistry::require_primary_asset(env)?;
+        let drawn = amount + OriginationFees::fee_for(env, &asset, amount);
+        crate::modules::ModuleRegistry::borrow(env, user, amount)?;
+
+        let rate = Self::current_rate(env);
+        let stable = match StableRateStorage::get_debt(env, user) {
+            Some(existing) if existing.amount > 0 => StableDebt {
+                amount: existing.amount + drawn,
+                rate: (existing.amount * existing.rate + drawn * rate) / (existing.amount + drawn),
+                updated_at: env.ledger().timestamp(),
+            },
+            _ => StableDebt {
+                amount: drawn,
+                rate,
+                updated_at: env.ledger().timestamp(),
+            },
+        };
+        StableRateStorage::save_debt(env, user, Some(&stable));
+        Self::publish(env, "stable_borrow", user, &stable);
+        Ok(stable.rate)
+    }
+
+    /// Move all of the user's primary debt to the other rate mode: stable debt becomes
+    /// variable, variable debt is locked at the current stable rate. Returns the new mode.
+    pub fn swap_mode(env: &Env, user: &Address) -> Result<BorrowRateMode, ProtocolError> {
+        user.require_auth();
+        let position = Self::accrue(env, user)?;
+        if StableRateStorage::get_debt(env, user).is_some() {
+            StableRateStorage::save_debt(env, user, None);
+            Self::publish_mode(env, user, BorrowRateMode::Variable);
+            return Ok(BorrowRateMode::Variable);
+        }
+        if !StableRateStorage::get_config(env).enabled {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        if position.debt <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let stable = StableDebt {
+            amount: position.debt,
+            rate: Self::current_rate(env),
+            updated_at: env.ledger().timestamp(),
+        };
+        StableRateStorage::save_debt(env, user, Some(&stable));
+        Self::publish_mode(env, user, BorrowRateMode::Stable);
+        Ok(BorrowRateMode::Stable)
+    }
+
+    /// Reset a user's locked rate to the current stable rate once they are further apart
+    /// than the rebalance threshold. Anyone may call. Returns the new rate.
+    pub fn rebalance(env: &Env, caller: &Address, user: &Address) -> Result<i128, ProtocolError> {
+        caller.require_auth();
+        Self::accrue(env, user)?;
+        let mut stable = StableRateStorage::get_debt(env, user).ok_or(ProtocolError::NotFound)?;
+        let rate = Self::current_rate(env);
+        if (rate - stable.rate).abs() <= StableRateStorage::get_config(env).rebalance_threshold {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        stable.rate = rate;
+        StableRateStorage::save_debt(env, user, Some(&stable));
+        Self::publish(env, "stable_rate_rebalanced", user, &stable);
+        Ok(rate)
+    }
+
+    pub fn mode(env: &Env, user: &Address) -> BorrowRateMode {
+        match StableRateStorage::get_debt(env, user) {
+            Some(_) => BorrowRateMode::Stable,
+            None => BorrowRateMode::Variable,
+        }
+    }
+
+    /// Total debt of `position` at borrow index `index`: the variable part rebased by the
+    /// index, the stable part grown at its locked rate since it was last updated
+    pub fn debt_at(env: &Env, position: &Position, index: i128) -> i128 {
+        let rebased = InterestRateManager::rebase(position.debt, position.borrow_index, index);
+        match StableRateStorage::get_debt(env, &position.user) {
+            Some(stable) if position.debt > 0 => {
+                let amount = stable.amount.min(position.debt);
+                rebased - InterestRateManager::rebase(amount, position.borrow_index, index)
+                    + Self::grown(env, &stable, amount)
+            }
+            _ => rebased,
+        }
+    }
+
+    /// `debt_at`, also bringing the stable bucket up to now; called from position accrual
+    pub fn accrue_position(env: &Env, position: &Position, index: i128) -> i128 {
+        let debt = Self::debt_at(env, position, index);
+        if let Some(mut stable) = StableRateStorage::get_debt(env, &position.user) {
+            let now = env.ledger().timestamp();
+            if stable.updated_at != now {
+                stable.amount = Self::grown(env, &stable, stable.amount.min(position.debt));
+                stable.updated_at = now;
+                StableRateStorage::save_debt(env, &position.user, Some(&stable));
+            }
+        }
+        debt
+    }
+
+    /// Keep the stable bucket within the position's debt; called whenever a primary
+    /// position is saved. Repaying everything returns the user to the variable rate.
+    pub fn on_position_change(env: &Env, position: &Position) {
+        let mut stable = match StableRateStorage::get_debt(env, &position.user) {
+            Some(stable) => stable,
+            None => return,
+        };
+        if position.debt <= 0 {
+            StableRateStorage::save_debt(env, &position.user, None);
+        } else if stable.amount > position.debt {
+            stable.amount = position.debt;
+            StableRateStorage::save_debt(env, &position.user, Some(&stable));
+        }
+    }
+
+    fn grown(env: &Env, stable: &StableDebt, amount: i128) -> i128 {
+        let elapsed = env.ledger().timestamp().saturating_sub(stable.updated_at);
+        amount.saturating_add(InterestRateManager::interest(amount, stable.rate, elapsed))
+    }
+
+    /// Accrue and save the user's primary position so the stable bucket is current
+    fn accrue(env: &Env, user: &Address) -> Result<Position, ProtocolError> {
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
+        StateHelper::save_position(env, &position);
+        Ok(position)
+    }
+
+    fn publish(env: &Env, action: &str, user: &Address, stable: &StableDebt) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "user")),
+            (
+                Symbol::new(env, "user"),
+                user.clone(),
+                Symbol::new(env, "amount"),
+                stable.amount,
+                Symbol::new(env, "rate"),
+                stable.rate,
+            ),
+        );
+    }
+
+    fn publish_mode(env: &Env, user: &Address, mode: BorrowRateMode) {
+        env.events().publish(
+            (
+                Symbol::new(env, "borrow_rate_mode_swapped"),
+                Symbol::new(env, "user"),
+            ),
+            (Symbol::new(env, "user"), user.clone(), mode),
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Subscribers module for StellarLend protocol
+//! Integrators register a contract that is called with `on_event(record: EventRecord)` for
+//! every protocol event whose type is in its topic filter (every event when the filter
+//! is empty). Delivery is best effort: a failing or missing subscriber never fails the
+//! protocol call, only its failure count grows, and a subscriber that fails
+//! `MAX_FAILURES` deliveries in a row is disabled. The registry is small and bounded so
+//! notifications add a fixed worst-case cost per event. The admin removes misbehaving
+//! subscribers; owners may unsubscribe their own.
+
+use crate::{EventRecord, ProtocolConfig, ProtocolError, ProtocolEvent};
+use soroban_sdk::{contracttype, vec, Address, Env, IntoVal, InvokeError, Symbol, Vec};
+
+/// Most subscribers registered at once
+const MAX_SUBSCRIBERS: u32 = 8;
+
+/// Most event types in one topic filter
+const MAX_TOPICS: u32 = 10;
+
+/// Consecutive failed deliveries after which a subscriber is disabled
+const MAX_FAILURES: u32 = 5;
+
+/// A registered event subscriber
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Subscriber {
+    pub id: u32,
+    pub owner: Address,
+    /// Contract called with `on_event`
+    pub target: Address,
+    /// Event types delivered; every type when empty
+    pub topics: Vec<Symbol>,
+    /// Failed deliveries since the last successful one
+    pub failures: u32,
+    pub delivered: u32,
+    pub active: bool,
+}
+
+/// Storage helper for the subscriber registry
+pub struct SubscriberStorage;
+
+impl SubscriberStorage {
+    fn list_key(env: &Env) -> Symbol {
+        Symbol::new(env, "subscribers")
+    }
+
+    fn next_id_key(env: &Env) -> Symbol {
+        Symbol::new(env, "subscriber_next_id")
+    }
+
+    pub fn get_all(env: &Env) -> Vec<Subscriber> {
+        env.storage()
+            .instance()
+            .get(&Self::list_key(env))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_all(env: &Env, subscribers: &Vec<Subscriber>) {
+        env.storage()
+            .instance()
+            .set(&Self::list_key(env), subscribers);
+    }
+
+    pub fn next_id(env: &Env) -> u32 {
+        let id: u32 = env
+            .storage()
+            .instance()
+            .get(&Self::next_id_key(env))
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::next_id_key(env), &id);
+        id
+    }
+}
+
+/// Subscriber registry and event delivery
+pub struct Subscribers;
+
+impl Subscribers {
+    /// Register `target` for events of the types in `topics`. Returns the subscriber id.
+    pub fn register(
+        env: &Env,
+        owner: &Address,
+        target: &Address,
+        topics: Vec<Symbol>,
+    ) -> Result<u32, ProtocolError> {
+        owner.require_auth();
+        if *target == env.current_contract_address() {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        if topics.len() > MAX_TOPICS {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let mut subscribers = SubscriberStorage::get_all(env);
+        if subscribers.len() >= MAX_SUBSCRIBERS {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let id = SubscriberStorage::next_id(env);
+        subscribers.push_back(Subscriber {
+            id,
+            owner: owner.clone(),
+            target: target.clone(),
+            topics: topics.clone(),
+            failures: 0,
+            delivered: 0,
+            active: true,
+        });
+        SubscriberStorage::save_all(env, &subscribers);
+        if topics.is_empty() {
+            ProtocolEvent::WebhookRegistered(target.clone(), Symbol::new(env, "all")).emit(env);
+        }
+        for topic in topics.iter() {
+            ProtocolEvent::WebhookRegistered(target.clone(), topic).emit(env);
+        }
+        Ok(id)
+    }
+
+    /// Remove a subscriber (admin, or the subscriber's owner)
+    pub fn remove(env: &Env, caller: &Address, id: u32) -> Result<(), ProtocolError> {
+        let mut subscribers = SubscriberStorage::get_all(env);
+        let index = subscribers
+            .iter()
+            .position(|subscriber| subscriber.id == id)
+            .ok_or(ProtocolError::NotFound)? as u32;
+        let subscriber = subscribers.get(index).ok_or(ProtocolError::NotFound)?;
+        if subscriber.owner == *caller {
+            caller.require_auth();
+        } else {
+            ProtocolConfig::require_admin(env, caller)?;
+        }
+        subscribers.remove(index);
+        SubscriberStorage::save_all(env, &subscribers);
+        Self::publish(env, "subscriber_removed", &subscriber);
+        Ok(())
+    }
+
+    pub fn get_all(env: &Env) -> Vec<Subscriber> {
+        SubscriberStorage::get_all(env)
+    }
+
+    /// Deliver a recorded event to every active subscriber whose filter matches it
+    pub fn notify(env: &Env, record: &EventRecord) {
+        let mut subscribers = SubscriberStorage::get_all(env);
+        if subscribers.is_empty() {
+            return;
+        }
+        let method = Symbol::new(env, "on_event");
+        let mut changed = false;
+        for index in 0..subscribers.len() {
+            let mut subscriber = match subscribers.get(index) {
+                Some(subscriber) => subscriber,
+                None => continue,
+            };
+            if !subscriber.active
+                || !(subscriber.topics.is_empty() || subscriber.topics.contains(&record.event_type))
+            {
+                continue;
+            }
+            let delivered = env
+                .try_invoke_contract::<(), InvokeError>(
+                    &subscriber.target,
+                    &method,
+                    vec![env, record.into_val(env)],
+                )
+                .is_ok_and(|result| result.is_ok());
+            if delivered {
+                subscriber.delivered = subscriber.delivered.saturating_add(1);
+                subscriber.failures = 0;
+            } else {
+                subscriber.failures += 1;
+                if subscriber.failures >= MAX_FAILURES {
+                    subscriber.active = false;
+                    Self::publish(env, "subscriber_disabled", &subscriber);
+                }
+            }
+            subscribers.set(index, subscriber);
+            changed = true;
+        }
+        if changed {
+            SubscriberStorage::save_all(env, &subscribers);
+        }
+    }
+
+    fn publish(env: &Env, action: &str, subscriber: &Subscriber) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "subscriber")),
+            (
+                Symbol::new(env, "id"),
+                subscriber.id,
+                Symbol::new(env, "target"),
+                subscriber.target.clone(),
+                Symbol::new(env, "failures"),
+                subscriber.failures,
+            ),
+        );
+    }
+}
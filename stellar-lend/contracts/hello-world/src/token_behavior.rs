@@ -0,0 +1,159 @@
+//! Token behavior module for StellarLend protocol
+//! Per-asset transfer profiles so fee-on-transfer and rebasing tokens verify and credit correctly
+
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Basis point scale
+const BPS_SCALE: i128 = 10000;
+
+/// How an asset's balances move on transfer
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum TokenBehavior {
+    /// Recipient receives exactly the sent amount
+    Standard,
+    /// Recipient receives the amount minus at most the declared fee (bps)
+    FeeOnTransfer(u32),
+    /// Balances drift independently of transfers
+    Rebasing,
+}
+
+/// Storage helper for token behavior profiles
+pub struct TokenBehaviorStorage;
+
+impl TokenBehaviorStorage {
+    fn key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "token_behavior"), asset.clone())
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> TokenBehavior {
+        env.storage()
+            .instance()
+            .get(&Self::key(env, asset))
+            .unwrap_or(TokenBehavior::Standard)
+    }
+
+    pub fn save(env: &Env, asset: &Address, behavior: &TokenBehavior) {
+        env.storage()
+            .instance()
+            .set(&Self::key(env, asset), behavior);
+    }
+}
+
+/// Token behavior profiles and balance-delta verification
+pub struct TokenBehaviorRegistry;
+
+impl TokenBehaviorRegistry {
+    /// Declare an asset's transfer behavior (admin only)
+    pub fn set(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        behavior: TokenBehavior,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let TokenBehavior::FeeOnTransfer(bps) = behavior {
+            if bps == 0 || bps as i128 >= BPS_SCALE {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        TokenBehaviorStorage::save(env, asset, &behavior);
+        Self::emit_profile(env, asset, &behavior);
+        Ok(())
+    }
+
+    /// Detect fee-on-transfer behavior by round-tripping `probe_amount` from the admin
+    /// through the contract (admin only). Rebasing cannot be observed within one
+    /// transaction and must be declared with `set`.
+    pub fn detect(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        probe_amount: i128,
+    ) -> Result<TokenBehavior, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if probe_amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let client = TokenClient::new(env, asset);
+        let contract = env.current_contract_address();
+        let before = client.balance(&contract);
+        client.transfer(caller, &contract, &probe_amount);
+        let received = client.balance(&contract).saturating_sub(before);
+        if received <= 0 || received > probe_amount {
+            return Err(ProtocolError::BalanceInvariantViolation);
+        }
+        client.transfer(&contract, caller, &received);
+
+        let behavior = if received == probe_amount {
+            TokenBehavior::Standard
+        } else {
+            // Round the observed fee up so the declared bound always covers it
+            let fee = probe_amount - received;
+            let bps = (fee * BPS_SCALE + probe_amount - 1) / probe_amount;
+            TokenBehavior::FeeOnTransfer(bps as u32)
+        };
+        TokenBehaviorStorage::save(env, asset, &behavior);
+        Self::emit_profile(env, asset, &behavior);
+        Ok(behavior)
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> TokenBehavior {
+        TokenBehaviorStorage::get(env, asset)
+    }
+
+    /// Check balance deltas of a transfer of `amount` from `sender_delta` (debited) to
+    /// `recipient_delta` (credited). Returns the amount to credit internally.
+    pub fn verify(
+        env: &Env,
+        asset: &Address,
+        amount: i128,
+        sender_delta: i128,
+        recipient_delta: i128,
+    ) -> Option<i128> {
+        match TokenBehaviorStorage::get(env, asset) {
+            TokenBehavior::Standard => {
+                (sender_delta == amount && recipient_delta == amount).then_some(amount)
+            }
+            TokenBehavior::FeeOnTransfer(bps) => {
+                let max_fee = (amount * bps as i128 + BPS_SCALE - 1) / BPS_SCALE;
+                (sender_delta == amount
+                    && recipient_delta <= amount
+                    && recipient_delta >= amount - max_fee)
+                    .then_some(recipient_delta)
+            }
+            TokenBehavior::Rebasing => {
+                (recipient_delta > 0 && recipient_delta <= amount).then_some(recipient_delta)
+            }
+        }
+    }
+
+    /// Least the recipient of a transfer of `amount` may receive under the asset's profile
+    pub fn min_received(env: &Env, asset: &Address, amount: i128) -> i128 {
+        match TokenBehaviorStorage::get(env, asset) {
+            TokenBehavior::Standard => amount,
+            TokenBehavior::FeeOnTransfer(bps) => {
+                amount - (amount * bps as i128 + BPS_SCALE - 1) / BPS_SCALE
+            }
+            TokenBehavior::Rebasing => amount.min(1),
+        }
+    }
+
+    fn emit_profile(env: &Env, asset: &Address, behavior: &TokenBehavior) {
+        env.events().publish(
+            (
+                Symbol::new(env, "token_behavior_set"),
+                Symbol::new(env, "asset"),
+            ),
+            (
+                Symbol::new(env, "asset"),
+                asset.clone(),
+                Symbol::new(env, "behavior"),
+                behavior.clone(),
+            ),
+        );
+    }
+}
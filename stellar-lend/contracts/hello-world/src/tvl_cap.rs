@@ -0,0 +1,257 @@
+//! TVL cap module for StellarLend protocol
+//! Guarded-launch ceilings on total and per-asset supplied value, growing on a weekly schedule
+//!
+//! Per-asset TVL is tracked in the asset's own units. The global TVL and its schedule are
+//! in primary-asset units; other assets are converted at their registered prices.
+
+use crate::cross_asset::CrossAssets;
+use crate::deposit::DepositError;
+use crate::normalization::Normalization;
+use crate::persistent::PersistentStore;
+use crate::{ProtocolConfig, ProtocolError, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Growth step length (1 week)
+const WEEK_SECONDS: u64 = 7 * 86400;
+
+/// Price (1e8 scale) of a primary asset without a registered price
+const PRICE_SCALE: i128 = 100_000_000;
+
+/// Protocol-wide TVL ceiling that increases every week until `max_cap`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TvlCapSchedule {
+    pub initial_cap: i128,
+    pub weekly_increase: i128,
+    pub max_cap: i128,
+    pub start: u64,
+}
+
+/// Current TVL against the global and per-asset ceilings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TvlHeadroom {
+    /// Supplied value across all assets, in primary-asset units
+    pub global_tvl: i128,
+    /// `None` when no global schedule is configured
+    pub global_cap: Option<i128>,
+    pub asset_tvl: i128,
+    /// `None` when the asset has no cap
+    pub asset_cap: Option<i128>,
+    /// Largest deposit of the asset currently accepted, `None` if unbounded
+    pub headroom: Option<i128>,
+}
+
+/// Storage helper for TVL caps and supplied totals
+pub struct TvlCapStorage;
+
+impl TvlCapStorage {
+    fn schedule_key(env: &Env) -> Symbol {
+        Symbol::new(env, "tvl_schedule")
+    }
+    fn global_tvl_key(env: &Env) -> Symbol {
+        Symbol::new(env, "tvl_global")
+    }
+    fn asset_cap_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "tvl_asset_cap"), asset.clone())
+    }
+    fn asset_tvl_key(env: &Env, asset: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "tvl_asset"), asset.clone())
+    }
+
+    pub fn get_schedule(env: &Env) -> Option<TvlCapSchedule> {
+        env.storage().instance().get(&Self::schedule_key(env))
+    }
+
+    pub fn set_schedule(env: &Env, schedule: Option<TvlCapSchedule>) {
+        match schedule {
+            Some(schedule) => env
+                .storage()
+                .instance()
+                .set(&Self::schedule_key(env), &schedule),
+            None => env.storage().instance().remove(&Self::schedule_key(env)),
+        }
+    }
+
+    pub fn get_asset_cap(env: &Env, asset: &Address) -> Option<i128> {
+        PersistentStore::get(env, &Self::asset_cap_key(env, asset))
+    }
+
+    pub fn set_asset_cap(env: &Env, asset: &Address, cap: Option<i128>) {
+        match cap {
+            Some(cap) => PersistentStore::set(env, &Self::asset_cap_key(env, asset), &cap),
+            None => PersistentStore::remove(env, &Self::asset_cap_key(env, asset)),
+        }
+    }
+
+    pub fn get_global_tvl(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::global_tvl_key(env))
+            .unwrap_or(0)
+    }
+
+    pub fn get_asset_tvl(env: &Env, asset: &Address) -> i128 {
+        PersistentStore::get(env, &Self::asset_tvl_key(env, asset)).unwrap_or(0)
+    }
+
+    /// Move an asset's TVL by `delta` (asset units) and the global TVL by
+    /// `global_delta` (primary-asset units)
+    pub fn add_tvl(env: &Env, asset: &Address, delta: i128, global_delta: i128) {
+        let global = (Self::get_global_tvl(env) + global_delta).max(0);
+        env.storage()
+            .instance()
+            .set(&Self::global_tvl_key(env), &global);
+        let asset_tvl = (Self::get_asset_tvl(env, asset) + delta).max(0);
+        PersistentStore::set(env, &Self::asset_tvl_key(env, asset), &asset_tvl);
+    }
+
+    /// Extend the TTL of an asset's cap and TVL, returning how many entries exist
+    pub fn bump_asset(env: &Env, asset: &Address) -> u32 {
+        PersistentStore::bump(env, &Self::asset_cap_key(env, asset)) as u32
+            + PersistentStore::bump(env, &Self::asset_tvl_key(env, asset)) as u32
+    }
+}
+
+/// Guarded-launch TVL ceilings
+pub struct TvlCaps;
+
+impl TvlCaps {
+    /// Configure the weekly-growing global cap (admin only). The schedule starts now.
+    pub fn set_schedule(
+        env: &Env,
+        caller: &Address,
+        initial_cap: i128,
+        weekly_increase: i128,
+        max_cap: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if initial_cap <= 0 || weekly_increase < 0 || max_cap < initial_cap {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        TvlCapStorage::set_schedule(
+            env,
+            Some(TvlCapSchedule {
+                initial_cap,
+                weekly_increase,
+                max_cap,
+                start: env.ledger().timestamp(),
+            }),
+        );
+        Ok(())
+    }
+
+    /// Remove the global cap, ending the guarded launch (admin only)
+    pub fn clear_schedule(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        TvlCapStorage::set_schedule(env, None);
+        Ok(())
+    }
+
+    /// Set or clear (`cap <= 0`) an asset's TVL cap (admin only)
+    pub fn set_asset_cap(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        cap: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        TvlCapStorage::set_asset_cap(env, asset, if cap > 0 { Some(cap) } else { None });
+        Ok(())
+    }
+
+    /// Global cap in effect now, if a schedule is configured
+    pub fn current_global_cap(env: &Env) -> Option<i128> {
+        TvlCapStorage::get_schedule(env).map(|schedule| {
+            let weeks = env.ledger().timestamp().saturating_sub(schedule.start) / WEEK_SECONDS;
+            schedule
+                .initial_cap
+                .saturating_add(schedule.weekly_increase.saturating_mul(weeks as i128))
+                .min(schedule.max_cap)
+        })
+    }
+
+    /// `amount` of `asset` in primary-asset units at registered prices. The primary asset
+    /// counts at face value, as does everything while no primary asset is set.
+    pub fn to_global(env: &Env, asset: &Address, amount: i128) -> Result<i128, ProtocolError> {
+        let primary = match TokenRegistry::require_primary_asset(env) {
+            Ok(primary) if primary != *asset => primary,
+            _ => return Ok(amount),
+        };
+        let value =
+            Normalization::value(env, asset, amount, CrossAssets::require_price(env, asset)?);
+        let primary_price = CrossAssets::valuation_price(env, &primary).unwrap_or(PRICE_SCALE);
+        Ok(Normalization::amount_for_value(
+            env,
+            &primary,
+            value,
+            primary_price,
+        ))
+    }
+
+    /// Primary-asset `amount` in units of `asset`, rounding down; the inverse of `to_global`
+    pub fn from_global(env: &Env, asset: &Address, amount: i128) -> Result<i128, ProtocolError> {
+        let primary = match TokenRegistry::require_primary_asset(env) {
+            Ok(primary) if primary != *asset => primary,
+            _ => return Ok(amount),
+        };
+        let primary_price = CrossAssets::valuation_price(env, &primary).unwrap_or(PRICE_SCALE);
+        let value = Normalization::value(env, &primary, amount, primary_price);
+        Ok(Normalization::amount_for_value(
+            env,
+            asset,
+            value,
+            CrossAssets::require_price(env, asset)?,
+        ))
+    }
+
+    /// Track supplied value; called whenever a position's collateral in `asset` changes.
+    /// An asset that can no longer be priced only moves its own total.
+    pub fn on_supply_change(env: &Env, asset: &Address, delta: i128) {
+        if delta == 0 {
+            return;
+        }
+        let global_delta = Self::to_global(env, asset, delta).unwrap_or(0);
+        TvlCapStorage::add_tvl(env, asset, delta, global_delta);
+    }
+
+    pub fn headroom(env: &Env, asset: &Address) -> TvlHeadroom {
+        let global_tvl = TvlCapStorage::get_global_tvl(env);
+        let global_cap = Self::current_global_cap(env);
+        let asset_tvl = TvlCapStorage::get_asset_tvl(env, asset);
+        let asset_cap = TvlCapStorage::get_asset_cap(env, asset);
+
+        // Global room is in primary-asset units; report it in `asset` units
+        let global_room = global_cap
+            .map(|cap| Self::from_global(env, asset, (cap - global_tvl).max(0)).unwrap_or(0));
+        let asset_room = asset_cap.map(|cap| (cap - asset_tvl).max(0));
+        let headroom = match (global_room, asset_room) {
+            (Some(g), Some(a)) => Some(g.min(a)),
+            (room, None) | (None, room) => room,
+        };
+        TvlHeadroom {
+            global_tvl,
+            global_cap,
+            asset_tvl,
+            asset_cap,
+            headroom,
+        }
+    }
+
+    /// Reject a deposit of `amount` of `asset` that would push TVL past the global or
+    /// asset cap
+    pub fn check_deposit(env: &Env, asset: &Address, amount: i128) -> Result<(), ProtocolError> {
+        let global_tvl = TvlCapStorage::get_global_tvl(env);
+        if let Some(cap) = Self::current_global_cap(env) {
+            if global_tvl.saturating_add(Self::to_global(env, asset, amount)?) > cap {
+                return Err(DepositError::TvlCapExceeded.into());
+            }
+        }
+        if let Some(cap) = TvlCapStorage::get_asset_cap(env, asset) {
+            if TvlCapStorage::get_asset_tvl(env, asset).saturating_add(amount) > cap {
+                return Err(DepositError::TvlCapExceeded.into());
+            }
+        }
+        Ok(())
+    }
+}
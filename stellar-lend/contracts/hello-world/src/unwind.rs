@@ -0,0 +1,157 @@
+//! Unwind module for StellarLend protocol
+//! Structured exit from Recovery. While the protocol is in Recovery only deposits and
+//! repayments run; the admin can force-accrue every market, cap the utilization borrows
+//! and withdrawals may leave, and schedule restricted operations to reopen in stages
+//! (e.g. borrow after a day, withdraw after two). Stages and the cap live in
+//! `EmergencyState`, are enforced by the operation gate and are cleared when the
+//! protocol leaves or re-enters Recovery.
+
+use crate::liquidity_guard::LiquidityGuard;
+use crate::{
+    EmergencyStatus, EmergencyStorage, InterestRateManager, OperationKind, ProtocolConfig,
+    ProtocolError, UnwindStage, UserManager,
+};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Utilization scale (1e8 = 100%)
+const UTILIZATION_SCALE: i128 = 100_000_000;
+
+/// Recovery unwind actions and their enforcement
+pub struct RecoveryUnwind;
+
+impl RecoveryUnwind {
+    /// Accrue every market to now (admin only, Recovery only). Returns how many markets
+    /// were accrued.
+    pub fn accrue_markets(env: &Env, caller: &Address) -> Result<u32, ProtocolError> {
+        Self::require_recovery_admin(env, caller)?;
+        let accrued = InterestRateManager::accrue_all_markets(env);
+        env.events().publish(
+            (
+                Symbol::new(env, "recovery_markets_accrued"),
+                Symbol::new(env, "admin"),
+            ),
+            (Symbol::new(env, "markets"), accrued),
+        );
+        Ok(accrued)
+    }
+
+    /// Cap the utilization borrows and withdrawals may leave while in Recovery, scaled by
+    /// 1e8; 0 removes the cap (admin only, Recovery only)
+    pub fn set_utilization_cap(
+        env: &Env,
+        caller: &Address,
+        cap: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::require_recovery_admin(env, caller)?;
+        if !(0..=UTILIZATION_SCALE).contains(&cap) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut state = EmergencyStorage::get(env);
+        state.recovery_utilization_cap = cap;
+        EmergencyStorage::save(env, &state);
+        env.events().publish(
+            (
+                Symbol::new(env, "recovery_utilization_cap"),
+                Symbol::new(env, "admin"),
+            ),
+            (Symbol::new(env, "cap"), cap),
+        );
+        Ok(())
+    }
+
+    /// Reopen `operation` (`borrow`, `withdraw`, `liquidate` or `flash_loan`) `delay`
+    /// seconds from now, replacing any earlier stage for it (admin only, Recovery only)
+    pub fn schedule_reenable(
+        env: &Env,
+        caller: &Address,
+        operation: Symbol,
+        delay: u64,
+    ) -> Result<u64, ProtocolError> {
+        Self::require_recovery_admin(env, caller)?;
+        let kind = crate::gate::OperationGate::parse_operation(env, &operation)?;
+        if !matches!(
+            kind,
+            OperationKind::Borrow
+                | OperationKind::Withdraw
+                | OperationKind::Liquidate
+                | OperationKind::FlashLoan
+        ) {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let enable_at = env.ledger().timestamp().saturating_add(delay);
+        let mut state = EmergencyStorage::get(env);
+        if let Some(index) = state
+            .unwind_stages
+            .iter()
+            .position(|stage| stage.operation == operation)
+        {
+            state.unwind_stages.remove(index as u32);
+        }
+        state.unwind_stages.push_back(UnwindStage {
+            operation: operation.clone(),
+            enable_at,
+        });
+        EmergencyStorage::save(env, &state);
+        env.events().publish(
+            (
+                Symbol::new(env, "recovery_stage_scheduled"),
+                operation.clone(),
+            ),
+            (
+                Symbol::new(env, "operation"),
+                operation,
+                Symbol::new(env, "enable_at"),
+                enable_at,
+            ),
+        );
+        Ok(enable_at)
+    }
+
+    /// Whether a stage has reopened `operation`
+    pub fn is_reenabled(env: &Env, operation: OperationKind) -> bool {
+        let stages = EmergencyStorage::get(env).unwind_stages;
+        if stages.is_empty() {
+            return false;
+        }
+        let operation = UserManager::operation_symbol(env, operation);
+        let now = env.ledger().timestamp();
+        stages
+            .iter()
+            .any(|stage| stage.operation == operation && stage.enable_at <= now)
+    }
+
+    /// Reject a Recovery borrow or withdrawal of `amount` that would leave the asset's
+    /// utilization above the cap
+    pub fn ensure_within_cap(
+        env: &Env,
+        asset: &Address,
+        operation: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        if !matches!(operation, OperationKind::Borrow | OperationKind::Withdraw) {
+            return Ok(());
+        }
+        let state = EmergencyStorage::get(env);
+        if state.status != EmergencyStatus::Recovery || state.recovery_utilization_cap == 0 {
+            return Ok(());
+        }
+        let cap = state.recovery_utilization_cap;
+        let available = if operation == OperationKind::Borrow {
+            LiquidityGuard::borrowable_under(env, asset, cap)
+        } else {
+            LiquidityGuard::withdrawable_under(env, asset, cap)
+        };
+        if amount > available {
+            return Err(ProtocolError::RecoveryModeRestricted);
+        }
+        Ok(())
+    }
+
+    fn require_recovery_admin(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if EmergencyStorage::get(env).status != EmergencyStatus::Recovery {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        Ok(())
+    }
+}
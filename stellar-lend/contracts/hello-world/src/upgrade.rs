@@ -0,0 +1,134 @@
+//! Upgrade module for StellarLend protocol
+//! Admin-only WASM upgrades with a stored storage-schema version. Before swapping the code,
+//! `upgrade` publishes a checksum of the core configuration and state so the post-upgrade
+//! state can be compared against it. After an upgrade, `migrate` brings storage from the
+//! stored version up to the code's `CONTRACT_VERSION`, running each step exactly once.
+
+use crate::{EventStorage, InterestRateStorage, ProtocolConfig, ProtocolError, RiskConfigStorage};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{Address, BytesN, Env, Symbol};
+
+/// Storage schema version this code expects
+pub const CONTRACT_VERSION: u32 = 3;
+
+/// Version assumed for deployments that predate version tracking
+const UNTRACKED_VERSION: u32 = 1;
+
+/// Storage helper for the contract version
+pub struct UpgradeStorage;
+
+impl UpgradeStorage {
+    fn version_key(env: &Env) -> Symbol {
+        Symbol::new(env, "contract_version")
+    }
+
+    pub fn get_version(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::version_key(env))
+            .unwrap_or(UNTRACKED_VERSION)
+    }
+
+    pub fn save_version(env: &Env, version: u32) {
+        env.storage()
+            .instance()
+            .set(&Self::version_key(env), &version);
+    }
+}
+
+/// Code upgrades and storage migrations
+pub struct Upgrade;
+
+impl Upgrade {
+    /// Replace the contract's code with the uploaded `wasm_hash` (admin only), publishing
+    /// the pre-upgrade state checksum first
+    pub fn upgrade(
+        env: &Env,
+        caller: &Address,
+        wasm_hash: BytesN<32>,
+    ) -> Result<BytesN<32>, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let checksum = Self::state_checksum(env);
+        env.events().publish(
+            (
+                Symbol::new(env, "contract_upgrade"),
+                Symbol::new(env, "admin"),
+            ),
+            (
+                Symbol::new(env, "version"),
+                UpgradeStorage::get_version(env),
+                Symbol::new(env, "wasm_hash"),
+                wasm_hash.clone(),
+                Symbol::new(env, "checksum"),
+                checksum.clone(),
+            ),
+        );
+        env.deployer().update_current_contract_wasm(wasm_hash);
+        Ok(checksum)
+    }
+
+    /// Run the migrations between the stored version and `CONTRACT_VERSION` (admin only).
+    /// Fails with `InvalidOperation` when storage is already current. Returns the new
+    /// version.
+    pub fn migrate(env: &Env, caller: &Address) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let from = UpgradeStorage::get_version(env);
+        if from >= CONTRACT_VERSION {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        for version in (from + 1)..=CONTRACT_VERSION {
+            Self::migrate_to(env, version);
+        }
+        UpgradeStorage::save_version(env, CONTRACT_VERSION);
+        env.events().publish(
+            (
+                Symbol::new(env, "contract_migrated"),
+                Symbol::new(env, "admin"),
+            ),
+            (
+                Symbol::new(env, "from"),
+                from,
+                Symbol::new(env, "to"),
+                CONTRACT_VERSION,
+            ),
+        );
+        Ok(CONTRACT_VERSION)
+    }
+
+    /// One-time storage changes that bring storage to `version`. Version 2 introduced
+    /// version tracking itself and changes no stored data; version 3 paged the event logs
+    /// and drops the old shared log.
+    fn migrate_to(env: &Env, version: u32) {
+        if version == 3 {
+            EventStorage::remove_legacy_logs(env);
+        }
+        env.events().publish(
+            (
+                Symbol::new(env, "migration_step"),
+                Symbol::new(env, "version"),
+            ),
+            version,
+        );
+    }
+
+    /// Mark fresh deployments as current so they never run migrations
+    pub fn on_initialize(env: &Env) {
+        UpgradeStorage::save_version(env, CONTRACT_VERSION);
+    }
+
+    pub fn version(env: &Env) -> u32 {
+        UpgradeStorage::get_version(env)
+    }
+
+    /// SHA-256 over the version, protocol configuration, risk configuration and interest
+    /// rate state
+    pub fn state_checksum(env: &Env) -> BytesN<32> {
+        let state = (
+            UpgradeStorage::get_version(env),
+            ProtocolConfig::snapshot(env),
+            RiskConfigStorage::get(env),
+            InterestRateStorage::get_state(env),
+        );
+        env.crypto().sha256(&state.to_xdr(env)).into()
+    }
+}
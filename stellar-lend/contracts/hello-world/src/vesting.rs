@@ -0,0 +1,201 @@
+//! Vesting module for StellarLend protocol
+//! Linear token streams for contributor compensation and long-term reward lockups. The
+//! admin creates a stream funded either from its own balance or from the token's protocol
+//! reserves; nothing vests before the cliff, the total vests linearly from `start` to
+//! `end`, and the recipient claims whatever has vested so far.
+
+use crate::persistent::PersistentStore;
+use crate::reserves::ReserveStorage;
+use crate::{ProtocolConfig, ProtocolError, TransferEnforcer};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Terms of a new stream
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct VestingSchedule {
+    pub recipient: Address,
+    pub token: Address,
+    pub total: i128,
+    pub start: u64,
+    /// Nothing can be claimed before this timestamp
+    pub cliff: u64,
+    pub end: u64,
+}
+
+/// A funded stream and what has been claimed from it
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct VestingStream {
+    pub id: u64,
+    pub schedule: VestingSchedule,
+    pub claimed: i128,
+}
+
+/// Storage helper for vesting streams
+pub struct VestingStorage;
+
+impl VestingStorage {
+    fn counter_key(env: &Env) -> Symbol {
+        Symbol::new(env, "vesting_next_id")
+    }
+
+    fn stream_key(env: &Env, id: u64) -> (Symbol, u64) {
+        (Symbol::new(env, "vesting"), id)
+    }
+
+    fn recipient_key(env: &Env, recipient: &Address) -> (Symbol, Address) {
+        (Symbol::new(env, "vesting_user"), recipient.clone())
+    }
+
+    pub fn next_id(env: &Env) -> u64 {
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&Self::counter_key(env))
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&Self::counter_key(env), &(id + 1));
+        id + 1
+    }
+
+    pub fn get(env: &Env, id: u64) -> Option<VestingStream> {
+        PersistentStore::get(env, &Self::stream_key(env, id))
+    }
+
+    pub fn save(env: &Env, stream: &VestingStream) {
+        PersistentStore::set(env, &Self::stream_key(env, stream.id), stream);
+    }
+
+    pub fn get_ids(env: &Env, recipient: &Address) -> Vec<u64> {
+        PersistentStore::get(env, &Self::recipient_key(env, recipient))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn add_id(env: &Env, recipient: &Address, id: u64) {
+        let mut ids = Self::get_ids(env, recipient);
+        ids.push_back(id);
+        PersistentStore::set(env, &Self::recipient_key(env, recipient), &ids);
+    }
+}
+
+/// Stream creation and claims
+pub struct Vesting;
+
+impl Vesting {
+    /// Create and fund a stream (admin only). With `from_reserves` the total is taken from
+    /// the token's protocol reserves, otherwise it is pulled from the admin. Returns the
+    /// stream id.
+    pub fn create(
+        env: &Env,
+        caller: &Address,
+        schedule: VestingSchedule,
+        from_reserves: bool,
+    ) -> Result<u64, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if schedule.total <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if schedule.start >= schedule.end
+            || schedule.cliff < schedule.start
+            || schedule.cliff > schedule.end
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let flow = Symbol::new(env, "vesting_fund");
+        let funded = if from_reserves {
+            let reserves = ReserveStorage::get(env, &schedule.token);
+            if schedule.total > reserves {
+                return Err(ProtocolError::InvalidAmount);
+            }
+            ReserveStorage::save(env, &schedule.token, reserves - schedule.total);
+            schedule.total
+        } else {
+            TransferEnforcer::transfer_in_asset(env, &schedule.token, caller, schedule.total, flow)?
+        };
+
+        let id = VestingStorage::next_id(env);
+        let stream = VestingStream {
+            id,
+            schedule: VestingSchedule {
+                total: funded,
+                ..schedule
+            },
+            claimed: 0,
+        };
+        VestingStorage::save(env, &stream);
+        VestingStorage::add_id(env, &stream.schedule.recipient, id);
+        Self::publish(env, "vesting_created", &stream, funded);
+        Ok(id)
+    }
+
+    /// Send the recipient everything vested and not yet claimed. Returns the amount sent.
+    pub fn claim(env: &Env, recipient: &Address, id: u64) -> Result<i128, ProtocolError> {
+        recipient.require_auth();
+        let mut stream = VestingStorage::get(env, id).ok_or(ProtocolError::NotFound)?;
+        if stream.schedule.recipient != *recipient {
+            return Err(ProtocolError::Unauthorized);
+        }
+        let amount = Self::vested(env, &stream) - stream.claimed;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        stream.claimed += amount;
+        VestingStorage::save(env, &stream);
+        TransferEnforcer::transfer_out_asset(
+            env,
+            &stream.schedule.token,
+            recipient,
+            amount,
+            Symbol::new(env, "vesting_claim"),
+        )?;
+        Self::publish(env, "vesting_claimed", &stream, amount);
+        Ok(amount)
+    }
+
+    /// Amount of the stream vested at the current ledger time
+    pub fn vested(env: &Env, stream: &VestingStream) -> i128 {
+        let schedule = &stream.schedule;
+        let now = env.ledger().timestamp();
+        if now < schedule.cliff {
+            0
+        } else if now >= schedule.end {
+            schedule.total
+        } else {
+            schedule.total * (now - schedule.start) as i128
+                / (schedule.end - schedule.start) as i128
+        }
+    }
+
+    /// Vested amount not yet claimed
+    pub fn claimable(env: &Env, id: u64) -> i128 {
+        VestingStorage::get(env, id)
+            .map(|stream| Self::vested(env, &stream) - stream.claimed)
+            .unwrap_or(0)
+    }
+
+    pub fn get(env: &Env, id: u64) -> Option<VestingStream> {
+        VestingStorage::get(env, id)
+    }
+
+    pub fn get_ids(env: &Env, recipient: &Address) -> Vec<u64> {
+        VestingStorage::get_ids(env, recipient)
+    }
+
+    fn publish(env: &Env, action: &str, stream: &VestingStream, amount: i128) {
+        env.events().publish(
+            (Symbol::new(env, action), Symbol::new(env, "recipient")),
+            (
+                Symbol::new(env, "id"),
+                stream.id,
+                Symbol::new(env, "recipient"),
+                stream.schedule.recipient.clone(),
+                Symbol::new(env, "token"),
+                stream.schedule.token.clone(),
+                Symbol::new(env, "amount"),
+                amount,
+            ),
+        );
+    }
+}
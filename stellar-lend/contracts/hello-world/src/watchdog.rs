@@ -0,0 +1,136 @@
+//! Watchdog module for StellarLend protocol
+//! Auto-pauses the protocol when accrual, oracle and keeper activity goes silent
+
+use crate::{EmergencyStatus, EmergencyStorage, ProtocolConfig, ProtocolError, ProtocolEvent};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol};
+
+/// Watchdog configuration and liveness tracking
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WatchdogState {
+    pub enabled: bool,
+    /// Maximum silence tolerated before auto-pausing (seconds)
+    pub interval: u64,
+    /// Timestamp of the last accrual/oracle/keeper activity
+    pub last_activity: u64,
+    /// Source of the last recorded activity
+    pub last_source: Symbol,
+}
+
+impl WatchdogState {
+    pub fn default(env: &Env) -> Self {
+        Self {
+            enabled: false,
+            interval: 0,
+            last_activity: 0,
+            last_source: Symbol::new(env, "none"),
+        }
+    }
+}
+
+/// Storage helper for the watchdog
+pub struct WatchdogStorage;
+
+impl WatchdogStorage {
+    fn key(env: &Env) -> Symbol {
+        Symbol::new(env, "watchdog")
+    }
+
+    pub fn get(env: &Env) -> WatchdogState {
+        env.storage()
+            .instance()
+            .get(&Self::key(env))
+            .unwrap_or_else(|| WatchdogState::default(env))
+    }
+
+    pub fn save(env: &Env, state: &WatchdogState) {
+        env.storage().instance().set(&Self::key(env), state);
+    }
+}
+
+/// Liveness watchdog
+pub struct Watchdog;
+
+impl Watchdog {
+    /// Enable or disable the watchdog (admin only). Enabling starts the clock from now.
+    pub fn configure(
+        env: &Env,
+        caller: &Address,
+        enabled: bool,
+        interval: u64,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if enabled && interval == 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut state = WatchdogStorage::get(env);
+        state.enabled = enabled;
+        state.interval = interval;
+        state.last_activity = env.ledger().timestamp();
+        state.last_source = Symbol::new(env, "configure");
+        WatchdogStorage::save(env, &state);
+        Ok(())
+    }
+
+    /// Record accrual/oracle/keeper activity. Activity after an expired interval first
+    /// persists the pause it was owed, so a late heartbeat never clears the condition.
+    pub fn heartbeat(env: &Env, source: &str) {
+        Self::trip_if_stale(env);
+        Self::restart(env, source);
+    }
+
+    /// Restart the clock from now without tripping, for an explicit resume
+    pub fn restart(env: &Env, source: &str) {
+        let mut state = WatchdogStorage::get(env);
+        state.last_activity = env.ledger().timestamp();
+        state.last_source = Symbol::new(env, source);
+        WatchdogStorage::save(env, &state);
+    }
+
+    /// Whether the watchdog has expired, without tripping it
+    pub fn is_expired(env: &Env) -> bool {
+        Self::is_stale(env, &WatchdogStorage::get(env))
+    }
+
+    fn is_stale(env: &Env, state: &WatchdogState) -> bool {
+        state.enabled
+            && env.ledger().timestamp().saturating_sub(state.last_activity) > state.interval
+    }
+
+    /// Pause the protocol if the watchdog has expired. Returns whether it tripped.
+    pub fn trip_if_stale(env: &Env) -> bool {
+        let watchdog = WatchdogStorage::get(env);
+        if !Self::is_stale(env, &watchdog) {
+            return false;
+        }
+        let mut state = EmergencyStorage::get(env);
+        if state.status != EmergencyStatus::Operational {
+            return false;
+        }
+
+        let reason = String::from_str(env, "watchdog: no keeper activity");
+        state.status = EmergencyStatus::Paused;
+        state.paused_by = None;
+        state.paused_at = env.ledger().timestamp();
+        state.reason = Some(reason.clone());
+        EmergencyStorage::save(env, &state);
+
+        ProtocolEvent::SecurityIncident(reason.clone()).emit(env);
+        ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "paused"), Some(reason)).emit(env);
+        true
+    }
+
+    /// Gate for user interactions: rejects the operation while the watchdog is expired.
+    /// A rejected call cannot persist the pause, so it only reads; `poke_watchdog`, keeper
+    /// runs and the next heartbeat write it.
+    pub fn check(env: &Env) -> Result<(), ProtocolError> {
+        if Self::is_expired(env) {
+            return Err(ProtocolError::ProtocolPaused);
+        }
+        Ok(())
+    }
+
+    pub fn get_state(env: &Env) -> WatchdogState {
+        WatchdogStorage::get(env)
+    }
+}
@@ -1,13 +1,24 @@
 //! Withdraw module for StellarLend protocol
-//! Handles collateral withdrawal functionality and related operations
+//! Handles collateral withdrawal functionality and related operations. Withdrawals may
+//! not lift an asset's utilization above the cap kept by `liquidity_guard`.
 
 use crate::analytics::AnalyticsModule;
+use crate::auction::LiquidationAuctions;
+use crate::beneficiary::Beneficiaries;
+use crate::bootstrap::Bootstrap;
+use crate::cross_asset::CrossAssets;
+use crate::gate::OperationGate;
+use crate::liquidity_guard::LiquidityGuard;
+use crate::oracle::Oracle;
+use crate::outflow_limit::OutflowLimits;
+use crate::referral::Referrals;
+use crate::release_schedule::ReleaseSchedule;
+use crate::risk_matrix::CollateralMatrix;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
-    TransferEnforcer, UserManager,
+    InterestRateManager, InterestRateStorage, OperationKind, ProtocolError, ProtocolEvent,
+    ReentrancyGuard, StateHelper, TokenRegistry, TransferEnforcer, UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Withdraw-specific errors
 #[contracterror]
@@ -20,6 +31,8 @@ pub enum WithdrawError {
     PositionNotFound = 4004,
     InsufficientCollateral = 4005,
     InsufficientCollateralRatio = 4006,
+    CollateralLocked = 4007,
+    BeneficiaryNotAllowed = 4008,
 }
 
 impl From<WithdrawError> for ProtocolError {
@@ -33,6 +46,8 @@ impl From<WithdrawError> for ProtocolError {
             WithdrawError::InsufficientCollateralRatio => {
                 ProtocolError::InsufficientCollateralRatio
             }
+            WithdrawError::CollateralLocked => ProtocolError::WithdrawCollateralLocked,
+            WithdrawError::BeneficiaryNotAllowed => ProtocolError::WithdrawBeneficiaryNotAllowed,
         }
     }
 }
@@ -71,28 +86,23 @@ impl WithdrawParams {
 pub struct WithdrawModule;
 
 impl WithdrawModule {
-    /// Withdraw collateral from the protocol
-    pub fn withdraw(env: &Env, withdrawer: &Address, amount: i128) -> Result<(), ProtocolError> {
+    /// Withdraw collateral from the protocol, paying out to `to` when it is an active
+    /// allowlisted beneficiary of the withdrawer
+    pub fn withdraw(
+        env: &Env,
+        withdrawer: &Address,
+        to: Option<Address>,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
         ReentrancyGuard::enter(env)?;
         let result = (|| -> Result<(), ProtocolError> {
             if amount <= 0 {
                 return Err(WithdrawError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Withdraw)?;
-
-            // Check if withdraw is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_withdraw {
-                return Err(WithdrawError::ProtocolPaused.into());
-            }
-
-            UserManager::ensure_operation_allowed(
-                env,
-                withdrawer,
-                OperationKind::Withdraw,
-                amount,
-            )?;
+            OperationGate::check(env, withdrawer, OperationKind::Withdraw, None, amount)?;
+            OutflowLimits::record_primary(env, amount)?;
+            let recipient = Beneficiaries::resolve_recipient(env, withdrawer, to)?;
 
             // Load user position
             let mut position = match StateHelper::get_position(env, withdrawer) {
@@ -107,17 +117,22 @@ impl WithdrawModule {
 
             // Accrue interest
             let state = InterestRateStorage::update_state(env);
-            InterestRateManager::accrue_interest_for_position(
-                env,
-                &mut position,
-                state.current_borrow_rate,
-                state.current_supply_rate,
-            );
+            InterestRateManager::accrue_interest_for_position(env, &mut position, &state);
 
-            // Check collateral ratio after withdrawal (only if there's debt)
+            // Scheduled tranches that have not unlocked yet and collateral escrowed for an
+            // active auction cannot be withdrawn
             let new_collateral = position.collateral - amount;
+            if new_collateral
+                < ReleaseSchedule::locked_amount(env, withdrawer)
+                    + LiquidationAuctions::escrowed_collateral(env, withdrawer)
+            {
+                return Err(WithdrawError::CollateralLocked.into());
+            }
+
+            // Check collateral ratio after withdrawal (only if there's debt)
             let collateral_ratio = if position.debt > 0 {
-                let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+                Oracle::ensure_primary_fresh(env)?;
+                let min_ratio = CollateralMatrix::effective_min_ratio(env);
                 let ratio = (new_collateral * 100) / position.debt;
                 if ratio < min_ratio {
                     return Err(WithdrawError::InsufficientCollateralRatio.into());
@@ -127,9 +142,21 @@ impl WithdrawModule {
                 0
             };
 
-            // Update position
+            // Update position; the withdrawal fee stays in the pool
             position.collateral = new_collateral;
-            TransferEnforcer::transfer_out(env, withdrawer, amount, Symbol::new(env, "withdraw"))?;
+            let asset = TokenRegistry::require_primary_asset(env)?;
+            let fee = Bootstrap::withdraw_fee(env, withdrawer, &asset, amount);
+            LiquidityGuard::ensure_withdrawable(env, &asset, amount - fee)?;
+            Referrals::on_fee(env, withdrawer, &asset, fee);
+            if amount - fee > 0 {
+                TransferEnforcer::transfer_out(
+                    env,
+                    &asset,
+                    &recipient,
+                    amount - fee,
+                    Symbol::new(env, "withdraw"),
+                )?;
+            }
             StateHelper::save_position(env, &position);
 
             // Emit event
@@ -152,56 +179,71 @@ impl WithdrawModule {
         result
     }
 
-    /// Withdraw collateral for a specific asset (checks cross-asset ratio)
-    pub fn _withdraw_asset(
+    /// Withdraw collateral of a specific asset, checking the ratio across all assets
+    pub fn withdraw_asset(
         env: &Env,
-        user: &String,
+        user: &Address,
         asset: &Address,
+        to: Option<Address>,
         amount: i128,
     ) -> Result<(), ProtocolError> {
         ReentrancyGuard::enter(env)?;
         let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(WithdrawError::InvalidAddress.into());
-            }
             if amount <= 0 {
                 return Err(WithdrawError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Withdraw)?;
+            OperationGate::check(env, user, OperationKind::Withdraw, Some(asset), amount)?;
+            OutflowLimits::record(env, asset, amount)?;
+            let recipient = Beneficiaries::resolve_recipient(env, user, to)?;
 
-            let user_addr = crate::AddressHelper::require_valid_address(env, user)?;
+            // Bring the market current before this flow moves its totals
+            InterestRateManager::accrue_market(env, asset);
 
-            // For cross-asset withdrawal, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
+            let mut position = match StateHelper::get_cross_position(env, user) {
                 Some(pos) => pos,
                 None => return Err(WithdrawError::PositionNotFound.into()),
             };
 
-            if position.collateral < amount {
+            InterestRateManager::accrue_interest_for_cross_position(env, &mut position);
+
+            let balance = position.collateral.get(asset.clone()).unwrap_or(0);
+            if balance < amount {
                 return Err(WithdrawError::InsufficientCollateral.into());
             }
 
             // Check ratio after withdrawal
-            let new_collateral = position.collateral - amount;
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let ratio = if position.debt > 0 {
-                (new_collateral * 100) / position.debt
+            if balance == amount {
+                position.collateral.remove(asset.clone());
             } else {
-                0
-            };
-
-            if position.debt > 0 && ratio < min_ratio {
+                position.collateral.set(asset.clone(), balance - amount);
+            }
+            if !position.debt.is_empty() {
+                Oracle::ensure_position_fresh(env, &position)?;
+            }
+            if !CrossAssets::is_healthy(env, &position)? {
                 return Err(WithdrawError::InsufficientCollateralRatio.into());
             }
+            // The withdrawal fee stays in the pool
+            let fee = Bootstrap::withdraw_fee(env, user, asset, amount);
+            LiquidityGuard::ensure_withdrawable(env, asset, amount - fee)?;
+            Referrals::on_fee(env, user, asset, fee);
+            if amount - fee > 0 {
+                TransferEnforcer::transfer_out(
+                    env,
+                    asset,
+                    &recipient,
+                    amount - fee,
+                    Symbol::new(env, "withdraw"),
+                )?;
+            }
+            StateHelper::save_cross_position(env, &position);
+            InterestRateStorage::apply_market_flow(env, asset, -amount, 0);
 
-            // Update position
-            position.collateral = new_collateral;
-            StateHelper::save_position(env, &position);
+            ProtocolEvent::CrossWithdraw(user.clone(), asset.clone(), amount).emit(env);
 
-            // Emit cross-asset withdraw event
-            ProtocolEvent::CrossWithdraw(user_addr, asset.clone(), amount).emit(env);
+            AnalyticsModule::record_activity(env, user, "withdraw", amount, Some(asset.clone()))?;
+            UserManager::record_activity(env, user, OperationKind::Withdraw, amount)?;
 
             Ok(())
         })();
@@ -221,7 +263,7 @@ impl WithdrawModule {
             return Ok(position.collateral);
         }
 
-        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        let min_ratio = CollateralMatrix::effective_min_ratio(env);
         let required_collateral = (position.debt * min_ratio) / 100;
 
         if position.collateral > required_collateral {